@@ -0,0 +1,130 @@
+use crate::interactive;
+use crate::models::ProjectType;
+use crate::ui;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct AboutInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub repository: String,
+    pub features: Vec<String>,
+    pub project_types: Vec<ProjectTypeInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectTypeInfo {
+    pub name: String,
+    pub operations: Vec<String>,
+}
+
+/// Build the about data from crate metadata and the registered capabilities,
+/// so it can't drift from what the binary actually supports.
+pub fn collect_about() -> AboutInfo {
+    let project_types = ProjectType::ALL
+        .iter()
+        .map(|project_type| {
+            let mut operations: Vec<String> = Vec::new();
+            for operation in project_type.operations() {
+                let flag = operation.as_flag();
+                if !operations.contains(&flag) {
+                    operations.push(flag);
+                }
+            }
+
+            ProjectTypeInfo {
+                name: project_type.to_string(),
+                operations,
+            }
+        })
+        .collect();
+
+    AboutInfo {
+        name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        description: env!("CARGO_PKG_DESCRIPTION").to_string(),
+        repository: env!("CARGO_PKG_REPOSITORY").to_string(),
+        features: interactive::main_menu_features(),
+        project_types,
+    }
+}
+
+pub fn print_about() {
+    let about = collect_about();
+
+    ui::status(&format!("❓ {} {}", about.name, about.version));
+    println!("{}", about.description);
+    println!();
+    ui::status("🎯 Main Features:");
+    for feature in &about.features {
+        ui::status(&format!("  • {}", feature));
+    }
+    println!();
+    ui::status("🏗️  Supported Projects:");
+    for project_type in &about.project_types {
+        println!(
+            "  • {}: {}",
+            project_type.name,
+            project_type.operations.join(", ")
+        );
+    }
+    println!();
+    ui::status(&format!(
+        "📚 For more information, visit: {}",
+        about.repository
+    ));
+    println!();
+}
+
+pub fn print_about_json() -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(&collect_about())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    fn about_json() -> serde_json::Value {
+        serde_json::to_value(collect_about()).unwrap()
+    }
+
+    #[test]
+    fn json_carries_the_crate_version() {
+        let json = about_json();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["name"], env!("CARGO_PKG_NAME"));
+        assert_eq!(json["repository"], env!("CARGO_PKG_REPOSITORY"));
+    }
+
+    #[test]
+    fn json_lists_every_project_type() {
+        let json = about_json();
+        let names: Vec<&str> = json["project_types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|project_type| project_type["name"].as_str().unwrap())
+            .collect();
+        for project_type in ProjectType::value_variants() {
+            assert!(
+                names.contains(&project_type.to_string().as_str()),
+                "{} missing from {:?}",
+                project_type,
+                names
+            );
+        }
+    }
+
+    #[test]
+    fn operations_are_listed_once() {
+        for project_type in collect_about().project_types {
+            let mut unique = project_type.operations.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), project_type.operations.len());
+        }
+    }
+}
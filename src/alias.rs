@@ -0,0 +1,161 @@
+use crate::capture;
+use crate::editor::display_command;
+use crate::resolve;
+use crate::ui;
+use crate::utils::{run_command, shell_command};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A command built interactively and saved under a name to replay later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedCommand {
+    pub executable: String,
+    pub args: Vec<String>,
+    /// Directory the command runs in
+    pub cwd: String,
+}
+
+pub fn aliases_file() -> anyhow::Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home_dir.join(".app-hoist").join("aliases.toml"))
+}
+
+pub fn load() -> anyhow::Result<BTreeMap<String, SavedCommand>> {
+    let path = aliases_file()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    toml::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid aliases file {}: {}", path.display(), e))
+}
+
+fn save(aliases: &BTreeMap<String, SavedCommand>) -> anyhow::Result<()> {
+    let path = aliases_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string(aliases)?)?;
+    Ok(())
+}
+
+/// After a command is built, offer to save it as an alias.
+pub fn offer_save(executable: &str, args: &[String], cwd: Option<&str>) -> anyhow::Result<()> {
+    if !ui::confirm("Save this command as an alias?", false)? {
+        return Ok(());
+    }
+
+    let mut aliases = load()?;
+    let name = loop {
+        let name = ui::text("Alias name (e.g. deploy-staging):", None)?;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            println!("Use letters, digits, '-', '_' and '.'");
+            continue;
+        }
+        if aliases.contains_key(&name)
+            && !ui::confirm(&format!("Alias '{}' exists. Replace it?", name), false)?
+        {
+            continue;
+        }
+        break name;
+    };
+
+    let cwd = match cwd {
+        Some(cwd) => std::path::absolute(cwd)?,
+        None => std::env::current_dir()?,
+    };
+    aliases.insert(
+        name.clone(),
+        SavedCommand {
+            executable: executable.to_string(),
+            args: args.to_vec(),
+            cwd: cwd.to_string_lossy().into_owned(),
+        },
+    );
+    save(&aliases)?;
+    ui::status(&format!(
+        "💾 Saved alias '{}', replay it with: app-hoist alias run {}",
+        name, name
+    ));
+    Ok(())
+}
+
+pub fn print_list() -> anyhow::Result<()> {
+    let aliases = load()?;
+    if aliases.is_empty() {
+        println!("No saved aliases");
+        return Ok(());
+    }
+
+    for (name, command) in aliases {
+        println!(
+            "{:<20} {}  (in {})",
+            name,
+            display_command(&command.executable, &command.args),
+            command.cwd
+        );
+    }
+    Ok(())
+}
+
+pub fn remove(name: &str) -> anyhow::Result<()> {
+    let mut aliases = load()?;
+    if aliases.remove(name).is_none() {
+        anyhow::bail!("No alias named '{}'", name);
+    }
+    save(&aliases)?;
+    ui::status(&format!("✅ Removed alias '{}'", name));
+    Ok(())
+}
+
+/// Run a saved alias without prompting. An executable that moved since it
+/// was saved is looked up on PATH again by its file name.
+pub async fn run(name: &str, dry_run: bool) -> anyhow::Result<()> {
+    let aliases = load()?;
+    let Some(command) = aliases.get(name) else {
+        if aliases.is_empty() {
+            anyhow::bail!("No alias named '{}', and no aliases are saved", name);
+        }
+        anyhow::bail!(
+            "No alias named '{}'; saved aliases: {}",
+            name,
+            aliases.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    };
+
+    // Bare names such as `cargo` are looked up on PATH when the command runs
+    let saved = Path::new(&command.executable);
+    let executable = if saved.exists() || saved.parent().is_none_or(|p| p.as_os_str().is_empty()) {
+        command.executable.clone()
+    } else {
+        let file_name = saved
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let resolution = resolve::resolve(&file_name);
+        let candidate = resolution.selected().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Alias '{}' runs {}, which no longer exists and isn't on PATH",
+                name,
+                command.executable
+            )
+        })?;
+        ui::status(&format!(
+            "⚠️  {} no longer exists, using {}",
+            command.executable, candidate.path
+        ));
+        candidate.path.clone()
+    };
+
+    if dry_run {
+        println!("Dry run: {}", shell_command(&executable, &command.args));
+        return Ok(());
+    }
+
+    capture::check_status(run_command(&executable, &command.args, &[], Some(&command.cwd)).await?)
+}
@@ -1,6 +1,9 @@
-use crate::models::ProjectType;
+use crate::help_parser::{EnvVarInfo, ExampleInfo, SubcommandInfo};
+use crate::lock::canonical_key;
+use crate::models::{OptionInfo, ProjectType};
+use crate::worktree::Checkout;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -11,9 +14,17 @@ pub struct ProjectCache {
     pub package_manager: Option<String>,
     pub last_updated: u64,
     pub metadata: HashMap<String, String>,
+    /// The detection rules that wrote the entry, so entries from older rules
+    /// are detected again, e.g. Poetry projects once cached as generic Python
+    #[serde(default)]
+    pub detection_version: u32,
 }
 
 impl ProjectCache {
+    /// Bumped whenever detection can reach a different type or entry point
+    /// for the same files.
+    pub const DETECTION_VERSION: u32 = 4;
+
     pub fn new(project_type: ProjectType, entry_point: String) -> Self {
         Self {
             project_type,
@@ -21,6 +32,7 @@ impl ProjectCache {
             package_manager: None,
             last_updated: Self::current_timestamp(),
             metadata: HashMap::new(),
+            detection_version: Self::DETECTION_VERSION,
         }
     }
 
@@ -29,6 +41,18 @@ impl ProjectCache {
         now.saturating_sub(self.last_updated) < max_age_seconds
     }
 
+    pub fn age_seconds(&self) -> u64 {
+        Self::current_timestamp().saturating_sub(self.last_updated)
+    }
+
+    /// Whether two entries describe the same project, ignoring when they were written.
+    pub fn same_content(&self, other: &ProjectCache) -> bool {
+        self.project_type == other.project_type
+            && self.entry_point == other.entry_point
+            && self.package_manager == other.package_manager
+            && self.metadata == other.metadata
+    }
+
     fn current_timestamp() -> u64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -37,21 +61,89 @@ impl ProjectCache {
     }
 }
 
+/// Options parsed from a tool's help, kept until the binary changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCache {
+    pub executable: String,
+    pub subcommand: Vec<String>,
+    pub fingerprint: BinaryFingerprint,
+    /// The app-hoist version that parsed the help, since parsing improves between releases
+    pub parser_version: String,
+    pub options: Vec<OptionInfo>,
+    pub subcommands: Vec<SubcommandInfo>,
+    pub from_man_page: bool,
+    /// Environment variables the help documents
+    #[serde(default)]
+    pub env_vars: Vec<EnvVarInfo>,
+    /// Sample command lines from the help's `Examples:` section
+    #[serde(default)]
+    pub examples: Vec<ExampleInfo>,
+    /// How long reading the help took, to spot tools that are slow to probe
+    #[serde(default)]
+    pub probe_millis: u64,
+}
+
+impl ToolCache {
+    pub fn new(executable: &str, subcommand: &[String], fingerprint: BinaryFingerprint) -> Self {
+        Self {
+            executable: executable.to_string(),
+            subcommand: subcommand.to_vec(),
+            fingerprint,
+            parser_version: env!("CARGO_PKG_VERSION").to_string(),
+            options: Vec::new(),
+            subcommands: Vec::new(),
+            from_man_page: false,
+            env_vars: Vec::new(),
+            examples: Vec::new(),
+            probe_millis: 0,
+        }
+    }
+}
+
+/// Size and modification time of an executable, to notice when it is
+/// upgraded or rebuilt.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BinaryFingerprint {
+    pub size: u64,
+    pub modified_nanos: u64,
+}
+
+impl BinaryFingerprint {
+    /// Read from the file the path resolves to, so a symlinked tool such as
+    /// `python3 -> python3.12` changes when its target does.
+    pub fn of(executable: &str) -> Option<Self> {
+        let metadata = std::fs::metadata(executable).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified_nanos: modified.as_nanos() as u64,
+        })
+    }
+}
+
 pub struct CacheManager {
     cache_dir: PathBuf,
     cache: HashMap<String, ProjectCache>,
+    dirty: HashSet<String>,
     max_age_seconds: u64,
 }
 
 impl CacheManager {
+    pub const DEFAULT_MAX_AGE_SECONDS: u64 = 3600; // 1 hour
+
     pub fn new() -> anyhow::Result<Self> {
-        let cache_dir = Self::get_cache_dir()?;
+        Self::in_dir(Self::get_cache_dir()?)
+    }
+
+    /// A cache kept in `cache_dir` rather than under the home directory.
+    pub fn in_dir(cache_dir: PathBuf) -> anyhow::Result<Self> {
         std::fs::create_dir_all(&cache_dir)?;
 
         Ok(Self {
             cache_dir,
             cache: HashMap::new(),
-            max_age_seconds: 3600, // 1 hour default
+            dirty: HashSet::new(),
+            max_age_seconds: Self::DEFAULT_MAX_AGE_SECONDS,
         })
     }
 
@@ -87,22 +179,128 @@ impl CacheManager {
         }
     }
 
+    /// The stored entry for a path, expired or not, without touching it.
+    pub fn peek(&self, path: &str) -> Option<ProjectCache> {
+        self.cache.get(path).cloned().or_else(|| {
+            self.load_cache_from_file(&self.get_cache_file_path(path))
+                .ok()
+        })
+    }
+
+    /// Store an entry in memory. Disk writes are deferred until `flush()` and
+    /// skipped entirely when the entry is unchanged.
     pub fn set(&mut self, path: String, mut cache: ProjectCache) -> anyhow::Result<()> {
+        // Worktrees of one repository are told apart by path but share its identity
+        if let Some(checkout) = Checkout::detect(Path::new(&path)) {
+            checkout.annotate(&mut cache.metadata);
+        }
+
+        if let Some(existing) = self.cache.get(&path)
+            && existing.same_content(&cache)
+        {
+            return Ok(());
+        }
+
         cache.last_updated = ProjectCache::current_timestamp();
 
-        // Store in memory
-        self.cache.insert(path.clone(), cache.clone());
+        self.cache.insert(path.clone(), cache);
+        self.dirty.insert(path);
 
-        // Store to file
-        let cache_file = self.get_cache_file_path(&path);
-        self.save_cache_to_file(&cache_file, &cache)?;
+        Ok(())
+    }
+
+    /// Keep an entry warm without re-detecting: an expired entry is renewed
+    /// when none of the project's marker files changed since it was written.
+    pub fn revalidate(&mut self, path: &str) -> Revalidation {
+        let cache_file = self.get_cache_file_path(path);
+        let Ok(mut cache) = self.load_cache_from_file(&cache_file) else {
+            return Revalidation::Missing;
+        };
+
+        if !cache.is_valid(self.max_age_seconds) {
+            if !markers_unchanged_since(path, cache.last_updated) {
+                return Revalidation::Changed(cache);
+            }
+            cache.last_updated = ProjectCache::current_timestamp();
+            self.dirty.insert(path.to_string());
+        }
+
+        self.cache.insert(path.to_string(), cache);
+        Revalidation::Warm
+    }
+
+    /// Write every entry changed since the last flush to disk.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        for path in std::mem::take(&mut self.dirty) {
+            if let Some(cache) = self.cache.get(&path) {
+                let cache_file = self.get_cache_file_path(&path);
+                self.save_cache_to_file(&cache_file, cache)?;
+            }
+        }
 
         Ok(())
     }
 
+    /// Cached options for a tool, unless its binary or app-hoist changed since.
+    pub fn get_tool(&self, executable: &str, subcommand: &[String]) -> Option<ToolCache> {
+        let cache_file = self.get_tool_file_path(executable, subcommand);
+        let content = std::fs::read_to_string(&cache_file).ok()?;
+        let Ok(cache) = serde_json::from_str::<ToolCache>(&content) else {
+            let _ = std::fs::remove_file(&cache_file);
+            return None;
+        };
+
+        if BinaryFingerprint::of(executable).as_ref() != Some(&cache.fingerprint)
+            || cache.parser_version != env!("CARGO_PKG_VERSION")
+        {
+            let _ = std::fs::remove_file(&cache_file);
+            return None;
+        }
+        Some(cache)
+    }
+
+    pub fn set_tool(&self, cache: &ToolCache) -> anyhow::Result<()> {
+        std::fs::create_dir_all(self.tool_dir())?;
+        let cache_file = self.get_tool_file_path(&cache.executable, &cache.subcommand);
+        write_json(&cache_file, cache)
+    }
+
+    /// Drop every cached option set of an executable, returning how many there were.
+    pub fn invalidate_tool(&self, executable: &str) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for (file, cache) in self.tool_entries() {
+            if cache.executable == executable {
+                std::fs::remove_file(file)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn clear_tools(&self) -> anyhow::Result<()> {
+        if self.tool_dir().exists() {
+            std::fs::remove_dir_all(self.tool_dir())?;
+        }
+        Ok(())
+    }
+
+    fn tool_entries(&self) -> Vec<(PathBuf, ToolCache)> {
+        let Ok(entries) = std::fs::read_dir(self.tool_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let content = std::fs::read_to_string(entry.path()).ok()?;
+                Some((entry.path(), serde_json::from_str(&content).ok()?))
+            })
+            .collect()
+    }
+
     pub fn invalidate(&mut self, path: &str) -> anyhow::Result<()> {
         // Remove from memory
         self.cache.remove(path);
+        self.dirty.remove(path);
 
         // Remove from disk
         let cache_file = self.get_cache_file_path(path);
@@ -116,6 +314,7 @@ impl CacheManager {
     pub fn clear_all(&mut self) -> anyhow::Result<()> {
         // Clear memory cache
         self.cache.clear();
+        self.dirty.clear();
 
         // Clear disk cache
         if self.cache_dir.exists() {
@@ -129,12 +328,21 @@ impl CacheManager {
     pub fn stats(&self) -> CacheStats {
         let memory_entries = self.cache.len();
         let mut file_entries = 0;
+        let mut tool_entries = 0;
         let mut total_size = 0;
 
-        if let Ok(entries) = std::fs::read_dir(&self.cache_dir) {
+        for (dir, count) in [
+            (self.cache_dir.clone(), &mut file_entries),
+            (self.tool_dir(), &mut tool_entries),
+        ] {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
             for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    file_entries += 1;
+                if let Ok(metadata) = entry.metadata()
+                    && metadata.is_file()
+                {
+                    *count += 1;
                     total_size += metadata.len();
                 }
             }
@@ -143,6 +351,7 @@ impl CacheManager {
         CacheStats {
             memory_entries,
             file_entries,
+            tool_entries,
             total_size,
             max_age_seconds: self.max_age_seconds,
         }
@@ -155,29 +364,120 @@ impl CacheManager {
     }
 
     fn get_cache_file_path(&self, path: &str) -> PathBuf {
-        // Create a safe filename from the path
-        let safe_name = path.replace(['/', '\\', ':', ' '], "_");
+        // Hash the path so any characters (spaces, unicode, separators) map to a safe filename.
+        // Symlinks are resolved so every route to a directory shares its entry,
+        // while each git worktree, being its own directory, keeps a separate one.
+        self.cache_dir
+            .join(format!("{:016x}.json", path_hash(&canonical_key(path))))
+    }
+
+    fn tool_dir(&self) -> PathBuf {
+        self.cache_dir.join("tools")
+    }
 
-        self.cache_dir.join(format!("{}.json", safe_name))
+    fn get_tool_file_path(&self, executable: &str, subcommand: &[String]) -> PathBuf {
+        let key = std::iter::once(executable)
+            .chain(subcommand.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\0");
+        self.tool_dir()
+            .join(format!("{:016x}.json", path_hash(&key)))
     }
 
     fn load_cache_from_file(&self, path: &Path) -> anyhow::Result<ProjectCache> {
         let content = std::fs::read_to_string(path)?;
         let cache: ProjectCache = serde_json::from_str(&content)?;
+        if cache.detection_version < ProjectCache::DETECTION_VERSION {
+            anyhow::bail!("Cache entry predates the current detection rules");
+        }
         Ok(cache)
     }
 
     fn save_cache_to_file(&self, path: &Path, cache: &ProjectCache) -> anyhow::Result<()> {
-        let content = serde_json::to_string_pretty(cache)?;
-        std::fs::write(path, content)?;
-        Ok(())
+        write_json(path, cache)
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    // Write then rename so a concurrent reader never sees a partial entry
+    let content = serde_json::to_string_pretty(value)?;
+    let temp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+impl Drop for CacheManager {
+    fn drop(&mut self) {
+        // Safety net for handlers that return early before flushing
+        let _ = self.flush();
     }
 }
 
+pub enum Revalidation {
+    Warm,
+    /// The project changed since this entry was written and needs detecting again
+    Changed(ProjectCache),
+    Missing,
+}
+
+// Files whose changes can alter what detection finds for a project
+const PROJECT_MARKERS: &[&str] = &[
+    "pyproject.toml",
+    "uv.lock",
+    "poetry.lock",
+    "requirements.txt",
+    "setup.py",
+    "go.mod",
+    "Cargo.toml",
+    "package.json",
+    "tsconfig.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "package-lock.json",
+    "bun.lock",
+    "bun.lockb",
+    "deno.json",
+    "deno.jsonc",
+    "GNUmakefile",
+    "makefile",
+    "Makefile",
+    "justfile",
+    "Justfile",
+    ".justfile",
+];
+
+/// Whether the project directory and its marker files are all older than `timestamp`.
+pub fn markers_unchanged_since(path: &str, timestamp: u64) -> bool {
+    let root = Path::new(path);
+    if !root.is_dir() {
+        return false;
+    }
+
+    // The directory's own mtime changes when marker files are added or removed
+    std::iter::once(root.to_path_buf())
+        .chain(PROJECT_MARKERS.iter().map(|marker| root.join(marker)))
+        .filter_map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+        .all(|modified| {
+            modified
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() < timestamp)
+                .unwrap_or(false)
+        })
+}
+
+/// Stable FNV-1a hash of a path, used to key files under the cache directory.
+pub fn path_hash(path: &str) -> u64 {
+    path.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 #[derive(Debug)]
 pub struct CacheStats {
     pub memory_entries: usize,
     pub file_entries: usize,
+    pub tool_entries: usize,
     pub total_size: u64,
     pub max_age_seconds: u64,
 }
@@ -186,8 +486,202 @@ impl std::fmt::Display for CacheStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Cache Stats: {} in memory, {} on disk, {} bytes total, {}s max age",
-            self.memory_entries, self.file_entries, self.total_size, self.max_age_seconds
+            "Cache Stats: {} in memory, {} on disk, {} tool option sets, {} bytes total, {}s max age",
+            self.memory_entries,
+            self.file_entries,
+            self.tool_entries,
+            self.total_size,
+            self.max_age_seconds
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> (tempfile::TempDir, CacheManager) {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = CacheManager::in_dir(dir.path().join("cache")).unwrap();
+        (dir, manager)
+    }
+
+    fn entry() -> ProjectCache {
+        ProjectCache::new(ProjectType::Rust, "src/main.rs".to_string())
+    }
+
+    #[test]
+    fn set_defers_the_write_until_flush() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        let file = manager.get_cache_file_path(&project);
+
+        manager.set(project.clone(), entry()).unwrap();
+        assert!(!file.exists());
+        manager.flush().unwrap();
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn unchanged_entries_are_not_rewritten() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        let file = manager.get_cache_file_path(&project);
+        manager.set(project.clone(), entry()).unwrap();
+        manager.flush().unwrap();
+
+        // A write would bring the file back
+        std::fs::remove_file(&file).unwrap();
+        manager.set(project.clone(), entry()).unwrap();
+        manager.flush().unwrap();
+        assert!(!file.exists());
+
+        let mut changed = entry();
+        changed.entry_point = "src/bin/other.rs".to_string();
+        manager.set(project, changed).unwrap();
+        manager.flush().unwrap();
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn memory_is_authoritative_before_flush() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        manager.set(project.clone(), entry()).unwrap();
+
+        let cached = manager.get(&project).unwrap().unwrap();
+        assert_eq!(cached.entry_point, "src/main.rs");
+    }
+
+    #[test]
+    fn drop_flushes_pending_entries() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        let file = manager.get_cache_file_path(&project);
+        manager.set(project, entry()).unwrap();
+        drop(manager);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn entries_survive_a_new_manager() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        manager.set(project.clone(), entry()).unwrap();
+        manager.flush().unwrap();
+
+        let mut reopened = CacheManager::in_dir(dir.path().join("cache")).unwrap();
+        assert!(reopened.get(&project).unwrap().is_some());
+    }
+
+    /// A stand-in executable and a tool entry cached for it.
+    fn tool(dir: &Path, subcommand: &[&str]) -> (String, ToolCache) {
+        let executable = dir.join("tool");
+        if !executable.exists() {
+            std::fs::write(&executable, "v1").unwrap();
+        }
+        let executable = executable.to_str().unwrap().to_string();
+        let subcommand: Vec<String> = subcommand.iter().map(|s| s.to_string()).collect();
+        let mut cache = ToolCache::new(
+            &executable,
+            &subcommand,
+            BinaryFingerprint::of(&executable).unwrap(),
+        );
+        cache.options = crate::help_parser::parse_help("Options:\n  -q, --quiet  Say less\n");
+        (executable, cache)
+    }
+
+    #[test]
+    fn tool_options_are_cached_per_subcommand() {
+        let (dir, manager) = manager();
+        let (executable, top) = tool(dir.path(), &[]);
+        let (_, build) = tool(dir.path(), &["build"]);
+        manager.set_tool(&top).unwrap();
+        manager.set_tool(&build).unwrap();
+
+        let cached = manager.get_tool(&executable, &[]).unwrap();
+        assert_eq!(cached.options[0].flags, ["-q", "--quiet"]);
+        assert!(
+            manager
+                .get_tool(&executable, &["build".to_string()])
+                .is_some()
+        );
+        assert!(
+            manager
+                .get_tool(&executable, &["test".to_string()])
+                .is_none()
+        );
+        assert_eq!(manager.stats().tool_entries, 2);
+    }
+
+    #[test]
+    fn changed_binaries_drop_their_tool_entry() {
+        let (dir, manager) = manager();
+        let (executable, cache) = tool(dir.path(), &[]);
+        manager.set_tool(&cache).unwrap();
+
+        std::fs::write(&executable, "version two").unwrap();
+        assert!(manager.get_tool(&executable, &[]).is_none());
+        assert_eq!(manager.stats().tool_entries, 0);
+    }
+
+    #[test]
+    fn tool_entries_can_be_invalidated_and_cleared() {
+        let (dir, manager) = manager();
+        let (executable, top) = tool(dir.path(), &[]);
+        let (_, build) = tool(dir.path(), &["build"]);
+        manager.set_tool(&top).unwrap();
+        manager.set_tool(&build).unwrap();
+
+        assert_eq!(manager.invalidate_tool(&executable).unwrap(), 2);
+        assert!(manager.get_tool(&executable, &[]).is_none());
+
+        manager.set_tool(&top).unwrap();
+        manager.clear_tools().unwrap();
+        assert_eq!(manager.stats().tool_entries, 0);
+    }
+
+    #[test]
+    fn entries_from_older_detection_rules_are_dropped() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        let file = manager.get_cache_file_path(&project);
+
+        // Written before Poetry was detected, without a detection version
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        let old = serde_json::json!({
+            "project_type": "Generic",
+            "entry_point": "main.py",
+            "package_manager": null,
+            "last_updated": ProjectCache::current_timestamp(),
+            "metadata": {},
+        });
+        std::fs::write(&file, old.to_string()).unwrap();
+        assert!(manager.get(&project).unwrap().is_none());
+        assert!(!file.exists());
+
+        // A type this version doesn't know is dropped the same way
+        let mut unknown = serde_json::to_value(entry()).unwrap();
+        unknown["project_type"] = "Sbt".into();
+        std::fs::write(&file, unknown.to_string()).unwrap();
+        assert!(manager.get(&project).unwrap().is_none());
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn poetry_entries_round_trip() {
+        let (dir, mut manager) = manager();
+        let project = dir.path().to_str().unwrap().to_string();
+        manager
+            .set(
+                project.clone(),
+                ProjectCache::new(ProjectType::Poetry, "main.py".to_string()),
+            )
+            .unwrap();
+        manager.flush().unwrap();
+
+        let mut reopened = CacheManager::in_dir(dir.path().join("cache")).unwrap();
+        let cached = reopened.get(&project).unwrap().unwrap();
+        assert_eq!(cached.project_type, ProjectType::Poetry);
+    }
+}
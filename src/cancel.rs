@@ -0,0 +1,360 @@
+use crate::ui::{self, ProgressGroup};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Key that opens the cancel menu during a multi-project run.
+pub const CANCEL_KEY: u8 = b'x';
+
+/// A task that was stopped by the user rather than failing on its own.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled by user")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[derive(Debug, Default)]
+struct TaskState {
+    pid: Option<u32>,
+    cancelled: bool,
+}
+
+/// The tasks of a parallel run that are still in flight, keyed by project,
+/// with the process group each one's command runs in.
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<BTreeMap<String, TaskState>>,
+}
+
+impl TaskRegistry {
+    pub fn start(&self, key: &str) {
+        self.lock().insert(key.to_string(), TaskState::default());
+    }
+
+    /// Record the command a task is running. Its pid is also its process
+    /// group id, since commands are spawned as group leaders.
+    pub fn set_child(&self, key: &str, pid: Option<u32>) {
+        if let Some(task) = self.lock().get_mut(key) {
+            task.pid = pid;
+        }
+    }
+
+    pub fn finish(&self, key: &str) {
+        self.lock().remove(key);
+    }
+
+    pub fn in_flight(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    pub fn is_cancelled(&self, key: &str) -> bool {
+        self.lock().get(key).is_some_and(|task| task.cancelled)
+    }
+
+    /// Mark a task cancelled and kill its command's process group. Returns
+    /// false if the task had already finished.
+    pub fn cancel(&self, key: &str) -> anyhow::Result<bool> {
+        let pid = {
+            let mut tasks = self.lock();
+            let Some(task) = tasks.get_mut(key) else {
+                return Ok(false);
+            };
+            task.cancelled = true;
+            task.pid
+        };
+
+        // A task between commands notices the flag when its next one would start
+        if let Some(pid) = pid {
+            kill_process_group(pid)?;
+        }
+        Ok(true)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TaskState>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(unix)]
+pub fn kill_process_group(pgid: u32) -> anyhow::Result<()> {
+    // SAFETY: kill has no memory-safety preconditions
+    let result = unsafe { libc::kill(-(pgid as libc::pid_t), libc::SIGKILL) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        // The group may have exited on its own in the meantime
+        if error.raw_os_error() != Some(libc::ESRCH) {
+            return Err(error.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn kill_process_group(pid: u32) -> anyhow::Result<()> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("taskkill failed for pid {}", pid);
+    }
+    Ok(())
+}
+
+/// Watches the keyboard during a run and offers to cancel a task when
+/// `CANCEL_KEY` is pressed. Stops when dropped.
+pub struct KeyListener {
+    stop: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl KeyListener {
+    /// `None` when stdin isn't a terminal, or on platforms without support.
+    pub fn spawn(registry: Arc<TaskRegistry>, group: Arc<ProgressGroup>) -> Option<Self> {
+        use std::io::IsTerminal;
+        if !cfg!(unix) || !std::io::stdin().is_terminal() {
+            return None;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = std::thread::spawn(move || {
+            let mut mode = KeypressMode::enable();
+            while !thread_stop.load(Ordering::Relaxed) {
+                if read_key(Duration::from_millis(100)) == Some(CANCEL_KEY) {
+                    // The prompt needs the terminal back in its usual mode
+                    drop(mode.take());
+                    group.suspend(|| {
+                        if let Err(e) = prompt_cancel(&registry) {
+                            eprintln!("Error: {}", e);
+                        }
+                    });
+                    mode = KeypressMode::enable();
+                }
+            }
+        });
+
+        Some(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+const KEEP_RUNNING: &str = "(don't cancel anything)";
+
+fn prompt_cancel(registry: &TaskRegistry) -> anyhow::Result<()> {
+    let mut choices = registry.in_flight();
+    if choices.is_empty() {
+        return Ok(());
+    }
+    choices.push(KEEP_RUNNING.to_string());
+
+    let choice = ui::select("Cancel which project?", choices)?;
+    if choice != KEEP_RUNNING && registry.cancel(&choice)? {
+        ui::status(&format!("⏭️  Cancelling {}", choice));
+    }
+    Ok(())
+}
+
+/// Terminal input without line buffering or echo, so single keypresses can
+/// be read. Output processing is left alone, so progress lines still render
+/// normally. The previous settings are restored on drop.
+#[cfg(unix)]
+struct KeypressMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl KeypressMode {
+    fn enable() -> Option<Self> {
+        // SAFETY: termios is plain data, filled in by tcgetattr before use
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return None;
+        }
+
+        let mut keypress = original;
+        keypress.c_lflag &= !(libc::ICANON | libc::ECHO);
+        keypress.c_cc[libc::VMIN] = 1;
+        keypress.c_cc[libc::VTIME] = 0;
+        // SAFETY: keypress is a valid termios copied from the current settings
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &keypress) } != 0 {
+            return None;
+        }
+
+        Some(Self { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for KeypressMode {
+    fn drop(&mut self) {
+        // SAFETY: original holds the settings read in enable
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Wait up to `timeout` for a byte on stdin.
+#[cfg(unix)]
+fn read_key(timeout: Duration) -> Option<u8> {
+    let mut fds = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let mut byte = 0u8;
+    // SAFETY: fds and byte outlive the calls that write to them
+    unsafe {
+        if libc::poll(&mut fds, 1, timeout.as_millis() as libc::c_int) <= 0 {
+            return None;
+        }
+        if libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) != 1 {
+            return None;
+        }
+    }
+    Some(byte)
+}
+
+#[cfg(not(unix))]
+struct KeypressMode;
+
+#[cfg(not(unix))]
+impl KeypressMode {
+    fn enable() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn read_key(timeout: Duration) -> Option<u8> {
+    std::thread::sleep(timeout);
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_are_in_flight_from_start_until_finish() {
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        registry.start("api");
+        assert_eq!(registry.in_flight(), vec!["api", "web"]);
+
+        registry.finish("web");
+        assert_eq!(registry.in_flight(), vec!["api"]);
+        registry.finish("api");
+        assert!(registry.in_flight().is_empty());
+    }
+
+    #[test]
+    fn finished_or_unknown_tasks_cannot_be_cancelled() {
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        registry.finish("web");
+
+        assert!(!registry.cancel("web").unwrap());
+        assert!(!registry.cancel("api").unwrap());
+        assert!(!registry.is_cancelled("web"));
+    }
+
+    #[test]
+    fn a_task_between_commands_is_only_marked_cancelled() {
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        registry.start("api");
+
+        assert!(registry.cancel("web").unwrap());
+        assert!(registry.is_cancelled("web"));
+        assert!(!registry.is_cancelled("api"));
+        // Still in flight until its runner notices and finishes it
+        assert_eq!(registry.in_flight(), vec!["api", "web"]);
+    }
+
+    #[test]
+    fn starting_a_task_again_clears_its_cancellation() {
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        registry.cancel("web").unwrap();
+        registry.finish("web");
+
+        registry.start("web");
+        assert!(!registry.is_cancelled("web"));
+    }
+
+    /// A stand-in for a long-running tool that starts a child of its own, in
+    /// its own process group the way project commands are spawned.
+    #[cfg(unix)]
+    fn long_running_group() -> std::process::Child {
+        use std::os::unix::process::CommandExt;
+        std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .stdout(std::process::Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_kills_the_whole_process_group() {
+        use std::io::Read;
+        use std::os::unix::process::ExitStatusExt;
+
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        let mut child = long_running_group();
+        registry.set_child("web", Some(child.id()));
+
+        let mut stdout = child.stdout.take().unwrap();
+        let (done, closed) = std::sync::mpsc::channel();
+        // The pipe only closes once the shell and the sleep it started are both gone
+        std::thread::spawn(move || {
+            let _ = stdout.read_to_end(&mut Vec::new());
+            let _ = done.send(());
+        });
+
+        assert!(registry.cancel("web").unwrap());
+        assert!(registry.is_cancelled("web"));
+        closed
+            .recv_timeout(Duration::from_secs(5))
+            .expect("the group's processes should all have been killed");
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_a_command_that_already_exited_succeeds() {
+        let registry = TaskRegistry::default();
+        registry.start("web");
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        registry.set_child("web", Some(pid));
+
+        assert!(registry.cancel("web").unwrap());
+        assert!(registry.is_cancelled("web"));
+    }
+}
@@ -0,0 +1,363 @@
+use crate::ui;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+static QUIET_CHILD: AtomicBool = AtomicBool::new(false);
+static IGNORE_EXIT_CODE: AtomicBool = AtomicBool::new(false);
+static TAIL_LINES: AtomicUsize = AtomicUsize::new(DEFAULT_TAIL_LINES);
+static LOG_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub const DEFAULT_TAIL_LINES: usize = 50;
+
+/// Capture child output instead of showing it, keeping `tail_lines` lines for
+/// failures. With `ignore_exit_code`, a failed command doesn't fail app-hoist.
+pub fn init(quiet_child: bool, tail_lines: usize, ignore_exit_code: bool) {
+    QUIET_CHILD.store(quiet_child, Ordering::Relaxed);
+    TAIL_LINES.store(tail_lines, Ordering::Relaxed);
+    IGNORE_EXIT_CODE.store(ignore_exit_code, Ordering::Relaxed);
+}
+
+pub fn tail_lines() -> usize {
+    TAIL_LINES.load(Ordering::Relaxed)
+}
+
+pub fn is_quiet() -> bool {
+    QUIET_CHILD.load(Ordering::Relaxed)
+}
+
+/// The last `capacity` lines written to it, in order.
+#[derive(Debug, Clone)]
+pub struct TailBuffer {
+    lines: VecDeque<String>,
+    capacity: usize,
+    total: usize,
+}
+
+impl TailBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        self.total += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &String> {
+        self.lines.iter()
+    }
+
+    /// How many lines were pushed in total, including those no longer kept.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+/// Output of one command, written in full to a log file with only the tail kept in memory.
+pub struct Capture {
+    log_path: PathBuf,
+    log: BufWriter<File>,
+    tail: TailBuffer,
+}
+
+impl Capture {
+    pub fn new(label: &str) -> anyhow::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("app-hoist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let name: String = label
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .take(40)
+            .collect();
+        let log_path = dir.join(format!(
+            "{:03}-{}.log",
+            LOG_COUNTER.fetch_add(1, Ordering::Relaxed),
+            name
+        ));
+
+        Ok(Self {
+            log: BufWriter::new(File::create(&log_path)?),
+            log_path,
+            tail: TailBuffer::new(tail_lines()),
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) {
+        let _ = writeln!(self.log, "{}", line);
+        self.tail.push(line.to_string());
+    }
+
+    pub fn finish(mut self) -> CapturedOutput {
+        let _ = self.log.flush();
+        CapturedOutput {
+            log_path: self.log_path,
+            tail: self.tail,
+        }
+    }
+}
+
+pub struct CapturedOutput {
+    pub log_path: PathBuf,
+    pub tail: TailBuffer,
+}
+
+impl CapturedOutput {
+    /// The captured tail followed by where to find the rest, for showing after a failure.
+    pub fn failure_report(&self) -> Vec<String> {
+        let shown = self.tail.lines().count();
+        let mut report = Vec::new();
+        if self.tail.total() > shown {
+            report.push(format!(
+                "... last {} of {} lines of output:",
+                shown,
+                self.tail.total()
+            ));
+        }
+        report.extend(self.tail.lines().cloned());
+        report.push(format!("Full output: {}", self.log_path.display()));
+        report
+    }
+}
+
+/// Run a command with its stdout and stderr captured rather than shown.
+pub async fn run_captured(
+    command: &mut Command,
+    label: &str,
+) -> anyhow::Result<(ExitStatus, CapturedOutput)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let capture = Arc::new(Mutex::new(Capture::new(label)?));
+    let readers = [
+        spawn_reader(child.stdout.take(), &capture),
+        spawn_reader(child.stderr.take(), &capture),
+    ];
+
+    let status = child.wait().await?;
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.await;
+    }
+
+    let capture = Arc::try_unwrap(capture)
+        .map_err(|_| anyhow::anyhow!("Output reader still running"))?
+        .into_inner()
+        .map_err(|_| anyhow::anyhow!("Output reader panicked"))?;
+
+    Ok((status, capture.finish()))
+}
+
+fn spawn_reader<R: AsyncRead + Unpin + Send + 'static>(
+    stream: Option<R>,
+    capture: &Arc<Mutex<Capture>>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let stream = stream?;
+    let capture = Arc::clone(capture);
+    Some(tokio::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        while reader
+            .read_until(b'\n', &mut buf)
+            .await
+            .is_ok_and(|n| n > 0)
+        {
+            let line = String::from_utf8_lossy(&buf);
+            if let Ok(mut capture) = capture.lock() {
+                capture.write_line(line.trim_end_matches(['\r', '\n']));
+            }
+            buf.clear();
+        }
+    }))
+}
+
+/// A command that exited unsuccessfully; app-hoist exits with the same code.
+#[derive(Debug)]
+pub struct ChildExit {
+    pub code: i32,
+}
+
+impl std::fmt::Display for ChildExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command exited with code {}", self.code)
+    }
+}
+
+impl std::error::Error for ChildExit {}
+
+/// Turn a failed exit status into a `ChildExit` error, unless
+/// `--ignore-exit-code` was given. A command killed by a signal counts as
+/// exiting with 128 plus the signal number, as in a shell.
+pub fn check_status(status: ExitStatus) -> anyhow::Result<()> {
+    if status.success() || IGNORE_EXIT_CODE.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal: Option<i32> = None;
+
+    let code = status
+        .code()
+        .or(signal.map(|signal| 128 + signal))
+        .unwrap_or(1);
+    Err(ChildExit { code }.into())
+}
+
+/// Run a command, capturing its output in quiet-child mode. A quiet run prints
+/// a one-line summary on success, or the tail of its output on failure.
+pub async fn run(command: &mut Command, label: &str) -> anyhow::Result<ExitStatus> {
+    if !is_quiet() {
+        return Ok(command.status().await?);
+    }
+
+    let started = Instant::now();
+    let (status, output) = run_captured(command, label).await?;
+
+    if status.success() {
+        ui::status(&format!(
+            "✅ {} finished in {}",
+            label,
+            format_duration(started.elapsed())
+        ));
+    } else {
+        for line in output.failure_report() {
+            println!("{}", line);
+        }
+    }
+
+    Ok(status)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs < 60.0 {
+        format!("{:.1}s", secs)
+    } else {
+        format!(
+            "{}m {:02}s",
+            duration.as_secs() / 60,
+            duration.as_secs() % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tail_of(capacity: usize, count: usize) -> TailBuffer {
+        let mut tail = TailBuffer::new(capacity);
+        for i in 1..=count {
+            tail.push(format!("line {}", i));
+        }
+        tail
+    }
+
+    fn lines(tail: &TailBuffer) -> Vec<&str> {
+        tail.lines().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn tail_keeps_everything_under_capacity() {
+        let tail = tail_of(5, 3);
+        assert_eq!(lines(&tail), ["line 1", "line 2", "line 3"]);
+        assert_eq!(tail.total(), 3);
+    }
+
+    #[test]
+    fn tail_keeps_the_newest_lines_in_order() {
+        let tail = tail_of(3, 10);
+        assert_eq!(lines(&tail), ["line 8", "line 9", "line 10"]);
+        assert_eq!(tail.total(), 10);
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing_but_counts() {
+        let tail = tail_of(0, 4);
+        assert!(lines(&tail).is_empty());
+        assert_eq!(tail.total(), 4);
+    }
+
+    #[test]
+    fn failure_report_says_when_output_was_cut() {
+        let output = CapturedOutput {
+            log_path: PathBuf::from("/tmp/run.log"),
+            tail: tail_of(2, 5),
+        };
+        assert_eq!(
+            output.failure_report(),
+            [
+                "... last 2 of 5 lines of output:",
+                "line 4",
+                "line 5",
+                "Full output: /tmp/run.log"
+            ]
+        );
+
+        let output = CapturedOutput {
+            log_path: PathBuf::from("/tmp/run.log"),
+            tail: tail_of(5, 2),
+        };
+        assert_eq!(
+            output.failure_report(),
+            ["line 1", "line 2", "Full output: /tmp/run.log"]
+        );
+    }
+
+    #[test]
+    fn capture_logs_every_line_but_keeps_only_the_tail() {
+        let mut capture = Capture::new("capture test").unwrap();
+        for i in 1..=(DEFAULT_TAIL_LINES + 10) {
+            capture.write_line(&format!("line {}", i));
+        }
+        let output = capture.finish();
+
+        let log = std::fs::read_to_string(&output.log_path).unwrap();
+        assert_eq!(log.lines().count(), DEFAULT_TAIL_LINES + 10);
+        assert_eq!(output.tail.lines().count(), DEFAULT_TAIL_LINES);
+        assert_eq!(output.tail.lines().next().unwrap(), "line 11");
+        let _ = std::fs::remove_file(&output.log_path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn captured_commands_keep_both_streams() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2; printf 'caf\\351\\n'; exit 3"]);
+        let (status, output) = run_captured(&mut command, "sh test").await.unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        let mut lines: Vec<&str> = output.tail.lines().map(String::as_str).collect();
+        lines.sort();
+        assert_eq!(lines, ["caf\u{fffd}", "err", "out"]);
+        let _ = std::fs::remove_file(&output.log_path);
+    }
+
+    #[test]
+    fn durations_switch_to_minutes() {
+        assert_eq!(format_duration(Duration::from_millis(1300)), "1.3s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59.0s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 05s");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::editor::format_size;
+use crate::models::ProjectType;
+use crate::ui;
+use std::path::{Path, PathBuf};
+
+/// Python caches and build output removed alongside every `__pycache__`.
+const PYTHON_ARTIFACTS: [&str; 2] = [".pytest_cache", "dist"];
+
+/// What `clean` removes in a project, for measuring how much space it frees.
+/// Nothing for Go: `go clean` decides what goes, mostly from its cache
+/// outside the project, so there is no figure to give.
+pub fn artifact_paths(project_type: &ProjectType, path: &str) -> Vec<PathBuf> {
+    let root = Path::new(path);
+    let paths = match project_type {
+        ProjectType::Rust => vec![root.join("target")],
+        ProjectType::JavaScript | ProjectType::TypeScript => vec![root.join("node_modules")],
+        ProjectType::Uv | ProjectType::Poetry | ProjectType::Venv | ProjectType::Generic => {
+            let mut paths: Vec<PathBuf> = PYTHON_ARTIFACTS
+                .iter()
+                .map(|name| root.join(name))
+                .collect();
+            paths.extend(pycache_dirs(root));
+            paths
+        }
+        ProjectType::Go | ProjectType::Make | ProjectType::Just | ProjectType::Deno => Vec::new(),
+    };
+    paths.into_iter().filter(|path| path.exists()).collect()
+}
+
+/// `__pycache__` directories outside virtualenvs, node_modules and hidden
+/// directories.
+fn pycache_dirs(root: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_str().unwrap_or("");
+            entry.depth() == 0
+                || !(name.starts_with('.')
+                    || name == "node_modules"
+                    || entry.path().join("pyvenv.cfg").is_file())
+        })
+        .flatten()
+        .filter(|entry| entry.file_type().is_dir() && entry.file_name() == "__pycache__")
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// The command that deletes a project's artifacts when its tool has no clean
+/// command of its own: `rm -rf --`, or `rmdir /s /q` on Windows. `None` when the
+/// tool cleans up itself, and an empty command when there's nothing to delete.
+pub fn removal_command(project_type: &ProjectType, path: &str) -> Option<Vec<String>> {
+    if !matches!(
+        project_type,
+        ProjectType::JavaScript
+            | ProjectType::TypeScript
+            | ProjectType::Uv
+            | ProjectType::Poetry
+            | ProjectType::Venv
+            | ProjectType::Generic
+    ) {
+        return None;
+    }
+    let paths = artifact_paths(project_type, path);
+    if paths.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut command: Vec<String> = if cfg!(windows) {
+        vec!["cmd", "/C", "rmdir", "/s", "/q"]
+    } else {
+        // A path starting with `-` is still a path
+        vec!["rm", "-rf", "--"]
+    }
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+    command.extend(paths.iter().map(|artifact| {
+        artifact
+            .strip_prefix(path)
+            .unwrap_or(artifact)
+            .to_string_lossy()
+            .into_owned()
+    }));
+    Some(command)
+}
+
+/// Total size in bytes of the files under `paths`.
+pub fn size(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .flat_map(|path| walkdir::WalkDir::new(path).into_iter().flatten())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// How much space went, given the size of `paths` before cleaning.
+pub fn reclaimed(before: u64, paths: &[PathBuf]) -> String {
+    format_size(before.saturating_sub(size(paths)) as usize)
+}
+
+/// Ask before deleting anything that takes more than a rebuild to get back,
+/// i.e. node_modules, which needs a fresh install. Asked even when nothing
+/// else is, so `--select clean` and multi-project runs can't skip it.
+pub fn confirm_destructive(projects: &[(&str, &ProjectType)]) -> anyhow::Result<bool> {
+    let doomed: Vec<PathBuf> = projects
+        .iter()
+        .filter(|(_, project_type)| {
+            matches!(
+                project_type,
+                ProjectType::JavaScript | ProjectType::TypeScript
+            )
+        })
+        .map(|(path, _)| Path::new(path).join("node_modules"))
+        .filter(|node_modules| node_modules.is_dir())
+        .collect();
+    if doomed.is_empty() {
+        return Ok(true);
+    }
+
+    for node_modules in &doomed {
+        println!("  {}", node_modules.display());
+    }
+    ui::confirm(
+        &format!(
+            "Delete {} node_modules director{} ({})? They'll need a fresh install",
+            doomed.len(),
+            if doomed.len() == 1 { "y" } else { "ies" },
+            format_size(size(&doomed) as usize)
+        ),
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let file = dir.path().join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "12345").unwrap();
+        }
+        dir
+    }
+
+    fn relative(dir: &tempfile::TempDir, paths: Vec<PathBuf>) -> Vec<String> {
+        let mut relative: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                path.strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        relative.sort();
+        relative
+    }
+
+    #[test]
+    fn python_artifacts_skip_venvs_and_hidden_directories() {
+        let dir = project(&[
+            "dist/demo.whl",
+            "__pycache__/main.pyc",
+            "pkg/__pycache__/mod.pyc",
+            ".venv/pyvenv.cfg",
+            ".venv/lib/__pycache__/site.pyc",
+            "env/pyvenv.cfg",
+            "env/lib/__pycache__/site.pyc",
+            "node_modules/x/__pycache__/y.pyc",
+            "main.py",
+        ]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(
+            relative(&dir, artifact_paths(&ProjectType::Uv, path)),
+            ["__pycache__", "dist", "pkg/__pycache__"]
+        );
+    }
+
+    #[test]
+    fn only_existing_artifacts_are_measured() {
+        let dir = project(&["Cargo.toml", "node_modules/x/index.js", "main.go"]);
+        let path = dir.path().to_str().unwrap();
+        assert!(artifact_paths(&ProjectType::Rust, path).is_empty());
+        // go clean works mostly outside the project, so it isn't measured
+        assert!(artifact_paths(&ProjectType::Go, path).is_empty());
+        let node_modules = artifact_paths(&ProjectType::JavaScript, path);
+        assert_eq!(relative(&dir, node_modules.clone()), ["node_modules"]);
+        assert_eq!(size(&node_modules), 5);
+
+        std::fs::remove_dir_all(dir.path().join("node_modules")).unwrap();
+        assert_eq!(reclaimed(5, &node_modules), format_size(5));
+    }
+
+    #[test]
+    fn removal_commands_end_their_options_before_the_paths() {
+        let dir = project(&["node_modules/x/index.js", "-pkg/__pycache__/a.pyc"]);
+        let path = dir.path().to_str().unwrap();
+
+        let command = removal_command(&ProjectType::JavaScript, path).unwrap();
+        let python = removal_command(&ProjectType::Generic, path).unwrap();
+        if cfg!(windows) {
+            assert_eq!(command[..5], ["cmd", "/C", "rmdir", "/s", "/q"]);
+        } else {
+            assert_eq!(command, ["rm", "-rf", "--", "node_modules"]);
+            assert_eq!(python, ["rm", "-rf", "--", "-pkg/__pycache__"]);
+        }
+
+        // Tools that clean up themselves need no command of ours
+        assert_eq!(removal_command(&ProjectType::Rust, path), None);
+        assert_eq!(removal_command(&ProjectType::Go, path), None);
+        // And nothing to delete leaves nothing to run
+        let empty = project(&["main.py"]);
+        assert_eq!(
+            removal_command(&ProjectType::Generic, empty.path().to_str().unwrap()),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn only_node_modules_needs_confirming() {
+        let dir = project(&["Cargo.toml", "target/debug/demo"]);
+        let path = dir.path().to_str().unwrap();
+        // Would prompt, and fail without a terminal, if it asked
+        assert!(confirm_destructive(&[(path, &ProjectType::Rust)]).unwrap());
+        assert!(confirm_destructive(&[(path, &ProjectType::JavaScript)]).unwrap());
+    }
+}
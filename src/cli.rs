@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::capture;
+use crate::completions::Shell;
+use crate::models::ProjectType;
+use crate::ui;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "hoist")]
@@ -20,10 +24,18 @@ pub struct Args {
     #[arg(long)]
     pub docker_path: Option<String>,
 
+    /// Saved run profile from .app-hoist.toml to use for `docker run`
+    #[arg(long, requires = "docker_path")]
+    pub docker_profile: Option<String>,
+
     /// Run operations on multiple projects in parallel
     #[arg(long)]
     pub multi_path: Option<Vec<String>>,
 
+    /// Write multi-project results to a JUnit XML file
+    #[arg(long, value_name = "FILE", requires = "multi_path")]
+    pub report_junit: Option<String>,
+
     /// Template and cache operations
     #[command(subcommand)]
     pub command: Option<AppCommand>,
@@ -31,6 +43,77 @@ pub struct Args {
     /// Dry run: show the command without executing
     #[arg(long)]
     pub dry_run: bool,
+
+    /// Choose an option or operation without prompting, as NAME or NAME=VALUE;
+    /// repeat for more, e.g. --select build --select add=serde
+    #[arg(long = "select", value_name = "NAME[=VALUE]")]
+    pub select: Vec<String>,
+
+    /// Set an environment variable for the hoisted tool or project command,
+    /// as KEY=VALUE; repeat for more, e.g. --env RUST_LOG=debug
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    pub env: Vec<String>,
+
+    /// Arguments for the program a project's run starts, split like a shell
+    /// would, e.g. --args "serve --port 8080"
+    #[arg(long = "args", value_name = "ARGS", allow_hyphen_values = true)]
+    pub program_args: Option<String>,
+
+    /// Don't preselect the options chosen the last time a tool was hoisted
+    #[arg(long)]
+    pub fresh: bool,
+
+    /// Print the parsed options (or project operations) as JSON and exit;
+    /// with -p, a subcommand may follow the tool, e.g. -p "git remote"
+    #[arg(long)]
+    pub list_options: bool,
+
+    /// Skip project detection and treat the project as this type; the
+    /// project's cache entry remembers it
+    #[arg(long, visible_alias = "project-type", value_enum, value_name = "TYPE")]
+    pub only_type: Option<ProjectType>,
+
+    /// Run this entry point instead of the detected one, e.g. --entry src/cli.py;
+    /// the project's cache entry remembers it
+    #[arg(long, value_name = "PATH")]
+    pub entry: Option<String>,
+
+    /// Check whether installed dependencies are stale relative to the lockfile
+    #[arg(long)]
+    pub version_check: bool,
+
+    /// Plain output: text labels instead of emoji, numbered menus, no spinners
+    #[arg(long)]
+    pub plain: bool,
+
+    /// How status glyphs are shown (default: emoji on UTF-8 terminals, ascii otherwise)
+    #[arg(long, value_enum)]
+    pub glyphs: Option<ui::GlyphMode>,
+
+    /// Disable colored output
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Capture output of executed commands and only show it when they fail
+    #[arg(long)]
+    pub quiet_child: bool,
+
+    /// Exit successfully even when the executed command fails, instead of
+    /// exiting with its exit code
+    #[arg(long)]
+    pub ignore_exit_code: bool,
+
+    /// Lines of captured output to show when a command fails in --quiet-child mode
+    #[arg(long, default_value_t = capture::DEFAULT_TAIL_LINES)]
+    pub tail_lines: usize,
+
+    /// Write progress events as JSON lines to this open file descriptor
+    #[arg(long, value_name = "FD", conflicts_with = "progress_file")]
+    pub progress_fd: Option<i32>,
+
+    /// Write progress events as JSON lines to this file
+    #[arg(long, value_name = "FILE")]
+    pub progress_file: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -41,6 +124,50 @@ pub enum AppCommand {
     /// Cache operations
     #[command(subcommand)]
     Cache(CacheCommand),
+    /// History operations
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Binaries installed by app-hoist
+    #[command(subcommand)]
+    Installed(InstalledCommand),
+    /// Shell completion scripts
+    #[command(subcommand)]
+    Completions(CompletionsCommand),
+    /// Commands saved by name to replay without prompting
+    #[command(subcommand)]
+    Alias(AliasCommand),
+    /// Show what project detection finds for a directory
+    Detect {
+        /// Project directory
+        #[arg(default_value = ".")]
+        path: String,
+        /// Show which marker files and rules led to the result
+        #[arg(long)]
+        explain: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// List every executable a name could resolve to, and which one runs
+    Which {
+        /// Program name or path
+        name: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Show version, repository, and supported capabilities
+    About {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand)]
@@ -68,6 +195,89 @@ pub enum TemplateCommand {
         /// Search query
         query: String,
     },
+    /// Check a template for common mistakes before sharing it
+    Lint {
+        /// Name of the template to lint
+        name: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryCommand {
+    /// List recently executed commands, numbered for `history rerun`
+    List {
+        /// How many commands to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Run a command from `history list` again, in the directory it ran in
+    Rerun {
+        /// Number from `history list` (1 is the most recent)
+        n: usize,
+        /// Run without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Print the command instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Drop old entries and corrupt lines from the history file
+    Prune {
+        /// Keep at most this many entries (overrides [history] max_entries)
+        #[arg(long)]
+        max_entries: Option<usize>,
+        /// Drop entries older than this many days (overrides [history] max_age_days)
+        #[arg(long)]
+        max_age_days: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum InstalledCommand {
+    /// List installed binaries and how they were installed
+    List,
+    /// Uninstall a binary the way it was installed
+    Remove {
+        /// Name of the installed binary
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasCommand {
+    /// Run a saved command
+    Run {
+        /// Alias name
+        name: String,
+        /// Print the command instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List saved commands
+    List,
+    /// Delete a saved command
+    Rm {
+        /// Alias name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CompletionsCommand {
+    /// Print the completion script for a shell
+    Generate {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Write the completion script where the shell loads it from
+    Install {
+        /// Shell to install for (defaults to $SHELL)
+        #[arg(value_enum)]
+        shell: Option<Shell>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -75,10 +285,17 @@ pub enum CacheCommand {
     /// Show cache statistics
     Stats,
     /// Clear all cached data
-    Clear,
+    Clear {
+        /// Only clear options cached from tools' help
+        #[arg(long)]
+        tools: bool,
+    },
     /// Clear cache for specific path
     Invalidate {
         /// Path to invalidate cache for
         path: String,
+        /// Treat the path as a tool name or executable and clear its cached options
+        #[arg(long)]
+        tool: bool,
     },
 }
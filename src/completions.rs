@@ -0,0 +1,648 @@
+use crate::cli::Args;
+use crate::ui;
+use clap::{CommandFactory, ValueEnum};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// The installed executable, which completions are registered for along with
+/// the `hoist` name the CLI calls itself.
+const BIN_NAME: &str = env!("CARGO_PKG_NAME");
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Zsh => write!(f, "zsh"),
+            Shell::Fish => write!(f, "fish"),
+        }
+    }
+}
+
+impl Shell {
+    /// The shell named by `$SHELL`, e.g. `/usr/bin/zsh`.
+    pub fn detect() -> Option<Self> {
+        let shell = std::env::var("SHELL").ok()?;
+        let name = Path::new(&shell).file_name()?.to_str()?;
+        Shell::from_str(name, true).ok()
+    }
+}
+
+/// A command's completions: its subcommands and flags, keyed by the
+/// subcommand words leading to it.
+struct Node {
+    path: Vec<String>,
+    subcommands: Vec<(String, String)>,
+    flags: Vec<Flag>,
+    /// Fixed values of positional arguments, e.g. a shell name
+    values: Vec<String>,
+}
+
+struct Flag {
+    long: String,
+    short: Option<char>,
+    help: String,
+    values: Vec<String>,
+}
+
+fn collect(command: &clap::Command, path: Vec<String>, nodes: &mut Vec<Node>) {
+    let about = |command: &clap::Command| {
+        command
+            .get_about()
+            .map(|about| about.to_string())
+            .unwrap_or_default()
+    };
+
+    let subcommands = command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| (sub.get_name().to_string(), about(sub)))
+        .collect();
+    let flags = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            Some(Flag {
+                long: arg.get_long()?.to_string(),
+                short: arg.get_short(),
+                help: arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
+                values: arg
+                    .get_possible_values()
+                    .iter()
+                    .map(|value| value.get_name().to_string())
+                    .collect(),
+            })
+        })
+        .collect();
+    let values = command
+        .get_positionals()
+        .flat_map(|arg| arg.get_possible_values())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    nodes.push(Node {
+        path: path.clone(),
+        subcommands,
+        flags,
+        values,
+    });
+
+    // clap's generated `help` subcommand mirrors the whole tree; offer it but don't descend
+    for sub in command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+    {
+        let mut sub_path = path.clone();
+        sub_path.push(sub.get_name().to_string());
+        collect(sub, sub_path, nodes);
+    }
+}
+
+/// The completion script for `shell`.
+pub fn generate(shell: Shell) -> String {
+    let mut command = Args::command();
+    command.build();
+    let mut nodes = Vec::new();
+    collect(&command, Vec::new(), &mut nodes);
+
+    match shell {
+        Shell::Bash => bash_script(&nodes, command.get_name()),
+        Shell::Zsh => zsh_script(&nodes, command.get_name()),
+        Shell::Fish => fish_script(&nodes, command.get_name()),
+    }
+}
+
+/// Words offered at a node: its subcommands, then its flags.
+fn words(node: &Node) -> String {
+    node.subcommands
+        .iter()
+        .map(|(name, _)| name.clone())
+        .chain(node.values.iter().cloned())
+        .chain(node.flags.iter().map(|flag| format!("--{}", flag.long)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Shell `case` arms that follow subcommand words into `cmdpath`, and offer
+/// each node's words and each flag's values. Shared by bash and zsh.
+fn case_arms(nodes: &[Node], offer: &str) -> (String, String, String) {
+    let mut walk = String::new();
+    for node in nodes {
+        for (name, _) in &node.subcommands {
+            let key = node
+                .path
+                .iter()
+                .chain([name])
+                .fold(String::new(), |acc, w| acc + " " + w);
+            let _ = writeln!(walk, "            \"{}\") cmdpath=\"{}\" ;;", key, key);
+        }
+    }
+
+    let mut words_arms = String::new();
+    for node in nodes {
+        let key: String = node.path.iter().map(|w| format!(" {}", w)).collect();
+        let _ = writeln!(
+            words_arms,
+            "        \"{}\") {} \"{}\" ;;",
+            key,
+            offer,
+            words(node)
+        );
+    }
+
+    let mut value_arms = String::new();
+    let mut seen = Vec::new();
+    for flag in nodes.iter().flat_map(|node| &node.flags) {
+        if !flag.values.is_empty() && !seen.contains(&flag.long) {
+            let _ = writeln!(
+                value_arms,
+                "        --{}) {} \"{}\"; return ;;",
+                flag.long,
+                offer,
+                flag.values.join(" ")
+            );
+            seen.push(flag.long.clone());
+        }
+    }
+
+    (walk, words_arms, value_arms)
+}
+
+fn bash_script(nodes: &[Node], name: &str) -> String {
+    let (walk, words_arms, value_arms) = case_arms(nodes, "_app_hoist_offer");
+    format!(
+        r#"# bash completion for {bin}
+_app_hoist_offer() {{
+    COMPREPLY=($(compgen -W "$1" -- "${{COMP_WORDS[COMP_CWORD]}}"))
+}}
+
+_app_hoist() {{
+    local cmdpath="" word
+    for word in "${{COMP_WORDS[@]:1:COMP_CWORD-1}}"; do
+        case "$cmdpath $word" in
+{walk}        esac
+    done
+
+    case "${{COMP_WORDS[COMP_CWORD-1]}}" in
+{value_arms}    esac
+
+    case "$cmdpath" in
+{words_arms}    esac
+}}
+
+complete -o default -F _app_hoist {bin} {name}
+"#,
+        bin = BIN_NAME,
+        name = name,
+        walk = walk,
+        value_arms = value_arms,
+        words_arms = words_arms,
+    )
+}
+
+fn zsh_script(nodes: &[Node], name: &str) -> String {
+    let (walk, words_arms, value_arms) = case_arms(nodes, "_app_hoist_offer");
+    format!(
+        r#"#compdef {bin} {name}
+
+_app_hoist_offer() {{
+    compadd -- ${{=1}}
+}}
+
+_app_hoist() {{
+    local cmdpath="" word
+    for word in ${{words[2,CURRENT-1]}}; do
+        case "$cmdpath $word" in
+{walk}        esac
+    done
+
+    case "${{words[CURRENT-1]}}" in
+{value_arms}    esac
+
+    case "$cmdpath" in
+{words_arms}    esac
+    _files
+}}
+
+_app_hoist "$@"
+"#,
+        bin = BIN_NAME,
+        name = name,
+        walk = walk,
+        value_arms = value_arms,
+        words_arms = words_arms,
+    )
+}
+
+fn fish_script(nodes: &[Node], name: &str) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let mut script = format!("# fish completion for {}\n", BIN_NAME);
+
+    for node in nodes {
+        // A node applies once its own subcommands are typed and none of its children are
+        let mut conditions: Vec<String> = node
+            .path
+            .iter()
+            .map(|word| format!("__fish_seen_subcommand_from {}", word))
+            .collect();
+        let children: Vec<&str> = node.subcommands.iter().map(|(n, _)| n.as_str()).collect();
+        if !children.is_empty() {
+            conditions.push(format!(
+                "not __fish_seen_subcommand_from {}",
+                children.join(" ")
+            ));
+        }
+        let condition = if node.path.is_empty() && children.is_empty() {
+            String::new()
+        } else if node.path.is_empty() {
+            " -n '__fish_use_subcommand'".to_string()
+        } else {
+            format!(" -n {}", quote(&conditions.join("; and ")))
+        };
+
+        for (sub, about) in &node.subcommands {
+            let _ = writeln!(
+                script,
+                "complete -c {} -f{} -a {} -d {}",
+                BIN_NAME,
+                condition,
+                sub,
+                quote(about)
+            );
+        }
+
+        // Flags stay available after a subcommand at the top level
+        let flag_condition = if node.path.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " -n {}",
+                quote(&conditions[..node.path.len()].join("; and "))
+            )
+        };
+        if !node.values.is_empty() {
+            let _ = writeln!(
+                script,
+                "complete -c {} -f{} -a {}",
+                BIN_NAME,
+                flag_condition,
+                quote(&node.values.join(" "))
+            );
+        }
+        for flag in &node.flags {
+            let short = flag.short.map(|s| format!(" -s {}", s)).unwrap_or_default();
+            let values = if flag.values.is_empty() {
+                String::new()
+            } else {
+                format!(" -x -a {}", quote(&flag.values.join(" ")))
+            };
+            let _ = writeln!(
+                script,
+                "complete -c {}{} -l {}{}{} -d {}",
+                BIN_NAME,
+                flag_condition,
+                flag.long,
+                short,
+                values,
+                quote(&flag.help)
+            );
+        }
+    }
+
+    let _ = writeln!(script, "complete -c {} -w {}", name, BIN_NAME);
+    script
+}
+
+/// The directories completions and rc files are found under: the user's
+/// home, and the XDG and `ZDOTDIR` overrides of places within it.
+pub struct Home {
+    pub dir: PathBuf,
+    pub data: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub zdotdir: Option<PathBuf>,
+}
+
+impl Home {
+    fn from_env() -> anyhow::Result<Self> {
+        let dir =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+        Ok(Self {
+            dir,
+            data: std::env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+            config: std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            zdotdir: std::env::var_os("ZDOTDIR").map(PathBuf::from),
+        })
+    }
+}
+
+/// Where a user's completions for `shell` are loaded from: bash-completion's
+/// user directory, `~/.zfunc` for zsh, and fish's completions directory.
+pub fn install_path(shell: Shell, home: &Home) -> PathBuf {
+    match shell {
+        Shell::Bash => home
+            .data
+            .clone()
+            .unwrap_or_else(|| home.dir.join(".local").join("share"))
+            .join("bash-completion")
+            .join("completions")
+            .join(BIN_NAME),
+        Shell::Zsh => home.dir.join(".zfunc").join(format!("_{}", BIN_NAME)),
+        Shell::Fish => home
+            .config
+            .clone()
+            .unwrap_or_else(|| home.dir.join(".config"))
+            .join("fish")
+            .join("completions")
+            .join(format!("{}.fish", BIN_NAME)),
+    }
+}
+
+/// Lines the shell's rc file needs before it loads completions from
+/// `install_path`, and which rc file that is. Bash and fish load those
+/// directories on their own, as long as bash-completion is installed.
+fn rc_lines(
+    shell: Shell,
+    home: &Home,
+    completion: &Path,
+    bash_completion: bool,
+) -> Option<(PathBuf, Vec<String>)> {
+    match shell {
+        Shell::Zsh => Some((
+            home.zdotdir.as_ref().unwrap_or(&home.dir).join(".zshrc"),
+            vec![
+                "fpath=(~/.zfunc $fpath)".to_string(),
+                "autoload -Uz compinit && compinit".to_string(),
+            ],
+        )),
+        Shell::Bash if !bash_completion => Some((
+            home.dir.join(".bashrc"),
+            vec![format!("source {}", completion.display())],
+        )),
+        Shell::Bash | Shell::Fish => None,
+    }
+}
+fn bash_completion_installed() -> bool {
+    [
+        "/usr/share/bash-completion/bash_completion",
+        "/usr/local/share/bash-completion/bash_completion",
+        "/opt/homebrew/etc/profile.d/bash_completion.sh",
+    ]
+    .iter()
+    .any(|path| Path::new(path).exists())
+}
+
+/// Append `line` to `rc` unless a line of it already reads the same.
+/// Returns whether it was added.
+pub fn append_line_once(rc: &Path, line: &str) -> anyhow::Result<bool> {
+    let existing = match std::fs::read_to_string(rc) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if existing.lines().any(|l| l.trim() == line) {
+        return Ok(false);
+    }
+
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(rc)?;
+    std::io::Write::write_all(&mut file, format!("{}{}\n", separator, line).as_bytes())?;
+    Ok(true)
+}
+
+/// Write the completion script where `shell` loads it from, then add any
+/// rc-file lines it still needs once the user agrees.
+pub fn install(shell: Option<Shell>) -> anyhow::Result<()> {
+    let shell = match shell.or_else(Shell::detect) {
+        Some(shell) => shell,
+        None => anyhow::bail!(
+            "Could not tell the shell from $SHELL; pass one: {} completions install bash|zsh|fish",
+            BIN_NAME
+        ),
+    };
+    install_in(
+        shell,
+        &Home::from_env()?,
+        bash_completion_installed(),
+        |rc| ui::confirm(&format!("Append to {}?", rc.display()), true),
+    )
+}
+
+/// `install` into `home`, asking `confirm` before changing an rc file.
+fn install_in(
+    shell: Shell,
+    home: &Home,
+    bash_completion: bool,
+    confirm: impl FnOnce(&Path) -> anyhow::Result<bool>,
+) -> anyhow::Result<()> {
+    let path = install_path(shell, home);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, generate(shell))?;
+    ui::status(&format!(
+        "✅ Wrote {} completions to {}",
+        shell,
+        path.display()
+    ));
+
+    let Some((rc, lines)) = rc_lines(shell, home, &path, bash_completion) else {
+        println!("Open a new shell to use them.");
+        return Ok(());
+    };
+
+    let rc_content = std::fs::read_to_string(&rc).unwrap_or_default();
+    let missing: Vec<&String> = lines
+        .iter()
+        .filter(|line| !rc_content.lines().any(|l| l.trim() == line.as_str()))
+        .collect();
+    if missing.is_empty() {
+        println!(
+            "{} is already set up. Open a new shell to use them.",
+            rc.display()
+        );
+        return Ok(());
+    }
+
+    println!("{} also needs:", rc.display());
+    for line in &missing {
+        println!("  {}", line);
+    }
+    // compinit has to run after fpath is extended, so an existing call is left to the user
+    if shell == Shell::Zsh && rc_content.contains("compinit") {
+        println!("Add the fpath line above the existing compinit call.");
+        return Ok(());
+    }
+    if confirm(&rc)? {
+        for line in missing {
+            append_line_once(&rc, line)?;
+        }
+        ui::status(&format!(
+            "✅ Updated {}. Open a new shell to use them.",
+            rc.display()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home(dir: &Path) -> Home {
+        Home {
+            dir: dir.to_path_buf(),
+            data: None,
+            config: None,
+            zdotdir: None,
+        }
+    }
+
+    fn agree(_: &Path) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
+    #[test]
+    fn each_shell_installs_where_it_loads_completions_from() {
+        let home = home(Path::new("/home/dev"));
+        assert_eq!(
+            install_path(Shell::Bash, &home),
+            Path::new("/home/dev/.local/share/bash-completion/completions").join(BIN_NAME)
+        );
+        assert_eq!(
+            install_path(Shell::Zsh, &home),
+            Path::new("/home/dev/.zfunc").join(format!("_{}", BIN_NAME))
+        );
+        assert_eq!(
+            install_path(Shell::Fish, &home),
+            Path::new("/home/dev/.config/fish/completions").join(format!("{}.fish", BIN_NAME))
+        );
+    }
+
+    #[test]
+    fn xdg_directories_override_the_home_defaults() {
+        let home = Home {
+            data: Some(PathBuf::from("/data")),
+            config: Some(PathBuf::from("/config")),
+            ..home(Path::new("/home/dev"))
+        };
+        assert!(install_path(Shell::Bash, &home).starts_with("/data/bash-completion"));
+        assert!(install_path(Shell::Fish, &home).starts_with("/config/fish"));
+        // zsh has no XDG location of its own
+        assert!(install_path(Shell::Zsh, &home).starts_with("/home/dev/.zfunc"));
+    }
+
+    #[test]
+    fn zsh_setup_goes_in_zdotdir_when_set() {
+        let completion = Path::new("/c");
+        let (rc, _) =
+            rc_lines(Shell::Zsh, &home(Path::new("/home/dev")), completion, true).unwrap();
+        assert_eq!(rc, Path::new("/home/dev/.zshrc"));
+
+        let home = Home {
+            zdotdir: Some(PathBuf::from("/home/dev/.config/zsh")),
+            ..home(Path::new("/home/dev"))
+        };
+        let (rc, _) = rc_lines(Shell::Zsh, &home, completion, true).unwrap();
+        assert_eq!(rc, Path::new("/home/dev/.config/zsh/.zshrc"));
+    }
+
+    #[test]
+    fn bash_only_needs_its_rc_without_bash_completion() {
+        let home = home(Path::new("/home/dev"));
+        let completion = Path::new("/home/dev/completion");
+        assert_eq!(rc_lines(Shell::Bash, &home, completion, true), None);
+        assert_eq!(
+            rc_lines(Shell::Bash, &home, completion, false),
+            Some((
+                PathBuf::from("/home/dev/.bashrc"),
+                vec!["source /home/dev/completion".to_string()]
+            ))
+        );
+        assert_eq!(rc_lines(Shell::Fish, &home, completion, false), None);
+    }
+
+    #[test]
+    fn installing_twice_sets_up_the_rc_file_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = home(dir.path());
+        let zshrc = dir.path().join(".zshrc");
+        std::fs::write(&zshrc, "export EDITOR=vi").unwrap();
+
+        install_in(Shell::Zsh, &home, true, agree).unwrap();
+        install_in(Shell::Zsh, &home, true, |_| panic!("nothing left to ask")).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&zshrc).unwrap(),
+            "export EDITOR=vi\nfpath=(~/.zfunc $fpath)\nautoload -Uz compinit && compinit\n"
+        );
+        let script = std::fs::read_to_string(install_path(Shell::Zsh, &home)).unwrap();
+        assert_eq!(script, generate(Shell::Zsh));
+    }
+
+    #[test]
+    fn declining_leaves_the_rc_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = home(dir.path());
+
+        install_in(Shell::Bash, &home, false, |_| Ok(false)).unwrap();
+
+        assert!(install_path(Shell::Bash, &home).exists());
+        assert!(!dir.path().join(".bashrc").exists());
+    }
+
+    #[test]
+    fn an_existing_compinit_call_is_left_to_the_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = home(dir.path());
+        let zshrc = dir.path().join(".zshrc");
+        std::fs::write(&zshrc, "autoload -U compinit; compinit\n").unwrap();
+
+        install_in(Shell::Zsh, &home, true, |_| panic!("should not ask")).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&zshrc).unwrap(),
+            "autoload -U compinit; compinit\n"
+        );
+    }
+
+    #[test]
+    fn shells_that_load_completions_themselves_leave_rc_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = home(dir.path());
+
+        install_in(Shell::Fish, &home, false, |_| panic!("should not ask")).unwrap();
+        install_in(Shell::Bash, &home, true, |_| panic!("should not ask")).unwrap();
+
+        assert!(install_path(Shell::Fish, &home).exists());
+        assert!(install_path(Shell::Bash, &home).exists());
+        assert!(!dir.path().join(".bashrc").exists());
+    }
+
+    #[test]
+    fn a_line_already_present_is_not_appended_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc = dir.path().join(".bashrc");
+        std::fs::write(&rc, "  source ~/completion  \n").unwrap();
+
+        assert!(!append_line_once(&rc, "source ~/completion").unwrap());
+        assert!(append_line_once(&rc, "alias h=hoist").unwrap());
+        assert!(!append_line_once(&rc, "alias h=hoist").unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&rc).unwrap(),
+            "  source ~/completion  \nalias h=hoist\n"
+        );
+    }
+}
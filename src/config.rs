@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+/// `~/.app-hoist/config.toml`, for settings that apply to every project.
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".app-hoist").join("config.toml"))
+}
+
+pub fn load_user_config() -> anyhow::Result<Option<toml::Value>> {
+    let Some(path) = user_config_path().filter(|path| path.exists()) else {
+        return Ok(None);
+    };
+
+    let content = std::fs::read_to_string(&path)?;
+    let value = toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(Some(value))
+}
+
+/// A boolean `[table] key = ...` from the user config, or `default` when unset.
+pub fn user_flag(table: &str, key: &str, default: bool) -> anyhow::Result<bool> {
+    Ok(load_user_config()?
+        .and_then(|config| config.get(table)?.get(key)?.as_bool())
+        .unwrap_or(default))
+}
+
+/// A non-negative integer `[table] key = ...` from the user config, if set.
+pub fn user_integer(table: &str, key: &str) -> anyhow::Result<Option<u64>> {
+    let Some(value) = load_user_config()?.and_then(|config| config.get(table)?.get(key).cloned())
+    else {
+        return Ok(None);
+    };
+
+    match value.as_integer() {
+        Some(n) if n >= 0 => Ok(Some(n as u64)),
+        _ => anyhow::bail!("[{}] {} must be a non-negative integer", table, key),
+    }
+}
+
+/// A list of strings `[table] key = [...]` from the user config, empty when unset.
+pub fn user_strings(table: &str, key: &str) -> anyhow::Result<Vec<String>> {
+    let Some(value) = load_user_config()?.and_then(|config| config.get(table)?.get(key).cloned())
+    else {
+        return Ok(Vec::new());
+    };
+
+    value
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| anyhow::anyhow!("[{}] {} must be a list of strings", table, key))
+}
+
+/// A string `[table] key = ...` from the user config, if set.
+pub fn user_string(table: &str, key: &str) -> anyhow::Result<Option<String>> {
+    Ok(load_user_config()?
+        .and_then(|config| config.get(table)?.get(key)?.as_str().map(str::to_string)))
+}
@@ -0,0 +1,840 @@
+use crate::cache::{CacheManager, ProjectCache};
+use crate::cli::OutputFormat;
+use crate::config;
+use crate::models::ProjectType;
+use crate::project::{
+    DENO_CONFIGS, JUSTFILES, MAKEFILES, VENV_DIRS, detect_entry_point_traced,
+    detect_package_manager_traced, detect_project_type_traced, venv_marker,
+};
+use crate::ui;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// One piece of evidence considered while detecting a project.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TraceStep {
+    /// A marker file that was looked for
+    File { file: String, found: bool },
+    /// A detection rule, in the order rules were evaluated
+    Rule {
+        rule: String,
+        matched: bool,
+        reason: String,
+    },
+}
+
+/// The steps a detection function took to reach its answer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DetectionTrace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl DetectionTrace {
+    /// Record whether `file` exists under `root`, and return it.
+    pub fn check(&mut self, root: &Path, file: &str) -> bool {
+        let found = root.join(file).exists();
+        self.steps.push(TraceStep::File {
+            file: file.to_string(),
+            found,
+        });
+        found
+    }
+
+    /// Whether `file` was found, or `None` if it wasn't looked for.
+    pub fn found(&self, file: &str) -> Option<bool> {
+        self.steps.iter().find_map(|step| match step {
+            TraceStep::File {
+                file: checked,
+                found,
+            } if checked == file => Some(*found),
+            _ => None,
+        })
+    }
+
+    pub fn rule(&mut self, rule: &str, matched: bool, reason: &str) -> bool {
+        self.steps.push(TraceStep::Rule {
+            rule: rule.to_string(),
+            matched,
+            reason: reason.to_string(),
+        });
+        matched
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                TraceStep::File { file, found: true } => format!("    ✅ {} found", file),
+                TraceStep::File { file, found: false } => format!("    ➖ {} absent", file),
+                TraceStep::Rule {
+                    rule,
+                    matched: true,
+                    reason,
+                } => format!("    🎯 rule '{}' matched: {}", rule, reason),
+                TraceStep::Rule {
+                    rule,
+                    matched: false,
+                    reason,
+                } => format!("    ⏭️  rule '{}' skipped: {}", rule, reason),
+            })
+            .collect()
+    }
+}
+
+/// A named detection rule. Probes run in priority order and the first that
+/// recognizes the directory decides its type.
+pub struct Probe {
+    pub name: &'static str,
+    detect: fn(&Path, &mut DetectionTrace) -> Option<ProjectType>,
+}
+
+pub const PROBES: &[Probe] = &[
+    Probe {
+        name: "uv",
+        detect: probe_uv,
+    },
+    Probe {
+        name: "poetry",
+        detect: probe_poetry,
+    },
+    Probe {
+        name: "venv",
+        detect: probe_venv,
+    },
+    Probe {
+        name: "go",
+        detect: |root, trace| marker(root, trace, "go", "go.mod", ProjectType::Go),
+    },
+    Probe {
+        name: "rust",
+        detect: |root, trace| marker(root, trace, "rust", "Cargo.toml", ProjectType::Rust),
+    },
+    Probe {
+        name: "javascript",
+        detect: probe_javascript,
+    },
+    Probe {
+        name: "deno",
+        detect: probe_deno,
+    },
+    Probe {
+        name: "just",
+        detect: probe_just,
+    },
+    Probe {
+        name: "make",
+        detect: probe_make,
+    },
+    Probe {
+        name: "generic-python",
+        detect: probe_generic_python,
+    },
+];
+
+struct Settings {
+    only_type: Option<ProjectType>,
+    entry: Option<String>,
+    disabled: Vec<String>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Apply `--only-type`, `--entry` and the `[detect] disabled` list from the
+/// user config.
+pub fn init(only_type: Option<ProjectType>, entry: Option<String>) -> anyhow::Result<()> {
+    let disabled = config::user_strings("detect", "disabled")?;
+    if let Some(unknown) = disabled
+        .iter()
+        .find(|name| !PROBES.iter().any(|probe| probe.name == name.as_str()))
+    {
+        anyhow::bail!(
+            "Unknown detector '{}' in [detect] disabled; expected one of: {}",
+            unknown,
+            PROBES
+                .iter()
+                .map(|probe| probe.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let _ = SETTINGS.set(Settings {
+        only_type,
+        entry,
+        disabled,
+    });
+    Ok(())
+}
+
+/// The type given with `--only-type`, which skips detection entirely.
+pub fn only_type() -> Option<ProjectType> {
+    SETTINGS.get()?.only_type.clone()
+}
+
+/// The entry point given with `--entry`, used instead of the detected one.
+pub fn entry_override() -> Option<String> {
+    SETTINGS.get()?.entry.clone()
+}
+
+/// A primary ecosystem: the marker files that mean a project uses it, the
+/// probes that tell which of its types it is, and those types.
+struct Ecosystem {
+    markers: &'static [&'static str],
+    probes: &'static [&'static str],
+    types: &'static [ProjectType],
+}
+
+/// In priority order. A virtualenv, a justfile, a Makefile or loose Python
+/// files sit alongside any language, so they aren't among them.
+const ECOSYSTEMS: &[Ecosystem] = &[
+    Ecosystem {
+        markers: &["pyproject.toml", "poetry.lock"],
+        probes: &["uv", "poetry"],
+        types: &[ProjectType::Uv, ProjectType::Poetry],
+    },
+    Ecosystem {
+        markers: &["go.mod"],
+        probes: &["go"],
+        types: &[ProjectType::Go],
+    },
+    Ecosystem {
+        markers: &["Cargo.toml"],
+        probes: &["rust"],
+        types: &[ProjectType::Rust],
+    },
+    Ecosystem {
+        markers: &["package.json", "deno.json", "deno.jsonc"],
+        probes: &["javascript", "deno"],
+        types: &[
+            ProjectType::JavaScript,
+            ProjectType::TypeScript,
+            ProjectType::Deno,
+        ],
+    },
+];
+
+/// One type for each primary ecosystem the directory is set up for, in
+/// priority order, e.g. Rust and JavaScript for a crate with a package.json
+/// for its docs tooling. `detected` stands for its own ecosystem. Markers
+/// `trace` already looked for aren't checked again, and another ecosystem's
+/// probes only run when one of its markers is present.
+pub fn competing_types(
+    root: &Path,
+    detected: &ProjectType,
+    trace: &DetectionTrace,
+) -> Vec<ProjectType> {
+    let disabled = SETTINGS.get().map(|s| s.disabled.as_slice()).unwrap_or(&[]);
+
+    let mut types = Vec::new();
+    for ecosystem in ECOSYSTEMS {
+        if ecosystem.types.contains(detected) {
+            types.push(detected.clone());
+            continue;
+        }
+        let present = ecosystem.markers.iter().any(|marker| {
+            trace
+                .found(marker)
+                .unwrap_or_else(|| root.join(marker).exists())
+        });
+        if !present {
+            continue;
+        }
+        if let Some(project_type) = PROBES
+            .iter()
+            .filter(|probe| {
+                ecosystem.probes.contains(&probe.name)
+                    && !disabled.iter().any(|name| name == probe.name)
+            })
+            .find_map(|probe| (probe.detect)(root, &mut DetectionTrace::default()))
+        {
+            types.push(project_type);
+        }
+    }
+    types
+}
+
+/// Run the enabled probes in order, falling back to a generic project.
+pub fn detect_type(root: &Path, trace: &mut DetectionTrace) -> anyhow::Result<ProjectType> {
+    detect_type_with(root, trace, SETTINGS.get())
+}
+
+/// `detect_type` with the given `--only-type` and `[detect] disabled`.
+fn detect_type_with(
+    root: &Path,
+    trace: &mut DetectionTrace,
+    settings: Option<&Settings>,
+) -> anyhow::Result<ProjectType> {
+    if let Some(only_type) = settings.and_then(|s| s.only_type.clone()) {
+        trace.rule(
+            "only-type",
+            true,
+            &format!("--only-type {}", only_type.key()),
+        );
+        return Ok(only_type);
+    }
+
+    let disabled = settings.map(|s| s.disabled.as_slice()).unwrap_or(&[]);
+    for probe in PROBES {
+        if disabled.iter().any(|name| name == probe.name) {
+            trace.rule(probe.name, false, "disabled in [detect] config");
+            continue;
+        }
+        if let Some(project_type) = (probe.detect)(root, trace) {
+            return Ok(project_type);
+        }
+    }
+
+    trace.rule("generic", true, "no other rule matched");
+    Ok(ProjectType::Generic)
+}
+
+/// A probe that matches when a single marker file exists.
+fn marker(
+    root: &Path,
+    trace: &mut DetectionTrace,
+    rule: &str,
+    file: &str,
+    project_type: ProjectType,
+) -> Option<ProjectType> {
+    let found = trace.check(root, file);
+    let reason = if found {
+        format!("{} is present", file)
+    } else {
+        format!("no {}", file)
+    };
+    trace.rule(rule, found, &reason).then_some(project_type)
+}
+
+fn probe_uv(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    if !trace.check(root, "pyproject.toml") {
+        trace.rule("uv", false, "no pyproject.toml");
+        return None;
+    }
+
+    // uv.lock is a stat; reading pyproject.toml is only needed without it
+    let matched = if trace.check(root, "uv.lock") {
+        trace.rule("uv", true, "uv.lock is present")
+    } else {
+        let has_uv_section = pyproject_has(root, "[tool.uv]");
+        let reason = if has_uv_section {
+            "pyproject.toml has a [tool.uv] section"
+        } else {
+            "pyproject.toml has no [tool.uv] section and there is no uv.lock"
+        };
+        trace.rule("uv", has_uv_section, reason)
+    };
+    matched.then_some(ProjectType::Uv)
+}
+
+fn probe_venv(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let marker = VENV_DIRS
+        .iter()
+        .map(|dir| venv_marker(dir))
+        .find(|marker| trace.check(root, marker));
+    let reason = match &marker {
+        Some(marker) => format!("{} is present", marker),
+        None => "no venv at the root, in .venv or in venv".to_string(),
+    };
+    trace
+        .rule("venv", marker.is_some(), &reason)
+        .then_some(ProjectType::Venv)
+}
+
+fn probe_poetry(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let matched = if trace.check(root, "poetry.lock") {
+        trace.rule("poetry", true, "poetry.lock is present")
+    } else if trace.check(root, "pyproject.toml") {
+        let has_poetry_section = pyproject_has(root, "[tool.poetry]");
+        let reason = if has_poetry_section {
+            "pyproject.toml has a [tool.poetry] section"
+        } else {
+            "pyproject.toml has no [tool.poetry] section and there is no poetry.lock"
+        };
+        trace.rule("poetry", has_poetry_section, reason)
+    } else {
+        trace.rule("poetry", false, "no pyproject.toml or poetry.lock")
+    };
+    matched.then_some(ProjectType::Poetry)
+}
+
+/// Whether pyproject.toml configures both uv and Poetry, which detection
+/// can't settle on its own.
+pub fn claims_uv_and_poetry(root: &Path) -> bool {
+    pyproject_has(root, "[tool.uv]") && pyproject_has(root, "[tool.poetry]")
+}
+
+fn pyproject_has(root: &Path, section: &str) -> bool {
+    std::fs::read_to_string(root.join("pyproject.toml"))
+        .map(|content| content.contains(section))
+        .unwrap_or(false)
+}
+
+fn probe_javascript(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    if !trace.check(root, "package.json") {
+        trace.rule("javascript", false, "no package.json");
+        return None;
+    }
+
+    if trace.check(root, "tsconfig.json") {
+        trace.rule("typescript", true, "package.json with tsconfig.json");
+        Some(ProjectType::TypeScript)
+    } else {
+        trace.rule("javascript", true, "package.json without tsconfig.json");
+        Some(ProjectType::JavaScript)
+    }
+}
+
+/// Deno projects with a package.json are left to the JavaScript probe before this one.
+fn probe_deno(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let file = DENO_CONFIGS.iter().find(|file| trace.check(root, file));
+    let reason = match file {
+        Some(file) => format!("{} without package.json", file),
+        None => "no deno.json or deno.jsonc".to_string(),
+    };
+    trace
+        .rule("deno", file.is_some(), &reason)
+        .then_some(ProjectType::Deno)
+}
+
+fn probe_just(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let file = JUSTFILES.iter().find(|file| trace.check(root, file));
+    let reason = match file {
+        Some(file) => format!("{} is present", file),
+        None => "no justfile".to_string(),
+    };
+    trace
+        .rule("just", file.is_some(), &reason)
+        .then_some(ProjectType::Just)
+}
+
+fn probe_make(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let file = MAKEFILES.iter().find(|file| trace.check(root, file));
+    let reason = match file {
+        Some(file) => format!("{} is present", file),
+        None => "no GNUmakefile, makefile or Makefile".to_string(),
+    };
+    trace
+        .rule("make", file.is_some(), &reason)
+        .then_some(ProjectType::Make)
+}
+
+fn probe_generic_python(root: &Path, trace: &mut DetectionTrace) -> Option<ProjectType> {
+    let file = ["pyproject.toml", "setup.py", "requirements.txt"]
+        .into_iter()
+        .find(|file| trace.check(root, file));
+    let reason = match file {
+        Some(file) => format!("{} without uv", file),
+        None => "no pyproject.toml, setup.py or requirements.txt".to_string(),
+    };
+    trace
+        .rule("generic-python", file.is_some(), &reason)
+        .then_some(ProjectType::Generic)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheInfo {
+    pub project_type: ProjectType,
+    pub entry_point: String,
+    pub age_seconds: u64,
+    pub valid: bool,
+}
+
+/// Everything detection found for a path, and optionally how it got there.
+#[derive(Debug, Serialize)]
+pub struct DetectionReport {
+    pub path: String,
+    pub project_type: ProjectType,
+    pub entry_point: String,
+    pub package_manager: Option<String>,
+    pub cache: Option<CacheInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<DetectionTraces>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectionTraces {
+    pub project_type: DetectionTrace,
+    pub entry_point: DetectionTrace,
+    pub package_manager: DetectionTrace,
+}
+
+pub fn detect(path: &str, explain: bool) -> anyhow::Result<DetectionReport> {
+    let mut type_trace = DetectionTrace::default();
+    let mut entry_trace = DetectionTrace::default();
+    let mut pm_trace = DetectionTrace::default();
+
+    let project_type = detect_project_type_traced(path, &mut type_trace)?;
+    let entry_point = detect_entry_point_traced(path, &mut entry_trace)?;
+    let package_manager = match project_type {
+        ProjectType::JavaScript | ProjectType::TypeScript => {
+            Some(detect_package_manager_traced(path, &mut pm_trace))
+        }
+        _ => None,
+    };
+
+    let cache = CacheManager::new()?
+        .peek(path)
+        .map(|cached: ProjectCache| CacheInfo {
+            age_seconds: cached.age_seconds(),
+            valid: cached.is_valid(CacheManager::DEFAULT_MAX_AGE_SECONDS),
+            project_type: cached.project_type,
+            entry_point: cached.entry_point,
+        });
+
+    Ok(DetectionReport {
+        path: path.to_string(),
+        project_type,
+        entry_point,
+        package_manager,
+        cache,
+        trace: explain.then_some(DetectionTraces {
+            project_type: type_trace,
+            entry_point: entry_trace,
+            package_manager: pm_trace,
+        }),
+    })
+}
+
+pub fn print_report(report: &DetectionReport, format: OutputFormat) -> anyhow::Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    ui::status(&format!("📁 {}", report.path));
+    println!("Project type:    {}", report.project_type);
+    if report.entry_point.is_empty() {
+        println!("Entry point:     none found");
+    } else {
+        println!("Entry point:     {}", report.entry_point);
+    }
+    if let Some(pm) = &report.package_manager {
+        println!("Package manager: {}", pm);
+    }
+
+    match &report.cache {
+        Some(cache) => println!(
+            "Cache entry:     {} / {}, {}s old ({})",
+            cache.project_type,
+            cache.entry_point,
+            cache.age_seconds,
+            if cache.valid { "in use" } else { "expired" }
+        ),
+        None => println!("Cache entry:     none"),
+    }
+
+    if let Some(trace) = &report.trace {
+        for (title, steps) in [
+            ("Project type", &trace.project_type),
+            ("Entry point", &trace.entry_point),
+            ("Package manager", &trace.package_manager),
+        ] {
+            if steps.steps.is_empty() {
+                continue;
+            }
+            println!();
+            println!("{} (checked in priority order):", title);
+            for line in steps.render() {
+                ui::status(&line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn competing(dir: &tempfile::TempDir) -> Vec<ProjectType> {
+        let mut trace = DetectionTrace::default();
+        let detected = detect_type(dir.path(), &mut trace).unwrap();
+        competing_types(dir.path(), &detected, &trace)
+    }
+
+    #[test]
+    fn two_primary_ecosystems_compete() {
+        let dir = fixture(&["Cargo.toml", "package.json"]);
+        assert_eq!(
+            competing(&dir),
+            [ProjectType::Rust, ProjectType::JavaScript]
+        );
+        let dir = fixture(&["go.mod", "poetry.lock"]);
+        assert_eq!(competing(&dir), [ProjectType::Poetry, ProjectType::Go]);
+    }
+
+    #[test]
+    fn a_venv_or_a_makefile_next_to_a_language_doesnt_compete() {
+        let dir = fixture(&["Cargo.toml", &venv_marker(".venv"), "Makefile"]);
+        assert_eq!(competing(&dir), [ProjectType::Rust]);
+        // A pyproject.toml for neither uv nor Poetry isn't a project of its own
+        let dir = fixture(&["go.mod", "pyproject.toml", "requirements.txt"]);
+        assert_eq!(competing(&dir), [ProjectType::Go]);
+    }
+
+    #[test]
+    fn markers_the_trace_looked_for_are_not_checked_again() {
+        let dir = fixture(&["Cargo.toml", "package.json"]);
+        let mut trace = DetectionTrace::default();
+        trace.steps.push(TraceStep::File {
+            file: "package.json".to_string(),
+            found: false,
+        });
+        assert_eq!(
+            competing_types(dir.path(), &ProjectType::Rust, &trace),
+            [ProjectType::Rust]
+        );
+    }
+
+    fn settings(only_type: Option<ProjectType>, disabled: &[&str]) -> Settings {
+        Settings {
+            only_type,
+            entry: None,
+            disabled: disabled.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    fn rules(trace: &DetectionTrace) -> Vec<(&str, bool, &str)> {
+        trace
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                TraceStep::Rule {
+                    rule,
+                    matched,
+                    reason,
+                } => Some((rule.as_str(), *matched, reason.as_str())),
+                TraceStep::File { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disabled_probes_are_skipped() {
+        let dir = fixture(&["go.mod", "Makefile"]);
+        let mut trace = DetectionTrace::default();
+        let detected =
+            detect_type_with(dir.path(), &mut trace, Some(&settings(None, &["go"]))).unwrap();
+        assert_eq!(detected, ProjectType::Make);
+        assert!(rules(&trace).contains(&("go", false, "disabled in [detect] config")));
+        // A disabled probe doesn't look for its marker
+        assert_eq!(trace.found("go.mod"), None);
+    }
+
+    #[test]
+    fn only_type_bypasses_every_probe() {
+        let dir = fixture(&["Cargo.toml", "package.json"]);
+        let mut trace = DetectionTrace::default();
+        let detected = detect_type_with(
+            dir.path(),
+            &mut trace,
+            Some(&settings(Some(ProjectType::Go), &[])),
+        )
+        .unwrap();
+        assert_eq!(detected, ProjectType::Go);
+        assert_eq!(rules(&trace), [("only-type", true, "--only-type go")]);
+        assert_eq!(trace.steps.len(), 1);
+    }
+
+    #[test]
+    fn pyproject_is_read_only_without_a_uv_lock() {
+        // uv.lock settles it without looking inside pyproject.toml
+        let dir = fixture(&["uv.lock", "pyproject.toml"]);
+        let mut trace = DetectionTrace::default();
+        assert_eq!(
+            detect_type_with(dir.path(), &mut trace, None).unwrap(),
+            ProjectType::Uv
+        );
+        assert_eq!(rules(&trace), [("uv", true, "uv.lock is present")]);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("pyproject.toml"),
+            "[project]\nname = \"demo\"\n[tool.uv]\n",
+        )
+        .unwrap();
+        let mut trace = DetectionTrace::default();
+        assert_eq!(
+            detect_type_with(dir.path(), &mut trace, None).unwrap(),
+            ProjectType::Uv
+        );
+        assert_eq!(
+            rules(&trace),
+            [("uv", true, "pyproject.toml has a [tool.uv] section")]
+        );
+    }
+
+    fn file(file: &str, found: bool) -> TraceStep {
+        TraceStep::File {
+            file: file.to_string(),
+            found,
+        }
+    }
+
+    fn rule(rule: &str, matched: bool, reason: &str) -> TraceStep {
+        TraceStep::Rule {
+            rule: rule.to_string(),
+            matched,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The steps taken before any Python marker is found, from the uv probe
+    /// through the venv probe.
+    fn no_python() -> Vec<TraceStep> {
+        let mut steps = vec![
+            file("pyproject.toml", false),
+            rule("uv", false, "no pyproject.toml"),
+            file("poetry.lock", false),
+            file("pyproject.toml", false),
+            rule("poetry", false, "no pyproject.toml or poetry.lock"),
+        ];
+        steps.extend(VENV_DIRS.iter().map(|dir| file(&venv_marker(dir), false)));
+        steps.push(rule(
+            "venv",
+            false,
+            "no venv at the root, in .venv or in venv",
+        ));
+        steps
+    }
+
+    fn traced(dir: &tempfile::TempDir) -> (ProjectType, DetectionTrace) {
+        let mut trace = DetectionTrace::default();
+        let detected = detect_type_with(dir.path(), &mut trace, None).unwrap();
+        (detected, trace)
+    }
+
+    #[test]
+    fn the_first_matching_probe_ends_the_trace() {
+        let dir = fixture(&["Cargo.toml", "package.json", "Makefile"]);
+        let (detected, trace) = traced(&dir);
+
+        assert_eq!(detected, ProjectType::Rust);
+        let mut expected = no_python();
+        expected.extend([
+            file("go.mod", false),
+            rule("go", false, "no go.mod"),
+            file("Cargo.toml", true),
+            rule("rust", true, "Cargo.toml is present"),
+        ]);
+        assert_eq!(trace.steps, expected);
+        // Markers of later probes were never looked for
+        assert_eq!(trace.found("package.json"), None);
+        assert_eq!(trace.found("Makefile"), None);
+    }
+
+    #[test]
+    fn a_poetry_lock_is_found_before_a_go_module() {
+        let dir = fixture(&["go.mod", "poetry.lock"]);
+        let (detected, trace) = traced(&dir);
+
+        assert_eq!(detected, ProjectType::Poetry);
+        assert_eq!(
+            trace.steps,
+            [
+                file("pyproject.toml", false),
+                rule("uv", false, "no pyproject.toml"),
+                file("poetry.lock", true),
+                rule("poetry", true, "poetry.lock is present"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_package_json_is_found_before_a_deno_config() {
+        let dir = fixture(&["package.json", "tsconfig.json", "deno.json"]);
+        let (detected, trace) = traced(&dir);
+
+        assert_eq!(detected, ProjectType::TypeScript);
+        let mut expected = no_python();
+        expected.extend([
+            file("go.mod", false),
+            rule("go", false, "no go.mod"),
+            file("Cargo.toml", false),
+            rule("rust", false, "no Cargo.toml"),
+            file("package.json", true),
+            file("tsconfig.json", true),
+            rule("typescript", true, "package.json with tsconfig.json"),
+        ]);
+        assert_eq!(trace.steps, expected);
+        assert_eq!(trace.found("deno.json"), None);
+    }
+
+    #[test]
+    fn a_pyproject_for_neither_tool_is_passed_over() {
+        let dir = fixture(&["pyproject.toml", "Makefile"]);
+        let (detected, trace) = traced(&dir);
+
+        // Make comes before the generic Python fallback
+        assert_eq!(detected, ProjectType::Make);
+        assert_eq!(
+            rules(&trace)[..2],
+            [
+                (
+                    "uv",
+                    false,
+                    "pyproject.toml has no [tool.uv] section and there is no uv.lock"
+                ),
+                (
+                    "poetry",
+                    false,
+                    "pyproject.toml has no [tool.poetry] section and there is no poetry.lock"
+                ),
+            ]
+        );
+        assert_eq!(
+            rules(&trace).last(),
+            Some(&("make", true, "Makefile is present"))
+        );
+    }
+
+    #[test]
+    fn traces_render_one_line_per_step() {
+        let dir = fixture(&["go.mod", "poetry.lock"]);
+        let (_, trace) = traced(&dir);
+
+        assert_eq!(
+            trace.render(),
+            [
+                "    ➖ pyproject.toml absent",
+                "    ⏭️  rule 'uv' skipped: no pyproject.toml",
+                "    ✅ poetry.lock found",
+                "    🎯 rule 'poetry' matched: poetry.lock is present",
+            ]
+        );
+    }
+
+    #[test]
+    fn traces_serialize_with_the_kind_of_each_step() {
+        let dir = fixture(&["go.mod", "poetry.lock"]);
+        let (_, trace) = traced(&dir);
+
+        let json = serde_json::to_value(&trace).unwrap();
+        assert_eq!(
+            json["steps"][2],
+            serde_json::json!({"kind": "file", "file": "poetry.lock", "found": true})
+        );
+        assert_eq!(
+            json["steps"][3],
+            serde_json::json!({
+                "kind": "rule",
+                "rule": "poetry",
+                "matched": true,
+                "reason": "poetry.lock is present",
+            })
+        );
+    }
+}
@@ -1,45 +1,65 @@
-use crate::models::OptionInfo;
-use crate::utils::{execute_project_command, select_options};
+use crate::capture;
+use crate::editor::display_command;
+use crate::models::{OptionInfo, ValueArity};
+use crate::run_profile;
+use crate::tools;
+use crate::ui;
+use crate::utils::{run_command, select_options, shell_command};
+use crate::value_sources::ValueSources;
 use anyhow::anyhow;
+use serde::Deserialize;
 use std::path::Path;
-use std::process::Command;
-
-pub fn handle_direct_docker_mode(command: &str, dry_run: bool) -> anyhow::Result<()> {
-    println!("Executing Docker command: {}", command);
-
-    if dry_run {
-        println!("Dry run: docker {}", command);
-        return Ok(());
-    }
+use tokio::process::Command;
 
+pub async fn handle_direct_docker_mode(command: &str, dry_run: bool) -> anyhow::Result<()> {
     // Parse the command and execute it
-    let args: Vec<&str> = command.split_whitespace().collect();
+    let args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
     if args.is_empty() {
         return Err(anyhow!("Empty Docker command"));
     }
+    let args = &args[1..]; // Skip "docker" if it was included
+
+    if dry_run {
+        println!("Dry run: {}", shell_command("docker", args));
+        return Ok(());
+    }
+    println!(
+        "Executing Docker command: {}",
+        shell_command("docker", args)
+    );
 
     let mut docker_cmd = Command::new("docker");
-    docker_cmd.args(&args[1..]); // Skip "docker" if it was included
+    docker_cmd.args(args);
 
-    let status = docker_cmd.status()?;
+    let status = capture::run(&mut docker_cmd, &display_command("docker", args)).await?;
     if !status.success() {
-        return Err(anyhow!(
-            "Docker command failed with exit code: {:?}",
-            status.code()
-        ));
+        println!("Docker command failed with exit code: {:?}", status.code());
     }
-
-    Ok(())
+    capture::check_status(status)
 }
 
-pub fn handle_docker_project_mode(path: &str, dry_run: bool) -> anyhow::Result<()> {
+pub async fn handle_docker_project_mode(
+    path: &str,
+    dry_run: bool,
+    profile: Option<&str>,
+) -> anyhow::Result<()> {
     println!("Managing Docker project: {}", path);
+    if !dry_run {
+        tools::require(&tools::resolve_from("docker", None)?, "Docker projects")?;
+    }
 
     // Detect Docker context
     let context = detect_docker_context(path)?;
 
-    // Get options based on context
-    let options = get_docker_options(&context)?;
+    // Get options based on context. Pruning reaches beyond the project, so
+    // it's only offered here and not next to a project's own operations
+    let mut options = get_docker_options(&context)?;
+    options.push(OptionInfo {
+        flags: vec!["prune".to_string()],
+        description: "Remove stopped containers, unused networks, dangling images and build cache"
+            .to_string(),
+        ..Default::default()
+    });
 
     println!(
         "Detected {} Docker setup with {} options",
@@ -54,23 +74,123 @@ pub fn handle_docker_project_mode(path: &str, dry_run: bool) -> anyhow::Result<(
         println!("No Docker options available.");
         Vec::new()
     } else {
-        select_options(&options)?
+        select_options(&options, &[], &ValueSources::default(), None)?
     };
 
     // Build and execute commands
-    for (flag, value) in selected_options {
-        let (command, args) = build_docker_command(&context, path, &flag, value.as_deref())?;
+    for selected in selected_options {
+        if selected.flag == "images" && !dry_run {
+            manage_images(path).await?;
+            continue;
+        }
+        if selected.flag == "prune" {
+            prune(dry_run).await?;
+            continue;
+        }
+        let value = selected.occurrences.into_iter().next().flatten();
+        let (command, args) = docker_step_command(
+            &context,
+            path,
+            &selected.flag,
+            value.as_deref(),
+            profile,
+            !dry_run,
+        )?;
 
         if dry_run {
-            println!("Dry run: {} {}", command, args.join(" "));
+            println!("Dry run: {}", shell_command(&command, &args));
         } else {
-            execute_project_command(&command, &args, path)?;
+            capture::check_status(run_command(&command, &args, &[], Some(path)).await?)?;
         }
     }
 
     Ok(())
 }
 
+/// `docker system prune`, after an explicit yes since it affects every
+/// project on the machine. Docker reports how much space it reclaimed.
+async fn prune(dry_run: bool) -> anyhow::Result<()> {
+    let args = vec![
+        "system".to_string(),
+        "prune".to_string(),
+        "--force".to_string(),
+    ];
+    if dry_run {
+        println!("Dry run: {}", shell_command("docker", &args));
+        return Ok(());
+    }
+    if !ui::confirm(
+        "docker system prune removes all stopped containers, unused networks, dangling images and build cache on this machine. Continue?",
+        false,
+    )? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let output = Command::new("docker").args(&args).output().await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "docker system prune failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout
+        .lines()
+        .find(|line| line.starts_with("Total reclaimed space"))
+    {
+        Some(line) => println!("{}", line),
+        None => println!("Nothing to prune"),
+    }
+    Ok(())
+}
+
+/// The Docker operations available in a directory, for menus that offer
+/// them alongside the project's own operations.
+pub struct DockerMenu {
+    context: DockerContext,
+    pub options: Vec<OptionInfo>,
+}
+
+impl DockerMenu {
+    /// `None` when the directory has no Dockerfile or compose file.
+    pub fn detect(path: &str) -> Option<Self> {
+        let context = detect_docker_context(path).ok()?;
+        let options = get_docker_options(&context).ok()?;
+        Some(Self { context, options })
+    }
+
+    pub fn command(
+        &self,
+        path: &str,
+        flag: &str,
+        value: Option<&str>,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        docker_step_command(&self.context, path, flag, value, None, true)
+    }
+}
+
+/// The command for one selected Docker operation. `docker run` for a single
+/// image goes through the project's run profile.
+fn docker_step_command(
+    context: &DockerContext,
+    path: &str,
+    flag: &str,
+    value: Option<&str>,
+    profile: Option<&str>,
+    interactive: bool,
+) -> anyhow::Result<(String, Vec<String>)> {
+    if flag == "run" && !matches!(context, DockerContext::Compose) {
+        let run_profile = run_profile::resolve_run_profile(path, profile, interactive)?;
+        Ok((
+            "docker".to_string(),
+            run_profile.to_run_args(&generate_image_name(path)),
+        ))
+    } else {
+        build_docker_command(context, path, flag, value)
+    }
+}
+
 #[derive(Debug, Clone)]
 enum DockerContext {
     SingleImage,
@@ -89,11 +209,8 @@ impl std::fmt::Display for DockerContext {
 }
 
 fn detect_docker_context(path: &str) -> anyhow::Result<DockerContext> {
-    let dockerfile_path = format!("{}/Dockerfile", path);
-    let compose_path = format!("{}/docker-compose.yml", path);
-
-    let has_dockerfile = Path::new(&dockerfile_path).exists();
-    let has_compose = Path::new(&compose_path).exists();
+    let has_dockerfile = Path::new(path).join("Dockerfile").exists();
+    let has_compose = Path::new(path).join("docker-compose.yml").exists();
 
     match (has_dockerfile, has_compose) {
         (true, true) => Ok(DockerContext::Hybrid),
@@ -114,59 +231,65 @@ fn get_docker_options(context: &DockerContext) -> anyhow::Result<Vec<OptionInfo>
             options.push(OptionInfo {
                 flags: vec!["build".to_string()],
                 description: "Build Docker image".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["run".to_string()],
                 description: "Run container".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["shell".to_string()],
                 description: "Access container shell".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["logs".to_string()],
                 description: "Show container logs".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["push".to_string()],
                 description: "Push image to registry".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["pull".to_string()],
                 description: "Pull image from registry".to_string(),
-                requires_value: false,
+                ..Default::default()
+            });
+            options.push(OptionInfo {
+                flags: vec!["images".to_string()],
+                description: "List, remove or re-tag images built for this project".to_string(),
+                ..Default::default()
             });
         }
         DockerContext::Compose => {
             options.push(OptionInfo {
                 flags: vec!["up".to_string()],
                 description: "Start services".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["down".to_string()],
                 description: "Stop services".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["build".to_string()],
                 description: "Build services".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["logs".to_string()],
                 description: "Show service logs".to_string(),
-                requires_value: false,
+                ..Default::default()
             });
             options.push(OptionInfo {
                 flags: vec!["shell".to_string()],
                 description: "Access service shell".to_string(),
-                requires_value: true, // service name
+                value: ValueArity::Required, // service name
+                ..Default::default()
             });
         }
         DockerContext::Hybrid => {
@@ -195,6 +318,8 @@ fn build_docker_command(
                         "build".to_string(),
                         "-t".to_string(),
                         image_name,
+                        "--label".to_string(),
+                        project_label(path),
                         ".".to_string(),
                     ],
                 )),
@@ -231,6 +356,7 @@ fn build_docker_command(
                 }
                 "push" => Ok(("docker".to_string(), vec!["push".to_string(), image_name])),
                 "pull" => Ok(("docker".to_string(), vec!["pull".to_string(), image_name])),
+                "images" => Ok(("docker".to_string(), list_images_args(path))),
                 _ => Err(anyhow!("Unknown Docker command: {}", flag)),
             }
         }
@@ -272,11 +398,204 @@ fn build_docker_command(
     }
 }
 
+/// Label put on images hoist builds, with the project's canonical path as value.
+const PROJECT_LABEL: &str = "org.app-hoist.project";
+
+fn project_label(path: &str) -> String {
+    let canonical = std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| Path::new(path).to_path_buf());
+    format!("{}={}", PROJECT_LABEL, canonical.display())
+}
+
+fn list_images_args(path: &str) -> Vec<String> {
+    vec![
+        "images".to_string(),
+        "--filter".to_string(),
+        format!("label={}", project_label(path)),
+    ]
+}
+
+/// One line of `docker images --format '{{json .}}'`.
+#[derive(Debug, Clone, Deserialize)]
+struct ImageSummary {
+    #[serde(rename = "Repository")]
+    repository: String,
+    #[serde(rename = "Tag")]
+    tag: String,
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Size", default)]
+    size: String,
+    #[serde(rename = "CreatedSince", default)]
+    created_since: String,
+}
+
+impl ImageSummary {
+    /// `repo:tag`, or the image ID for untagged (`<none>`) images.
+    fn reference(&self) -> String {
+        if self.repository == "<none>" || self.tag == "<none>" {
+            self.id.clone()
+        } else {
+            format!("{}:{}", self.repository, self.tag)
+        }
+    }
+}
+
+impl std::fmt::Display for ImageSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<40} {:>10}  {}",
+            self.reference(),
+            self.size,
+            self.created_since
+        )
+    }
+}
+
+async fn project_images(path: &str) -> anyhow::Result<Vec<ImageSummary>> {
+    let mut args = list_images_args(path);
+    args.extend(["--format".to_string(), "{{json .}}".to_string()]);
+
+    let output = Command::new("docker").args(&args).output().await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "docker images failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("Unexpected docker images output '{}': {}", line, e))
+        })
+        .collect()
+}
+
+/// List the images built for this project and remove or re-tag them one at a time.
+async fn manage_images(path: &str) -> anyhow::Result<()> {
+    const DONE: &str = "Done";
+
+    loop {
+        let images = project_images(path).await?;
+        if images.is_empty() {
+            println!("No images built by app-hoist for this project");
+            return Ok(());
+        }
+
+        let mut choices: Vec<String> = images.iter().map(ImageSummary::to_string).collect();
+        choices.push(DONE.to_string());
+        let choice = ui::select(
+            &format!("{} image(s) built for this project:", images.len()),
+            choices.clone(),
+        )?;
+        let Some(image) = choices
+            .iter()
+            .position(|c| *c == choice)
+            .and_then(|idx| images.get(idx))
+        else {
+            return Ok(());
+        };
+
+        let action = ui::select(
+            &format!("{}:", image.reference()),
+            vec![
+                "Remove".to_string(),
+                "Re-tag".to_string(),
+                "Back".to_string(),
+            ],
+        )?;
+        match action.as_str() {
+            "Remove" if ui::confirm(&format!("Remove {}?", image.reference()), false)? => {
+                run_command("docker", &["rmi".to_string(), image.reference()], &[], None).await?;
+            }
+            "Re-tag" => {
+                let target = ui::text("New tag (e.g. registry.example.com/app:1.0):", None)?;
+                if !target.trim().is_empty() {
+                    run_command(
+                        "docker",
+                        &[
+                            "tag".to_string(),
+                            image.reference(),
+                            target.trim().to_string(),
+                        ],
+                        &[],
+                        None,
+                    )
+                    .await?;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn generate_image_name(path: &str) -> String {
     let dir_name = Path::new(path)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("app");
 
-    format!("{}-app", dir_name.to_lowercase())
+    let image_name = sanitize_image_name(&format!("{}-app", dir_name));
+    if image_name != format!("{}-app", dir_name.to_lowercase()) {
+        ui::status(&format!(
+            "⚠️  Directory name '{}' is not a valid image name, using '{}'",
+            dir_name, image_name
+        ));
+    }
+
+    image_name
+}
+
+/// Reduce a name to Docker's reference grammar: lowercase ASCII alphanumerics
+/// joined by single dashes.
+fn sanitize_image_name(raw: &str) -> String {
+    let mut name = String::new();
+    for c in raw.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        "app".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_names_follow_the_reference_grammar() {
+        assert_eq!(sanitize_image_name("My App (2024)-app"), "my-app-2024-app");
+        assert_eq!(sanitize_image_name("café ünïcode-app"), "caf-n-code-app");
+        assert_eq!(sanitize_image_name("--web__server--"), "web-server");
+        assert_eq!(sanitize_image_name("api-app"), "api-app");
+    }
+
+    #[test]
+    fn names_without_valid_characters_become_app() {
+        assert_eq!(sanitize_image_name("(())"), "app");
+        assert_eq!(sanitize_image_name("日本"), "app");
+        assert_eq!(sanitize_image_name(""), "app");
+    }
+
+    #[test]
+    fn image_names_come_from_the_directory() {
+        assert_eq!(
+            generate_image_name("/work/My App (2024)"),
+            "my-app-2024-app"
+        );
+        assert_eq!(generate_image_name("/work/api"), "api-app");
+    }
 }
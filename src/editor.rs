@@ -0,0 +1,166 @@
+use crate::models::ValueType;
+use crate::ui;
+use crate::utils::{expand_tilde, shell_quote};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Values longer than this are edited in $EDITOR rather than on one line.
+pub const LONG_VALUE_BYTES: usize = 200;
+
+/// Typed at a value prompt to open $EDITOR instead.
+pub const EDIT_KEYWORD: &str = "@edit";
+
+/// Ask for an option value. Multi-line or very long values (a JSON body, a
+/// script for `python -c`) don't survive a single-line prompt, so typing
+/// `@edit`, or entering more than `LONG_VALUE_BYTES`, moves to $EDITOR.
+/// Pressing Enter on an empty prompt accepts `default`. Typed values must
+/// fit `value_type`. Paths complete with Tab and have a leading `~`
+/// expanded; one that doesn't exist only gets a warning, since it may be
+/// an output file.
+pub fn prompt_value(
+    message: &str,
+    default: Option<&str>,
+    value_type: ValueType,
+) -> anyhow::Result<String> {
+    let value = ui::text_with_help(
+        message,
+        default,
+        &format!(
+            "Type {} to write a long or multi-line value in $EDITOR",
+            EDIT_KEYWORD
+        ),
+        value_type == ValueType::Path,
+        move |input| {
+            // Empty answers pass so optional values can still be left out
+            if input.is_empty() || input.trim() == EDIT_KEYWORD {
+                Ok(())
+            } else {
+                value_type.check(input)
+            }
+        },
+    )?;
+
+    let value = if value.trim() == EDIT_KEYWORD {
+        edit("")?
+    } else if value.len() > LONG_VALUE_BYTES {
+        println!("Long value, opening it in your editor to review");
+        edit(&value)?
+    } else {
+        value
+    };
+
+    if value_type != ValueType::Path || value.is_empty() {
+        return Ok(value);
+    }
+    let value = expand_tilde(&value)?;
+    if !Path::new(&value).exists() {
+        ui::status(&format!("⚠️  {} does not exist", value));
+    }
+    Ok(value)
+}
+
+/// Open `initial` in the user's editor and return what they saved, byte for
+/// byte apart from the newline editors add at the end of the file.
+pub fn edit(initial: &str) -> anyhow::Result<String> {
+    let file = ValueFile::create(initial)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = crate::tools::split_words(&editor)?.into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+
+    let status = Command::new(&program)
+        .args(words)
+        .arg(&file.path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor '{}': {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {}, value discarded", status);
+    }
+
+    let content = std::fs::read_to_string(&file.path)?;
+    Ok(content
+        .strip_suffix("\r\n")
+        .or_else(|| content.strip_suffix('\n'))
+        .unwrap_or(&content)
+        .to_string())
+}
+
+/// A temporary file only the current user can read, removed on drop.
+struct ValueFile {
+    path: PathBuf,
+}
+
+impl ValueFile {
+    fn create(content: &str) -> anyhow::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "app-hoist-value-{}-{}.txt",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(&path)?;
+        let value_file = Self { path };
+        file.write_all(content.as_bytes())?;
+        Ok(value_file)
+    }
+}
+
+impl Drop for ValueFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// How an argument is shown in dry runs, history and progress lines. Long or
+/// multi-line values are cut down to a marker such as `{"a": … (1.2 KB value)`;
+/// the command that runs always gets the full value.
+pub fn display_arg(arg: &str) -> String {
+    const PREVIEW_CHARS: usize = 24;
+
+    if !arg.contains('\n') && arg.len() <= LONG_VALUE_BYTES {
+        return arg.to_string();
+    }
+
+    let preview: String = arg
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(PREVIEW_CHARS)
+        .collect();
+    format!("{}… ({} value)", preview, format_size(arg.len()))
+}
+
+/// A program and its arguments shell-quoted for display, with long values
+/// shortened as in `display_arg`.
+pub fn display_command(program: &str, args: &[String]) -> String {
+    std::iter::once(shell_quote(program))
+        .chain(args.iter().map(|arg| display_arg(&shell_quote(arg))))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
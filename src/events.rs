@@ -0,0 +1,312 @@
+use crate::plan::ExecutionPlan;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::time::Duration;
+
+/// Bumped whenever an event or field changes meaning; new fields don't bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Events queued for the writer before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+// How long exit waits for a slow reader to take the last events
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Progress for wrappers, written one JSON object per line to the side channel
+/// given with `--progress-fd` or `--progress-file`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    PhaseStarted {
+        phase: String,
+    },
+    PhaseFinished {
+        phase: String,
+        duration_ms: u64,
+    },
+    Plan {
+        steps: Vec<PlanStepEvent>,
+    },
+    ProjectStarted {
+        project: String,
+    },
+    ProjectSucceeded {
+        project: String,
+        duration_ms: u64,
+        warnings: usize,
+    },
+    ProjectFailed {
+        project: String,
+        duration_ms: u64,
+        error: String,
+    },
+    ProjectCancelled {
+        project: String,
+        duration_ms: u64,
+    },
+    /// Execution is blocked until someone answers a prompt.
+    PromptRequired {
+        message: String,
+    },
+    /// The writer fell behind and this many events were discarded.
+    EventsDropped {
+        count: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanStepEvent {
+    pub label: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Event {
+    pub fn plan(plan: &ExecutionPlan) -> Self {
+        Event::Plan {
+            steps: plan
+                .steps
+                .iter()
+                .map(|step| PlanStepEvent {
+                    label: step.label.clone(),
+                    program: step.program.clone(),
+                    args: step.args.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn phase_finished(phase: &str, duration: Duration) -> Self {
+        Event::PhaseFinished {
+            phase: phase.to_string(),
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+pub fn to_line(event: &Event) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(&Envelope {
+        version: SCHEMA_VERSION,
+        event,
+    })?)
+}
+
+struct Sink {
+    sender: SyncSender<String>,
+    drained: Receiver<()>,
+}
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Start writing events to an already open file descriptor or to a file.
+pub fn init(fd: Option<i32>, file: Option<&str>) -> anyhow::Result<()> {
+    let output: Box<dyn Write + Send> = match (fd, file) {
+        (Some(fd), _) => open_fd(fd)?,
+        (None, Some(file)) => Box::new(
+            std::fs::File::create(file)
+                .map_err(|e| anyhow::anyhow!("Could not open progress file {}: {}", file, e))?,
+        ),
+        (None, None) => return Ok(()),
+    };
+
+    let (sender, receiver) = mpsc::sync_channel::<String>(QUEUE_CAPACITY);
+    let (done, drained) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = std::io::LineWriter::new(output);
+        for line in receiver {
+            // A reader that went away shouldn't take the run down with it
+            if writeln!(output, "{}", line).is_err() {
+                break;
+            }
+        }
+        let _ = done.send(());
+    });
+
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(Sink { sender, drained });
+    Ok(())
+}
+
+#[cfg(unix)]
+fn open_fd(fd: i32) -> anyhow::Result<Box<dyn Write + Send>> {
+    use std::os::fd::FromRawFd;
+
+    if fd <= 2 {
+        anyhow::bail!(
+            "--progress-fd must not be stdin, stdout or stderr (got {})",
+            fd
+        );
+    }
+    // SAFETY: fcntl only queries the descriptor
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        anyhow::bail!("--progress-fd {} is not an open file descriptor", fd);
+    }
+    // SAFETY: the descriptor is open and handed to us by the parent process
+    Ok(Box::new(unsafe { std::fs::File::from_raw_fd(fd) }))
+}
+
+#[cfg(not(unix))]
+fn open_fd(_fd: i32) -> anyhow::Result<Box<dyn Write + Send>> {
+    anyhow::bail!("--progress-fd is only supported on Unix; use --progress-file")
+}
+
+/// Queue an event without ever waiting: when the writer is behind, the event
+/// is counted and dropped instead.
+pub fn emit(event: Event) {
+    let sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(sink) = sink.as_ref() else {
+        return;
+    };
+    let Ok(line) = to_line(&event) else {
+        return;
+    };
+
+    match sink.sender.try_send(line) {
+        Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+        Err(TrySendError::Full(_)) => {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Report any dropped events and give the writer a moment to drain the queue.
+pub fn finish() {
+    let Some(sink) = SINK.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+
+    let count = DROPPED.swap(0, Ordering::Relaxed);
+    if count > 0
+        && let Ok(line) = to_line(&Event::EventsDropped { count })
+    {
+        let _ = sink.sender.try_send(line);
+    }
+    drop(sink.sender);
+    let _ = sink.drained.recv_timeout(DRAIN_TIMEOUT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(event: &Event) -> serde_json::Value {
+        serde_json::from_str(&to_line(event).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn every_line_carries_the_schema_version_and_event_name() {
+        let value = line(&Event::ProjectStarted {
+            project: "/src/api".to_string(),
+        });
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "version": SCHEMA_VERSION,
+                "event": "project_started",
+                "project": "/src/api",
+            })
+        );
+    }
+
+    #[test]
+    fn event_names_and_fields_are_snake_case() {
+        let cases = [
+            (
+                Event::phase_finished("run", Duration::from_millis(1500)),
+                serde_json::json!({"event": "phase_finished", "phase": "run", "duration_ms": 1500}),
+            ),
+            (
+                Event::ProjectSucceeded {
+                    project: "api".to_string(),
+                    duration_ms: 12,
+                    warnings: 3,
+                },
+                serde_json::json!({
+                    "event": "project_succeeded",
+                    "project": "api",
+                    "duration_ms": 12,
+                    "warnings": 3,
+                }),
+            ),
+            (
+                Event::ProjectFailed {
+                    project: "api".to_string(),
+                    duration_ms: 12,
+                    error: "exit status 1".to_string(),
+                },
+                serde_json::json!({
+                    "event": "project_failed",
+                    "project": "api",
+                    "duration_ms": 12,
+                    "error": "exit status 1",
+                }),
+            ),
+            (
+                Event::ProjectCancelled {
+                    project: "api".to_string(),
+                    duration_ms: 12,
+                },
+                serde_json::json!({"event": "project_cancelled", "project": "api", "duration_ms": 12}),
+            ),
+            (
+                Event::PromptRequired {
+                    message: "Run these commands?".to_string(),
+                },
+                serde_json::json!({"event": "prompt_required", "message": "Run these commands?"}),
+            ),
+            (
+                Event::EventsDropped { count: 7 },
+                serde_json::json!({"event": "events_dropped", "count": 7}),
+            ),
+        ];
+
+        for (event, mut expected) in cases {
+            expected["version"] = SCHEMA_VERSION.into();
+            assert_eq!(line(&event), expected);
+        }
+    }
+
+    #[test]
+    fn plans_list_each_step_in_order() {
+        let mut plan = ExecutionPlan::default();
+        plan.push("api", "go", &["test".to_string(), "./...".to_string()]);
+        plan.push("web", "npm", &["test".to_string()]);
+
+        assert_eq!(
+            line(&Event::plan(&plan)),
+            serde_json::json!({
+                "version": SCHEMA_VERSION,
+                "event": "plan",
+                "steps": [
+                    {"label": "api", "program": "go", "args": ["test", "./..."]},
+                    {"label": "web", "program": "npm", "args": ["test"]},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn events_are_written_on_a_single_line() {
+        let event = Event::ProjectFailed {
+            project: "api".to_string(),
+            duration_ms: 0,
+            error: "first line\nsecond line".to_string(),
+        };
+        let line = to_line(&event).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        assert!(
+            line.contains(r#""error":"first line\nsecond line""#),
+            "{}",
+            line
+        );
+    }
+}
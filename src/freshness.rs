@@ -0,0 +1,358 @@
+use crate::models::ProjectType;
+use crate::tools;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Freshness {
+    Fresh,
+    Stale(String),
+    Unknown,
+}
+
+impl std::fmt::Display for Freshness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Freshness::Fresh => write!(f, "up to date"),
+            Freshness::Stale(reason) => write!(f, "stale ({})", reason),
+            Freshness::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+pub fn check_project(project_type: &ProjectType, path: &str) -> Freshness {
+    match project_type {
+        ProjectType::JavaScript | ProjectType::TypeScript => check_node(path),
+        ProjectType::Uv => check_uv(path),
+        ProjectType::Rust => check_cargo(path),
+        ProjectType::Go => check_go(path),
+        ProjectType::Poetry
+        | ProjectType::Venv
+        | ProjectType::Generic
+        | ProjectType::Make
+        | ProjectType::Just
+        | ProjectType::Deno => Freshness::Unknown,
+    }
+}
+
+/// Command that brings installed dependencies back in line with the lockfile.
+pub fn sync_command(
+    project_type: &ProjectType,
+    path: &str,
+) -> anyhow::Result<Option<(String, Vec<String>)>> {
+    let (tool, args) = match project_type {
+        ProjectType::JavaScript | ProjectType::TypeScript => {
+            let root = Path::new(path);
+            let pm = if root.join("pnpm-lock.yaml").exists() {
+                "pnpm"
+            } else if root.join("bun.lock").exists() || root.join("bun.lockb").exists() {
+                "bun"
+            } else {
+                "npm"
+            };
+            (pm, vec!["install".to_string()])
+        }
+        ProjectType::Uv => ("uv", vec!["sync".to_string()]),
+        ProjectType::Rust => ("cargo", vec!["build".to_string()]),
+        ProjectType::Go => ("go", vec!["mod".to_string(), "download".to_string()]),
+        ProjectType::Poetry
+        | ProjectType::Venv
+        | ProjectType::Generic
+        | ProjectType::Make
+        | ProjectType::Just
+        | ProjectType::Deno => {
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(tools::command(tool, path, args)?))
+}
+
+pub fn check_node(path: &str) -> Freshness {
+    let root = Path::new(path);
+
+    // npm records its view of node_modules in .package-lock.json, pnpm in .modules.yaml
+    let (lockfile, marker) = if root.join("package-lock.json").exists() {
+        (
+            root.join("package-lock.json"),
+            root.join("node_modules").join(".package-lock.json"),
+        )
+    } else if root.join("pnpm-lock.yaml").exists() {
+        (
+            root.join("pnpm-lock.yaml"),
+            root.join("node_modules").join(".modules.yaml"),
+        )
+    } else {
+        return Freshness::Unknown;
+    };
+
+    compare_lockfile(&lockfile, &marker, "node_modules")
+}
+
+pub fn check_uv(path: &str) -> Freshness {
+    let root = Path::new(path);
+    let lockfile = root.join("uv.lock");
+    if !lockfile.exists() {
+        return Freshness::Unknown;
+    }
+
+    // uv rewrites pyvenv.cfg whenever it syncs the environment
+    compare_lockfile(&lockfile, &root.join(".venv").join("pyvenv.cfg"), ".venv")
+}
+
+pub fn check_cargo(path: &str) -> Freshness {
+    let root = Path::new(path);
+    if !root.join("Cargo.lock").exists() {
+        return Freshness::Unknown;
+    }
+
+    let target = root.join("target");
+    let has_fingerprints = ["debug", "release"]
+        .iter()
+        .any(|profile| target.join(profile).join(".fingerprint").is_dir());
+
+    if has_fingerprints {
+        Freshness::Fresh
+    } else {
+        Freshness::Stale("target has not been built yet".to_string())
+    }
+}
+
+pub fn check_go(path: &str) -> Freshness {
+    match go_mod_cache(
+        std::env::var_os("GOMODCACHE"),
+        std::env::var_os("GOPATH"),
+        dirs::home_dir(),
+    ) {
+        Some(cache) => check_go_modules(Path::new(path), &cache),
+        None => Freshness::Unknown,
+    }
+}
+
+/// Where `go` keeps downloaded modules: `$GOMODCACHE`, else `pkg/mod` under
+/// the first `$GOPATH` entry, else under `~/go`.
+fn go_mod_cache(
+    gomodcache: Option<OsString>,
+    gopath: Option<OsString>,
+    home: Option<PathBuf>,
+) -> Option<PathBuf> {
+    if let Some(cache) = gomodcache.filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(cache));
+    }
+    let gopath = gopath
+        .as_deref()
+        .and_then(|gopath| std::env::split_paths(gopath).next())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| Some(home?.join("go")))?;
+    Some(gopath.join("pkg").join("mod"))
+}
+
+/// Whether every module go.sum has source hashes for was downloaded into
+/// the module cache, the files `go mod verify` would check. Only their
+/// presence is looked at, so nothing is hashed and nothing touches the
+/// network.
+fn check_go_modules(root: &Path, cache: &Path) -> Freshness {
+    let Ok(go_sum) = std::fs::read_to_string(root.join("go.sum")) else {
+        return Freshness::Unknown;
+    };
+
+    // Lines for a module's go.mod alone (`<module> <version>/go.mod h1:...`)
+    // don't mean its source was needed
+    let missing = go_sum
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            Some((words.next()?, words.next()?))
+        })
+        .filter(|(_, version)| !version.ends_with("/go.mod"))
+        .filter(|(module, version)| {
+            !cache
+                .join("cache")
+                .join("download")
+                .join(escape_module_path(module))
+                .join("@v")
+                .join(format!("{}.zip", version))
+                .is_file()
+        })
+        .count();
+
+    match missing {
+        0 => Freshness::Fresh,
+        1 => Freshness::Stale("1 module is missing from the module cache".to_string()),
+        n => Freshness::Stale(format!("{} modules are missing from the module cache", n)),
+    }
+}
+
+/// A module path as the module cache spells it: each capital letter becomes
+/// `!` and its lowercase form, for case-insensitive file systems.
+fn escape_module_path(module: &str) -> String {
+    let mut escaped = String::with_capacity(module.len());
+    for c in module.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn compare_lockfile(lockfile: &Path, marker: &Path, installed: &str) -> Freshness {
+    let Some(lock_time) = modified(lockfile) else {
+        return Freshness::Unknown;
+    };
+
+    match modified(marker) {
+        None => Freshness::Stale(format!("{} is missing", installed)),
+        Some(marker_time) if lock_time > marker_time => {
+            let lock_name = lockfile
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("lockfile");
+            Freshness::Stale(format!("{} is newer than {}", lock_name, installed))
+        }
+        Some(_) => Freshness::Fresh,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    /// Create `relative` under `root` with the given age in seconds.
+    fn touch(root: &Path, relative: &str, age_secs: u64) {
+        let file = root.join(relative);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        File::create(&file)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+    }
+
+    fn project() -> tempfile::TempDir {
+        tempfile::tempdir().unwrap()
+    }
+
+    #[test]
+    fn node_without_lockfile_is_unknown() {
+        let dir = project();
+        touch(dir.path(), "package.json", 10);
+        assert_eq!(check_node(dir.path().to_str().unwrap()), Freshness::Unknown);
+    }
+
+    #[test]
+    fn node_is_stale_until_installed() {
+        let dir = project();
+        touch(dir.path(), "package-lock.json", 10);
+        assert_eq!(
+            check_node(dir.path().to_str().unwrap()),
+            Freshness::Stale("node_modules is missing".to_string())
+        );
+
+        touch(dir.path(), "node_modules/.package-lock.json", 5);
+        assert_eq!(check_node(dir.path().to_str().unwrap()), Freshness::Fresh);
+    }
+
+    #[test]
+    fn node_lockfile_newer_than_install_is_stale() {
+        let dir = project();
+        touch(dir.path(), "node_modules/.package-lock.json", 20);
+        touch(dir.path(), "package-lock.json", 10);
+        assert_eq!(
+            check_node(dir.path().to_str().unwrap()),
+            Freshness::Stale("package-lock.json is newer than node_modules".to_string())
+        );
+    }
+
+    #[test]
+    fn pnpm_uses_its_own_marker() {
+        let dir = project();
+        touch(dir.path(), "pnpm-lock.yaml", 20);
+        touch(dir.path(), "node_modules/.modules.yaml", 10);
+        assert_eq!(check_node(dir.path().to_str().unwrap()), Freshness::Fresh);
+    }
+
+    #[test]
+    fn uv_compares_against_the_venv() {
+        let dir = project();
+        touch(dir.path(), "uv.lock", 10);
+        touch(dir.path(), ".venv/pyvenv.cfg", 20);
+        assert_eq!(
+            check_uv(dir.path().to_str().unwrap()),
+            Freshness::Stale("uv.lock is newer than .venv".to_string())
+        );
+
+        touch(dir.path(), ".venv/pyvenv.cfg", 0);
+        assert_eq!(check_uv(dir.path().to_str().unwrap()), Freshness::Fresh);
+    }
+
+    #[test]
+    fn cargo_is_fresh_once_fingerprints_exist() {
+        let dir = project();
+        touch(dir.path(), "Cargo.lock", 10);
+        assert!(matches!(
+            check_cargo(dir.path().to_str().unwrap()),
+            Freshness::Stale(_)
+        ));
+
+        std::fs::create_dir_all(dir.path().join("target/debug/.fingerprint")).unwrap();
+        assert_eq!(check_cargo(dir.path().to_str().unwrap()), Freshness::Fresh);
+    }
+
+    const GO_SUM: &str = "github.com/BurntSushi/toml v1.3.2 h1:abc=
+github.com/BurntSushi/toml v1.3.2/go.mod h1:def=
+golang.org/x/text v0.14.0/go.mod h1:ghi=
+";
+
+    #[test]
+    fn go_is_fresh_once_every_needed_module_is_downloaded() {
+        let dir = project();
+        let cache = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("go.sum"), GO_SUM).unwrap();
+        assert_eq!(
+            check_go_modules(dir.path(), cache.path()),
+            Freshness::Stale("1 module is missing from the module cache".to_string())
+        );
+
+        // Only go.mod was needed from golang.org/x/text
+        touch(
+            cache.path(),
+            "cache/download/github.com/!burnt!sushi/toml/@v/v1.3.2.zip",
+            0,
+        );
+        assert_eq!(check_go_modules(dir.path(), cache.path()), Freshness::Fresh);
+    }
+
+    #[test]
+    fn go_without_go_sum_is_unknown() {
+        let dir = project();
+        touch(dir.path(), "go.mod", 10);
+        assert_eq!(check_go_modules(dir.path(), dir.path()), Freshness::Unknown);
+    }
+
+    #[test]
+    fn the_module_cache_follows_gomodcache_then_gopath_then_home() {
+        let home = Some(PathBuf::from("/home/me"));
+        assert_eq!(
+            go_mod_cache(Some("/cache".into()), Some("/gopath".into()), home.clone()),
+            Some(PathBuf::from("/cache"))
+        );
+        assert_eq!(
+            go_mod_cache(Some("".into()), Some("/gopath".into()), home.clone()),
+            Some(Path::new("/gopath").join("pkg").join("mod"))
+        );
+        assert_eq!(
+            go_mod_cache(None, None, home),
+            Some(Path::new("/home/me").join("go").join("pkg").join("mod"))
+        );
+        assert_eq!(go_mod_cache(None, None, None), None);
+    }
+}
@@ -0,0 +1,1658 @@
+//! Reading a tool's options, subcommands and environment variables from its
+//! `--help` output (or man page), without any prompting.
+
+use crate::models::{OptionInfo, ValueArity, ValueType};
+use regex::Regex;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::process::Command as AsyncCommand;
+
+/// Ways of asking a tool for help, tried in order until one prints usable help.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HelpStrategy {
+    LongFlag,
+    ShortFlag,
+    HelpSubcommand,
+    /// Windows-native tools such as robocopy and ipconfig
+    SlashQuestion,
+    NoArgs,
+}
+
+impl HelpStrategy {
+    pub const ALL: [HelpStrategy; 5] = [
+        HelpStrategy::LongFlag,
+        HelpStrategy::ShortFlag,
+        HelpStrategy::HelpSubcommand,
+        HelpStrategy::SlashQuestion,
+        HelpStrategy::NoArgs,
+    ];
+
+    pub fn args(&self, subcommand: &[String]) -> Vec<String> {
+        let mut args = Vec::new();
+        match self {
+            HelpStrategy::LongFlag => {
+                args.extend_from_slice(subcommand);
+                args.push("--help".to_string());
+            }
+            HelpStrategy::ShortFlag => {
+                args.extend_from_slice(subcommand);
+                args.push("-h".to_string());
+            }
+            HelpStrategy::HelpSubcommand => {
+                args.push("help".to_string());
+                args.extend_from_slice(subcommand);
+            }
+            HelpStrategy::SlashQuestion => {
+                args.extend_from_slice(subcommand);
+                args.push("/?".to_string());
+            }
+            HelpStrategy::NoArgs => args.extend_from_slice(subcommand),
+        }
+        args
+    }
+}
+
+impl std::fmt::Display for HelpStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HelpStrategy::LongFlag => write!(f, "--help"),
+            HelpStrategy::ShortFlag => write!(f, "-h"),
+            HelpStrategy::HelpSubcommand => write!(f, "help"),
+            HelpStrategy::SlashQuestion => write!(f, "/?"),
+            HelpStrategy::NoArgs => write!(f, "no arguments"),
+        }
+    }
+}
+
+/// How long a help probe may run before it is killed.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A help probe that was still running when its time ran out. The probe
+/// itself is killed, but anything it started is left to the caller, which
+/// can stop it through the process group `pid` leads on Unix.
+#[derive(Debug)]
+pub struct ProbeTimedOut {
+    pub command: String,
+    pub pid: Option<u32>,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for ProbeTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "help probe timed out: '{}' was still running after {}s",
+            self.command,
+            self.timeout.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for ProbeTimedOut {}
+
+/// The help text of `exe`, asked for each way `HelpStrategy` lists. Blocks
+/// until done, so it must not be called from within an async runtime.
+pub fn probe_help(exe: &Path) -> anyhow::Result<String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(probe_help_output(exe, &[], DEFAULT_PROBE_TIMEOUT))
+}
+
+/// The help text of `executable`, or of its `subcommand`, from the first
+/// way of asking that prints something that looks like help.
+pub async fn probe_help_output(
+    executable: &Path,
+    subcommand: &[String],
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    for strategy in HelpStrategy::ALL {
+        let args = strategy.args(subcommand);
+        // Some programs ignore an unknown flag and wait for input or run
+        // forever, so stdin is closed and the probe is killed when it's late
+        let mut command = AsyncCommand::new(executable);
+        command
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Its own process group, so a timeout can also stop anything it started
+        #[cfg(unix)]
+        command.process_group(0);
+        let Ok(child) = command.spawn() else {
+            continue;
+        };
+        let pid = child.id();
+
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(_)) => continue,
+            Err(_) => {
+                return Err(ProbeTimedOut {
+                    command: std::iter::once(executable.display().to_string())
+                        .chain(args)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    pid,
+                    timeout,
+                }
+                .into());
+            }
+        };
+
+        // Many tools exit 1 or 2 after printing help, so judge by the text, not the status
+        let text = help_text(
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        );
+        if looks_like_help(&text) {
+            return Ok(text);
+        }
+    }
+
+    let attempts: Vec<String> = HelpStrategy::ALL.iter().map(|s| s.to_string()).collect();
+    anyhow::bail!(
+        "Failed to get help output from {} (tried {})",
+        executable.display(),
+        attempts.join(", ")
+    );
+}
+
+/// Combine the two output streams of a help invocation. python, ffmpeg and
+/// many argparse scripts print usage to stderr, so use whichever stream has
+/// the help, or both when each carries part of it.
+pub fn help_text(stdout: &str, stderr: &str) -> String {
+    // Windows tools end lines with CRLF, also when run under Wine or WSL
+    let (stdout, stderr) = (normalize_newlines(stdout), normalize_newlines(stderr));
+    let (stdout, stderr) = (stdout.as_str(), stderr.as_str());
+    match (looks_like_help(stdout), looks_like_help(stderr)) {
+        (true, false) => stdout.to_string(),
+        (false, true) => stderr.to_string(),
+        _ if stderr.trim().is_empty() => stdout.to_string(),
+        _ if stdout.trim().is_empty() => stderr.to_string(),
+        _ => format!("{}\n{}", stdout.trim_end(), stderr),
+    }
+}
+
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Whether text has something we can build a menu from.
+pub fn looks_like_help(text: &str) -> bool {
+    if text.trim().is_empty() {
+        return false;
+    }
+
+    text.to_lowercase().contains("usage")
+        || !parse_subcommands(text).is_empty()
+        || parse_options(text).is_ok_and(|options| !options.is_empty())
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SubcommandInfo {
+    pub name: String,
+    pub description: String,
+}
+
+/// An environment variable the help documents, e.g. under `Environment:`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EnvVarInfo {
+    pub name: String,
+    pub description: String,
+}
+
+impl std::fmt::Display for EnvVarInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.description)?;
+        if let Ok(current) = std::env::var(&self.name) {
+            write!(f, " [currently: {}]", current)?;
+        }
+        Ok(())
+    }
+}
+
+/// Variables from sections such as `Environment:`, `Environment variables:`
+/// or `ENVIRONMENT VARIABLES`, listed one per line as `NAME  description`
+/// (or `$NAME`), with the description on the same or following lines.
+pub fn parse_env_vars(help_text: &str) -> Vec<EnvVarInfo> {
+    let entry = Regex::new(r"^\s+\$?([A-Z_][A-Z0-9_]+)(?:=\S*)?(?:\s{2,}|\s*$)(.*)$")
+        .expect("env var pattern is valid");
+    let mut env_vars: Vec<EnvVarInfo> = Vec::new();
+    let mut in_section = false;
+    let mut entry_indent = 0;
+
+    for line in help_text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            in_section = line
+                .trim_end()
+                .trim_end_matches(':')
+                .to_lowercase()
+                .starts_with("environment");
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        match entry.captures(line) {
+            Some(captures) if env_vars.is_empty() || indent <= entry_indent => {
+                entry_indent = indent;
+                let name = captures[1].to_string();
+                if !env_vars.iter().any(|var| var.name == name) {
+                    env_vars.push(EnvVarInfo {
+                        name,
+                        description: captures[2].trim().to_string(),
+                    });
+                }
+            }
+            // Anything indented further continues the last description
+            _ => {
+                if let Some(last) = env_vars.last_mut()
+                    && indent > entry_indent
+                {
+                    if !last.description.is_empty() {
+                        last.description.push(' ');
+                    }
+                    last.description.push_str(line.trim());
+                }
+            }
+        }
+    }
+
+    env_vars
+}
+
+/// A sample command line from the help's `Examples:` section.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExampleInfo {
+    /// The command as printed, starting with the tool's name
+    pub command: String,
+    /// Its `# comment`, on the same line or the one before
+    pub description: String,
+}
+
+impl std::fmt::Display for ExampleInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.command)?;
+        if !self.description.is_empty() {
+            write!(f, "  # {}", self.description)?;
+        }
+        Ok(())
+    }
+}
+
+/// Command lines under `Examples:` (or `EXAMPLES`) headings that run `tool`,
+/// e.g. `tar -xf archive.tar  # Extract all files`. A leading `$ ` prompt is
+/// dropped, and prose between the examples is skipped.
+pub fn parse_examples(help_text: &str, tool: &str) -> Vec<ExampleInfo> {
+    let mut examples: Vec<ExampleInfo> = Vec::new();
+    let mut in_section = false;
+    let mut comment = String::new();
+
+    for line in help_text.lines() {
+        if let Some(heading) = section_heading(line).or_else(|| {
+            // man-style headings have no colon
+            Some(line.trim()).filter(|_| !line.starts_with(char::is_whitespace))
+        }) {
+            in_section = heading.to_lowercase().starts_with("example");
+            comment.clear();
+            continue;
+        }
+        let trimmed = line.trim();
+        if !in_section || trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(text) = trimmed.strip_prefix('#') {
+            comment = text.trim().to_string();
+            continue;
+        }
+        let trimmed = trimmed.strip_prefix("$ ").unwrap_or(trimmed);
+        let (command, inline) = match trimmed.split_once(" #") {
+            Some((command, inline)) => (command.trim(), Some(inline.trim())),
+            None => (trimmed, None),
+        };
+
+        let runs_tool = command.split_whitespace().next().is_some_and(|first| {
+            std::path::Path::new(first)
+                .file_stem()
+                .is_some_and(|stem| stem == tool)
+        });
+        if runs_tool && !examples.iter().any(|example| example.command == command) {
+            examples.push(ExampleInfo {
+                command: command.to_string(),
+                description: inline
+                    .map(str::to_string)
+                    .unwrap_or_else(|| std::mem::take(&mut comment)),
+            });
+        }
+        comment.clear();
+    }
+
+    examples
+}
+
+/// Find subcommands listed under `Commands:`, `Subcommands:` or similar
+/// headings such as docker's `Management Commands:`.
+pub fn parse_subcommands(help_text: &str) -> Vec<SubcommandInfo> {
+    let entry = Regex::new(r"^\s+([A-Za-z][\w-]*)(?:,\s*[\w-]+)*(?:\s{2,}(.*))?$").unwrap();
+    let mut subcommands: Vec<SubcommandInfo> = Vec::new();
+    let mut in_commands = false;
+
+    for line in help_text.lines() {
+        let trimmed = line.trim();
+
+        // Unindented lines ending in ':' are section headings. Other unindented
+        // lines are left alone, as git groups its commands under lines such as
+        // "start a working area (see also: git help tutorial)".
+        if !line.starts_with(char::is_whitespace) {
+            let heading = trimmed.to_lowercase();
+            // e.g. "Commands:" or winget's "The following commands are available:"
+            if heading.ends_with(':') {
+                in_commands = heading.contains("commands");
+            }
+            continue;
+        }
+
+        if !in_commands || trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(captures) = entry.captures(line) {
+            let name = captures[1].to_string();
+            if name == "help" || subcommands.iter().any(|s| s.name == name) {
+                continue;
+            }
+            subcommands.push(SubcommandInfo {
+                name,
+                description: captures
+                    .get(2)
+                    .map(|d| d.as_str().trim().to_string())
+                    .unwrap_or_default(),
+            });
+        }
+    }
+
+    subcommands
+}
+
+/// The options listed in a tool's help text.
+pub fn parse_help(text: &str) -> Vec<OptionInfo> {
+    let mut options = parse_options(text).expect("help patterns are valid");
+    link_conflicts(&mut options);
+    options
+}
+
+/// Record which options can't be combined, from descriptions such as
+/// `[conflicts with --json]` or "cannot be used with -q". Help often says so
+/// on only one side, so every conflict is recorded on both.
+fn link_conflicts(options: &mut [OptionInfo]) {
+    let phrase = Regex::new(
+        r"(?i)(?:conflicts with|cannot be used with|can't be used with|cannot be combined with|incompatible with|mutually exclusive with|not compatible with)((?:[\s,:]*(?:or|and)?[\s'`\[]*--?[A-Za-z0-9][\w-]*[\]'`]*)+)",
+    )
+    .expect("conflict pattern is valid");
+    let flag = Regex::new(r"--?[A-Za-z0-9][\w-]*").expect("flag pattern is valid");
+
+    let mut pairs = Vec::new();
+    for (i, option) in options.iter().enumerate() {
+        for captures in phrase.captures_iter(&option.description) {
+            for mentioned in flag.find_iter(&captures[1]) {
+                if let Some(j) = options
+                    .iter()
+                    .position(|other| other.flags.iter().any(|f| f == mentioned.as_str()))
+                    .filter(|&j| j != i)
+                {
+                    pairs.push((i, j));
+                }
+            }
+        }
+    }
+
+    for (i, j) in pairs {
+        for (from, to) in [(i, j), (j, i)] {
+            let flag = options[to].preferred_flag().to_string();
+            if !options[from].conflicts.contains(&flag) {
+                options[from].conflicts.push(flag);
+            }
+        }
+    }
+}
+
+fn parse_options(help_text: &str) -> anyhow::Result<Vec<OptionInfo>> {
+    if is_windows_dialect(help_text) {
+        return Ok(parse_windows_options(help_text));
+    }
+
+    let lines: Vec<&str> = help_text.lines().collect();
+    let mut options: Vec<OptionInfo> = Vec::new();
+    let mut section: Option<String> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        // Unindented lines ending in ':' head a section, e.g. clap's `Options:`,
+        // `Arguments:` or `Display Options:`. Flags are taken from any of them.
+        // Example command lines aren't options, so their sections are skipped.
+        if let Some(heading) = section_heading(line) {
+            section = Some(heading.to_string()).filter(|heading| !is_example_heading(heading));
+            i += 1;
+            continue;
+        }
+
+        if section.is_none() {
+            i += 1;
+            continue;
+        }
+
+        // Check if this is a flag line (starts with 2 spaces and contains -)
+        if line.starts_with("  ") && line.trim().starts_with('-') {
+            // Parse the flag line; single-line layouts put the description
+            // after a gap on the same line
+            let (flag_part, inline) = split_inline_description(line.trim());
+            let spec = parse_flag_line(flag_part);
+
+            // Collect description from subsequent lines
+            let mut description_lines: Vec<&str> = inline.into_iter().collect();
+            i += 1;
+            while i < lines.len() {
+                if lines[i].starts_with("          ") {
+                    description_lines.push(lines[i].trim());
+                } else if !(lines[i].trim().is_empty()
+                    && lines
+                        .get(i + 1)
+                        .is_some_and(|next| next.starts_with("          ")))
+                {
+                    // Blank lines only continue a description in clap's long help
+                    break;
+                }
+                i += 1;
+            }
+            let possible_values = parse_possible_values(flag_part, &description_lines)?;
+            let repeatable = is_repeatable(flag_part, &description_lines.join(" "))?;
+            let (description, default) = split_default(&description_lines.join(" "))?;
+
+            // Some tools list an option again under a second heading
+            if !spec.flags.is_empty() && !options.iter().any(|option| option.flags == spec.flags) {
+                options.push(OptionInfo {
+                    value: spec.arity(&possible_values),
+                    value_type: ValueType::infer(spec.value_name.as_deref(), &description),
+                    flags: spec.flags,
+                    description,
+                    possible_values,
+                    default,
+                    repeatable,
+                    section: section.clone(),
+                    value_name: spec.value_name,
+                    joined_value: spec.joined,
+                    ..Default::default()
+                });
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    // Pick up single-line entries outside any section, skipping flags the
+    // first pass already has
+    for option in parse_options_fallback(help_text)? {
+        if !option
+            .flags
+            .iter()
+            .any(|flag| options.iter().any(|known| known.flags.contains(flag)))
+        {
+            options.push(option);
+        }
+    }
+
+    Ok(options)
+}
+
+/// Split `-f, --file=FILE   read from FILE` into its flags and description.
+/// The gap is usually two or more spaces, but argp leaves a single space when
+/// the flags fill their column, so there the description starts at the first
+/// word that is neither a flag nor a value placeholder.
+fn split_inline_description(line: &str) -> (&str, Option<&str>) {
+    if let Some((flags, description)) = line.split_once("  ") {
+        return (flags, Some(description.trim()));
+    }
+
+    let is_flag_word = |word: &str| {
+        let word = word.trim_end_matches(',');
+        // argparse repeats multi-value placeholders: "-c <name> [<file> ...]"
+        word.starts_with(['-', '<', '[', '{'])
+            || word.ends_with([']', '>', '}'])
+            || word
+                .trim_end_matches([',', '.'])
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+    };
+    let mut offset = 0;
+    for word in line.split(' ') {
+        if !word.is_empty() && !is_flag_word(word) {
+            return (line[..offset].trim_end(), Some(&line[offset..]));
+        }
+        offset += word.len() + 1;
+    }
+    (line, None)
+}
+
+fn is_example_heading(heading: &str) -> bool {
+    let heading = heading.to_lowercase();
+    heading == "usage" || heading.starts_with("example")
+}
+
+/// The name of a section heading such as `Global Options:`, or argp's
+/// ` Main operation mode:`, indented by a single space.
+fn section_heading(line: &str) -> Option<&str> {
+    let line = line.strip_prefix(' ').unwrap_or(line);
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    line.trim_end()
+        .strip_suffix(':')
+        .filter(|name| !name.is_empty())
+}
+
+/// The flags of one option and the value they take, if any.
+#[derive(Debug, Default, PartialEq)]
+struct FlagSpec {
+    flags: Vec<String>,
+    value: ValueArity,
+    /// The help's placeholder for the value, e.g. `FILE`
+    value_name: Option<String>,
+    /// Whether the help joins the value to the flag, as in `--file=FILE`,
+    /// or attaches it to a short one, as in `-Dkey=value`
+    joined: bool,
+}
+
+impl FlagSpec {
+    /// Add one flag as written in the help, e.g. `--file=FILE`,
+    /// `--color[=WHEN]` or `-o`, with the word that follows it.
+    fn push(&mut self, word: &str, next: Option<&str>) {
+        // clap marks repeatable flags as "--verbose..."
+        let word = word.trim_end_matches("...");
+
+        if let Some((flag, value)) = split_attached(word) {
+            self.flags.push(flag.to_string());
+            let arity = if value.starts_with('[') {
+                ValueArity::Optional
+            } else {
+                ValueArity::Required
+            };
+            self.value = self.value.max(arity);
+            self.value_name
+                .get_or_insert_with(|| value.replace(['<', '>', '[', ']'], ""));
+            self.joined = true;
+        } else if let Some((flag, value)) = word.split_once("[=") {
+            // An optional value is left out unless the user adds it
+            self.flags.push(flag.to_string());
+            self.value = self.value.max(ValueArity::Optional);
+            self.value_name
+                .get_or_insert_with(|| placeholder_name(value));
+            self.joined |= flag.starts_with("--");
+        } else if let Some((flag, value)) = word.split_once('=') {
+            self.flags.push(flag.to_string());
+            self.value = ValueArity::Required;
+            self.value_name
+                .get_or_insert_with(|| placeholder_name(value));
+            self.joined |= flag.starts_with("--");
+        } else {
+            self.flags.push(word.to_string());
+            if let Some(next) = next {
+                // "--color [WHEN]" may be left without a value, "--file <FILE>" may not
+                let arity = if next.starts_with('[') {
+                    ValueArity::Optional
+                } else {
+                    ValueArity::Required
+                };
+                self.value = self.value.max(arity);
+                self.value_name
+                    .get_or_insert_with(|| placeholder_name(next));
+            }
+        }
+    }
+
+    /// The option's value, counting a list of accepted values as a
+    /// required one when the flags show no placeholder.
+    fn arity(&self, possible_values: &[String]) -> ValueArity {
+        if self.value == ValueArity::None && !possible_values.is_empty() {
+            ValueArity::Required
+        } else {
+            self.value
+        }
+    }
+}
+
+/// A short flag written with its value attached, such as Java's
+/// `-Dkey=value`, `-I<dir>` or `-O[LEVEL]`, split into `-D` and the rest.
+/// Single-dash long options like find's `-name` aren't split.
+fn split_attached(word: &str) -> Option<(&str, &str)> {
+    let rest = word
+        .strip_prefix('-')
+        .filter(|rest| !rest.starts_with('-'))?;
+    let first = rest.chars().next().filter(char::is_ascii_alphanumeric)?;
+    let (flag, value) = word.split_at(1 + first.len_utf8());
+    let attached = (value.starts_with(['<', '[']) && !value.starts_with("[="))
+        || (value.starts_with(|c: char| c.is_ascii_alphabetic()) && value.contains('='));
+    attached.then_some((flag, value))
+}
+
+/// `FILE` from `<FILE>`, `FILE]`, `<FILE>...` or robocopy's `copyflag[s]`.
+fn placeholder_name(raw: &str) -> String {
+    let name = raw.trim_matches(|c| matches!(c, '<' | '>' | '[' | ']' | '.'));
+    name.split('[').next().unwrap_or(name).to_string()
+}
+
+// Patterns applied to every option, compiled once
+static CHOICES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{[^}]*\}").unwrap());
+static INLINE_VALUES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[possible values: ([^\]]+)\]").unwrap());
+static BRACED_VALUES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{([\w.-]+(?:,\s*[\w.-]+)+)\}").unwrap());
+static VALUE_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^- ([^\s:]+)").unwrap());
+static REPEATABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(can|may) be (used|given|specified|passed) (multiple|several|up to \d+) times|\b(can|may) be repeated|\bmore than once\b|\brepeatable\b",
+    )
+    .unwrap()
+});
+static DEFAULT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\s*(?:\[default: ([^\]]*)\]|\(default: ([^)]*)\))"#).unwrap());
+
+fn parse_flag_line(line: &str) -> FlagSpec {
+    // Examples: "-c, --config <CONFIG>", "-f, --file=FILE", "--init" or
+    // argparse's "--mode {a,b}"
+    let mut spec = FlagSpec::default();
+
+    // Choice lists contain commas of their own, so take them out first
+    let line = CHOICES.replace_all(line, "<CHOICE>");
+
+    // Split by comma to handle multiple flags. Commas inside a value, as in
+    // tar's `--pax-option=keyword[[:]=value][,keyword[[:]=value]]...`,
+    // leave parts that aren't flags.
+    for part in line.split(',') {
+        let mut words = part.split_whitespace();
+        if let Some(word) = words.next().filter(|word| word.starts_with('-')) {
+            spec.push(word, words.next());
+        }
+    }
+
+    spec
+}
+
+/// Values an option is limited to, from clap's `[possible values: a, b]` and
+/// indented `Possible values:` list, or argparse's `{a,b,c}`.
+fn parse_possible_values(flag_part: &str, description: &[&str]) -> anyhow::Result<Vec<String>> {
+    let split = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+
+    let text = description.join(" ");
+    if let Some(captures) = INLINE_VALUES.captures(&text) {
+        return Ok(split(&captures[1]));
+    }
+
+    // clap's long help puts each value on its own line: "- json: JSON output"
+    if let Some(start) = description
+        .iter()
+        .position(|line| line.eq_ignore_ascii_case("possible values:"))
+    {
+        let values: Vec<String> = description[start + 1..]
+            .iter()
+            .map_while(|line| VALUE_ITEM.captures(line).map(|c| c[1].to_string()))
+            .collect();
+        if !values.is_empty() {
+            return Ok(values);
+        }
+    }
+
+    if let Some(captures) = BRACED_VALUES
+        .captures(flag_part)
+        .or_else(|| BRACED_VALUES.captures(&text))
+    {
+        return Ok(split(&captures[1]));
+    }
+
+    Ok(Vec::new())
+}
+
+/// Whether an option may be given more than once: a `<PATH>...` style
+/// placeholder, or a description saying so.
+fn is_repeatable(flag_part: &str, description: &str) -> anyhow::Result<bool> {
+    Ok(flag_part.contains("...") || REPEATABLE.is_match(description))
+}
+
+/// Take a `[default: 8080]` or `(default: info)` marker out of a description,
+/// returning the remaining text and the default. The value runs to the closing
+/// bracket, so it may contain spaces and colons.
+fn split_default(description: &str) -> anyhow::Result<(String, Option<String>)> {
+    let Some(captures) = DEFAULT.captures(description) else {
+        return Ok((description.trim().to_string(), None));
+    };
+    let value = captures
+        .get(1)
+        .or_else(|| captures.get(2))
+        .map(|m| m.as_str().trim())
+        .unwrap_or_default();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+
+    let remaining = DEFAULT.replace(description, "").trim().to_string();
+    Ok((remaining, Some(value.to_string())))
+}
+
+/// Remove the backspace sequences man uses for bold (`X\bX`) and underline
+/// (`_\bX`) when writing to a pipe, along with any ANSI styling.
+pub fn strip_overstrike(text: &str) -> String {
+    let mut result: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            result.pop();
+        } else {
+            result.push(c);
+        }
+    }
+
+    let ansi = Regex::new(r"\x1b\[[0-9;]*m").expect("valid regex");
+    ansi.replace_all(&result.into_iter().collect::<String>(), "")
+        .into_owned()
+}
+
+/// Parse the OPTIONS section of a rendered man page. Flags sit at one indent
+/// with their description either after a gap on the same line or on the more
+/// deeply indented lines that follow.
+/// The options in the OPTIONS section of a man page, as `man` prints it to a pipe.
+pub fn parse_man_page(page: &str) -> Vec<OptionInfo> {
+    let mut options =
+        parse_man_options(&strip_overstrike(page)).expect("man page patterns are valid");
+    link_conflicts(&mut options);
+    options
+}
+
+fn parse_man_options(page: &str) -> anyhow::Result<Vec<OptionInfo>> {
+    let flag_line = Regex::new(r"^(\s+)(-\S.*?)(?:\s{2,}(\S.*))?$")?;
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let mut options = Vec::new();
+    let mut lines = page
+        .lines()
+        .skip_while(|line| !is_options_heading(line))
+        .skip(1)
+        // The next unindented line is the following section's heading
+        .take_while(|line| line.trim().is_empty() || line.starts_with(char::is_whitespace))
+        .peekable();
+
+    while let Some(line) = lines.next() {
+        let Some(captures) = flag_line.captures(line) else {
+            continue;
+        };
+        let flag_indent = captures[1].len();
+        let spec = parse_man_flags(&captures[2]);
+
+        let mut description: Vec<&str> = captures.get(3).map(|m| m.as_str()).into_iter().collect();
+        while let Some(next) = lines.peek() {
+            if next.trim().is_empty() {
+                if !description.is_empty() {
+                    break;
+                }
+            } else if indent(next) > flag_indent {
+                description.push(next.trim());
+            } else {
+                break;
+            }
+            lines.next();
+        }
+
+        if spec.flags.is_empty() {
+            continue;
+        }
+        let description = description.join(" ");
+        let possible_values = parse_possible_values(&captures[2], &[&description])?;
+        let repeatable = is_repeatable(&captures[2], &description)?;
+        let (description, default) = split_default(&description)?;
+        options.push(OptionInfo {
+            value: spec.arity(&possible_values),
+            value_type: ValueType::infer(spec.value_name.as_deref(), &description),
+            flags: spec.flags,
+            description,
+            possible_values,
+            default,
+            repeatable,
+            value_name: spec.value_name,
+            joined_value: spec.joined,
+            ..Default::default()
+        });
+    }
+
+    Ok(options)
+}
+
+fn is_options_heading(line: &str) -> bool {
+    let heading = line.trim_end();
+    !heading.starts_with(char::is_whitespace)
+        && (heading == "OPTIONS" || heading.ends_with(" OPTIONS"))
+}
+
+/// Flags in man style, e.g. "-f FILE, --file=FILE" or "--color[=WHEN]". A
+/// value is required when a placeholder follows the flag; an optional
+/// `[=VALUE]` doesn't count.
+fn parse_man_flags(line: &str) -> FlagSpec {
+    let mut spec = FlagSpec::default();
+
+    for part in line.split(", ") {
+        let mut words = part.split_whitespace();
+        if let Some(word) = words.next().filter(|word| word.starts_with('-')) {
+            spec.push(word, words.next());
+        }
+    }
+
+    spec
+}
+
+/// Options from a hand-written usage line such as
+/// `Usage: myscript [-v] [-o output] input`, for scripts whose help has
+/// nothing more to go on. Only bracketed flags count; a cluster such as
+/// `[-abc]` is three separate switches, and `[-q | -v]` two.
+pub fn parse_usage(text: &str) -> Vec<OptionInfo> {
+    // One level of nesting, for operands such as `[-D [address:]port]`
+    let group =
+        Regex::new(r"\[\s*(-[^\[\]]*(?:\[[^\[\]]*\][^\[\]]*)*)\]").expect("usage pattern is valid");
+    let mut options: Vec<OptionInfo> = Vec::new();
+
+    for line in usage_lines(text) {
+        for captures in group.captures_iter(line) {
+            for alternative in captures[1].split('|') {
+                let words: Vec<&str> = alternative.split_whitespace().collect();
+                let Some(&first) = words.first().filter(|word| word.starts_with('-')) else {
+                    continue;
+                };
+
+                let specs: Vec<FlagSpec> = match first.strip_prefix('-') {
+                    Some(cluster)
+                        if words.len() == 1
+                            && cluster.len() > 1
+                            && cluster.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                    {
+                        cluster
+                            .chars()
+                            .map(|c| FlagSpec {
+                                flags: vec![format!("-{}", c)],
+                                ..FlagSpec::default()
+                            })
+                            .collect()
+                    }
+                    _ => {
+                        let mut spec = FlagSpec::default();
+                        spec.push(first, words.get(1).copied());
+                        // The brackets make the whole option optional, not its operand,
+                        // and `[bind_address:]port` names the operand as a whole
+                        if words.len() > 1 {
+                            spec.value = ValueArity::Required;
+                            spec.value_name = Some(words[1..].join(" ").replace(['[', ']'], ""));
+                        }
+                        vec![spec]
+                    }
+                };
+
+                for spec in specs {
+                    if options.iter().any(|option| option.flags == spec.flags) {
+                        continue;
+                    }
+                    options.push(OptionInfo {
+                        value: spec.value,
+                        value_type: ValueType::infer(spec.value_name.as_deref(), ""),
+                        flags: spec.flags,
+                        description: String::new(),
+                        value_name: spec.value_name,
+                        joined_value: spec.joined,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+
+    options
+}
+
+/// The `Usage:` line and the indented lines continuing it.
+fn usage_lines(text: &str) -> Vec<&str> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !line.trim_start().to_lowercase().starts_with("usage"));
+    let Some(first) = lines.next() else {
+        return Vec::new();
+    };
+
+    std::iter::once(first)
+        .chain(
+            lines.take_while(|line| {
+                line.starts_with(char::is_whitespace) && !line.trim().is_empty()
+            }),
+        )
+        .collect()
+}
+
+/// Options from single-line `-f, --flag  description` entries anywhere in
+/// the help, for layouts the section-based pass misses. Only indented lines
+/// starting with a flag count, and example sections (and a `Usage:` heading
+/// on a line of its own) are skipped, since their command lines look like
+/// options.
+fn parse_options_fallback(help_text: &str) -> anyhow::Result<Vec<OptionInfo>> {
+    let flag_start = Regex::new(r"^--?[A-Za-z0-9?#]")?;
+    let mut options: Vec<OptionInfo> = Vec::new();
+    let mut skipping = false;
+
+    for line in help_text.lines() {
+        // curl's `Usage: curl [options...] <url>` is followed directly by options
+        if let Some(heading) = section_heading(line) {
+            skipping = is_example_heading(heading);
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            skipping &= line.trim().is_empty();
+            continue;
+        }
+        if skipping || !flag_start.is_match(line.trim_start()) {
+            continue;
+        }
+        let Some((flags_str, description)) = split_flag_columns(line.trim()) else {
+            continue;
+        };
+        let spec = parse_flag_line(flags_str);
+        if spec.flags.is_empty() || options.iter().any(|option| option.flags == spec.flags) {
+            continue;
+        }
+
+        let possible_values = parse_possible_values(flags_str, &[description])?;
+        let repeatable = is_repeatable(flags_str, description)?;
+        let (description, default) = split_default(description)?;
+
+        options.push(OptionInfo {
+            value: spec.arity(&possible_values),
+            value_type: ValueType::infer(spec.value_name.as_deref(), &description),
+            flags: spec.flags,
+            description,
+            possible_values,
+            default,
+            repeatable,
+            value_name: spec.value_name,
+            joined_value: spec.joined,
+            ..Default::default()
+        });
+    }
+
+    Ok(options)
+}
+
+/// Split `-n,  --lines=NUM  print NUM lines` at the gap before the
+/// description. Columns are separated by two or more spaces, but a flag list
+/// may contain such a gap too, after a comma. Without any such gap, the
+/// description starts at the first word that can't be part of the flags.
+fn split_flag_columns(line: &str) -> Option<(&str, &str)> {
+    let gap = Regex::new(r"\s{2,}").expect("gap pattern is valid");
+    for found in gap.find_iter(line) {
+        let (flags, rest) = (&line[..found.start()], &line[found.end()..]);
+        if !(flags.ends_with(',') && rest.starts_with('-')) {
+            return Some((flags, rest.trim()));
+        }
+    }
+    match split_inline_description(line) {
+        (flags, Some(description)) => Some((flags, description.trim())),
+        (_, None) => None,
+    }
+}
+
+/// A Windows-style flag such as `/S`, `/LEV:n` or `/?`, split into the flag
+/// and what follows its colon.
+fn slash_flag(word: &str) -> Option<(&str, Option<&str>)> {
+    let rest = word.strip_prefix('/')?;
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '?' | '+' | '-' | '#')))
+        .unwrap_or(rest.len());
+    if end == 0 {
+        return None;
+    }
+    let (name, tail) = word.split_at(end + 1);
+    match tail {
+        "" => Some((name, None)),
+        _ if tail.starts_with(':') || tail.starts_with("[:") => Some((name, Some(tail))),
+        // Paths such as /usr/bin aren't flags
+        _ => None,
+    }
+}
+
+/// Whether help is written in the Windows dialect, with `/X` flags. Judged
+/// by the text rather than the OS, so it also holds for Windows tools
+/// probed under Wine or WSL.
+fn is_windows_dialect(help_text: &str) -> bool {
+    let (mut slash, mut dash) = (0, 0);
+    for word in help_text
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+    {
+        if slash_flag(word).is_some() {
+            slash += 1;
+        } else if word.len() > 1 && word.starts_with('-') && !word.starts_with("---") {
+            dash += 1;
+        }
+    }
+    slash >= 2 && slash > dash
+}
+
+/// Options from Windows-style help, e.g. robocopy's `/LEV:n :: only copy
+/// the top n LEVels` or ipconfig's `/all   Display full configuration`.
+fn parse_windows_options(help_text: &str) -> Vec<OptionInfo> {
+    let mut options: Vec<OptionInfo> = Vec::new();
+    let mut section = None;
+    // Indent of the last option's flag, so deeper lines continue its description
+    let mut option_indent = None;
+
+    for line in help_text.lines() {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if !trimmed.starts_with('/') {
+            // robocopy heads sections with ":: Copy options :", ipconfig with "Options:"
+            let heading = trimmed.trim_start_matches(':').trim();
+            if let Some(name) = heading.strip_suffix(':').map(str::trim)
+                && !name.is_empty()
+                && name.split_whitespace().count() <= 4
+            {
+                section = Some(name.to_string());
+                option_indent = None;
+            } else if !trimmed.is_empty()
+                && option_indent.is_some_and(|flag_indent| indent > flag_indent)
+                && let Some(option) = options.last_mut()
+            {
+                let more = trimmed.trim_start_matches("::").trim();
+                option.description = format!("{} {}", option.description, more)
+                    .trim()
+                    .to_string();
+            } else {
+                option_indent = None;
+            }
+            continue;
+        }
+
+        // Usage synopses list flags too, e.g. "/renew [adapter] | /release [adapter]"
+        let in_usage = section
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case("usage"));
+        let (flag_part, description) = split_windows_description(trimmed);
+        let spec = parse_windows_flags(flag_part);
+        if in_usage
+            || spec.flags.is_empty()
+            || options.iter().any(|option| option.flags == spec.flags)
+        {
+            option_indent = None;
+            continue;
+        }
+
+        options.push(OptionInfo {
+            value: spec.value,
+            flags: spec.flags,
+            description: description.to_string(),
+            repeatable: flag_part.contains("..."),
+            section: section.clone(),
+            value_type: ValueType::infer(spec.value_name.as_deref(), description),
+            value_name: spec.value_name,
+            joined_value: spec.joined,
+            ..Default::default()
+        });
+        option_indent = Some(indent);
+    }
+
+    options
+}
+
+/// Split a Windows option line at `::`, ` - ` or a wide gap.
+fn split_windows_description(line: &str) -> (&str, &str) {
+    if let Some((flags, description)) = line.split_once("::") {
+        return (flags.trim(), description.trim());
+    }
+    if let Some((flags, description)) = line.split_once(" - ") {
+        return (flags.trim(), description.trim());
+    }
+    match line.split_once("  ") {
+        Some((flags, description)) => (flags.trim(), description.trim()),
+        None => (line, ""),
+    }
+}
+
+/// `/LEV:n` takes a value joined by a colon, `/XF file [file]...` a separate
+/// one, and `/NAME[:value]` an optional one. Flags keep their slash.
+fn parse_windows_flags(flag_part: &str) -> FlagSpec {
+    let mut spec = FlagSpec::default();
+    let mut words = flag_part.split_whitespace().peekable();
+
+    while let Some(word) = words.next() {
+        let Some((flag, value)) = slash_flag(word) else {
+            continue;
+        };
+        spec.flags.push(flag.to_string());
+
+        if let Some(value) = value {
+            let (arity, value) = match value.strip_prefix("[:") {
+                Some(value) => (ValueArity::Optional, value),
+                None => (ValueArity::Required, &value[1..]),
+            };
+            spec.value = spec.value.max(arity);
+            spec.joined = true;
+            spec.value_name
+                .get_or_insert_with(|| placeholder_name(value));
+        } else if let Some(next) = words.next_if(|next| slash_flag(next).is_none()) {
+            let arity = if next.starts_with('[') {
+                ValueArity::Optional
+            } else {
+                ValueArity::Required
+            };
+            spec.value = spec.value.max(arity);
+            spec.value_name
+                .get_or_insert_with(|| placeholder_name(next));
+        }
+    }
+
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(subcommands: &[SubcommandInfo]) -> Vec<&str> {
+        subcommands.iter().map(|sub| sub.name.as_str()).collect()
+    }
+
+    #[test]
+    fn clap_commands_are_found_without_help() {
+        let help = "\
+A tool
+
+Usage: tool [OPTIONS] <COMMAND>
+
+Commands:
+  build  Compile the project
+  test   Run the tests
+  help   Print this message or the help of the given subcommand(s)
+
+Options:
+  -v, --verbose  Use verbose output
+";
+        let subcommands = parse_subcommands(help);
+        assert_eq!(names(&subcommands), ["build", "test"]);
+        assert_eq!(subcommands[0].description, "Compile the project");
+        // Options don't leak into the subcommands, nor subcommands into the options
+        assert!(
+            parse_help(help)
+                .iter()
+                .all(|option| option.flags.iter().all(|flag| flag.starts_with('-')))
+        );
+    }
+
+    #[test]
+    fn aliased_and_sectioned_commands_are_found_once() {
+        let help = "\
+Usage: cargo [OPTIONS] [COMMAND]
+
+Commands:
+    build, b    Compile the current package
+    check, c    Analyze the current package and report errors
+
+Management Commands:
+  builder     Manage builds
+  build       Compile again
+  container
+";
+        let subcommands = parse_subcommands(help);
+        assert_eq!(
+            names(&subcommands),
+            ["build", "check", "builder", "container"]
+        );
+        assert_eq!(subcommands[3].description, "");
+    }
+
+    #[test]
+    fn tools_without_commands_have_no_subcommands() {
+        let help = "\
+Usage: grep [OPTION]... PATTERNS [FILE]...
+
+Options:
+  -i, --ignore-case         ignore case distinctions
+";
+        assert!(parse_subcommands(help).is_empty());
+    }
+
+    #[test]
+    fn help_strategies_put_the_subcommand_where_the_tool_expects_it() {
+        let subcommand = vec!["remote".to_string(), "add".to_string()];
+        assert_eq!(
+            HelpStrategy::LongFlag.args(&subcommand),
+            ["remote", "add", "--help"]
+        );
+        assert_eq!(
+            HelpStrategy::HelpSubcommand.args(&subcommand),
+            ["help", "remote", "add"]
+        );
+        assert_eq!(HelpStrategy::SlashQuestion.args(&[]), ["/?"]);
+        assert_eq!(HelpStrategy::NoArgs.args(&subcommand), ["remote", "add"]);
+    }
+
+    fn option<'a>(options: &'a [OptionInfo], flag: &str) -> &'a OptionInfo {
+        options
+            .iter()
+            .find(|option| option.flags.iter().any(|f| f == flag))
+            .unwrap_or_else(|| panic!("{} wasn't parsed", flag))
+    }
+
+    #[test]
+    fn inline_possible_values_are_listed() {
+        let options = parse_help(
+            "\
+Options:
+      --format <FORMAT>  Output format [possible values: json, yaml, table]
+  -q, --quiet            Say less
+",
+        );
+        assert_eq!(
+            option(&options, "--format").possible_values,
+            ["json", "yaml", "table"]
+        );
+        assert!(option(&options, "--quiet").possible_values.is_empty());
+    }
+
+    #[test]
+    fn clap_long_help_lists_possible_values_on_their_own_lines() {
+        let options = parse_help(
+            "\
+Options:
+      --color <WHEN>
+          Coloring
+
+          Possible values:
+          - auto:   Detect a terminal
+          - always: Always color
+          - never:  Never color
+
+  -h, --help
+          Print help
+",
+        );
+        assert_eq!(
+            option(&options, "--color").possible_values,
+            ["auto", "always", "never"]
+        );
+    }
+
+    #[test]
+    fn argparse_braces_are_choices_and_need_a_value() {
+        let options = parse_help(
+            "\
+options:
+  --level {debug,info,warn}
+                        log level
+  -m {a,b}, --mode {a,b}
+                        mode to use
+",
+        );
+        let level = option(&options, "--level");
+        assert_eq!(level.possible_values, ["debug", "info", "warn"]);
+        assert_eq!(level.value, ValueArity::Required);
+        let mode = option(&options, "-m");
+        assert_eq!(mode.flags, ["-m", "--mode"]);
+        assert_eq!(mode.possible_values, ["a", "b"]);
+    }
+
+    #[test]
+    fn defaults_are_taken_out_of_the_description() {
+        let options = parse_help(
+            "\
+Options:
+  -p, --port <PORT>    Port to listen on [default: 8080]
+      --level <LEVEL>  Log level (default: info)
+      --addr <ADDR>    Bind address [default: http://0.0.0.0:80]
+      --name <NAME>    Greeting [default: \"hello world\"]
+",
+        );
+        let port = option(&options, "--port");
+        assert_eq!(port.default.as_deref(), Some("8080"));
+        assert_eq!(port.description, "Port to listen on");
+        assert_eq!(option(&options, "--level").default.as_deref(), Some("info"));
+        assert_eq!(
+            option(&options, "--addr").default.as_deref(),
+            Some("http://0.0.0.0:80")
+        );
+        assert_eq!(
+            option(&options, "--name").default.as_deref(),
+            Some("hello world")
+        );
+    }
+
+    #[test]
+    fn options_without_a_default_have_none() {
+        let options = parse_help("Options:\n  --quiet  Say less\n");
+        assert_eq!(option(&options, "--quiet").default, None);
+        assert_eq!(option(&options, "--quiet").description, "Say less");
+    }
+
+    #[test]
+    fn repeatable_options_are_marked() {
+        let options = parse_help(
+            "\
+Options:
+  -v, --verbose...          More output
+  -I, --include <PATH>...   Search path
+      --tag <TAG>           Tag to add, can be used multiple times
+      --exclude <GLOB>      May be repeated
+  -q, --quiet               Less output
+",
+        );
+        for flag in ["--verbose", "--include", "--tag", "--exclude"] {
+            assert!(option(&options, flag).repeatable, "{}", flag);
+        }
+        assert!(!option(&options, "--quiet").repeatable);
+        // The marker isn't part of the flag
+        assert_eq!(option(&options, "-v").flags, ["-v", "--verbose"]);
+        assert_eq!(option(&options, "--verbose").value, ValueArity::None);
+    }
+
+    #[test]
+    fn options_are_collected_from_every_section_with_its_name() {
+        let options = parse_help(
+            "\
+Usage: fd [OPTIONS] [pattern] [path]...
+
+Arguments:
+  [pattern]  the search pattern
+  [path]...  the root directories
+
+Options:
+  -H, --hidden  Search hidden files
+
+Display Options:
+  -l, --list-details  Use a long listing format
+
+Global Options:
+      --color <WHEN>  When to use colors
+",
+        );
+        let sections: Vec<(&str, Option<&str>)> = options
+            .iter()
+            .map(|option| (option.preferred_flag(), option.section.as_deref()))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                ("--hidden", Some("Options")),
+                ("--list-details", Some("Display Options")),
+                ("--color", Some("Global Options")),
+            ]
+        );
+    }
+
+    #[test]
+    fn usage_and_example_lines_are_not_options() {
+        let options = parse_help(
+            "\
+Usage:
+  tool --init [--force]
+
+Examples:
+  --init  sets things up
+
+Options:
+  -f, --force  Overwrite
+",
+        );
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].flags, ["-f", "--force"]);
+    }
+
+    #[test]
+    fn coreutils_equals_values_are_split_from_the_flag() {
+        let options = parse_help(
+            "\
+Usage: ls [OPTION]... [FILE]...
+
+Mandatory arguments to long options are mandatory for short options too.
+  -a, --all                  do not ignore entries starting with .
+      --block-size=SIZE      with -l, scale sizes by SIZE when printing them;
+  -I, --ignore=PATTERN       do not list implied entries matching shell PATTERN
+  -T, --tabsize=COLS         assume tab stops at each COLS instead of 8
+",
+        );
+        let block_size = option(&options, "--block-size");
+        assert_eq!(block_size.flags, ["--block-size"]);
+        assert_eq!(block_size.value, ValueArity::Required);
+        assert_eq!(block_size.value_name.as_deref(), Some("SIZE"));
+        assert!(block_size.joined_value);
+
+        let ignore = option(&options, "-I");
+        assert_eq!(ignore.flags, ["-I", "--ignore"]);
+        assert_eq!(ignore.value_name.as_deref(), Some("PATTERN"));
+        assert_eq!(option(&options, "--all").value, ValueArity::None);
+    }
+
+    #[test]
+    fn space_separated_values_are_not_joined() {
+        let options = parse_help("Options:\n  -o, --output <FILE>  Write to FILE\n");
+        let output = option(&options, "--output");
+        assert_eq!(output.value, ValueArity::Required);
+        assert!(!output.joined_value);
+    }
+
+    #[test]
+    fn bracketed_values_are_optional_and_angled_ones_required() {
+        let options = parse_help(
+            "\
+Options:
+      --color[=WHEN]     colorize the output
+      --pager [PAGER]    page the output
+  -O[LEVEL]              optimize
+  -c, --config <FILE>    config file
+  -n, --dry-run          do nothing
+",
+        );
+        let arity = |flag| option(&options, flag).value;
+        assert_eq!(arity("--color"), ValueArity::Optional);
+        assert_eq!(arity("--pager"), ValueArity::Optional);
+        assert_eq!(arity("-O"), ValueArity::Optional);
+        assert_eq!(arity("--config"), ValueArity::Required);
+        assert_eq!(arity("--dry-run"), ValueArity::None);
+        assert_eq!(
+            option(&options, "--color").value_name.as_deref(),
+            Some("WHEN")
+        );
+    }
+
+    #[test]
+    fn robocopy_slash_flags_are_parsed_from_crlf_help() {
+        let raw = include_str!("../tests/fixtures/help/robocopy.txt");
+        assert!(raw.contains("\r\n"));
+        let options = parse_help(&help_text(raw, ""));
+
+        let flags: Vec<&str> = options.iter().map(OptionInfo::preferred_flag).collect();
+        assert_eq!(
+            flags,
+            [
+                "/S", "/E", "/LEV", "/Z", "/B", "/ZB", "/COPY", "/MIR", "/A", "/XF", "/XD", "/R",
+                "/W", "/L", "/LOG"
+            ]
+        );
+
+        let lev = option(&options, "/LEV");
+        assert_eq!(lev.value, ValueArity::Required);
+        assert_eq!(lev.value_name.as_deref(), Some("n"));
+        assert!(lev.joined_value);
+        assert_eq!(lev.section.as_deref(), Some("Copy options"));
+
+        let exclude = option(&options, "/XF");
+        assert!(exclude.repeatable);
+        assert!(!exclude.joined_value);
+        assert_eq!(exclude.section.as_deref(), Some("File Selection Options"));
+
+        // Continuation lines join the description; "::" separators don't leak in
+        let copy = option(&options, "/COPY");
+        assert!(copy.description.ends_with("U=aUditing info)."));
+        assert!(!copy.description.contains("::"));
+        assert_eq!(option(&options, "/S").value, ValueArity::None);
+    }
+
+    #[test]
+    fn winget_lists_commands_and_dash_options() {
+        let text = help_text(include_str!("../tests/fixtures/help/winget.txt"), "");
+        assert!(!text.contains('\r'));
+
+        let subcommands = parse_subcommands(&text);
+        assert_eq!(
+            names(&subcommands),
+            [
+                "install",
+                "show",
+                "source",
+                "search",
+                "list",
+                "upgrade",
+                "uninstall"
+            ]
+        );
+        let options = parse_help(&text);
+        assert_eq!(option(&options, "-v").flags, ["-v", "--version"]);
+        assert_eq!(option(&options, "--logs").flags, ["--logs", "--open-logs"]);
+        assert!(
+            options
+                .iter()
+                .all(|option| option.value == ValueArity::None)
+        );
+        assert_eq!(options.len(), 7);
+    }
+
+    #[test]
+    fn argparse_layouts_are_parsed() {
+        let options = parse_help(
+            "\
+usage: convert [-h] [-o OUTPUT] input
+
+positional arguments:
+  input                 file to read
+
+optional arguments:
+  -h, --help            show this help message and exit
+  -o OUTPUT, --output OUTPUT
+                        where to write the result
+  -j N, --jobs N        number of parallel jobs
+  --files FILE [FILE ...]
+                        files to add
+",
+        );
+        assert_eq!(options.len(), 4);
+
+        let output = option(&options, "--output");
+        assert_eq!(output.flags, ["-o", "--output"]);
+        assert_eq!(output.value, ValueArity::Required);
+        assert_eq!(output.value_name.as_deref(), Some("OUTPUT"));
+        assert_eq!(output.description, "where to write the result");
+        assert_eq!(output.section.as_deref(), Some("optional arguments"));
+
+        let jobs = option(&options, "-j");
+        assert_eq!(jobs.flags, ["-j", "--jobs"]);
+        assert_eq!(jobs.description, "number of parallel jobs");
+        assert_eq!(option(&options, "--files").value, ValueArity::Required);
+        assert!(option(&options, "--files").repeatable);
+        assert_eq!(option(&options, "--help").value, ValueArity::None);
+    }
+
+    #[test]
+    fn single_line_options_outside_sections_are_found_without_prose() {
+        let options = parse_help(
+            "\
+Usage: head [OPTION]... [FILE]...
+  -n,  --lines=NUM  print the first NUM lines
+  -q, --quiet       never print headers
+  some indented prose  with a wide gap
+This paragraph has  two spaces  and -dashes in it.
+
+Examples:
+  -x  is only an example here
+",
+        );
+        let flags: Vec<&[String]> = options.iter().map(|option| &option.flags[..]).collect();
+        assert_eq!(flags, [&["-n", "--lines"][..], &["-q", "--quiet"][..]]);
+        let lines = option(&options, "--lines");
+        assert_eq!(lines.value, ValueArity::Required);
+        assert_eq!(lines.description, "print the first NUM lines");
+    }
+
+    #[test]
+    fn options_in_a_section_are_not_repeated_by_the_fallback() {
+        let options = parse_help(
+            "\
+  -v, --verbose  say more
+Options:
+  -v, --verbose  say more
+  -o <FILE>      output
+",
+        );
+        assert_eq!(options.len(), 2);
+        assert_eq!(option(&options, "-v").section.as_deref(), Some("Options"));
+    }
+
+    #[test]
+    fn conflicts_are_recorded_on_both_sides() {
+        let options = parse_help(
+            "\
+Options:
+      --json       JSON output [conflicts with --quiet]
+  -q, --quiet      Less output
+      --table      Cannot be used with --json or -q
+      --color      Colorize
+",
+        );
+        let conflicts = |flag| option(&options, flag).conflicts.clone();
+        assert_eq!(conflicts("--json"), ["--quiet", "--table"]);
+        assert_eq!(conflicts("--quiet"), ["--json", "--table"]);
+        assert_eq!(conflicts("--table"), ["--json", "--quiet"]);
+        // No hint, no restriction
+        assert!(conflicts("--color").is_empty());
+    }
+
+    #[test]
+    fn getopts_usage_lines_give_basic_options() {
+        let options = parse_usage("Usage: myscript [-abc] [-v] [-o output] input\n");
+        let parsed: Vec<(&str, ValueArity)> = options
+            .iter()
+            .map(|option| (option.preferred_flag(), option.value))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                ("-a", ValueArity::None),
+                ("-b", ValueArity::None),
+                ("-c", ValueArity::None),
+                ("-v", ValueArity::None),
+                ("-o", ValueArity::Required),
+            ]
+        );
+        assert_eq!(option(&options, "-o").value_name.as_deref(), Some("output"));
+    }
+
+    #[test]
+    fn usage_lines_without_flags_give_nothing() {
+        assert!(parse_usage("Usage: myscript input [output]\n").is_empty());
+        assert!(parse_usage("A tool without a usage line [-x]\n").is_empty());
+    }
+}
@@ -0,0 +1,720 @@
+use crate::capture;
+use crate::config;
+use crate::editor::{display_arg, display_command};
+use crate::paths;
+use crate::ui;
+use crate::utils::{env_prefix, run_command, shell_command};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub path: String,
+    pub operation: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Environment set for the command on top of app-hoist's own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<(String, String)>,
+    /// Absolute directory the command ran in
+    #[serde(default)]
+    pub cwd: String,
+    /// None when the command was killed by a signal or its code isn't known
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub duration_millis: u64,
+}
+
+impl HistoryEntry {
+    pub fn new(path: &str, operation: &str, program: &str, args: &[String]) -> Self {
+        Self {
+            timestamp: now(),
+            path: path.to_string(),
+            operation: operation.to_string(),
+            program: program.to_string(),
+            args: args.to_vec(),
+            env: Vec::new(),
+            cwd: String::new(),
+            exit_code: None,
+            duration_millis: 0,
+        }
+    }
+
+    /// A command run outside any project operation, e.g. from package mode.
+    pub fn command(program: &str, args: &[String], env: &[(String, String)]) -> Self {
+        Self {
+            env: env.to_vec(),
+            ..Self::new("", "", program, args)
+        }
+    }
+
+    /// Where the command ran and how it ended.
+    pub fn finished(mut self, cwd: &Path, exit_code: Option<i32>, duration: Duration) -> Self {
+        self.cwd = cwd.to_string_lossy().into_owned();
+        self.exit_code = exit_code;
+        self.duration_millis = duration.as_millis() as u64;
+        self
+    }
+
+    /// Directory to run the command in again; entries from before the
+    /// directory was recorded only have the project path.
+    fn run_dir(&self) -> &str {
+        if self.cwd.is_empty() {
+            &self.path
+        } else {
+            &self.cwd
+        }
+    }
+}
+
+pub fn history_file() -> anyhow::Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home_dir.join(".app-hoist").join("history.jsonl"))
+}
+
+pub fn record(entry: &HistoryEntry) -> anyhow::Result<()> {
+    let path = history_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    drop(file);
+
+    // Retention is applied lazily, only once the file has clearly outgrown it
+    let policy = RetentionPolicy::load()?;
+    if needs_compaction(&path, &policy, now())? {
+        compact(&path, &policy, now())?;
+    }
+
+    Ok(())
+}
+
+/// Stream history entries in order, skipping lines that don't parse.
+pub fn entries() -> anyhow::Result<impl Iterator<Item = HistoryEntry>> {
+    let path = history_file()?;
+    let reader = open_for_reading(&path)?;
+    Ok(reader
+        .into_iter()
+        .flat_map(|reader| reader.lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str(&line).ok()))
+}
+
+/// Open the history file for a streaming read. A compaction replaces the file
+/// with a rename, which on some platforms leaves a moment where it is missing,
+/// so a missing file is retried briefly before being treated as empty.
+fn open_for_reading(path: &Path) -> anyhow::Result<Option<BufReader<File>>> {
+    const ATTEMPTS: u32 = 3;
+
+    for attempt in 1..=ATTEMPTS {
+        match File::open(path) {
+            Ok(file) => return Ok(Some(BufReader::new(file))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if attempt < ATTEMPTS && compaction_in_progress(path) {
+                    std::thread::sleep(Duration::from_millis(50));
+                    continue;
+                }
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// The most recent entry for a project path and primary operation.
+pub fn last_for(path: &str, operation: &str) -> anyhow::Result<Option<HistoryEntry>> {
+    Ok(entries()?
+        .filter(|entry| entry.path == path && entry.operation == operation)
+        .last())
+}
+
+/// Distinct project paths from history, most recently used first.
+pub fn recent_paths(limit: usize) -> anyhow::Result<Vec<String>> {
+    // Position of each path's latest use, so only distinct paths are held
+    let mut latest: HashMap<String, usize> = HashMap::new();
+    for (i, entry) in entries()?.enumerate() {
+        // Commands from package mode aren't tied to a project
+        if entry.path.is_empty() {
+            continue;
+        }
+        latest.insert(entry.path, i);
+    }
+
+    let mut paths: Vec<(String, usize)> = latest.into_iter().collect();
+    paths.sort_by_key(|(_, latest)| std::cmp::Reverse(*latest));
+    Ok(paths
+        .into_iter()
+        .take(limit)
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// Entries kept when `[history] max_entries` isn't set.
+pub const DEFAULT_MAX_ENTRIES: usize = 500;
+
+/// How much history to keep, from `[history]` in `~/.app-hoist/config.toml`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn load() -> anyhow::Result<Self> {
+        let max_entries = config::user_integer("history", "max_entries")?
+            .map_or(DEFAULT_MAX_ENTRIES, |n| n as usize);
+        Ok(Self {
+            max_entries: Some(max_entries),
+            max_age_days: config::user_integer("history", "max_age_days")?,
+        })
+    }
+
+    fn cutoff(&self, now: u64) -> Option<u64> {
+        self.max_age_days
+            .map(|days| now.saturating_sub(days * SECONDS_PER_DAY))
+    }
+
+    /// Which of `timestamps` (oldest first) to keep: those within the age
+    /// limit, and of those only the newest `max_entries`.
+    pub fn keep(&self, timestamps: &[u64], now: u64) -> Vec<bool> {
+        let cutoff = self.cutoff(now).unwrap_or(0);
+        let mut keep: Vec<bool> = timestamps.iter().map(|&ts| ts >= cutoff).collect();
+
+        if let Some(max) = self.max_entries {
+            let mut excess = keep.iter().filter(|&&k| k).count().saturating_sub(max);
+            for k in keep.iter_mut() {
+                if excess == 0 {
+                    break;
+                }
+                if *k {
+                    *k = false;
+                    excess -= 1;
+                }
+            }
+        }
+
+        keep
+    }
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Whether the file has grown past the policy by enough to be worth
+/// rewriting: 10% more entries than allowed, or a first entry more than a
+/// day past the age limit. Only the line count and first line are read.
+fn needs_compaction(path: &Path, policy: &RetentionPolicy, now: u64) -> anyhow::Result<bool> {
+    let Some(mut reader) = open_for_reading(path)? else {
+        return Ok(false);
+    };
+
+    let mut first = String::new();
+    reader.read_line(&mut first)?;
+    if let Some(cutoff) = policy.cutoff(now)
+        && let Ok(entry) = serde_json::from_str::<HistoryEntry>(&first)
+        && entry.timestamp + SECONDS_PER_DAY < cutoff
+    {
+        return Ok(true);
+    }
+
+    if let Some(max) = policy.max_entries {
+        let lines = 1 + reader.lines().count();
+        return Ok(lines > max + max / 10);
+    }
+
+    Ok(false)
+}
+
+/// What a compaction removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompactionReport {
+    pub kept: usize,
+    pub pruned: usize,
+    pub corrupt: usize,
+}
+
+/// Rewrite the history file keeping only what `policy` allows and dropping
+/// lines that don't parse. The new file is written beside the old one and
+/// renamed over it, so readers see either the old file or the new one.
+pub fn compact(
+    path: &Path,
+    policy: &RetentionPolicy,
+    now: u64,
+) -> anyhow::Result<CompactionReport> {
+    let mut report = CompactionReport::default();
+    let Some(reader) = open_for_reading(path)? else {
+        return Ok(report);
+    };
+
+    // First pass: timestamps of the valid lines, to evaluate the policy
+    let mut timestamps = Vec::new();
+    for line in reader.lines() {
+        match serde_json::from_str::<HistoryEntry>(&line?) {
+            Ok(entry) => timestamps.push(entry.timestamp),
+            Err(_) => report.corrupt += 1,
+        }
+    }
+    let keep = policy.keep(&timestamps, now);
+
+    // Second pass: copy the kept lines as they are
+    let temp = compaction_temp_path(path);
+    let mut out = std::io::BufWriter::new(File::create(&temp)?);
+    let mut valid = 0;
+    if let Some(reader) = open_for_reading(path)? {
+        for line in reader.lines() {
+            let line = line?;
+            if serde_json::from_str::<HistoryEntry>(&line).is_err() {
+                continue;
+            }
+            // Lines appended since the first pass are always kept
+            if keep.get(valid).copied().unwrap_or(true) {
+                writeln!(out, "{}", line)?;
+                report.kept += 1;
+            } else {
+                report.pruned += 1;
+            }
+            valid += 1;
+        }
+    }
+    out.flush()?;
+    drop(out);
+
+    if let Err(e) = std::fs::rename(&temp, path) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+
+    Ok(report)
+}
+
+fn compaction_temp_path(path: &Path) -> PathBuf {
+    path.with_extension(format!("jsonl.compact-{}", std::process::id()))
+}
+
+fn compaction_in_progress(path: &Path) -> bool {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let prefix = format!("{}.compact-", name.to_string_lossy());
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+    })
+}
+
+/// `app-hoist history prune`: apply the retention policy now, with optional
+/// overrides for this run.
+pub fn prune(max_entries: Option<usize>, max_age_days: Option<u64>) -> anyhow::Result<()> {
+    let configured = RetentionPolicy::load()?;
+    let policy = RetentionPolicy {
+        max_entries: max_entries.or(configured.max_entries),
+        max_age_days: max_age_days.or(configured.max_age_days),
+    };
+
+    let report = compact(&history_file()?, &policy, now())?;
+    ui::status(&format!(
+        "✅ History compacted: kept {}, pruned {}, dropped {} corrupt line(s)",
+        report.kept, report.pruned, report.corrupt
+    ));
+
+    Ok(())
+}
+
+/// The last `limit` entries, most recent first, so `1` is the last command run.
+fn recent(limit: usize) -> anyhow::Result<Vec<HistoryEntry>> {
+    let mut entries: Vec<HistoryEntry> = entries()?.collect();
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// `app-hoist history list`: the most recent commands, numbered for `history rerun`.
+pub fn print_list(limit: usize) -> anyhow::Result<()> {
+    let entries = recent(limit)?;
+    if entries.is_empty() {
+        println!("No commands recorded yet");
+        return Ok(());
+    }
+
+    for (n, entry) in entries.iter().enumerate().rev() {
+        let when = chrono::DateTime::from_timestamp(entry.timestamp as i64, 0)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let outcome = match entry.exit_code {
+            Some(0) => style("ok".to_string()).green(),
+            Some(code) => style(format!("exit {}", code)).red(),
+            None => style("-".to_string()).dim(),
+        };
+        println!(
+            "{:>4}  {}  {:<7} {:>7}  {}{}",
+            n + 1,
+            when,
+            outcome,
+            capture::format_duration(Duration::from_millis(entry.duration_millis)),
+            env_prefix(&entry.env),
+            display_command(&entry.program, &entry.args)
+        );
+        if !entry.run_dir().is_empty() {
+            println!(
+                "      in {}",
+                paths::shorten_home(Path::new(entry.run_dir()))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `app-hoist history rerun <n>`: run the `n`th most recent command again,
+/// in the directory it ran in, after confirming unless `yes` is set.
+pub async fn rerun(n: usize, yes: bool, dry_run: bool) -> anyhow::Result<()> {
+    let entry = n
+        .checked_sub(1)
+        .and_then(|index| recent(n).ok()?.into_iter().nth(index))
+        .ok_or_else(|| {
+            anyhow::anyhow!("No command #{} in history; see app-hoist history list", n)
+        })?;
+    let cwd = Some(entry.run_dir()).filter(|dir| !dir.is_empty());
+
+    let command = format!(
+        "{}{}",
+        env_prefix(&entry.env),
+        shell_command(&entry.program, &entry.args)
+    );
+    if dry_run {
+        println!("Dry run: {}", command);
+        return Ok(());
+    }
+
+    if !yes {
+        println!("{}", command);
+        if let Some(cwd) = cwd {
+            println!("  in {}", paths::shorten_home(Path::new(cwd)));
+        }
+        if !ui::confirm("Run this command again?", true)? {
+            return Ok(());
+        }
+    }
+
+    capture::check_status(run_command(&entry.program, &entry.args, &entry.env, cwd).await?)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgChange {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Word-level diff of two argvs based on their longest common subsequence.
+pub fn argv_diff(old: &[String], new: &[String]) -> Vec<ArgChange> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(ArgChange::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(ArgChange::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            changes.push(ArgChange::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().cloned().map(ArgChange::Removed));
+    changes.extend(new[j..].iter().cloned().map(ArgChange::Added));
+
+    changes
+}
+
+/// Print how a command differs from the last one run for the same project and operation.
+pub fn show_diff_against_last(path: &str, operation: &str, program: &str, args: &[String]) {
+    let Ok(Some(previous)) = last_for(path, operation) else {
+        return;
+    };
+
+    let mut old = vec![previous.program.clone()];
+    old.extend(previous.args.iter().cloned());
+    let mut new = vec![program.to_string()];
+    new.extend(args.iter().cloned());
+
+    let changes = argv_diff(&old, &new);
+    if changes.iter().all(|c| matches!(c, ArgChange::Same(_))) {
+        println!(
+            "Same command as last time you ran {} here",
+            style(operation).bold()
+        );
+        return;
+    }
+
+    let rendered: Vec<String> = changes
+        .iter()
+        .map(|change| match change {
+            ArgChange::Same(arg) => display_arg(arg),
+            ArgChange::Added(arg) => style(format!("+{}", display_arg(arg))).green().to_string(),
+            ArgChange::Removed(arg) => style(format!("-{}", display_arg(arg))).red().to_string(),
+        })
+        .collect();
+
+    println!("Compared to last time you ran {} here:", operation);
+    println!("  {}", rendered.join(" "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn same(arg: &str) -> ArgChange {
+        ArgChange::Same(arg.to_string())
+    }
+
+    fn added(arg: &str) -> ArgChange {
+        ArgChange::Added(arg.to_string())
+    }
+
+    fn removed(arg: &str) -> ArgChange {
+        ArgChange::Removed(arg.to_string())
+    }
+
+    #[test]
+    fn identical_argvs_are_all_same() {
+        let args = argv("cargo build --release");
+        assert_eq!(
+            argv_diff(&args, &args),
+            [same("cargo"), same("build"), same("--release")]
+        );
+    }
+
+    #[test]
+    fn insertions_are_added() {
+        assert_eq!(
+            argv_diff(&argv("cargo build"), &argv("cargo build --features tls")),
+            [
+                same("cargo"),
+                same("build"),
+                added("--features"),
+                added("tls")
+            ]
+        );
+    }
+
+    #[test]
+    fn removals_are_removed() {
+        assert_eq!(
+            argv_diff(
+                &argv("cargo build --release -p api"),
+                &argv("cargo build -p api")
+            ),
+            [
+                same("cargo"),
+                same("build"),
+                removed("--release"),
+                same("-p"),
+                same("api")
+            ]
+        );
+    }
+
+    #[test]
+    fn replacements_remove_before_adding() {
+        assert_eq!(
+            argv_diff(
+                &argv("cargo build --release"),
+                &argv("cargo build --features tls")
+            ),
+            [
+                same("cargo"),
+                same("build"),
+                removed("--release"),
+                added("--features"),
+                added("tls")
+            ]
+        );
+    }
+
+    #[test]
+    fn reordering_keeps_the_longest_common_run() {
+        let changes = argv_diff(
+            &argv("go test -v -race ./..."),
+            &argv("go test -race -v ./..."),
+        );
+        assert_eq!(
+            changes,
+            [
+                same("go"),
+                same("test"),
+                removed("-v"),
+                same("-race"),
+                added("-v"),
+                same("./...")
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_argvs_diff_to_all_added_or_removed() {
+        assert_eq!(argv_diff(&[], &argv("ls")), [added("ls")]);
+        assert_eq!(argv_diff(&argv("ls"), &[]), [removed("ls")]);
+        assert!(argv_diff(&[], &[]).is_empty());
+    }
+
+    const NOW: u64 = 100 * SECONDS_PER_DAY;
+
+    fn entry_line(timestamp: u64, operation: &str) -> String {
+        serde_json::to_string(&HistoryEntry {
+            timestamp,
+            ..HistoryEntry::new("/code/api", operation, "cargo", &argv("build"))
+        })
+        .unwrap()
+    }
+
+    /// A history file holding `lines` in order.
+    fn history(lines: &[String]) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        (dir, path)
+    }
+
+    fn operations(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<HistoryEntry>(line)
+                    .unwrap()
+                    .operation
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_newest_entries_are_kept_up_to_max_entries() {
+        let lines: Vec<String> = (0..5)
+            .map(|i| entry_line(NOW - 10 + i, &format!("op{}", i)))
+            .collect();
+        let (_dir, path) = history(&lines);
+        let policy = RetentionPolicy {
+            max_entries: Some(2),
+            max_age_days: None,
+        };
+
+        let report = compact(&path, &policy, NOW).unwrap();
+        assert_eq!(
+            report,
+            CompactionReport {
+                kept: 2,
+                pruned: 3,
+                corrupt: 0
+            }
+        );
+        assert_eq!(operations(&path), ["op3", "op4"]);
+    }
+
+    #[test]
+    fn entries_past_max_age_are_pruned() {
+        let (_dir, path) = history(&[
+            entry_line(NOW - 40 * SECONDS_PER_DAY, "old"),
+            entry_line(NOW - 20 * SECONDS_PER_DAY, "recent"),
+            entry_line(NOW, "today"),
+        ]);
+        let policy = RetentionPolicy {
+            max_entries: Some(10),
+            max_age_days: Some(30),
+        };
+
+        let report = compact(&path, &policy, NOW).unwrap();
+        assert_eq!((report.kept, report.pruned), (2, 1));
+        assert_eq!(operations(&path), ["recent", "today"]);
+    }
+
+    #[test]
+    fn corrupt_lines_are_dropped_and_counted() {
+        let (_dir, path) = history(&[
+            entry_line(NOW, "first"),
+            "{\"timestamp\": ".to_string(),
+            "not json".to_string(),
+            entry_line(NOW, "second"),
+        ]);
+        let report = compact(&path, &RetentionPolicy::default(), NOW).unwrap();
+        assert_eq!(
+            report,
+            CompactionReport {
+                kept: 2,
+                pruned: 0,
+                corrupt: 2
+            }
+        );
+        assert_eq!(operations(&path), ["first", "second"]);
+        // Nothing is left behind beside the file
+        assert!(!compaction_in_progress(&path));
+    }
+
+    #[test]
+    fn compaction_waits_until_the_file_clearly_outgrows_the_policy() {
+        let policy = RetentionPolicy {
+            max_entries: Some(10),
+            max_age_days: Some(30),
+        };
+        let lines = |count: u64, timestamp: u64| -> Vec<String> {
+            (0..count).map(|_| entry_line(timestamp, "op")).collect()
+        };
+
+        // 10% over the limit is tolerated
+        let (_dir, path) = history(&lines(11, NOW));
+        assert!(!needs_compaction(&path, &policy, NOW).unwrap());
+        let (_dir, path) = history(&lines(12, NOW));
+        assert!(needs_compaction(&path, &policy, NOW).unwrap());
+
+        // As is a first entry less than a day past the age limit
+        let (_dir, path) = history(&lines(1, NOW - 30 * SECONDS_PER_DAY - 60));
+        assert!(!needs_compaction(&path, &policy, NOW).unwrap());
+        let (_dir, path) = history(&lines(1, NOW - 32 * SECONDS_PER_DAY));
+        assert!(needs_compaction(&path, &policy, NOW).unwrap());
+
+        // A missing file has nothing to compact
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!needs_compaction(&dir.path().join("none.jsonl"), &policy, NOW).unwrap());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::ui;
+use crate::utils::{in_system_install_dir, shell_command, shell_quote};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a binary got onto the PATH, which decides how it comes off again.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallKind {
+    /// `cargo install`, removed with `cargo uninstall`
+    Cargo,
+    /// A Go build moved into place, removed by deleting the file
+    Go,
+    /// A generated wrapper script, removed by deleting the file
+    Script,
+}
+
+impl std::fmt::Display for InstallKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallKind::Cargo => write!(f, "cargo"),
+            InstallKind::Go => write!(f, "go"),
+            InstallKind::Script => write!(f, "script"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledEntry {
+    pub name: String,
+    pub kind: InstallKind,
+    /// Where the binary ended up
+    pub path: String,
+    /// Project it was installed from
+    pub project: String,
+    /// Crate to pass to `cargo uninstall`, for cargo installs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crate_name: Option<String>,
+    /// Install root, when cargo was given one other than its default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    pub installed_at: u64,
+}
+
+impl InstalledEntry {
+    pub fn new(name: &str, kind: InstallKind, path: &str, project: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            path: path.to_string(),
+            project: project.to_string(),
+            crate_name: None,
+            root: None,
+            installed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+
+    /// An entry for `cargo install` run with `args` in `project`.
+    pub fn cargo(crate_name: &str, project: &str, args: &[String]) -> Self {
+        let root = cargo_root(args);
+        let bin = root
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("CARGO_HOME").map(PathBuf::from))
+            .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+            .unwrap_or_default()
+            .join("bin")
+            .join(format!("{}{}", crate_name, std::env::consts::EXE_SUFFIX));
+
+        let mut entry = Self::new(
+            crate_name,
+            InstallKind::Cargo,
+            &bin.to_string_lossy(),
+            project,
+        );
+        entry.crate_name = Some(crate_name.to_string());
+        entry.root = root;
+        entry
+    }
+}
+
+/// The install root cargo will use, if it isn't the default: `--root` on the
+/// command line, then `CARGO_INSTALL_ROOT`.
+fn cargo_root(args: &[String]) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == "--root")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--root=").map(str::to_string))
+        })
+        .or_else(|| std::env::var("CARGO_INSTALL_ROOT").ok())
+}
+
+pub fn manifest_file() -> anyhow::Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home_dir.join(".app-hoist").join("installed.json"))
+}
+
+pub fn load() -> anyhow::Result<Vec<InstalledEntry>> {
+    let path = manifest_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid install manifest {}: {}", path.display(), e))
+}
+
+fn save(entries: &[InstalledEntry]) -> anyhow::Result<()> {
+    let path = manifest_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(entries)?)?;
+    Ok(())
+}
+
+/// Add an install to the manifest, replacing any earlier one of the same name.
+pub fn record(entry: InstalledEntry) -> anyhow::Result<()> {
+    let mut entries = load()?;
+    entries.retain(|existing| existing.name != entry.name);
+    entries.push(entry);
+    save(&entries)
+}
+
+pub fn print_list() -> anyhow::Result<()> {
+    let entries = load()?;
+    if entries.is_empty() {
+        println!("No binaries installed by app-hoist");
+        return Ok(());
+    }
+
+    for entry in entries {
+        println!(
+            "{:<20} {:<7} {}  (from {})",
+            entry.name, entry.kind, entry.path, entry.project
+        );
+    }
+    Ok(())
+}
+
+/// How one manifest entry is removed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Removal {
+    /// `cargo uninstall` with these arguments
+    CargoUninstall(Vec<String>),
+    /// Delete the file, through sudo when it was installed with sudo
+    DeleteFile { path: String, sudo: bool },
+}
+
+impl Removal {
+    pub fn for_entry(entry: &InstalledEntry) -> Self {
+        match entry.kind {
+            InstallKind::Cargo => {
+                let mut args = vec![
+                    "uninstall".to_string(),
+                    entry
+                        .crate_name
+                        .clone()
+                        .unwrap_or_else(|| entry.name.clone()),
+                ];
+                if let Some(root) = &entry.root {
+                    args.push("--root".to_string());
+                    args.push(root.clone());
+                }
+                Removal::CargoUninstall(args)
+            }
+            // Go installs need sudo only when they went into the system directory
+            InstallKind::Go => Removal::DeleteFile {
+                path: entry.path.clone(),
+                sudo: in_system_install_dir(&entry.path),
+            },
+            InstallKind::Script => Removal::DeleteFile {
+                path: entry.path.clone(),
+                sudo: false,
+            },
+        }
+    }
+}
+
+/// Remove an installed binary by the mechanism that installed it, then drop
+/// it from the manifest.
+pub fn remove(name: &str) -> anyhow::Result<()> {
+    let mut entries = load()?;
+    let Some(pos) = entries.iter().position(|entry| entry.name == name) else {
+        anyhow::bail!("'{}' is not in the install manifest", name);
+    };
+
+    let entry = &entries[pos];
+    match Removal::for_entry(entry) {
+        Removal::CargoUninstall(args) => {
+            println!("Executing: {}", shell_command("cargo", &args));
+            let output = Command::new("cargo").args(&args).output()?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                // Removed outside of cargo's tracking; the file may still be there
+                if stderr.contains("did not match any packages")
+                    || stderr.contains("is not installed")
+                {
+                    ui::status(&format!(
+                        "⚠️  cargo does not know '{}', deleting {} instead",
+                        entry.name, entry.path
+                    ));
+                    delete_file(&entry.path, false)?;
+                } else {
+                    anyhow::bail!("cargo uninstall failed: {}", stderr.trim());
+                }
+            }
+        }
+        Removal::DeleteFile { path, sudo } => delete_file(&path, sudo)?,
+    }
+
+    ui::status(&format!("✅ Removed {} ({})", entry.name, entry.kind));
+    entries.remove(pos);
+    save(&entries)
+}
+
+fn delete_file(path: &str, sudo: bool) -> anyhow::Result<()> {
+    if !Path::new(path).exists() {
+        ui::status(&format!("⚠️  {} is already gone", path));
+        return Ok(());
+    }
+
+    if sudo {
+        println!("Executing: sudo rm {}", shell_quote(path));
+        let status = Command::new("sudo").args(["rm", path]).status()?;
+        if !status.success() {
+            anyhow::bail!("Failed to remove {}", path);
+        }
+    } else {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
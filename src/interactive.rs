@@ -1,11 +1,20 @@
-use crate::cli::{CacheCommand, TemplateCommand};
+use crate::about;
+use crate::capture;
+use crate::cli::{CacheCommand, OutputFormat, TemplateCommand};
+use crate::config;
+use crate::detection;
 use crate::docker;
+use crate::history;
 use crate::models::ProjectType;
 use crate::multi_project;
 use crate::package;
+use crate::paths;
 use crate::project;
-use crate::template;
-use inquire::{Confirm, Select, Text};
+use crate::refresh;
+use crate::resolve;
+use crate::ui;
+use crate::utils::expand_tilde;
+use crate::worktree;
 
 #[derive(Debug, Clone)]
 enum MainMenuChoice {
@@ -19,65 +28,102 @@ enum MainMenuChoice {
     Exit,
 }
 
-impl std::fmt::Display for MainMenuChoice {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl MainMenuChoice {
+    const ALL: [MainMenuChoice; 8] = [
+        MainMenuChoice::PackageManagement,
+        MainMenuChoice::ProjectManagement,
+        MainMenuChoice::DockerOperations,
+        MainMenuChoice::MultiProjectOperations,
+        MainMenuChoice::TemplateOperations,
+        MainMenuChoice::CacheOperations,
+        MainMenuChoice::Help,
+        MainMenuChoice::Exit,
+    ];
+
+    fn icon(&self) -> &'static str {
         match self {
-            MainMenuChoice::PackageManagement => {
-                write!(f, "📦 Package Management - Hoist executables/packages")
-            }
-            MainMenuChoice::ProjectManagement => {
-                write!(f, "🏗️  Project Management - Manage development projects")
-            }
-            MainMenuChoice::DockerOperations => {
-                write!(f, "🐳 Docker Operations - Container management")
-            }
-            MainMenuChoice::MultiProjectOperations => write!(
-                f,
-                "🔄 Multi-Project Operations - Parallel project management"
-            ),
-            MainMenuChoice::TemplateOperations => {
-                write!(f, "📋 Template Operations - Project scaffolding")
-            }
-            MainMenuChoice::CacheOperations => {
-                write!(f, "💾 Cache Operations - Manage cached data")
+            MainMenuChoice::PackageManagement => "📦",
+            MainMenuChoice::ProjectManagement => "🏗️ ",
+            MainMenuChoice::DockerOperations => "🐳",
+            MainMenuChoice::MultiProjectOperations => "🔄",
+            MainMenuChoice::TemplateOperations => "📋",
+            MainMenuChoice::CacheOperations => "💾",
+            MainMenuChoice::Help => "❓",
+            MainMenuChoice::Exit => "🚪",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            MainMenuChoice::PackageManagement => "Package Management - Hoist executables/packages",
+            MainMenuChoice::ProjectManagement => "Project Management - Manage development projects",
+            MainMenuChoice::DockerOperations => "Docker Operations - Container management",
+            MainMenuChoice::MultiProjectOperations => {
+                "Multi-Project Operations - Parallel project management"
             }
-            MainMenuChoice::Help => write!(f, "❓ Help/About - Information and help"),
-            MainMenuChoice::Exit => write!(f, "🚪 Exit - Quit app-hoist"),
+            MainMenuChoice::TemplateOperations => "Template Operations - Project scaffolding",
+            MainMenuChoice::CacheOperations => "Cache Operations - Manage cached data",
+            MainMenuChoice::Help => "Help/About - Information and help",
+            MainMenuChoice::Exit => "Exit - Quit app-hoist",
         }
     }
 }
 
+/// Labels of the main-menu features, excluding navigation entries.
+pub fn main_menu_features() -> Vec<String> {
+    MainMenuChoice::ALL
+        .iter()
+        .filter(|choice| !matches!(choice, MainMenuChoice::Help | MainMenuChoice::Exit))
+        .map(|choice| choice.label().to_string())
+        .collect()
+}
+
+impl std::fmt::Display for MainMenuChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.icon(), self.label())
+    }
+}
+
+/// A command that failed has already said so; the menu carries on rather
+/// than ending the session with its exit code.
+fn keep_going(result: anyhow::Result<()>) -> anyhow::Result<()> {
+    match result {
+        Err(e) if e.is::<capture::ChildExit>() => Ok(()),
+        result => result,
+    }
+}
+
 pub async fn run_interactive_mode() -> anyhow::Result<()> {
-    println!("🚀 Welcome to app-hoist interactive mode!");
+    ui::status("🚀 Welcome to app-hoist interactive mode!");
     println!("==========================================");
     println!("Select an option below to get started.\n");
 
+    // Warm the cache for recent projects while the menu is up; dropped (and
+    // so cancelled) when interactive mode returns
+    let _refresh = if config::user_flag("interactive", "background_refresh", true).unwrap_or(true) {
+        let paths = history::recent_paths(refresh::REFRESH_LIMIT).unwrap_or_default();
+        Some(refresh::spawn_refresh(paths, refresh::REFRESH_BUDGET))
+    } else {
+        None
+    };
+
     loop {
-        let choices = vec![
-            MainMenuChoice::PackageManagement,
-            MainMenuChoice::ProjectManagement,
-            MainMenuChoice::DockerOperations,
-            MainMenuChoice::MultiProjectOperations,
-            MainMenuChoice::TemplateOperations,
-            MainMenuChoice::CacheOperations,
-            MainMenuChoice::Help,
-            MainMenuChoice::Exit,
-        ];
-
-        let selection = Select::new("What would you like to do?", choices).prompt()?;
+        let choices = MainMenuChoice::ALL.to_vec();
+
+        let selection = ui::select("What would you like to do?", choices)?;
 
         match selection {
             MainMenuChoice::PackageManagement => {
-                handle_package_management().await?;
+                keep_going(handle_package_management().await)?;
             }
             MainMenuChoice::ProjectManagement => {
-                handle_project_management().await?;
+                keep_going(handle_project_management().await)?;
             }
             MainMenuChoice::DockerOperations => {
-                handle_docker_operations().await?;
+                keep_going(handle_docker_operations().await)?;
             }
             MainMenuChoice::MultiProjectOperations => {
-                handle_multi_project_operations().await?;
+                keep_going(handle_multi_project_operations().await)?;
             }
             MainMenuChoice::TemplateOperations => {
                 handle_template_operations()?;
@@ -86,20 +132,17 @@ pub async fn run_interactive_mode() -> anyhow::Result<()> {
                 handle_cache_operations()?;
             }
             MainMenuChoice::Help => {
-                show_help();
+                about::print_about();
             }
             MainMenuChoice::Exit => {
-                println!("👋 Goodbye! Thanks for using app-hoist.");
+                ui::status("👋 Goodbye! Thanks for using app-hoist.");
                 break;
             }
         }
 
         // Ask if user wants to continue
-        if !Confirm::new("Would you like to perform another operation?")
-            .with_default(true)
-            .prompt()?
-        {
-            println!("👋 Goodbye! Thanks for using app-hoist.");
+        if !ui::confirm("Would you like to perform another operation?", true)? {
+            ui::status("👋 Goodbye! Thanks for using app-hoist.");
             break;
         }
         println!(); // Add spacing
@@ -109,67 +152,103 @@ pub async fn run_interactive_mode() -> anyhow::Result<()> {
 }
 
 async fn handle_package_management() -> anyhow::Result<()> {
-    println!("📦 Package Management");
+    ui::status("📦 Package Management");
     println!("Hoist executables and packages to make them available system-wide.\n");
 
-    let package_name = Text::new("Enter the name of the package/executable to hoist:").prompt()?;
+    let package_name = ui::text(
+        "Enter the name of the package/executable to hoist (empty to browse PATH):",
+        None,
+    )?;
+    let package_name = match package_name.trim() {
+        "" => browse_path_executables()?,
+        name => name.to_string(),
+    };
+
+    let dry_run = ui::confirm(
+        "Dry run? (Show what would be done without executing)",
+        false,
+    )?;
+
+    package::handle_package_mode(&package_name, dry_run, false, &[], &[]).await?;
+    Ok(())
+}
 
-    let dry_run = Confirm::new("Dry run? (Show what would be done without executing)")
-        .with_default(false)
-        .prompt()?;
+/// Pick an executable from everything on PATH.
+fn browse_path_executables() -> anyhow::Result<String> {
+    let mut names = resolve::path_executables();
+    // Thousands of numbered lines are no use without narrowing them first
+    if ui::is_plain() {
+        let filter = ui::text("Part of the name to look for:", None)?;
+        names.retain(|name| name.contains(filter.trim()));
+    }
+    if names.is_empty() {
+        anyhow::bail!("No matching executables found on PATH");
+    }
 
-    package::handle_package_mode(&package_name, dry_run)?;
-    Ok(())
+    ui::select(
+        &format!(
+            "Select an executable ({} on PATH, type to filter):",
+            names.len()
+        ),
+        names,
+    )
 }
 
 async fn handle_project_management() -> anyhow::Result<()> {
-    println!("🏗️  Project Management");
+    ui::status("🏗️  Project Management");
     println!("Manage development projects (Rust, Go, Python, JavaScript/TypeScript).\n");
 
     // Auto-detect current directory
     let current_dir = std::env::current_dir()?;
     let current_path = current_dir.to_string_lossy();
 
-    println!("Current directory: {}", current_path);
+    println!("Current directory: {}", paths::shorten_home(&current_dir));
 
     // Try to detect project type
     let detected_type = detect_project_in_current_dir()?;
 
     match detected_type {
         Some(project_type) => {
-            println!("✅ Detected {} project in current directory", project_type);
+            ui::status(&format!(
+                "✅ Detected {} project in current directory",
+                project_type
+            ));
 
             // Show available operations for this project type
             let available_ops = get_available_operations(&project_type);
             if !available_ops.is_empty() {
-                println!("📋 Available operations: {}", available_ops.join(", "));
+                ui::status(&format!(
+                    "📋 Available operations: {}",
+                    available_ops.join(", ")
+                ));
+            }
+
+            if ui::confirm("Explain how this was detected?", false)? {
+                let report = detection::detect(&current_path, true)?;
+                detection::print_report(&report, OutputFormat::Text)?;
+                println!();
             }
 
-            let use_current = Confirm::new(&format!("Use current directory ({})?", current_path))
-                .with_default(true)
-                .prompt()?;
+            let use_current =
+                ui::confirm(&format!("Use current directory ({})?", current_path), true)?;
 
             if use_current {
-                project::handle_project_mode(&current_path, false)?;
+                project::handle_project_mode(&current_path, false, false, &[], &[], None).await?;
             } else {
-                let path_input = Text::new("Enter project path:")
-                    .with_default(".")
-                    .prompt()?;
+                let path_input = ui::text("Enter project path:", Some("."))?;
                 let path = expand_tilde(&path_input)?;
-                project::handle_project_mode(&path, false)?;
+                project::handle_project_mode(&path, false, false, &[], &[], None).await?;
             }
         }
         None => {
-            println!("❌ No project detected in current directory");
-            println!(
-                "💡 Supported project types: Rust (Cargo.toml), Go (go.mod), Python (pyproject.toml/uv), JavaScript/TypeScript (package.json)"
+            ui::status("❌ No project detected in current directory");
+            ui::status(
+                "💡 Supported project types: Rust (Cargo.toml), Go (go.mod), Python (pyproject.toml/uv), JavaScript/TypeScript (package.json)",
             );
 
-            let path_input = Text::new("Enter project path:")
-                .with_default(".")
-                .prompt()?;
+            let path_input = ui::text("Enter project path:", Some("."))?;
             let path = expand_tilde(&path_input)?;
-            project::handle_project_mode(&path, false)?;
+            project::handle_project_mode(&path, false, false, &[], &[], None).await?;
         }
     }
 
@@ -177,32 +256,31 @@ async fn handle_project_management() -> anyhow::Result<()> {
 }
 
 async fn handle_docker_operations() -> anyhow::Result<()> {
-    println!("🐳 Docker Operations");
+    ui::status("🐳 Docker Operations");
     println!("Choose between direct Docker commands or managing Docker-enabled projects.\n");
 
     let docker_choices = vec!["Direct Docker Commands", "Docker Project Management"];
 
-    let selection = Select::new("Select Docker operation type:", docker_choices).prompt()?;
+    let selection = ui::select("Select Docker operation type:", docker_choices)?;
 
     match selection {
         "Direct Docker Commands" => {
-            let command =
-                Text::new("Enter Docker command (e.g., 'ps -a', 'images', 'system prune'):")
-                    .prompt()?;
+            let command = ui::text(
+                "Enter Docker command (e.g., 'ps -a', 'images', 'system prune'):",
+                None,
+            )?;
 
-            let dry_run = Confirm::new("Dry run?").with_default(false).prompt()?;
+            let dry_run = ui::confirm("Dry run?", false)?;
 
-            docker::handle_direct_docker_mode(&command, dry_run)?;
+            docker::handle_direct_docker_mode(&command, dry_run).await?;
         }
         "Docker Project Management" => {
-            let path_input = Text::new("Enter path to Docker-enabled project:")
-                .with_default(".")
-                .prompt()?;
+            let path_input = ui::text("Enter path to Docker-enabled project:", Some("."))?;
             let path = expand_tilde(&path_input)?;
 
-            let dry_run = Confirm::new("Dry run?").with_default(false).prompt()?;
+            let dry_run = ui::confirm("Dry run?", false)?;
 
-            docker::handle_docker_project_mode(&path, dry_run)?;
+            docker::handle_docker_project_mode(&path, dry_run, None).await?;
         }
         _ => unreachable!(),
     }
@@ -211,18 +289,17 @@ async fn handle_docker_operations() -> anyhow::Result<()> {
 }
 
 async fn handle_multi_project_operations() -> anyhow::Result<()> {
-    println!("🔄 Multi-Project Operations");
+    ui::status("🔄 Multi-Project Operations");
     println!("Run operations across multiple projects in parallel.\n");
 
     let mut paths = Vec::new();
 
     println!("Enter project paths (one per line, leave empty to finish):");
     loop {
-        let path_input = Text::new(&format!(
-            "Project path {} (leave empty to finish):",
-            paths.len() + 1
-        ))
-        .prompt()?;
+        let path_input = ui::text(
+            &format!("Project path {} (leave empty to finish):", paths.len() + 1),
+            None,
+        )?;
 
         if path_input.trim().is_empty() {
             break;
@@ -232,10 +309,8 @@ async fn handle_multi_project_operations() -> anyhow::Result<()> {
 
         // Validate path exists
         if !std::path::Path::new(&path).exists() {
-            println!("⚠️  Warning: Path '{}' does not exist", path);
-            let continue_anyway = Confirm::new("Continue anyway?")
-                .with_default(false)
-                .prompt()?;
+            ui::status(&format!("⚠️  Warning: Path '{}' does not exist", path));
+            let continue_anyway = ui::confirm("Continue anyway?", false)?;
             if !continue_anyway {
                 continue;
             }
@@ -244,35 +319,44 @@ async fn handle_multi_project_operations() -> anyhow::Result<()> {
         paths.push(path);
 
         if paths.len() >= 10 {
-            println!("⚠️  Maximum of 10 projects reached");
+            ui::status("⚠️  Maximum of 10 projects reached");
             break;
         }
     }
 
     if paths.is_empty() {
-        println!("❌ No paths provided. Operation cancelled.");
+        ui::status("❌ No paths provided. Operation cancelled.");
         return Ok(());
     }
 
-    println!(
+    ui::status(&format!(
         "📂 Selected {} projects for parallel operations:",
         paths.len()
-    );
+    ));
+    // Worktrees of one repository are named as such so they can be told apart
+    let checkouts: std::collections::HashMap<String, String> =
+        worktree::shared_repositories(&paths)
+            .into_values()
+            .flatten()
+            .map(|(path, checkout)| (path, checkout.label()))
+            .collect();
     for (i, path) in paths.iter().enumerate() {
-        println!("  {}. {}", i + 1, path);
+        let shown = paths::shorten_home(std::path::Path::new(path));
+        match checkouts.get(path) {
+            Some(label) => println!("  {}. {}  [{}]", i + 1, shown, label),
+            None => println!("  {}. {}", i + 1, shown),
+        }
     }
     println!();
 
-    let dry_run = Confirm::new("Dry run (preview commands without executing)?")
-        .with_default(false)
-        .prompt()?;
+    let dry_run = ui::confirm("Dry run (preview commands without executing)?", false)?;
 
-    multi_project::handle_multi_project_mode(&paths, dry_run).await?;
+    multi_project::handle_multi_project_mode(&paths, dry_run, &[], None).await?;
     Ok(())
 }
 
 fn handle_template_operations() -> anyhow::Result<()> {
-    println!("📋 Template Operations");
+    ui::status("📋 Template Operations");
     println!("Manage project templates for quick scaffolding.\n");
 
     let template_choices = vec![
@@ -280,42 +364,48 @@ fn handle_template_operations() -> anyhow::Result<()> {
         "Initialize Project from Template",
         "Create Template from Project",
         "Search Templates",
+        "Lint Template",
     ];
 
-    let selection = Select::new("Select template operation:", template_choices).prompt()?;
+    let selection = ui::select("Select template operation:", template_choices)?;
 
     match selection {
         "List Available Templates" => {
             let cmd = TemplateCommand::List;
-            handle_template_mode(&cmd)?;
+            crate::handle_template_mode(&cmd)?;
         }
         "Initialize Project from Template" => {
-            let template = Text::new("Enter template name:").prompt()?;
+            let template = ui::text("Enter template name:", None)?;
 
-            let target_input = Text::new("Enter target directory:")
-                .with_default(".")
-                .prompt()?;
+            let target_input = ui::text("Enter target directory:", Some("."))?;
             let target = expand_tilde(&target_input)?;
 
             let cmd = TemplateCommand::Init { template, target };
-            handle_template_mode(&cmd)?;
+            crate::handle_template_mode(&cmd)?;
         }
         "Create Template from Project" => {
-            let name = Text::new("Enter template name:").prompt()?;
+            let name = ui::text("Enter template name:", None)?;
 
-            let source_input = Text::new("Enter source project path:")
-                .with_default(".")
-                .prompt()?;
+            let source_input = ui::text("Enter source project path:", Some("."))?;
             let source = expand_tilde(&source_input)?;
 
             let cmd = TemplateCommand::Create { name, source };
-            handle_template_mode(&cmd)?;
+            crate::handle_template_mode(&cmd)?;
         }
         "Search Templates" => {
-            let query = Text::new("Enter search query:").prompt()?;
+            let query = ui::text("Enter search query:", None)?;
 
             let cmd = TemplateCommand::Search { query };
-            handle_template_mode(&cmd)?;
+            crate::handle_template_mode(&cmd)?;
+        }
+        "Lint Template" => {
+            let name = ui::text("Enter template name:", None)?;
+
+            let cmd = TemplateCommand::Lint {
+                name,
+                format: OutputFormat::Text,
+            };
+            crate::handle_template_mode(&cmd)?;
         }
         _ => unreachable!(),
     }
@@ -324,40 +414,45 @@ fn handle_template_operations() -> anyhow::Result<()> {
 }
 
 fn handle_cache_operations() -> anyhow::Result<()> {
-    println!("💾 Cache Operations");
-    println!("Manage cached project detection data.\n");
+    ui::status("💾 Cache Operations");
+    println!("Manage cached project detection data and tool options.\n");
 
     let cache_choices = vec![
         "Show Cache Statistics",
         "Clear All Cache",
         "Invalidate Specific Path",
+        "Invalidate Tool Options",
     ];
 
-    let selection = Select::new("Select cache operation:", cache_choices).prompt()?;
+    let selection = ui::select("Select cache operation:", cache_choices)?;
 
     match selection {
         "Show Cache Statistics" => {
             let cmd = CacheCommand::Stats;
-            handle_cache_mode(&cmd)?;
+            crate::handle_cache_mode(&cmd)?;
         }
         "Clear All Cache" => {
-            let confirm = Confirm::new("Are you sure you want to clear all cached data?")
-                .with_default(false)
-                .prompt()?;
+            let confirm = ui::confirm("Are you sure you want to clear all cached data?", false)?;
 
             if confirm {
-                let cmd = CacheCommand::Clear;
-                handle_cache_mode(&cmd)?;
+                let cmd = CacheCommand::Clear { tools: false };
+                crate::handle_cache_mode(&cmd)?;
             } else {
                 println!("Operation cancelled.");
             }
         }
         "Invalidate Specific Path" => {
-            let path_input = Text::new("Enter path to invalidate:").prompt()?;
+            let path_input = ui::text("Enter path to invalidate:", None)?;
             let path = expand_tilde(&path_input)?;
 
-            let cmd = CacheCommand::Invalidate { path };
-            handle_cache_mode(&cmd)?;
+            let cmd = CacheCommand::Invalidate { path, tool: false };
+            crate::handle_cache_mode(&cmd)?;
+        }
+        "Invalidate Tool Options" => {
+            let path = ui::text("Enter tool name or executable path:", None)?;
+
+            let cmd = CacheCommand::Invalidate { path, tool: true };
+            crate::handle_cache_mode(&cmd)?;
         }
         _ => unreachable!(),
     }
@@ -365,44 +460,15 @@ fn handle_cache_operations() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn show_help() {
-    println!("❓ App-Hoist Help");
-    println!("=================");
-    println!();
-    println!("App-hoist is a dynamic CLI tool for managing packages, projects, and containers.");
-    println!();
-    println!("🎯 Main Features:");
-    println!("  • Package Management: Hoist executables and packages system-wide");
-    println!("  • Project Management: Manage development projects (Rust, Go, Python, JS/TS)");
-    println!("  • Docker Operations: Direct Docker commands and containerized projects");
-    println!("  • Multi-Project: Run operations across multiple projects in parallel");
-    println!("  • Templates: Project scaffolding and boilerplate management");
-    println!("  • Cache: Intelligent caching for fast project detection");
-    println!();
-    println!("💡 Pro Tips:");
-    println!("  • Use dry-run mode to preview commands before execution");
-    println!("  • Auto-detection works in project directories");
-    println!("  • Multi-project operations run in parallel for speed");
-    println!("  • Templates help you quickly scaffold new projects");
-    println!();
-    println!("📚 For more information, visit: https://github.com/sst/opencode");
-    println!();
-}
-
 fn get_available_operations(project_type: &ProjectType) -> Vec<String> {
-    match project_type {
-        ProjectType::Rust => vec!["run", "build", "test", "check", "clippy", "install"],
-        ProjectType::Go => vec!["run", "build", "test", "tidy", "get"],
-        ProjectType::Uv | ProjectType::Venv | ProjectType::Generic => {
-            vec!["run", "sync/add/remove"]
-        }
-        ProjectType::JavaScript | ProjectType::TypeScript => {
-            vec!["run", "install", "add", "test", "build"]
+    let mut operations: Vec<String> = Vec::new();
+    for operation in project_type.operations() {
+        let flag = operation.as_flag();
+        if !operations.contains(&flag) {
+            operations.push(flag);
         }
     }
-    .into_iter()
-    .map(|s| s.to_string())
-    .collect()
+    operations
 }
 
 fn detect_project_in_current_dir() -> anyhow::Result<Option<ProjectType>> {
@@ -411,7 +477,6 @@ fn detect_project_in_current_dir() -> anyhow::Result<Option<ProjectType>> {
     // Quick detection logic (simplified version of what's in project.rs)
     let pyproject_path = current_dir.join("pyproject.toml");
     let uv_lock_path = current_dir.join("uv.lock");
-    let activate_path = current_dir.join("bin").join("activate");
     let go_mod_path = current_dir.join("go.mod");
     let cargo_toml_path = current_dir.join("Cargo.toml");
     let package_json_path = current_dir.join("package.json");
@@ -429,8 +494,16 @@ fn detect_project_in_current_dir() -> anyhow::Result<Option<ProjectType>> {
         }
     }
 
+    // Check for Poetry project
+    if current_dir.join("poetry.lock").exists()
+        || std::fs::read_to_string(&pyproject_path)
+            .is_ok_and(|content| content.contains("[tool.poetry]"))
+    {
+        return Ok(Some(ProjectType::Poetry));
+    }
+
     // Check for venv
-    if activate_path.exists() {
+    if project::find_venv(&current_dir.to_string_lossy()).is_some() {
         return Ok(Some(ProjectType::Venv));
     }
 
@@ -453,6 +526,30 @@ fn detect_project_in_current_dir() -> anyhow::Result<Option<ProjectType>> {
         }
     }
 
+    // Deno project
+    if project::DENO_CONFIGS
+        .iter()
+        .any(|name| current_dir.join(name).exists())
+    {
+        return Ok(Some(ProjectType::Deno));
+    }
+
+    // justfile-driven project
+    if project::JUSTFILES
+        .iter()
+        .any(|name| current_dir.join(name).exists())
+    {
+        return Ok(Some(ProjectType::Just));
+    }
+
+    // Makefile-driven project
+    if project::MAKEFILES
+        .iter()
+        .any(|name| current_dir.join(name).exists())
+    {
+        return Ok(Some(ProjectType::Make));
+    }
+
     // Generic Python project
     if pyproject_path.exists() {
         return Ok(Some(ProjectType::Generic));
@@ -461,74 +558,35 @@ fn detect_project_in_current_dir() -> anyhow::Result<Option<ProjectType>> {
     Ok(None)
 }
 
-// Re-export the handler functions from main.rs for reuse
-fn handle_template_mode(command: &TemplateCommand) -> anyhow::Result<()> {
-    match command {
-        TemplateCommand::List => {
-            let templates = template::list_available_templates()?;
-            if templates.is_empty() {
-                println!("No templates found. Create your first template with:");
-                println!("  app-hoist template create <name>");
-            } else {
-                println!("Available templates:");
-                for template in templates {
-                    println!("  - {}", template);
-                }
-            }
-        }
-        TemplateCommand::Init { template, target } => {
-            template::init_project_from_template(template, target)?;
-        }
-        TemplateCommand::Create { name, source } => {
-            template::create_template_from_project(source, name)?;
-        }
-        TemplateCommand::Search { query } => {
-            let templates = template::list_available_templates()?;
-            let matches: Vec<_> = templates
-                .into_iter()
-                .filter(|t| t.to_lowercase().contains(&query.to_lowercase()))
-                .collect();
-
-            if matches.is_empty() {
-                println!("No templates found matching '{}'", query);
-            } else {
-                println!("Templates matching '{}':", query);
-                for template in matches {
-                    println!("  - {}", template);
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_main_menu_uses_text_labels() {
+        let rendered: Vec<String> = MainMenuChoice::ALL
+            .iter()
+            .map(|choice| ui::render_with(&choice.to_string(), ui::GlyphMode::Ascii))
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "[package] Package Management - Hoist executables/packages",
+                "[project] Project Management - Manage development projects",
+                "[docker] Docker Operations - Container management",
+                "[multi] Multi-Project Operations - Parallel project management",
+                "[list] Template Operations - Project scaffolding",
+                "[cache] Cache Operations - Manage cached data",
+                "[help] Help/About - Information and help",
+                "[exit] Exit - Quit app-hoist",
+            ]
+        );
     }
-    Ok(())
-}
-
-fn handle_cache_mode(command: &CacheCommand) -> anyhow::Result<()> {
-    let mut cache_manager = crate::cache::CacheManager::new()?;
 
-    match command {
-        CacheCommand::Stats => {
-            let stats = cache_manager.stats();
-            println!("{}", stats);
-        }
-        CacheCommand::Clear => {
-            cache_manager.clear_all()?;
-            println!("✅ All cache cleared");
-        }
-        CacheCommand::Invalidate { path } => {
-            cache_manager.invalidate(path)?;
-            println!("✅ Cache invalidated for: {}", path);
-        }
-    }
-
-    Ok(())
-}
-
-fn expand_tilde(path: &str) -> anyhow::Result<String> {
-    if path.starts_with("~") {
-        let home = std::env::var("HOME")
-            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-        Ok(path.replacen("~", &home, 1))
-    } else {
-        Ok(path.to_string())
+    #[test]
+    fn main_menu_features_leave_out_navigation() {
+        let features = main_menu_features();
+        assert_eq!(features.len(), MainMenuChoice::ALL.len() - 2);
+        assert!(!features.iter().any(|label| label.starts_with("Exit")));
     }
 }
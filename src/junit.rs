@@ -0,0 +1,128 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+/// One project's result within a suite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub classname: String,
+    pub time: Duration,
+    /// Set when the case didn't run to completion, with the reason
+    pub skipped: Option<String>,
+    pub failure: Option<Failure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub message: String,
+    pub details: String,
+}
+
+/// All projects that ran one operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSuite {
+    pub name: String,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    fn failures(&self) -> usize {
+        self.cases.iter().filter(|c| c.failure.is_some()).count()
+    }
+
+    fn skipped(&self) -> usize {
+        self.cases.iter().filter(|c| c.skipped.is_some()).count()
+    }
+
+    fn time(&self) -> Duration {
+        self.cases.iter().map(|c| c.time).sum()
+    }
+}
+
+pub fn to_xml(suites: &[TestSuite]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let tests: usize = suites.iter().map(|s| s.cases.len()).sum();
+    let failures: usize = suites.iter().map(TestSuite::failures).sum();
+    let skipped: usize = suites.iter().map(TestSuite::skipped).sum();
+    let time: Duration = suites.iter().map(TestSuite::time).sum();
+
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        tests,
+        failures,
+        skipped,
+        time.as_secs_f64()
+    );
+
+    for suite in suites {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape(&suite.name),
+            suite.cases.len(),
+            suite.failures(),
+            suite.skipped(),
+            suite.time().as_secs_f64()
+        );
+
+        for case in &suite.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&case.name),
+                escape(&case.classname),
+                case.time.as_secs_f64()
+            );
+            match (&case.failure, &case.skipped) {
+                (None, Some(reason)) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                        escape(reason)
+                    );
+                }
+                (None, None) => xml.push_str("/>\n"),
+                (Some(failure), _) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                        escape(&failure.message),
+                        escape_text(&failure.details)
+                    );
+                }
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for use in an XML attribute value.
+pub fn escape(text: &str) -> String {
+    // Attribute values would otherwise have their newlines normalised to spaces
+    escape_text(text).replace('\n', "&#10;")
+}
+
+/// Escape text for element content, where newlines can stay as they are.
+/// Control characters that XML 1.0 can't represent at all (common in raw
+/// tool output, e.g. ANSI escapes) are dropped.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,6 @@
+//! The parts of app-hoist that read a tool's help, for use without the
+//! interactive binary: `help_parser::parse_help` turns help text into
+//! options, and `help_parser::probe_help` runs a tool to get that text.
+
+pub mod help_parser;
+pub mod models;
@@ -0,0 +1,348 @@
+use crate::cache::path_hash;
+use crate::ui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Who holds a project lock, as recorded in the lock file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub operation: String,
+    pub path: String,
+    pub started_at: String,
+}
+
+impl std::fmt::Display for LockInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pid {} running '{}' since {}",
+            self.pid, self.operation, self.started_at
+        )
+    }
+}
+
+/// An advisory lock on one project, released when dropped.
+pub struct ProjectLock {
+    file: PathBuf,
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.file);
+    }
+}
+
+pub enum TryLock {
+    Acquired(ProjectLock),
+    Held(LockInfo),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Contention {
+    Wait,
+    Proceed,
+    Abort,
+}
+
+impl std::fmt::Display for Contention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Contention::Wait => write!(f, "Wait for it to finish"),
+            Contention::Proceed => write!(f, "Proceed anyway"),
+            Contention::Abort => write!(f, "Abort"),
+        }
+    }
+}
+
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn lock_dir() -> anyhow::Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home_dir.join(".app-hoist").join("locks"))
+}
+
+/// Canonical form of a project path, so `./app`, `/home/me/app` and symlinks
+/// to it share a lock.
+pub fn canonical_key(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+pub fn lock_file_for(dir: &Path, path: &str) -> PathBuf {
+    dir.join(format!("{:016x}.lock", path_hash(&canonical_key(path))))
+}
+
+/// Try once to take the lock, clearing it first if its holder has died.
+pub fn try_lock(
+    dir: &Path,
+    path: &str,
+    operation: &str,
+    pid: u32,
+    is_alive: impl Fn(u32) -> bool,
+) -> anyhow::Result<TryLock> {
+    std::fs::create_dir_all(dir)?;
+    let file = lock_file_for(dir, path);
+
+    // The lock file appears with its contents already written, by linking a
+    // finished temp file into place, so no one can read it half-written
+    let info = LockInfo {
+        pid,
+        operation: operation.to_string(),
+        path: canonical_key(path),
+        started_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    let temp = file.with_extension(format!("lock.{}.tmp", pid));
+    std::fs::write(&temp, serde_json::to_string(&info)?)?;
+    let result = link_lock(&temp, &file, &is_alive);
+    let _ = std::fs::remove_file(&temp);
+    result
+}
+
+fn link_lock(temp: &Path, file: &Path, is_alive: impl Fn(u32) -> bool) -> anyhow::Result<TryLock> {
+    loop {
+        match std::fs::hard_link(temp, file) {
+            Ok(()) => {
+                return Ok(TryLock::Acquired(ProjectLock {
+                    file: file.to_path_buf(),
+                }));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let stale = read_lock(file);
+                match &stale {
+                    Some(holder) if is_alive(holder.pid) => {
+                        return Ok(TryLock::Held(holder.clone()));
+                    }
+                    // Dead holder or unreadable contents: the lock is stale,
+                    // unless someone replaced it while it was being checked
+                    _ => {
+                        if read_lock(file).map(|info| info.pid) == stale.map(|info| info.pid) {
+                            let _ = std::fs::remove_file(file);
+                        }
+                    }
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Lock a project before running `operation`, asking the user what to do if
+/// another process holds it. Returns `None` when they chose to proceed anyway.
+pub async fn acquire(path: &str, operation: &str) -> anyhow::Result<Option<ProjectLock>> {
+    let dir = lock_dir()?;
+    let pid = std::process::id();
+
+    let holder = match try_lock(&dir, path, operation, pid, process_alive)? {
+        TryLock::Acquired(lock) => return Ok(Some(lock)),
+        TryLock::Held(holder) => holder,
+    };
+
+    ui::status(&format!("⚠️  {} is locked by {}", path, holder));
+    let choice = ui::select(
+        "What would you like to do?",
+        vec![Contention::Wait, Contention::Proceed, Contention::Abort],
+    )?;
+
+    match choice {
+        Contention::Wait => {
+            println!("Waiting for pid {} to finish...", holder.pid);
+            loop {
+                tokio::time::sleep(WAIT_INTERVAL).await;
+                if let TryLock::Acquired(lock) =
+                    try_lock(&dir, path, operation, pid, process_alive)?
+                {
+                    return Ok(Some(lock));
+                }
+            }
+        }
+        Contention::Proceed => Ok(None),
+        Contention::Abort => anyhow::bail!("Aborted: {} is locked by {}", path, holder),
+    }
+}
+
+/// Lock several projects at once. Locks are always taken in the same order
+/// (by lock file name) so two concurrent batch runs can't deadlock each other.
+pub async fn acquire_all(paths: &[String], operation: &str) -> anyhow::Result<Vec<ProjectLock>> {
+    let dir = lock_dir()?;
+    let mut locks = Vec::new();
+    for path in lock_order(&dir, paths) {
+        if let Some(lock) = acquire(path, operation).await? {
+            locks.push(lock);
+        }
+    }
+    Ok(locks)
+}
+
+pub fn lock_order<'a>(dir: &Path, paths: &'a [String]) -> Vec<&'a String> {
+    let mut ordered: Vec<(PathBuf, &String)> = paths
+        .iter()
+        .map(|path| (lock_file_for(dir, path), path))
+        .collect();
+    ordered.sort();
+    ordered.dedup_by(|a, b| a.0 == b.0);
+    ordered.into_iter().map(|(_, path)| path).collect()
+}
+
+fn read_lock(file: &Path) -> Option<LockInfo> {
+    let content = std::fs::read_to_string(file).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+
+    if cfg!(unix) {
+        return std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+    }
+
+    // Without a cheap liveness check, treat the holder as alive and let the user decide
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OURS: u32 = 1111;
+    const OTHER: u32 = 2222;
+
+    fn lock(dir: &Path, project: &Path, pid: u32, is_alive: impl Fn(u32) -> bool) -> TryLock {
+        try_lock(dir, project.to_str().unwrap(), "build", pid, is_alive).unwrap()
+    }
+
+    fn write_holder(dir: &Path, project: &Path, pid: u32) {
+        let info = LockInfo {
+            pid,
+            operation: "test".to_string(),
+            path: canonical_key(project.to_str().unwrap()),
+            started_at: "2024-01-01 00:00:00".to_string(),
+        };
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            lock_file_for(dir, project.to_str().unwrap()),
+            serde_json::to_string(&info).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn a_free_project_is_locked_with_our_details() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        let TryLock::Acquired(held) = lock(&locks, dir.path(), OURS, |_| true) else {
+            panic!("lock should be free");
+        };
+
+        let info = read_lock(&held.file).unwrap();
+        assert_eq!(info.pid, OURS);
+        assert_eq!(info.operation, "build");
+        // Only the lock file is left behind, not the temp file it was linked from
+        assert_eq!(std::fs::read_dir(&locks).unwrap().count(), 1);
+
+        drop(held);
+        assert_eq!(std::fs::read_dir(&locks).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn a_live_holder_keeps_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        write_holder(&locks, dir.path(), OTHER);
+
+        match lock(&locks, dir.path(), OURS, |pid| pid == OTHER) {
+            TryLock::Held(holder) => {
+                assert_eq!(holder.pid, OTHER);
+                assert_eq!(holder.operation, "test");
+            }
+            TryLock::Acquired(_) => panic!("a live holder's lock was taken"),
+        }
+        assert_eq!(
+            read_lock(&lock_file_for(&locks, dir.path().to_str().unwrap()))
+                .unwrap()
+                .pid,
+            OTHER
+        );
+    }
+
+    #[test]
+    fn a_dead_holders_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        write_holder(&locks, dir.path(), OTHER);
+
+        let TryLock::Acquired(held) = lock(&locks, dir.path(), OURS, |_| false) else {
+            panic!("a dead holder's lock should be stale");
+        };
+        assert_eq!(read_lock(&held.file).unwrap().pid, OURS);
+    }
+
+    #[test]
+    fn an_unreadable_lock_is_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        std::fs::create_dir_all(&locks).unwrap();
+        std::fs::write(lock_file_for(&locks, dir.path().to_str().unwrap()), "").unwrap();
+
+        assert!(matches!(
+            lock(&locks, dir.path(), OURS, |_| true),
+            TryLock::Acquired(_)
+        ));
+    }
+
+    #[test]
+    fn a_second_taker_sees_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        let _first = lock(&locks, dir.path(), OURS, |_| true);
+
+        match lock(&locks, dir.path(), OTHER, |_| true) {
+            TryLock::Held(holder) => assert_eq!(holder.pid, OURS),
+            TryLock::Acquired(_) => panic!("the lock was taken twice"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_paths_share_a_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        let link = dir.path().join("link");
+        std::fs::create_dir(&project).unwrap();
+        std::os::unix::fs::symlink(&project, &link).unwrap();
+
+        let locks = dir.path().join("locks");
+        assert_eq!(
+            lock_file_for(&locks, project.to_str().unwrap()),
+            lock_file_for(&locks, link.to_str().unwrap())
+        );
+    }
+
+    #[test]
+    fn batches_lock_in_a_stable_order_without_duplicates() {
+        let dir = Path::new("/locks");
+        let paths: Vec<String> = ["/a", "/b", "/c", "/a"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let reversed: Vec<String> = paths.iter().rev().cloned().collect();
+
+        let ordered = lock_order(dir, &paths);
+        assert_eq!(ordered.len(), 3);
+        assert_eq!(ordered, lock_order(dir, &reversed));
+    }
+
+    #[test]
+    fn our_own_process_is_alive() {
+        assert!(process_alive(std::process::id()));
+    }
+}
@@ -1,20 +1,77 @@
+mod about;
+mod alias;
 mod cache;
+mod cancel;
+mod capture;
+mod clean;
 mod cli;
+mod completions;
+mod config;
+mod detection;
 mod docker;
+mod editor;
+mod events;
+mod freshness;
+mod history;
+mod installed;
 mod interactive;
-mod models;
+mod junit;
+mod lock;
 mod multi_project;
 mod package;
+mod paths;
+mod pin;
+mod plan;
+mod prefetch;
 mod project;
+mod recall;
+mod refresh;
+mod resolve;
+mod run_profile;
 mod template;
+mod template_lint;
+mod tools;
+mod ui;
 mod utils;
+mod value_sources;
+mod worktree;
 
-use crate::cli::{AppCommand, Args, CacheCommand, TemplateCommand};
+// Help parsing and the types it produces live in the library, so they can be
+// used (and tested) without the interactive parts
+use app_hoist::{help_parser, models};
+
+use crate::cli::{
+    AliasCommand, AppCommand, Args, CacheCommand, CompletionsCommand, HistoryCommand,
+    InstalledCommand, OutputFormat, TemplateCommand,
+};
 use clap::Parser;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let result = run().await;
+    events::finish();
+    match result {
+        Err(e) if e.chain().any(|cause| cause.is::<ui::PromptTimedOut>()) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(ui::PROMPT_TIMEOUT_EXIT_CODE);
+        }
+        // The command's own failure was already reported as it ended
+        Err(e) if e.is::<capture::ChildExit>() => {
+            let code = e
+                .downcast_ref::<capture::ChildExit>()
+                .map_or(1, |exit| exit.code);
+            std::process::exit(code);
+        }
+        result => result,
+    }
+}
+
+async fn run() -> anyhow::Result<()> {
     let args = Args::parse();
+    ui::init(args.plain, args.no_color, args.glyphs);
+    capture::init(args.quiet_child, args.tail_lines, args.ignore_exit_code);
+    events::init(args.progress_fd, args.progress_file.as_deref())?;
+    detection::init(args.only_type.clone(), args.entry.clone())?;
 
     if let Some(app_cmd) = &args.command {
         match app_cmd {
@@ -26,6 +83,60 @@ async fn main() -> anyhow::Result<()> {
                 // Cache mode
                 handle_cache_mode(cache_cmd)?;
             }
+            AppCommand::History(HistoryCommand::Prune {
+                max_entries,
+                max_age_days,
+            }) => {
+                history::prune(*max_entries, *max_age_days)?;
+            }
+            AppCommand::History(HistoryCommand::List { limit }) => {
+                history::print_list(*limit)?;
+            }
+            AppCommand::History(HistoryCommand::Rerun { n, yes, dry_run }) => {
+                history::rerun(*n, *yes, *dry_run).await?;
+            }
+            AppCommand::Installed(InstalledCommand::List) => {
+                installed::print_list()?;
+            }
+            AppCommand::Installed(InstalledCommand::Remove { name }) => {
+                installed::remove(name)?;
+            }
+            AppCommand::Alias(AliasCommand::Run { name, dry_run }) => {
+                alias::run(name, *dry_run).await?;
+            }
+            AppCommand::Alias(AliasCommand::List) => {
+                alias::print_list()?;
+            }
+            AppCommand::Alias(AliasCommand::Rm { name }) => {
+                alias::remove(name)?;
+            }
+            AppCommand::Completions(CompletionsCommand::Generate { shell }) => {
+                print!("{}", completions::generate(*shell));
+            }
+            AppCommand::Completions(CompletionsCommand::Install { shell }) => {
+                completions::install(*shell)?;
+            }
+            AppCommand::Detect {
+                path,
+                explain,
+                format,
+            } => {
+                let report = detection::detect(path, *explain)?;
+                detection::print_report(&report, *format)?;
+            }
+            AppCommand::Which { name, format } => {
+                let mut resolution = resolve::resolve(name);
+                if resolution.candidates.is_empty() {
+                    // Explains aliases, builtins and functions
+                    package::find_executable(name)?;
+                }
+                resolve::probe_versions(&mut resolution).await;
+                resolve::print_resolution(&resolution, *format)?;
+            }
+            AppCommand::About { format } => match format {
+                OutputFormat::Text => about::print_about(),
+                OutputFormat::Json => about::print_about_json()?,
+            },
         }
     } else {
         match (
@@ -35,25 +146,57 @@ async fn main() -> anyhow::Result<()> {
             &args.docker_path,
             &args.multi_path,
         ) {
+            (Some(package), None, None, None, None) if args.list_options => {
+                package::print_options(package).await?;
+            }
+            (None, Some(path), None, None, None) if args.list_options => {
+                project::print_options(path)?;
+            }
             (Some(package), None, None, None, None) => {
                 // Tool mode: hoist a package/executable
-                package::handle_package_mode(package, args.dry_run)?;
+                package::handle_package_mode(
+                    package,
+                    args.dry_run,
+                    args.fresh,
+                    &args.select,
+                    &args.env,
+                )
+                .await?;
             }
             (None, Some(path), None, None, None) => {
                 // Project mode: manage a project (Python, Go, Rust, or JS/TS)
-                project::handle_project_mode(path, args.dry_run)?;
+                project::handle_project_mode(
+                    path,
+                    args.dry_run,
+                    args.version_check,
+                    &args.select,
+                    &args.env,
+                    args.program_args.as_deref(),
+                )
+                .await?;
             }
             (None, None, Some(cmd), None, None) => {
                 // Direct Docker mode: execute Docker commands directly
-                docker::handle_direct_docker_mode(cmd, args.dry_run)?;
+                docker::handle_direct_docker_mode(cmd, args.dry_run).await?;
             }
             (None, None, None, Some(path), None) => {
                 // Docker project mode: manage Docker-enabled projects
-                docker::handle_docker_project_mode(path, args.dry_run)?;
+                docker::handle_docker_project_mode(
+                    path,
+                    args.dry_run,
+                    args.docker_profile.as_deref(),
+                )
+                .await?;
             }
             (None, None, None, None, Some(paths)) => {
                 // Multi-project mode: run operations on multiple projects in parallel
-                multi_project::handle_multi_project_mode(paths, args.dry_run).await?;
+                multi_project::handle_multi_project_mode(
+                    paths,
+                    args.dry_run,
+                    &args.select,
+                    args.report_junit.as_deref(),
+                )
+                .await?;
             }
             (None, None, None, None, None) => {
                 // Interactive mode: no arguments provided, show interactive menu
@@ -114,6 +257,27 @@ fn handle_template_mode(command: &TemplateCommand) -> anyhow::Result<()> {
                 }
             }
         }
+        TemplateCommand::Lint { name, format } => {
+            let issues = template_lint::lint_template(name)?;
+
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&issues)?),
+                OutputFormat::Text => {
+                    for issue in &issues {
+                        println!("{}", issue);
+                    }
+                    let errors = issues
+                        .iter()
+                        .filter(|i| i.severity == template_lint::Severity::Error)
+                        .count();
+                    println!("{} error(s), {} warning(s)", errors, issues.len() - errors);
+                }
+            }
+
+            if template_lint::has_errors(&issues) {
+                anyhow::bail!("Template '{}' has lint errors", name);
+            }
+        }
     }
     Ok(())
 }
@@ -126,13 +290,27 @@ fn handle_cache_mode(command: &CacheCommand) -> anyhow::Result<()> {
             let stats = cache_manager.stats();
             println!("{}", stats);
         }
-        CacheCommand::Clear => {
+        CacheCommand::Clear { tools: false } => {
             cache_manager.clear_all()?;
-            println!("✅ All cache cleared");
+            ui::status("✅ All cache cleared");
+        }
+        CacheCommand::Clear { tools: true } => {
+            cache_manager.clear_tools()?;
+            ui::status("✅ Cached tool options cleared");
         }
-        CacheCommand::Invalidate { path } => {
+        CacheCommand::Invalidate { path, tool: false } => {
             cache_manager.invalidate(path)?;
-            println!("✅ Cache invalidated for: {}", path);
+            ui::status(&format!("✅ Cache invalidated for: {}", path));
+        }
+        CacheCommand::Invalidate { path, tool: true } => {
+            let executable = package::find_executable(path)?
+                .to_string_lossy()
+                .into_owned();
+            let removed = cache_manager.invalidate_tool(&executable)?;
+            ui::status(&format!(
+                "✅ Cleared {} cached option set(s) for: {}",
+                removed, executable
+            ));
         }
     }
 
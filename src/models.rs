@@ -1,8 +1,176 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct OptionInfo {
     pub flags: Vec<String>,
     pub description: String,
-    pub requires_value: bool,
+    pub value: ValueArity,
+    /// Values the help text lists as the only accepted ones, if any
+    pub possible_values: Vec<String>,
+    /// The value used when the option is left out, from `[default: ...]`
+    pub default: Option<String>,
+    /// Whether the option may be passed more than once, e.g. `-v -v`
+    pub repeatable: bool,
+    /// The help section the option is listed under, e.g. `Display Options`
+    pub section: Option<String>,
+    /// The help's placeholder for the value, e.g. `FILE`
+    pub value_name: Option<String>,
+    /// Whether the help writes the value joined to the flag, `--file=FILE`
+    pub joined_value: bool,
+    /// What kind of value the option takes, guessed from its placeholder
+    #[serde(default)]
+    pub value_type: ValueType,
+    /// Options this one can't be combined with, by preferred flag
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+}
+
+/// Whether an option takes a value: none, an optional `[VALUE]` that may be
+/// left out, or a required `<VALUE>`. Ordered so the stricter reading wins.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum ValueArity {
+    #[default]
+    None,
+    Optional,
+    Required,
+}
+
+impl ValueArity {
+    pub fn takes_value(self) -> bool {
+        self != ValueArity::None
+    }
+}
+
+/// The kind of value an option expects, used to check input before the
+/// command runs. `String` accepts anything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ValueType {
+    #[default]
+    String,
+    Integer,
+    Path,
+    Url,
+}
+
+/// Placeholder words that give away a value's type, e.g. `PORT` or the
+/// `FILE` in `CONFIG_FILE`.
+const PLACEHOLDER_TYPES: &[(&str, ValueType)] = &[
+    ("PORT", ValueType::Integer),
+    ("NUM", ValueType::Integer),
+    ("NUMBER", ValueType::Integer),
+    ("COUNT", ValueType::Integer),
+    ("N", ValueType::Integer),
+    ("INT", ValueType::Integer),
+    ("JOBS", ValueType::Integer),
+    ("SECS", ValueType::Integer),
+    ("SECONDS", ValueType::Integer),
+    ("FILE", ValueType::Path),
+    ("FILENAME", ValueType::Path),
+    ("PATH", ValueType::Path),
+    ("DIR", ValueType::Path),
+    ("DIRECTORY", ValueType::Path),
+    ("URL", ValueType::Url),
+    ("URI", ValueType::Url),
+];
+
+/// Description phrases that give away a value's type, for options whose
+/// help shows no placeholder.
+const DESCRIPTION_TYPES: &[(&str, ValueType)] = &[
+    ("port number", ValueType::Integer),
+    ("number of", ValueType::Integer),
+    ("path to", ValueType::Path),
+    ("url", ValueType::Url),
+];
+
+impl ValueType {
+    /// Guess the type from the placeholder, or failing that the description.
+    pub fn infer(value_name: Option<&str>, description: &str) -> Self {
+        if let Some(name) = value_name {
+            let name = name.to_uppercase();
+            let words: Vec<&str> = name.split(|c: char| !c.is_ascii_alphanumeric()).collect();
+            return PLACEHOLDER_TYPES
+                .iter()
+                .find(|(word, _)| words.contains(word))
+                .map_or(ValueType::String, |(_, value_type)| *value_type);
+        }
+
+        let description = description.to_lowercase();
+        let words: Vec<&str> = description
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let text = words.join(" ");
+        DESCRIPTION_TYPES
+            .iter()
+            .find(|(phrase, _)| format!(" {} ", text).contains(&format!(" {} ", phrase)))
+            .map_or(ValueType::String, |(_, value_type)| *value_type)
+    }
+
+    /// Why `value` isn't of this type, if it isn't. Paths aren't checked
+    /// here, since one that doesn't exist yet may be an output file.
+    pub fn check(self, value: &str) -> Result<(), String> {
+        match self {
+            ValueType::Integer if value.trim().parse::<i64>().is_err() => {
+                Err(format!("'{}' is not a whole number", value))
+            }
+            ValueType::Url if !value.contains("://") => {
+                Err(format!("'{}' is not a URL (expected scheme://...)", value))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An option chosen for a command, with the value for each time it is passed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SelectedOption {
+    pub flag: String,
+    pub occurrences: Vec<Option<String>>,
+    /// Pass values as `--flag=value` rather than `--flag value`
+    pub joined: bool,
+}
+
+impl OptionInfo {
+    /// The first long flag, e.g. `--config`.
+    pub fn long(&self) -> Option<&str> {
+        self.flags
+            .iter()
+            .map(String::as_str)
+            .find(|flag| flag.starts_with("--"))
+    }
+
+    /// The first short flag, e.g. `-c`.
+    pub fn short(&self) -> Option<&str> {
+        self.flags
+            .iter()
+            .map(String::as_str)
+            .find(|flag| flag.starts_with('-') && !flag.starts_with("--"))
+    }
+
+    /// The flag to put on the command line: the long form when there is one,
+    /// since it reads better in dry runs and shell history.
+    pub fn preferred_flag(&self) -> &str {
+        self.long()
+            .or_else(|| self.short())
+            .or_else(|| self.flags.first().map(String::as_str))
+            .unwrap_or_default()
+    }
+
+    /// All spellings for display, short form first, e.g. `-c, --config`.
+    pub fn flag_label(&self) -> String {
+        let mut flags: Vec<&str> = self.flags.iter().map(String::as_str).collect();
+        flags.sort_by_key(|flag| flag.starts_with("--"));
+        flags.join(", ")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -14,27 +182,393 @@ pub struct CliArg {
     pub requires_value: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+/// Per-run settings that shape the commands built for the selected operations.
+#[derive(Debug, Clone, Default)]
+pub struct BuildSettings {
+    pub go_build_tags: Vec<String>,
+    /// The Go main package to run and build, e.g. `./cmd/server`
+    pub go_package: Option<String>,
+    /// GOOS and GOARCH to cross-compile for; the binary is left in the
+    /// project as `<name>-<os>-<arch>` instead of being installed
+    pub go_target: Option<(String, String)>,
+    /// Parallel compiler jobs, when a build shares the machine with others
+    pub jobs: Option<usize>,
+    /// Arguments the user typed after choosing options, appended as given
+    pub extra_args: Vec<String>,
+    /// Arguments for the program that run starts, after `--` for cargo and
+    /// npm, directly after the entry point otherwise
+    pub program_args: Vec<String>,
+    /// The package manager recorded in the project's cache entry, used
+    /// instead of detecting it again
+    pub package_manager: Option<String>,
+    /// The venv directory recorded for the project, e.g. `.venv`
+    pub venv_dir: Option<String>,
+    /// An entry point given with `--entry`, run instead of the detected one
+    pub entry_point: Option<String>,
+    /// The Cargo workspace member to act on, passed as `-p`
+    pub cargo_package: Option<String>,
+    /// Cargo features to enable, passed as `--features a,b`
+    pub cargo_features: Vec<String>,
+    pub no_default_features: bool,
+    /// The binary `cargo run` starts, passed as `--bin`
+    pub cargo_bin: Option<String>,
+    /// The Cargo profile for build and run (`debug`, `release` or a custom
+    /// one); unset builds in release and runs in debug
+    pub cargo_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, clap::ValueEnum)]
 pub enum ProjectType {
     Uv,
+    Poetry,
     Venv,
     Generic,
     Go,
     Rust,
+    #[value(name = "js")]
     JavaScript,
+    #[value(name = "ts")]
     TypeScript,
+    Make,
+    Just,
+    Deno,
+}
+
+impl ProjectType {
+    /// The name used in config files and on the command line, e.g. `js`.
+    pub fn key(&self) -> String {
+        clap::ValueEnum::to_possible_value(self)
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    pub const ALL: [ProjectType; 11] = [
+        ProjectType::Uv,
+        ProjectType::Poetry,
+        ProjectType::Venv,
+        ProjectType::Generic,
+        ProjectType::Go,
+        ProjectType::Rust,
+        ProjectType::JavaScript,
+        ProjectType::TypeScript,
+        ProjectType::Make,
+        ProjectType::Just,
+        ProjectType::Deno,
+    ];
 }
 
 impl std::fmt::Display for ProjectType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ProjectType::Uv => write!(f, "UV"),
+            ProjectType::Poetry => write!(f, "Poetry"),
             ProjectType::Venv => write!(f, "venv"),
             ProjectType::Generic => write!(f, "generic Python"),
             ProjectType::Go => write!(f, "Go"),
             ProjectType::Rust => write!(f, "Rust"),
             ProjectType::JavaScript => write!(f, "JavaScript"),
             ProjectType::TypeScript => write!(f, "TypeScript"),
+            ProjectType::Make => write!(f, "Make"),
+            ProjectType::Just => write!(f, "just"),
+            ProjectType::Deno => write!(f, "Deno"),
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Operation {
+    Run,
+    /// Run with a CLI argument detected from the project's own argument parser
+    RunWithArg {
+        name: String,
+        requires_value: bool,
+    },
+    Build,
+    Install,
+    InstallDeps,
+    InstallPackage,
+    UninstallPackage,
+    Sync,
+    Add,
+    Remove,
+    Get,
+    Test,
+    Check,
+    Clippy,
+    Tidy,
+    Generate,
+    Format,
+    Lint,
+    Update,
+    Audit,
+    Clean,
+    /// A Makefile target, justfile recipe, package.json script or Deno task, with its
+    /// description if it has one and the parameters a recipe takes, e.g. `target="debug"`
+    Target {
+        name: String,
+        description: Option<String>,
+        parameters: Vec<String>,
+    },
+}
+
+impl Operation {
+    pub fn as_flag(&self) -> String {
+        match self {
+            Operation::Run => "run".to_string(),
+            Operation::RunWithArg { name, .. } => format!("run --{}", name),
+            Operation::Build => "build".to_string(),
+            Operation::Install | Operation::InstallDeps | Operation::InstallPackage => {
+                "install".to_string()
+            }
+            Operation::UninstallPackage => "uninstall".to_string(),
+            Operation::Sync => "sync".to_string(),
+            Operation::Add => "add".to_string(),
+            Operation::Remove => "remove".to_string(),
+            Operation::Get => "get".to_string(),
+            Operation::Test => "test".to_string(),
+            Operation::Check => "check".to_string(),
+            Operation::Clippy => "clippy".to_string(),
+            Operation::Tidy => "tidy".to_string(),
+            Operation::Generate => "generate".to_string(),
+            Operation::Format => "fmt".to_string(),
+            Operation::Lint => "lint".to_string(),
+            Operation::Update => "update".to_string(),
+            Operation::Audit => "audit".to_string(),
+            Operation::Clean => "clean".to_string(),
+            Operation::Target { name, .. } => name.clone(),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Operation::Run => "Run the app".to_string(),
+            Operation::RunWithArg { name, .. } => format!("Run with --{} argument", name),
+            Operation::Build => "Build the project".to_string(),
+            Operation::Install => "Build and install to ~/.cargo/bin".to_string(),
+            Operation::InstallDeps => "Install dependencies".to_string(),
+            Operation::InstallPackage => "Install a package".to_string(),
+            Operation::UninstallPackage => "Uninstall a package".to_string(),
+            Operation::Sync => "Sync dependencies".to_string(),
+            Operation::Add => "Add a package".to_string(),
+            Operation::Remove => "Uninstall a package".to_string(),
+            Operation::Get => "Add a dependency".to_string(),
+            Operation::Test => "Run tests".to_string(),
+            Operation::Check => "Check code without building".to_string(),
+            Operation::Clippy => "Run linter".to_string(),
+            Operation::Tidy => "Clean up dependencies".to_string(),
+            Operation::Generate => "Run code generators (go generate)".to_string(),
+            Operation::Format => "Format the code".to_string(),
+            Operation::Lint => "Run linter".to_string(),
+            Operation::Update => "Update dependencies".to_string(),
+            Operation::Audit => "Check dependencies for known vulnerabilities".to_string(),
+            Operation::Clean => "Remove build artifacts and caches".to_string(),
+            Operation::Target {
+                name, description, ..
+            } => description
+                .clone()
+                .unwrap_or_else(|| format!("Run {}", name)),
+        }
+    }
+
+    pub fn requires_value(&self) -> bool {
+        match self {
+            Operation::RunWithArg { requires_value, .. } => *requires_value,
+            Operation::Target { parameters, .. } => !parameters.is_empty(),
+            Operation::InstallPackage
+            | Operation::UninstallPackage
+            | Operation::Add
+            | Operation::Remove
+            | Operation::Get => true,
+            Operation::Run
+            | Operation::Build
+            | Operation::Install
+            | Operation::InstallDeps
+            | Operation::Sync
+            | Operation::Test
+            | Operation::Check
+            | Operation::Clippy
+            | Operation::Tidy
+            | Operation::Generate
+            | Operation::Format
+            | Operation::Lint
+            | Operation::Update
+            | Operation::Audit
+            | Operation::Clean => false,
+        }
+    }
+
+    /// Whether two operations run the same thing, e.g. `test` targets with
+    /// different comments in two projects' Makefiles or justfiles.
+    pub fn same_action(&self, other: &Operation) -> bool {
+        match (self, other) {
+            (Operation::Target { name, .. }, Operation::Target { name: other, .. }) => {
+                name == other
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Whether the operation can run unattended alongside other projects.
+    pub fn is_batch_safe(&self) -> bool {
+        !self.requires_value() && !matches!(self, Operation::Run | Operation::RunWithArg { .. })
+    }
+
+    pub fn to_option_info(&self) -> OptionInfo {
+        OptionInfo {
+            flags: vec![self.as_flag()],
+            description: self.description(),
+            value: if self.requires_value() {
+                ValueArity::Required
+            } else {
+                ValueArity::None
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl ProjectType {
+    /// Operations offered for this project type, in menu order.
+    pub fn operations(&self) -> Vec<Operation> {
+        match self {
+            ProjectType::Uv => vec![
+                Operation::Run,
+                Operation::Sync,
+                Operation::Add,
+                Operation::Remove,
+                Operation::Update,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::Poetry => vec![
+                Operation::Run,
+                Operation::InstallDeps,
+                Operation::Add,
+                Operation::Remove,
+                Operation::Test,
+                Operation::Update,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::Venv => vec![
+                Operation::Run,
+                Operation::InstallPackage,
+                Operation::UninstallPackage,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::Go => vec![
+                Operation::Run,
+                Operation::Generate,
+                Operation::Build,
+                Operation::Test,
+                Operation::Tidy,
+                Operation::Get,
+                Operation::Update,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::Rust => vec![
+                Operation::Run,
+                Operation::Build,
+                Operation::Install,
+                Operation::Test,
+                Operation::Check,
+                Operation::Clippy,
+                Operation::Update,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::JavaScript | ProjectType::TypeScript => vec![
+                Operation::Run,
+                Operation::InstallDeps,
+                Operation::Add,
+                Operation::Test,
+                Operation::Build,
+                Operation::Update,
+                Operation::Audit,
+                Operation::Format,
+                Operation::Clean,
+            ],
+            ProjectType::Generic => vec![Operation::Run, Operation::Format, Operation::Clean],
+            // Targets are read from the project's Makefile or justfile
+            ProjectType::Make | ProjectType::Just => Vec::new(),
+            // Followed by the tasks from deno.json
+            ProjectType::Deno => vec![Operation::Test, Operation::Format, Operation::Lint],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_give_away_the_value_type() {
+        let cases = [
+            ("PORT", ValueType::Integer),
+            ("n", ValueType::Integer),
+            ("CONFIG_FILE", ValueType::Path),
+            ("dir", ValueType::Path),
+            ("BASE-URL", ValueType::Url),
+            ("WHEN", ValueType::String),
+            // A whole word only: "NAME" isn't "N"
+            ("NAME", ValueType::String),
+        ];
+        for (placeholder, expected) in cases {
+            assert_eq!(
+                ValueType::infer(Some(placeholder), ""),
+                expected,
+                "{}",
+                placeholder
+            );
+        }
+    }
+
+    #[test]
+    fn descriptions_are_a_fallback_for_missing_placeholders() {
+        assert_eq!(
+            ValueType::infer(None, "The number of retries"),
+            ValueType::Integer
+        );
+        assert_eq!(
+            ValueType::infer(None, "Path to the config"),
+            ValueType::Path
+        );
+        assert_eq!(ValueType::infer(None, "Server URL."), ValueType::Url);
+        assert_eq!(ValueType::infer(None, "Curly output"), ValueType::String);
+        // A placeholder wins over the description
+        assert_eq!(
+            ValueType::infer(Some("MODE"), "number of modes"),
+            ValueType::String
+        );
+    }
+
+    #[test]
+    fn values_are_checked_against_their_type() {
+        assert!(ValueType::Integer.check(" 8080 ").is_ok());
+        assert_eq!(
+            ValueType::Integer.check("banana").unwrap_err(),
+            "'banana' is not a whole number"
+        );
+        assert!(ValueType::Url.check("https://example.com").is_ok());
+        assert!(ValueType::Url.check("example.com").is_err());
+        // Output files may not exist yet
+        assert!(ValueType::Path.check("/no/such/file").is_ok());
+        assert!(ValueType::String.check("anything at all").is_ok());
+    }
+
+    #[test]
+    fn operations_become_plain_options() {
+        let option = Operation::Build.to_option_info();
+        assert_eq!(option.flags, [Operation::Build.as_flag()]);
+        assert_eq!(option.value, ValueArity::None);
+        assert!(option.conflicts.is_empty() && option.section.is_none());
+        assert_eq!(option.preferred_flag(), Operation::Build.as_flag());
+    }
+}
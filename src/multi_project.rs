@@ -1,87 +1,131 @@
 use crate::cache::{CacheManager, ProjectCache};
-use crate::models::{OptionInfo, ProjectType};
-use crate::utils::{execute_project_command_async, select_options};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use crate::cancel::{self, Cancelled, KeyListener, TaskRegistry};
+use crate::capture;
+use crate::clean;
+use crate::config;
+use crate::events::{self, Event};
+use crate::junit::{self, Failure, TestCase, TestSuite};
+use crate::lock;
+use crate::models::{BuildSettings, Operation, OptionInfo, ProjectType};
+use crate::paths::PathDisplay;
+use crate::plan::ExecutionPlan;
+use crate::project::{
+    build_project_command, cached_go_build_tags, cached_package_manager, detect_entry_point,
+    detect_project_type, get_project_operations, prompt_cargo_profile, uses_cargo_profile,
+    uses_go_build_tags,
+};
+use crate::tools::{self, MissingRuntime};
+use crate::ui::{self, Progress, ProgressGroup};
+use crate::utils::{
+    CommandFailed, execute_project_command_async, select_from_flags, select_option_indices,
+    shell_command,
+};
+use crate::value_sources::ValueSources;
+use crate::worktree;
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 
-pub async fn handle_multi_project_mode(paths: &[String], dry_run: bool) -> anyhow::Result<()> {
+pub async fn handle_multi_project_mode(
+    paths: &[String],
+    dry_run: bool,
+    selections: &[String],
+    report_junit: Option<&str>,
+) -> anyhow::Result<()> {
     println!("Managing {} projects in parallel", paths.len());
+    let detect_started = phase_started("detect");
 
     // Initialize cache manager
     let mut cache_manager = CacheManager::new()?;
 
-    // Create multi-progress bar
-    let multi_progress = Arc::new(MultiProgress::new());
-
-    // Limit concurrent operations to prevent overwhelming the system
-    let semaphore = Arc::new(Semaphore::new(num_cpus::get()));
-
     // Collect all project information
     let mut project_infos = Vec::new();
+    let mut missing_runtimes = Vec::new();
     for path in paths {
         let path_clone = path.clone();
 
-        // Try to get cached project info first
-        let cached_info = if let Ok(Some(cached)) = cache_manager.get(&path_clone) {
-            Some((path_clone.clone(), cached.project_type, cached.entry_point))
-        } else {
-            None
+        // Try to get cached project info first, or detect project type and entry point
+        let mut cache = match cache_manager.get(&path_clone) {
+            Ok(Some(cached)) => cached,
+            _ => {
+                let project_type = detect_project_type(&path_clone)?;
+                let mut cache =
+                    ProjectCache::new(project_type.clone(), detect_entry_point(&path_clone)?);
+                cache.package_manager = cached_package_manager(&project_type, &path_clone);
+                cache
+            }
         };
 
-        let project_info = if let Some(info) = cached_info {
-            info
-        } else {
-            // Detect project type and entry point
-            let project_type = detect_project_type(&path_clone)?;
-            let entry_point = detect_entry_point(&path_clone)?;
-
-            // Cache the results
-            let cache = ProjectCache::new(project_type.clone(), entry_point.clone());
-            let _ = cache_manager.set(path_clone.clone(), cache);
-
-            (path_clone, project_type, entry_point)
-        };
+        // Every project's runtime is checked before anything is chosen
+        if !dry_run {
+            match tools::check_runtime(&path_clone, &mut cache) {
+                Ok(()) => {}
+                Err(e) if e.is::<MissingRuntime>() => {
+                    missing_runtimes.push(format!("{}: {}", path_clone, e))
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
-        project_infos.push(project_info);
+        // Cache the results
+        let _ = cache_manager.set(path_clone.clone(), cache.clone());
+        project_infos.push((path_clone, cache.project_type, cache.package_manager));
     }
 
-    // Get common options across all projects (intersection of available options)
-    let common_options = if project_infos.is_empty() {
-        Vec::new()
-    } else {
-        let (first_path, first_type, first_entry) = &project_infos[0];
+    // Persist newly detected projects in one batch
+    cache_manager.flush()?;
+    if !missing_runtimes.is_empty() {
+        for missing in &missing_runtimes {
+            eprintln!("Error: {}", missing);
+        }
+        anyhow::bail!(
+            "{} project(s) can't run until their tools are installed",
+            missing_runtimes.len()
+        );
+    }
+    events::emit(Event::phase_finished("detect", detect_started.elapsed()));
 
-        let mut common_opts = get_project_options(first_type, first_entry, first_path)?;
+    let common_operations = common_operations(
+        &project_infos
+            .iter()
+            .map(|(path, project_type, _)| (path.as_str(), project_type))
+            .collect::<Vec<_>>(),
+    );
 
-        // Filter to only options that exist in all projects
-        for (path, project_type, entry_point) in &project_infos[1..] {
-            let project_opts = get_project_options(project_type, entry_point, path)?;
-            let project_flags: std::collections::HashSet<_> = project_opts
+    let common_options: Vec<OptionInfo> = common_operations
+        .iter()
+        .map(|op| {
+            let tools: Vec<String> = project_infos
                 .iter()
-                .flat_map(|opt| opt.flags.iter())
+                .filter_map(|(path, project_type, _)| tool_for(project_type, path, op))
                 .collect();
-
-            common_opts.retain(|opt| opt.flags.iter().any(|flag| project_flags.contains(flag)));
-        }
-
-        common_opts
-    };
+            with_tool_breakdown(op.to_option_info(), &tools)
+        })
+        .collect();
 
     println!(
         "Found {} common operations across all projects",
         common_options.len()
     );
 
-    let selected_options = if dry_run {
-        println!("Dry run: skipping interactive selection, using no arguments.");
-        Vec::new()
+    // Dry runs ask too, since the plan is what they're for
+    let interactive = selections.is_empty();
+    let selected_options = if !interactive {
+        select_from_flags(&common_options, selections, "these projects")?
+            .into_iter()
+            .map(|(idx, value)| (common_operations[idx].clone(), value))
+            .collect()
     } else if common_options.is_empty() {
         println!("No common options available, proceeding with no arguments.");
         Vec::new()
     } else {
         // Interactive selection
-        select_options(&common_options)?
+        select_option_indices(&common_options, &[], &ValueSources::default())?
+            .into_iter()
+            .map(|(idx, value)| (common_operations[idx].clone(), value))
+            .collect()
     };
 
     if selected_options.is_empty() {
@@ -89,48 +133,235 @@ pub async fn handle_multi_project_mode(paths: &[String], dry_run: bool) -> anyho
         return Ok(());
     }
 
+    // One profile for every Rust project, e.g. debug builds for fast feedback
+    let cargo_profile = if uses_cargo_profile(&selected_options)
+        && project_infos
+            .iter()
+            .any(|(_, project_type, _)| *project_type == ProjectType::Rust)
+    {
+        if interactive {
+            let profiles = vec!["debug".to_string(), "release".to_string()];
+            Some(prompt_cargo_profile(profiles, &selected_options)?)
+        } else {
+            config::user_string("rust", "profile")?
+        }
+    } else {
+        None
+    };
+
+    // Progress lines and tables name projects by their shortest unique path
+    let names = Arc::new(PathDisplay::new(
+        &project_infos
+            .iter()
+            .map(|(p, _, _)| p.clone())
+            .collect::<Vec<_>>(),
+    ));
+
+    // Heavy builds split the CPU budget between them instead of each using
+    // every core, so a batch of cargo builds doesn't oversubscribe the machine
+    let budget = cpu_budget()?;
+    let weights: Vec<Weight> = project_infos
+        .iter()
+        .map(|(_, project_type, _)| project_weight(project_type, &selected_options))
+        .collect();
+    let heavy = weights.iter().filter(|w| **w == Weight::Heavy).count();
+    let share = heavy_share(budget, heavy);
+    if let Some(share) = share {
+        println!(
+            "Sharing {} CPU slots: {} heavy projects get {} job(s) each",
+            budget, heavy, share
+        );
+    }
+    // Go projects build with the tags last answered for them in project mode
+    let go_tags = uses_go_build_tags(&selected_options);
+    let settings: Vec<BuildSettings> = weights
+        .iter()
+        .zip(&project_infos)
+        .map(
+            |(weight, (path, project_type, package_manager))| BuildSettings {
+                jobs: share.filter(|_| *weight == Weight::Heavy),
+                package_manager: package_manager.clone(),
+                cargo_profile: cargo_profile.clone(),
+                go_build_tags: if go_tags && *project_type == ProjectType::Go {
+                    cache_manager
+                        .get(path)
+                        .ok()
+                        .flatten()
+                        .map(|cache| cached_go_build_tags(&cache))
+                        .unwrap_or_default()
+                } else {
+                    Vec::new()
+                },
+                ..BuildSettings::default()
+            },
+        )
+        .collect();
+
+    // Show exactly what will run in each project before running it
+    let mut plan = ExecutionPlan::default();
+    let mut commands = Vec::new();
+    let mut unbuildable = 0;
+    for ((path, project_type, _), settings) in project_infos.iter().zip(&settings) {
+        // A project that can't run the selection, e.g. for a missing formatter,
+        // fails on its own while the others run
+        let command = build_project_command(project_type, path, &selected_options, settings);
+        match &command {
+            Ok((executable, args)) if !args.is_empty() => {
+                plan.push(&names.show(path), executable, args)
+            }
+            Ok(_) => {}
+            Err(e) => {
+                ui::status(&format!("⚠️  {}: {}", names.show(path), e));
+                unbuildable += 1;
+            }
+        }
+        commands.push(command);
+    }
+
+    if plan.is_empty() {
+        if unbuildable > 0 {
+            anyhow::bail!(
+                "{} project(s) can't run the selected operations",
+                unbuildable
+            );
+        }
+        println!("No command to execute in any project. Exiting.");
+        return Ok(());
+    }
+
+    println!("Plan:");
+    for line in plan.render() {
+        println!("{}", line);
+    }
+    events::emit(Event::plan(&plan));
+    warn_shared_repositories(&project_infos, &selected_options);
+    if !dry_run && interactive && !ui::confirm("Run these commands?", true)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    let cleaning = !dry_run
+        && selected_options
+            .iter()
+            .any(|(op, _)| *op == Operation::Clean);
+    if cleaning {
+        let projects: Vec<(&str, &ProjectType)> = project_infos
+            .iter()
+            .map(|(path, project_type, _)| (path.as_str(), project_type))
+            .collect();
+        if !clean::confirm_destructive(&projects)? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    // Lock every project up front so nothing else runs against them mid-batch;
+    // a dry run changes nothing, so it doesn't
+    let operation_label = selected_options
+        .iter()
+        .map(|(op, _)| op.as_flag())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let _locks = if dry_run {
+        Vec::new()
+    } else {
+        let lock_paths: Vec<String> = project_infos.iter().map(|(p, _, _)| p.clone()).collect();
+        lock::acquire_all(&lock_paths, &operation_label).await?
+    };
+
     // Execute operations in parallel
+    let run_started = phase_started("run");
+    let semaphore = Arc::new(Semaphore::new(budget));
+    let progress_group = Arc::new(ProgressGroup::new(project_infos.len()));
+    let registry = Arc::new(TaskRegistry::default());
+    let listener = KeyListener::spawn(Arc::clone(&registry), Arc::clone(&progress_group));
+    if listener.is_some() {
+        progress_group.suspend(|| {
+            println!(
+                "Press '{}' to cancel a single project",
+                cancel::CANCEL_KEY as char
+            )
+        });
+    }
     let mut handles = Vec::new();
 
-    for project_info in project_infos {
-        let (path, project_type, entry_point) = project_info;
-        let selected_opts = selected_options.clone();
+    for ((project_info, weight), command) in project_infos.into_iter().zip(weights).zip(commands) {
+        let (path, project_type, _package_manager) = project_info;
+        let permits = permits_for(weight, share);
         let dry_run_flag = dry_run;
-        let multi_pb = Arc::clone(&multi_progress);
+        let group = Arc::clone(&progress_group);
         let sem = Arc::clone(&semaphore);
+        let registry = Arc::clone(&registry);
+        let names = Arc::clone(&names);
 
         let handle = tokio::spawn(async move {
-            let _permit = sem.acquire().await.unwrap();
+            let _permit = sem.acquire_many(permits as u32).await.unwrap();
 
             // Create progress bar for this project
-            let pb = multi_pb.add(ProgressBar::new_spinner());
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} [{elapsed_precise}] {msg}")
-                    .unwrap(),
-            );
-            pb.set_message(format!("Processing {}", path));
-
-            let result = execute_project_operations(
-                &path,
-                &project_type,
-                &entry_point,
-                &selected_opts,
-                dry_run_flag,
-                &pb,
-            )
-            .await;
+            let name = names.show(&path);
+            let pb = group.task(format!("Processing {}", name));
+            let started = Instant::now();
+            registry.start(&path);
+            events::emit(Event::ProjectStarted {
+                project: path.clone(),
+            });
+
+            // Measured before anything is deleted
+            let artifacts = if cleaning {
+                clean::artifact_paths(&project_type, &path)
+            } else {
+                Vec::new()
+            };
+            let artifacts_size = clean::size(&artifacts);
+
+            let result = match command {
+                Ok(command) => {
+                    execute_project_operations(
+                        &path,
+                        &project_type,
+                        &name,
+                        &command,
+                        dry_run_flag,
+                        &pb,
+                        &registry,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            registry.finish(&path);
 
             match &result {
-                Ok(_) => {
-                    pb.finish_with_message(format!("✅ {} completed", path));
+                Ok(warnings) => {
+                    let reclaimed = if cleaning && !artifacts.is_empty() {
+                        format!(
+                            ", reclaimed {}",
+                            clean::reclaimed(artifacts_size, &artifacts)
+                        )
+                    } else {
+                        String::new()
+                    };
+                    pb.finish_with_message(format!(
+                        "✅ {} completed{}{}",
+                        name,
+                        ui::warning_suffix(*warnings),
+                        reclaimed
+                    ));
+                }
+                Err(e) if e.is::<Cancelled>() => {
+                    pb.finish_with_message(format!("⏭️  {} cancelled by user", name));
                 }
                 Err(e) => {
-                    pb.finish_with_message(format!("❌ {} failed: {}", path, e));
+                    pb.finish_with_message(format!("❌ {} failed: {}", name, e));
                 }
             }
 
-            result
+            let run = ProjectRun {
+                path,
+                result,
+                duration: started.elapsed(),
+            };
+            events::emit(run.event());
+            run
         });
 
         handles.push(handle);
@@ -141,16 +372,44 @@ pub async fn handle_multi_project_mode(paths: &[String], dry_run: bool) -> anyho
     for handle in handles {
         results.push(handle.await?);
     }
+    drop(listener);
+    progress_group.finish();
+    events::emit(Event::phase_finished("run", run_started.elapsed()));
+
+    print_summary(&results, &names);
+    if selected_options
+        .iter()
+        .all(|(op, _)| *op == Operation::Audit)
+    {
+        print_audit_summary(&results, &names);
+    }
+
+    if let Some(report_path) = report_junit {
+        std::fs::write(report_path, junit_report(&operation_label, &results))?;
+        println!("JUnit report written to {}", report_path);
+    }
+
+    // Check for failures; cancelled projects are reported but aren't failures
+    let (cancelled, failures): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .filter_map(|run| run.result.err().map(|e| (run.path, e)))
+        .partition(|(_, e)| e.is::<Cancelled>());
 
-    // Check for failures
-    let failures: Vec<_> = results.into_iter().filter_map(|r| r.err()).collect();
+    if !cancelled.is_empty() {
+        ui::status(&format!(
+            "⏭️  {} project(s) cancelled by user",
+            cancelled.len()
+        ));
+    }
 
-    if failures.is_empty() {
-        println!("✅ All operations completed successfully!");
+    if failures.is_empty() && cancelled.is_empty() {
+        ui::status("✅ All operations completed successfully!");
+    } else if failures.is_empty() {
+        ui::status("✅ All other operations completed successfully");
     } else {
-        println!("❌ {} operations failed", failures.len());
-        for failure in failures {
-            eprintln!("Error: {}", failure);
+        ui::status(&format!("❌ {} operations failed", failures.len()));
+        for (path, failure) in failures {
+            eprintln!("Error: {}: {}", names.show(&path), failure);
         }
         anyhow::bail!("Some operations failed");
     }
@@ -161,307 +420,486 @@ pub async fn handle_multi_project_mode(paths: &[String], dry_run: bool) -> anyho
 async fn execute_project_operations(
     path: &str,
     project_type: &ProjectType,
-    _entry_point: &str,
-    selected_options: &[(String, Option<String>)],
+    name: &str,
+    (executable, args): &(String, Vec<String>),
     dry_run: bool,
-    pb: &ProgressBar,
-) -> anyhow::Result<()> {
-    // Build command for this project type
-    let (executable, args) = build_project_command(project_type, path, selected_options)?;
-
+    pb: &Progress,
+    registry: &TaskRegistry,
+) -> anyhow::Result<usize> {
     if args.is_empty() {
-        pb.set_message(format!("{}: No command to execute", path));
-        return Ok(());
+        pb.set_message(format!("{}: No command to execute", name));
+        return Ok(0);
     }
 
     if dry_run {
         pb.set_message(format!(
-            "{}: Dry run - {} {}",
-            path,
-            executable,
-            args.join(" ")
+            "{}: Dry run - {}",
+            name,
+            shell_command(executable, args)
         ));
-        return Ok(());
+        return Ok(0);
     }
 
     // Execute the command asynchronously
-    execute_project_command_async(&executable, &args, path, pb).await?;
+    execute_project_command_async(executable, args, path, project_type, pb, registry).await
+}
 
-    Ok(())
+/// Worktrees of one repository build independently, but an install from
+/// each of them writes the same binary, so the last one to finish wins.
+fn warn_shared_repositories(
+    project_infos: &[(String, ProjectType, Option<String>)],
+    selected: &[(Operation, Option<String>)],
+) {
+    let shared_effects: Vec<String> = selected
+        .iter()
+        .filter(|(op, _)| matches!(op, Operation::Install))
+        .map(|(op, _)| op.as_flag())
+        .collect();
+    if shared_effects.is_empty() {
+        return;
+    }
+
+    let paths: Vec<String> = project_infos.iter().map(|(p, _, _)| p.clone()).collect();
+    for checkouts in worktree::shared_repositories(&paths).values() {
+        let labels: Vec<String> = checkouts
+            .iter()
+            .map(|(_, checkout)| checkout.label())
+            .collect();
+        ui::status(&format!(
+            "⚠️  {} are checkouts of the same repository; '{}' from each will overwrite the others",
+            labels.join(", "),
+            shared_effects.join(" ")
+        ));
+    }
 }
 
-fn build_project_command(
-    project_type: &ProjectType,
-    path: &str,
-    selected: &[(String, Option<String>)],
-) -> anyhow::Result<(String, Vec<String>)> {
-    // This is a simplified version - we could reuse the logic from project.rs
-    // but for now, let's implement basic support for common operations
-
-    match project_type {
-        ProjectType::Rust => {
-            let mut args = Vec::new();
-            for (flag, _) in selected {
-                match flag.as_str() {
-                    "build" => {
-                        args.push("build".to_string());
-                        args.push("--release".to_string());
-                    }
-                    "test" => {
-                        args.push("test".to_string());
-                    }
-                    "check" => {
-                        args.push("check".to_string());
-                    }
-                    _ => {}
-                }
-            }
-            if !args.is_empty() {
-                Ok(("cargo".to_string(), args))
-            } else {
-                Ok(("cargo".to_string(), vec![]))
-            }
-        }
-        ProjectType::Go => {
-            let mut args = Vec::new();
-            for (flag, _) in selected {
-                match flag.as_str() {
-                    "build" => {
-                        args.push("build".to_string());
-                        args.push(".".to_string());
-                    }
-                    "test" => {
-                        args.push("test".to_string());
-                        args.push("./...".to_string());
-                    }
-                    _ => {}
-                }
-            }
-            if !args.is_empty() {
-                Ok(("go".to_string(), args))
-            } else {
-                Ok(("go".to_string(), vec![]))
-            }
-        }
-        ProjectType::JavaScript | ProjectType::TypeScript => {
-            let pm = detect_package_manager(path);
-            let mut args = vec![pm];
-
-            for (flag, _) in selected {
-                match flag.as_str() {
-                    "install" => {
-                        args.push("install".to_string());
-                    }
-                    "test" => {
-                        args.push("test".to_string());
-                    }
-                    "build" => {
-                        args.push("run".to_string());
-                        args.push("build".to_string());
-                    }
-                    _ => {}
-                }
-            }
-            if args.len() > 1 {
-                Ok(("npx".to_string(), args))
-            } else {
-                Ok(("npx".to_string(), vec![]))
-            }
-        }
-        ProjectType::Uv => {
-            let mut args = vec!["--project".to_string(), path.to_string()];
-            for (flag, _) in selected {
-                if flag.as_str() == "sync" {
-                    args.push("sync".to_string());
-                }
-            }
-            if args.len() > 2 {
-                Ok(("uv".to_string(), args))
-            } else {
-                Ok(("uv".to_string(), vec![]))
-            }
-        }
-        _ => {
-            // For other project types, return empty command for now
-            Ok(("".to_string(), vec![]))
-        }
+/// How hard an operation leans on the machine, for sharing it between the
+/// projects of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Weight {
+    Light,
+    /// Mostly waiting on the network or disk
+    IoBound,
+    /// Compiles, using as many cores as it can get
+    Heavy,
+}
+
+/// The batch-safe operations every project offers, in the first project's order.
+fn common_operations(projects: &[(&str, &ProjectType)]) -> Vec<Operation> {
+    let Some(((first_path, first_type), others)) = projects.split_first() else {
+        return Vec::new();
+    };
+
+    let mut common_ops: Vec<Operation> = get_project_operations(first_type, first_path)
+        .into_iter()
+        .filter(Operation::is_batch_safe)
+        .collect();
+
+    // Filter to only operations that exist in all projects
+    for (path, project_type) in others {
+        let project_ops = get_project_operations(project_type, path);
+        common_ops.retain(|op| project_ops.iter().any(|other| op.same_action(other)));
     }
+
+    common_ops
 }
 
-fn detect_package_manager(path: &str) -> String {
-    // Check for lock files to determine package manager
-    let yarn_lock = format!("{}/yarn.lock", path);
-    let pnpm_lock = format!("{}/pnpm-lock.yaml", path);
+fn operation_weight(project_type: &ProjectType, operation: &Operation) -> Weight {
+    match (project_type, operation) {
+        (
+            ProjectType::Rust,
+            Operation::Build
+            | Operation::Install
+            | Operation::Test
+            | Operation::Run
+            | Operation::RunWithArg { .. },
+        ) => Weight::Heavy,
+        (ProjectType::Go, Operation::Build | Operation::Test) => Weight::Heavy,
+        (ProjectType::JavaScript | ProjectType::TypeScript, Operation::Build) => Weight::Heavy,
+        (
+            _,
+            Operation::InstallDeps
+            | Operation::InstallPackage
+            | Operation::Sync
+            | Operation::Add
+            | Operation::Get
+            | Operation::Tidy,
+        ) => Weight::IoBound,
+        _ => Weight::Light,
+    }
+}
 
-    if std::path::Path::new(&yarn_lock).exists() {
-        "yarn".to_string()
-    } else if std::path::Path::new(&pnpm_lock).exists() {
-        "pnpm".to_string()
-    } else {
-        "npm".to_string() // default
+/// A project's weight is that of its heaviest selected operation.
+fn project_weight(project_type: &ProjectType, selected: &[(Operation, Option<String>)]) -> Weight {
+    selected
+        .iter()
+        .map(|(op, _)| operation_weight(project_type, op))
+        .max()
+        .unwrap_or(Weight::Light)
+}
+
+/// CPU slots a run shares out: `[multi] cpu_budget` in the user config, or one
+/// per core.
+fn cpu_budget() -> anyhow::Result<usize> {
+    let budget = config::user_integer("multi", "cpu_budget")?
+        .map(|n| n as usize)
+        .unwrap_or_else(num_cpus::get);
+    Ok(budget.max(1))
+}
+
+/// Slots each heavy project gets, as semaphore permits and as its compiler's
+/// job count, so the heavy projects running at once fit in the budget. A lone
+/// heavy project keeps the compiler's own default.
+fn heavy_share(budget: usize, heavy: usize) -> Option<usize> {
+    if heavy < 2 {
+        return None;
     }
+    Some((budget / heavy.min(budget)).max(1))
 }
 
-fn detect_project_type(path: &str) -> anyhow::Result<ProjectType> {
-    // Check for uv project
-    let pyproject_path = format!("{}/pyproject.toml", path);
-    let uv_lock_path = format!("{}/uv.lock", path);
-    if std::path::Path::new(&pyproject_path).exists() {
-        // Check for [tool.uv] section OR uv.lock file
-        let has_uv_section = std::fs::read_to_string(&pyproject_path)
-            .map(|content| content.contains("[tool.uv]"))
-            .unwrap_or(false);
-        let has_uv_lock = std::path::Path::new(&uv_lock_path).exists();
-
-        if has_uv_section || has_uv_lock {
-            return Ok(ProjectType::Uv);
-        }
+fn permits_for(weight: Weight, share: Option<usize>) -> usize {
+    match (weight, share) {
+        (Weight::Heavy, Some(share)) => share,
+        _ => 1,
     }
+}
 
-    // Check for venv
-    let activate_path = format!("{}/bin/activate", path);
-    if std::path::Path::new(&activate_path).exists() {
-        return Ok(ProjectType::Venv);
+/// Outcome of running the selected operations in one project.
+struct ProjectRun {
+    path: String,
+    result: anyhow::Result<usize>,
+    duration: Duration,
+}
+
+impl ProjectRun {
+    fn event(&self) -> Event {
+        let project = self.path.clone();
+        let duration_ms = self.duration.as_millis() as u64;
+        match &self.result {
+            Ok(warnings) => Event::ProjectSucceeded {
+                project,
+                duration_ms,
+                warnings: *warnings,
+            },
+            Err(e) if e.is::<Cancelled>() => Event::ProjectCancelled {
+                project,
+                duration_ms,
+            },
+            Err(e) => Event::ProjectFailed {
+                project,
+                duration_ms,
+                error: e.to_string(),
+            },
+        }
     }
+}
+
+fn phase_started(phase: &str) -> Instant {
+    events::emit(Event::PhaseStarted {
+        phase: phase.to_string(),
+    });
+    Instant::now()
+}
+
+fn print_summary(results: &[ProjectRun], names: &PathDisplay) {
+    let width = results
+        .iter()
+        .map(|run| console::measure_text_width(&names.show(&run.path)))
+        .max()
+        .unwrap_or(0)
+        .max("Project".len());
 
-    // Check for Go project
-    let go_mod_path = format!("{}/go.mod", path);
-    if std::path::Path::new(&go_mod_path).exists() {
-        return Ok(ProjectType::Go);
+    println!();
+    println!(
+        "{}  {:<9} {:<9} Warnings",
+        console::pad_str("Project", width, console::Alignment::Left, None),
+        "Status",
+        "Time"
+    );
+    for run in results {
+        let (status, warnings) = match &run.result {
+            Ok(warnings) => ("ok", warnings.to_string()),
+            Err(e) if e.is::<Cancelled>() => ("cancelled", "-".to_string()),
+            Err(_) => ("failed", "-".to_string()),
+        };
+        println!(
+            "{}  {:<9} {:<9} {}",
+            console::pad_str(
+                &names.show(&run.path),
+                width,
+                console::Alignment::Left,
+                None
+            ),
+            status,
+            capture::format_duration(run.duration),
+            warnings
+        );
     }
+}
 
-    // Check for Rust project
-    let cargo_toml_path = format!("{}/Cargo.toml", path);
-    if std::path::Path::new(&cargo_toml_path).exists() {
-        return Ok(ProjectType::Rust);
+/// Which projects an audit found vulnerabilities in and which were clean.
+/// Auditors exit unsuccessfully when they find any; a project whose auditor
+/// didn't run to an exit code isn't counted either way.
+fn print_audit_summary(results: &[ProjectRun], names: &PathDisplay) {
+    println!();
+    for line in audit_summary(results, names) {
+        println!("{}", line);
     }
+}
 
-    // Check for JavaScript/TypeScript project
-    let package_json_path = format!("{}/package.json", path);
-    if std::path::Path::new(&package_json_path).exists() {
-        // Check for TypeScript
-        let tsconfig_path = format!("{}/tsconfig.json", path);
-        if std::path::Path::new(&tsconfig_path).exists() {
-            return Ok(ProjectType::TypeScript);
-        } else {
-            return Ok(ProjectType::JavaScript);
+fn audit_summary(results: &[ProjectRun], names: &PathDisplay) -> Vec<String> {
+    let mut vulnerable = Vec::new();
+    let mut clean = Vec::new();
+    let mut unaudited = Vec::new();
+    for run in results {
+        let name = names.show(&run.path);
+        match &run.result {
+            Ok(_) => clean.push(name),
+            Err(e)
+                if e.downcast_ref::<CommandFailed>()
+                    .is_some_and(|failed| failed.code.is_some()) =>
+            {
+                vulnerable.push(name)
+            }
+            Err(_) => unaudited.push(name),
         }
     }
 
-    // Generic Python project
-    if std::path::Path::new(&pyproject_path).exists() {
-        return Ok(ProjectType::Generic);
+    [
+        ("Vulnerabilities reported", vulnerable),
+        ("Clean", clean),
+        ("Not audited", unaudited),
+    ]
+    .into_iter()
+    .filter(|(_, projects)| !projects.is_empty())
+    .map(|(label, projects)| format!("{}: {}", label, projects.join(", ")))
+    .collect()
+}
+
+/// One testsuite for the operation, with a testcase per project.
+fn junit_report(operation: &str, results: &[ProjectRun]) -> String {
+    let cases = results
+        .iter()
+        .map(|run| TestCase {
+            name: run.path.clone(),
+            classname: format!("app-hoist.{}", operation.replace(' ', "-")),
+            time: run.duration,
+            skipped: run
+                .result
+                .as_ref()
+                .err()
+                .filter(|e| e.is::<Cancelled>())
+                .map(|e| e.to_string()),
+            failure: run
+                .result
+                .as_ref()
+                .err()
+                .filter(|e| !e.is::<Cancelled>())
+                .map(|e| {
+                    let details = e
+                        .downcast_ref::<CommandFailed>()
+                        .map(|failed| failed.stderr_tail.join("\n"))
+                        .unwrap_or_default();
+                    Failure {
+                        message: e.to_string(),
+                        details,
+                    }
+                }),
+        })
+        .collect();
+
+    junit::to_xml(&[TestSuite {
+        name: operation.to_string(),
+        cases,
+    }])
+}
+
+/// The program that runs `operation` in one project, e.g. `cargo` or `pnpm`.
+fn tool_for(project_type: &ProjectType, path: &str, operation: &Operation) -> Option<String> {
+    let (executable, args) = build_project_command(
+        project_type,
+        path,
+        &[(operation.clone(), None)],
+        &BuildSettings::default(),
+    )
+    .ok()?;
+
+    // JavaScript commands go through npx, with the package manager as the first argument,
+    // and chained commands through the shell, led by their first program
+    let program = match executable.as_str() {
+        "npx" => args.first()?.clone(),
+        "bash" | "cmd" => args
+            .last()?
+            .split_whitespace()
+            .next()?
+            .trim_matches(['\'', '"'])
+            .to_string(),
+        _ => executable,
+    };
+
+    Path::new(&program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Append a per-tool count to an option's description, e.g.
+/// "Build the project — cargo(3), go(1), pnpm(2)".
+pub fn with_tool_breakdown(mut option: OptionInfo, tools: &[String]) -> OptionInfo {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tool in tools {
+        *counts.entry(tool).or_default() += 1;
     }
 
-    Ok(ProjectType::Generic)
+    if !counts.is_empty() {
+        let breakdown: Vec<String> = counts
+            .into_iter()
+            .map(|(tool, count)| format!("{}({})", tool, count))
+            .collect();
+        option.description = format!("{} — {}", option.description, breakdown.join(", "));
+    }
+    option
 }
 
-fn detect_entry_point(path: &str) -> anyhow::Result<String> {
-    // Check if this is a Go project
-    let go_mod_path = format!("{}/go.mod", path);
-    if std::path::Path::new(&go_mod_path).exists() {
-        let go_candidates = ["main.go", "cmd/main.go"];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for candidate in &go_candidates {
-            let full_path = format!("{}/{}", path, candidate);
-            if std::path::Path::new(&full_path).exists() {
-                return Ok(candidate.to_string());
-            }
-        }
+    fn tools(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
 
-        // Default to current directory for Go
-        return Ok(".".to_string());
+    #[test]
+    fn tool_breakdown_counts_each_tool_in_order() {
+        let option = with_tool_breakdown(
+            Operation::Build.to_option_info(),
+            &tools(&["go", "cargo", "cargo", "cargo"]),
+        );
+        assert_eq!(
+            option.description,
+            format!("{} — cargo(3), go(1)", Operation::Build.description())
+        );
+        assert_eq!(option.flags, vec![Operation::Build.as_flag()]);
     }
 
-    // Check if this is a Rust project
-    let cargo_toml_path = format!("{}/Cargo.toml", path);
-    if std::path::Path::new(&cargo_toml_path).exists() {
-        return Ok(".".to_string()); // Run current directory for Rust
+    #[test]
+    fn tool_breakdown_without_tools_keeps_the_description() {
+        let option = with_tool_breakdown(Operation::Test.to_option_info(), &[]);
+        assert_eq!(option.description, Operation::Test.description());
     }
 
-    // Check if this is a JavaScript/TypeScript project
-    let package_json_path = format!("{}/package.json", path);
-    if std::path::Path::new(&package_json_path).exists() {
-        return Ok(".".to_string()); // Run with package manager
+    #[test]
+    fn heavy_projects_share_the_budget() {
+        assert_eq!(heavy_share(8, 1), None);
+        assert_eq!(heavy_share(8, 2), Some(4));
+        assert_eq!(heavy_share(8, 3), Some(2));
+        assert_eq!(heavy_share(2, 5), Some(1));
+        assert_eq!(permits_for(Weight::Heavy, Some(4)), 4);
+        assert_eq!(permits_for(Weight::Light, Some(4)), 1);
+        assert_eq!(permits_for(Weight::Heavy, None), 1);
     }
 
-    // Python project detection
-    let candidates = ["app.py", "main.py", "__main__.py"];
+    #[test]
+    fn a_project_weighs_as_much_as_its_heaviest_operation() {
+        let selected = vec![(Operation::Clean, None), (Operation::Build, None)];
+        assert_eq!(project_weight(&ProjectType::Rust, &selected), Weight::Heavy);
+        assert_eq!(
+            project_weight(&ProjectType::Go, &[(Operation::Tidy, None)]),
+            Weight::IoBound
+        );
+        assert_eq!(project_weight(&ProjectType::Rust, &[]), Weight::Light);
+    }
 
-    for candidate in &candidates {
-        let full_path = format!("{}/{}", path, candidate);
-        if std::path::Path::new(&full_path).exists() {
-            return Ok(candidate.to_string());
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let file = dir.path().join(name);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
         }
+        dir
     }
 
-    // Default to app.py if none found
-    Ok("app.py".to_string())
-}
+    #[test]
+    fn formatting_is_common_to_every_ecosystem() {
+        let rust = project(&[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ]);
+        let go = project(&[("go.mod", "module demo\n"), ("main.go", "package main\n")]);
+        let js = project(&[("package.json", "{\"scripts\": {\"start\": \"node .\"}}")]);
+        let python = project(&[("main.py", "")]);
+        let projects = [
+            (rust.path().to_str().unwrap(), &ProjectType::Rust),
+            (go.path().to_str().unwrap(), &ProjectType::Go),
+            (js.path().to_str().unwrap(), &ProjectType::JavaScript),
+            (python.path().to_str().unwrap(), &ProjectType::Generic),
+        ];
+
+        let common = common_operations(&projects);
+        assert!(common.contains(&Operation::Format), "{:?}", common);
+        assert!(!common.contains(&Operation::Update), "{:?}", common);
+        // Running needs a person watching, so it's never offered for a batch
+        assert!(!common.contains(&Operation::Run));
+        assert!(common_operations(&[]).is_empty());
+    }
 
-fn get_project_options(
-    project_type: &ProjectType,
-    _entry_point: &str,
-    _path: &str,
-) -> anyhow::Result<Vec<OptionInfo>> {
-    let mut options = Vec::new();
-
-    match project_type {
-        ProjectType::Uv => {
-            options.push(OptionInfo {
-                flags: vec!["sync".to_string()],
-                description: "Sync dependencies".to_string(),
-                requires_value: false,
-            });
-        }
-        ProjectType::Go => {
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: "Build the application".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: "Run tests".to_string(),
-                requires_value: false,
-            });
-        }
-        ProjectType::Rust => {
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: "Build the project".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: "Run tests".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["check".to_string()],
-                description: "Check code without building".to_string(),
-                requires_value: false,
-            });
+    #[test]
+    fn updating_is_common_to_projects_with_a_package_manager() {
+        let rust = project(&[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ]);
+        let go = project(&[("go.mod", "module demo\n"), ("main.go", "package main\n")]);
+        let uv = project(&[
+            ("pyproject.toml", "[project]\nname = \"demo\"\n"),
+            ("uv.lock", ""),
+        ]);
+        let projects = [
+            (rust.path().to_str().unwrap(), &ProjectType::Rust),
+            (go.path().to_str().unwrap(), &ProjectType::Go),
+            (uv.path().to_str().unwrap(), &ProjectType::Uv),
+        ];
+        let common = common_operations(&projects);
+        assert!(common.contains(&Operation::Update), "{:?}", common);
+    }
+
+    fn run(path: &str, result: anyhow::Result<usize>) -> ProjectRun {
+        ProjectRun {
+            path: path.to_string(),
+            result,
+            duration: Duration::ZERO,
         }
-        ProjectType::JavaScript | ProjectType::TypeScript => {
-            options.push(OptionInfo {
-                flags: vec!["install".to_string()],
-                description: "Install dependencies".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: "Run tests".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: "Build project".to_string(),
-                requires_value: false,
-            });
+    }
+
+    fn failed(code: Option<i32>) -> anyhow::Error {
+        CommandFailed {
+            code,
+            warnings: 0,
+            stderr_tail: Vec::new(),
         }
-        _ => {} // No common options for other types
+        .into()
     }
 
-    Ok(options)
+    #[test]
+    fn audits_are_summarized_by_exit_code() {
+        let paths = tools(&["/work/api", "/work/web", "/work/cli", "/work/docs"]);
+        let results = [
+            run("/work/api", Err(failed(Some(1)))),
+            run("/work/web", Ok(0)),
+            run("/work/cli", Err(failed(None))),
+            run("/work/docs", Err(anyhow::anyhow!("audit needs pip-audit"))),
+        ];
+        assert_eq!(
+            audit_summary(&results, &PathDisplay::new(&paths)),
+            [
+                "Vulnerabilities reported: api",
+                "Clean: web",
+                "Not audited: cli, docs"
+            ]
+        );
+        assert_eq!(
+            audit_summary(&results[1..2], &PathDisplay::new(&paths)),
+            ["Clean: web"]
+        );
+    }
 }
@@ -1,178 +1,861 @@
-use crate::models::OptionInfo;
-use crate::utils::{build_command, execute_command, select_options};
-use regex::Regex;
-use std::process::Command;
-
-pub fn handle_package_mode(package: &str, dry_run: bool) -> anyhow::Result<()> {
+use crate::alias;
+use crate::cache::{BinaryFingerprint, CacheManager, ToolCache};
+use crate::cancel;
+use crate::capture::{self, format_duration};
+use crate::config;
+use crate::editor::{self, display_command};
+use crate::help_parser::{
+    DEFAULT_PROBE_TIMEOUT, EnvVarInfo, ExampleInfo, ProbeTimedOut, SubcommandInfo, parse_env_vars,
+    parse_examples, parse_help, parse_man_page, parse_subcommands, parse_usage, probe_help_output,
+};
+use crate::models::{OptionInfo, ValueType};
+use crate::prefetch::Prefetcher;
+use crate::recall;
+use crate::resolve;
+use crate::ui;
+use crate::utils::{
+    build_command, env_prefix, group_selected, prompt_extra_args, run_command, select_from_flags,
+    select_options, shell_command, tool_name,
+};
+use crate::value_sources::ValueSources;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Hoist a tool: pick a subcommand and options from its help, then run it.
+/// The options chosen last time start out selected unless `fresh` is set.
+/// Options named in `selections` (`--select`) are used without any prompts.
+/// `env` holds `KEY=VALUE` assignments (`--env`) for the tool's environment.
+pub async fn handle_package_mode(
+    package: &str,
+    dry_run: bool,
+    fresh: bool,
+    selections: &[String],
+    env: &[String],
+) -> anyhow::Result<()> {
     println!("Hoisting package: {}", package);
+    let env = parse_env_assignments(env)?;
 
     // Discover the executable
-    let executable = find_executable(package)?;
+    let executable = find_executable(package)?.to_string_lossy().into_owned();
+    let cache_manager = CacheManager::new()?;
+
+    let mut help = tool_help(&cache_manager, &executable, package, &[]).await?;
+
+    // Let the user descend into subcommands, level by level, when the tool has them
+    let mut subcommand: Vec<String> = Vec::new();
+    let mut seen_levels = HashSet::new();
+    while !help.subcommands.is_empty() && !dry_run && selections.is_empty() {
+        // Some tools print the same help at every level
+        if !seen_levels.insert(subcommand_names(&help.subcommands)) {
+            ui::status(&format!(
+                "💡 {} lists the same subcommands again, using its options",
+                display_command(&executable, &subcommand)
+            ));
+            break;
+        }
+        if subcommand.len() >= MAX_SUBCOMMAND_DEPTH {
+            ui::status(&format!(
+                "💡 Stopping at {} levels of subcommands",
+                MAX_SUBCOMMAND_DEPTH
+            ));
+            break;
+        }
+        println!("Found {} subcommands", help.subcommands.len());
+
+        // Each drill-down into a slow tool costs another probe, so start on
+        // them while the list is being read
+        let mut prefetcher = Prefetcher::new(PREFETCH_CONCURRENCY);
+        let probe_time = Duration::from_millis(help.probe_millis);
+        if probe_time >= SLOW_PROBE {
+            ui::status(&format!(
+                "💡 {} is slow to probe ({}), reading subcommand help in the background",
+                display_command(package, &subcommand),
+                format_duration(probe_time)
+            ));
+            for name in help
+                .subcommands
+                .iter()
+                .take(PREFETCH_LIMIT)
+                .map(|sub| [subcommand.as_slice(), std::slice::from_ref(&sub.name)].concat())
+                .filter(|key| cache_manager.get_tool(&executable, key).is_none())
+            {
+                let (executable, package) = (executable.clone(), package.to_string());
+                prefetcher.spawn(name.clone(), async move {
+                    probe_tool(&CacheManager::new()?, &executable, &package, &name).await
+                });
+            }
+        }
+        let stop = if subcommand.is_empty() {
+            SubcommandChoice::TopLevel
+        } else {
+            SubcommandChoice::Stop(subcommand.join(" "))
+        };
+        let mut choices = vec![stop];
+        choices.extend(
+            std::mem::take(&mut help.subcommands)
+                .into_iter()
+                .map(SubcommandChoice::Subcommand),
+        );
+
+        let message = if subcommand.is_empty() {
+            "Select a subcommand:".to_string()
+        } else {
+            format!("Select a subcommand of {}:", subcommand.join(" "))
+        };
+        let SubcommandChoice::Subcommand(chosen) = ui::select(&message, choices)? else {
+            break;
+        };
+        subcommand.push(chosen.name);
+        help = match prefetcher.take(&subcommand).await {
+            Some(prefetched) => {
+                println!(
+                    "Using prefetched options for {}",
+                    display_command(&executable, &subcommand)
+                );
+                prefetched?
+            }
+            None => tool_help(&cache_manager, &executable, package, &subcommand).await?,
+        };
+        prefetcher.cancel();
+    }
 
-    // Get help output
-    let help_output = get_help_output(&executable)?;
+    run_with_options(
+        &executable,
+        help,
+        subcommand,
+        dry_run,
+        fresh,
+        selections,
+        env,
+    )
+    .await
+}
 
-    // Parse options from help
-    let options = parse_options(&help_output)?;
+/// Split `--env` assignments into names and values.
+pub fn parse_env_assignments(env: &[String]) -> anyhow::Result<Vec<(String, String)>> {
+    env.iter()
+        .map(|assignment| match assignment.split_once('=') {
+            Some((name, value)) if is_env_name(name) => Ok((name.to_string(), value.to_string())),
+            _ => anyhow::bail!("--env takes KEY=VALUE, not '{}'", assignment),
+        })
+        .collect()
+}
 
-    println!("Found {} options", options.len());
+fn is_env_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// What `--list-options` prints for a tool.
+#[derive(serde::Serialize)]
+struct ToolOptions<'a> {
+    executable: &'a str,
+    subcommand: &'a [String],
+    source: OptionSource,
+    options: &'a [OptionInfo],
+    subcommands: &'a [SubcommandInfo],
+    env_vars: &'a [EnvVarInfo],
+    examples: &'a [ExampleInfo],
+}
 
-    let selected_options = if dry_run {
+/// Print the options parsed from a tool's help as JSON instead of prompting.
+/// `package` may name a subcommand after the tool, e.g. `"git remote"`.
+pub async fn print_options(package: &str) -> anyhow::Result<()> {
+    let mut words = package.split_whitespace().map(str::to_string);
+    let name = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No package given"))?;
+    let subcommand: Vec<String> = words.collect();
+
+    let executable = find_executable(&name)?.to_string_lossy().into_owned();
+    // Nothing but the JSON goes to stdout, so no "Using cached options" line
+    let cache_manager = CacheManager::new()?;
+    let help = match cache_manager.get_tool(&executable, &subcommand) {
+        Some(cached) => cached,
+        None => probe_tool(&cache_manager, &executable, &name, &subcommand).await?,
+    };
+    let output = ToolOptions {
+        executable: &executable,
+        subcommand: &subcommand,
+        source: if help.from_man_page {
+            OptionSource::ManPage
+        } else {
+            OptionSource::Help
+        },
+        options: &help.options,
+        subcommands: &help.subcommands,
+        env_vars: &help.env_vars,
+        examples: &help.examples,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// How many subcommands deep package mode offers to go, e.g. `git remote add`.
+const MAX_SUBCOMMAND_DEPTH: usize = 4;
+/// Help probes at least this slow get subcommand help prefetched.
+const SLOW_PROBE: Duration = Duration::from_secs(1);
+/// How many subcommands are probed at once, and at most in total.
+const PREFETCH_CONCURRENCY: usize = 4;
+const PREFETCH_LIMIT: usize = 32;
+
+/// A tool's options and subcommands, from the cache while its binary is
+/// unchanged, otherwise from its help (or man page) and then cached.
+async fn tool_help(
+    cache_manager: &CacheManager,
+    executable: &str,
+    package: &str,
+    subcommand: &[String],
+) -> anyhow::Result<ToolCache> {
+    if let Some(cached) = cache_manager.get_tool(executable, subcommand) {
+        println!(
+            "Using cached options for {}",
+            display_command(executable, subcommand)
+        );
+        return Ok(cached);
+    }
+    probe_tool(cache_manager, executable, package, subcommand).await
+}
+
+/// Read a tool's options from its help (or man page), noting how long that
+/// took, and cache them.
+async fn probe_tool(
+    cache_manager: &CacheManager,
+    executable: &str,
+    package: &str,
+    subcommand: &[String],
+) -> anyhow::Result<ToolCache> {
+    let started = Instant::now();
+
+    // A binary that can't be fingerprinted can't tell us when to re-read it
+    let fingerprint = BinaryFingerprint::of(executable);
+    let mut help = ToolCache::new(
+        executable,
+        subcommand,
+        fingerprint.clone().unwrap_or_default(),
+    );
+
+    // Get help output; a tool without any still has a chance via its man page
+    let mut help_output = None;
+    match get_help_output(executable, subcommand).await {
+        Ok(output) => {
+            help.subcommands = parse_subcommands(&output);
+            help.options = parse_help(&output);
+            help.env_vars = parse_env_vars(&output);
+            help.examples = parse_examples(&output, &tool_name(executable));
+            help_output = Some(output);
+        }
+        Err(e) => {
+            if !subcommand.is_empty() {
+                return Err(e);
+            }
+            match man_page_options(package) {
+                Some(options) if !options.is_empty() => {
+                    help.options = options;
+                    help.from_man_page = true;
+                }
+                _ => return Err(e),
+            }
+        }
+    }
+
+    // Parse options from help, falling back to the man page for the top level
+    if help.options.is_empty()
+        && subcommand.is_empty()
+        && let Some(man_options) = man_page_options(package)
+        && !man_options.is_empty()
+    {
+        help.options = man_options;
+        help.from_man_page = true;
+    }
+    // A hand-written usage line is the last resort
+    if help.options.is_empty()
+        && let Some(help_output) = &help_output
+    {
+        help.options = parse_usage(help_output);
+    }
+
+    help.probe_millis = started.elapsed().as_millis() as u64;
+    if fingerprint.is_some() {
+        cache_manager.set_tool(&help)?;
+    }
+    Ok(help)
+}
+
+/// Where a tool's options were read from.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum OptionSource {
+    Help,
+    ManPage,
+}
+
+impl std::fmt::Display for OptionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionSource::Help => write!(f, "help output"),
+            OptionSource::ManPage => write!(f, "man page"),
+        }
+    }
+}
+
+async fn run_with_options(
+    executable: &str,
+    help: ToolCache,
+    subcommand: Vec<String>,
+    dry_run: bool,
+    fresh: bool,
+    selections: &[String],
+    mut env: Vec<(String, String)>,
+) -> anyhow::Result<()> {
+    let options = help.options;
+    let source = if help.from_man_page {
+        OptionSource::ManPage
+    } else {
+        OptionSource::Help
+    };
+    println!("Found {} options (from {})", options.len(), source);
+    let interactive = selections.is_empty();
+
+    // Running one of the help's examples is a shortcut past the option list
+    if interactive
+        && !dry_run
+        && let Some(example) = choose_example(&help.examples)?
+    {
+        return run_example(executable, example, env).await;
+    }
+    let recall_key = (!subcommand.is_empty()).then(|| subcommand.join(" "));
+
+    let selected_options = if !interactive {
+        group_selected(
+            &options,
+            select_from_flags(&options, selections, executable)?,
+            Some(&tool_name(executable)),
+        )?
+    } else if dry_run {
         println!("Dry run: skipping interactive selection, using no arguments.");
         Vec::new()
     } else if options.is_empty() {
         println!("No options found, proceeding with no arguments.");
         Vec::new()
     } else {
-        // Interactive selection
-        select_options(&options)?
+        // Interactive selection, starting from the last one for this tool
+        let previous = if fresh {
+            Vec::new()
+        } else {
+            recall::load(executable, recall_key.as_deref())
+        };
+        let selected = select_options(
+            &options,
+            &previous,
+            &ValueSources::load(executable)?,
+            Some(&tool_name(executable)),
+        )?;
+        recall::save(executable, recall_key.as_deref(), &selected)?;
+        selected
+    };
+    if interactive && !dry_run && !help.env_vars.is_empty() {
+        env = prompt_env_vars(&help.env_vars, env)?;
+    }
+    let extra_args = if dry_run || !interactive {
+        Vec::new()
+    } else {
+        prompt_extra_args()?
     };
 
     // Build the command
-    let command_args = build_command(&selected_options)?;
+    let mut command_args = subcommand;
+    command_args.extend(build_command(&selected_options, &extra_args)?);
 
     // Execute the command
     if dry_run {
-        println!("Dry run: {} {}", executable, command_args.join(" "));
+        println!(
+            "Dry run: {}{}",
+            env_prefix(&env),
+            shell_command(executable, &command_args)
+        );
     } else {
-        execute_command(&executable, &command_args)?;
+        if interactive {
+            alias::offer_save(executable, &command_args, None)?;
+        }
+        capture::check_status(run_command(executable, &command_args, &env, None).await?)?;
     }
 
     Ok(())
 }
 
-fn find_executable(name: &str) -> anyhow::Result<String> {
-    // Try to run 'which' to find the executable
-    let output = Command::new("which").arg(name).output()?;
+/// The executable `name` runs: a path given directly, or the first match on
+/// PATH (then cargo's bin directory and the install manifest).
+pub fn find_executable(name: &str) -> anyhow::Result<PathBuf> {
+    if resolve::is_path(name) {
+        let path = Path::new(name);
+        return match resolve::resolve(name).selected() {
+            Some(candidate) => Ok(std::path::absolute(&candidate.path)?),
+            None if path.is_file() => anyhow::bail!("'{}' exists but is not executable", name),
+            None if path.exists() => anyhow::bail!("'{}' is not a file", name),
+            None => anyhow::bail!("'{}' does not exist", name),
+        };
+    }
+
+    // Builtins that change shell state can't do anything useful as a separate process
+    if SHELL_ONLY_BUILTINS.contains(&name) {
+        anyhow::bail!(
+            "'{}' is a shell builtin; it runs inside your shell and can't be hoisted",
+            name
+        );
+    }
 
-    if output.status.success() {
-        let path = String::from_utf8(output.stdout)?.trim().to_string();
-        Ok(path)
+    if let Some(candidate) = resolve::resolve(name).selected() {
+        Ok(PathBuf::from(&candidate.path))
     } else {
-        anyhow::bail!("Executable '{}' not found in PATH", name);
+        let aliases = load_alias_snapshot();
+        let kind = aliases
+            .get(name)
+            .map(|expansion| ShellKind::Alias(expansion.clone()))
+            .or_else(|| probe_shell_kind(name));
+        anyhow::bail!(not_found_message(name, kind.as_ref()));
     }
 }
 
-fn get_help_output(executable: &str) -> anyhow::Result<String> {
-    let output = Command::new(executable).arg("--help").output()?;
+// Builtins with no standalone binary equivalent worth hoisting
+const SHELL_ONLY_BUILTINS: &[&str] = &[
+    "cd", "alias", "unalias", "export", "unset", "set", "source", ".", "eval", "exec", "exit",
+    "logout", "return", "shift", "trap", "wait", "jobs", "fg", "bg", "disown", "suspend",
+    "history", "fc", "pushd", "popd", "dirs", "declare", "typeset", "local", "readonly", "let",
+    "shopt", "setopt", "unsetopt", "bind", "builtin", "enable", "hash", "umask", "ulimit",
+    "complete", "compgen", "rehash",
+];
+
+/// What a name means to the user's shell, when it isn't an executable on PATH.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellKind {
+    Builtin,
+    Keyword,
+    Function,
+    Alias(String),
+}
 
-    if output.status.success() {
-        Ok(String::from_utf8(output.stdout)?)
+pub fn not_found_message(name: &str, kind: Option<&ShellKind>) -> String {
+    match kind {
+        Some(ShellKind::Builtin) => format!(
+            "'{}' is a shell builtin; it runs inside your shell and can't be hoisted",
+            name
+        ),
+        Some(ShellKind::Keyword) => format!("'{}' is a shell keyword and can't be hoisted", name),
+        Some(ShellKind::Function) => format!(
+            "'{}' is a shell function defined in your shell config and can't be hoisted",
+            name
+        ),
+        Some(ShellKind::Alias(expansion)) if expansion.is_empty() => format!(
+            "'{}' is a shell alias and can't be hoisted; save the output of `alias` to \
+             ~/.app-hoist/aliases to see which program it runs",
+            name
+        ),
+        Some(ShellKind::Alias(expansion)) => match alias_target(expansion) {
+            Some(target) if target != name => format!(
+                "'{}' is an alias for '{}' and can't be hoisted; try: hoist -p {}",
+                name, expansion, target
+            ),
+            _ => format!(
+                "'{}' is an alias for '{}' and can't be hoisted",
+                name, expansion
+            ),
+        },
+        None => format!("Executable '{}' not found in PATH", name),
+    }
+}
+
+/// Ask the user's shell what `name` is. Only bash and zsh report this reliably.
+fn probe_shell_kind(name: &str) -> Option<ShellKind> {
+    let shell = std::env::var("SHELL").ok()?;
+    let script = if shell.ends_with("bash") {
+        r#"type -t -- "$1""#
+    } else if shell.ends_with("zsh") {
+        r#"whence -w -- "$1""#
     } else {
-        anyhow::bail!("Failed to get help output from {}", executable);
+        return None;
+    };
+
+    let output = Command::new(&shell)
+        .args(["-c", script, "hoist", name])
+        .output()
+        .ok()?;
+    parse_type_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `type -t` (bash: `builtin`) or `whence -w` (zsh: `cd: builtin`) output.
+pub fn parse_type_output(output: &str) -> Option<ShellKind> {
+    let word = output.trim().rsplit(": ").next()?.trim();
+    match word {
+        "builtin" => Some(ShellKind::Builtin),
+        "keyword" | "reserved" => Some(ShellKind::Keyword),
+        "function" => Some(ShellKind::Function),
+        // The expansion isn't available here, only in the alias snapshot
+        "alias" => Some(ShellKind::Alias(String::new())),
+        _ => None,
     }
 }
 
-fn parse_options(help_text: &str) -> anyhow::Result<Vec<OptionInfo>> {
-    let lines: Vec<&str> = help_text.lines().collect();
-    let mut options = Vec::new();
-    let mut in_options = false;
+/// Aliases from `~/.app-hoist/aliases`, which users can opt into by saving
+/// the output of `alias` from their interactive shell there.
+fn load_alias_snapshot() -> HashMap<String, String> {
+    dirs::home_dir()
+        .map(|home| home.join(".app-hoist").join("aliases"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|content| parse_alias_output(&content))
+        .unwrap_or_default()
+}
 
-    let mut i = 0;
-    while i < lines.len() {
-        let line = lines[i];
+/// Parse `alias` output from bash (`alias ll='ls -l'`) or zsh (`ll='ls -l'`).
+pub fn parse_alias_output(output: &str) -> HashMap<String, String> {
+    let mut aliases = HashMap::new();
 
-        if line.trim() == "Options:" {
-            in_options = true;
-            i += 1;
+    for line in output.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("alias ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
             continue;
-        }
+        };
+
+        let value = value.trim();
+        let value = if value.len() >= 2
+            && ((value.starts_with('\'') && value.ends_with('\''))
+                || (value.starts_with('"') && value.ends_with('"')))
+        {
+            // bash writes an embedded quote as '\''
+            value[1..value.len() - 1].replace("'\\''", "'")
+        } else {
+            value.to_string()
+        };
 
-        if !in_options {
-            i += 1;
-            continue;
-        }
+        aliases.insert(name.trim().to_string(), value);
+    }
+
+    aliases
+}
+
+/// The program an alias ends up running, skipping env assignments and wrappers.
+pub fn alias_target(expansion: &str) -> Option<String> {
+    expansion
+        .split_whitespace()
+        .find(|word| {
+            !word.contains('=') && !matches!(*word, "command" | "builtin" | "noglob" | "nocorrect")
+        })
+        .map(|word| word.trim_start_matches('\\').to_string())
+}
+
+/// How long a help probe may run before it is killed, unless
+/// `[package] help_timeout_secs` says otherwise.
+fn help_timeout() -> anyhow::Result<Duration> {
+    Ok(config::user_integer("package", "help_timeout_secs")?
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_PROBE_TIMEOUT))
+}
 
-        // Check if this is a flag line (starts with 2 spaces and contains -)
-        if line.starts_with("  ") && line.trim().starts_with('-') {
-            // Parse the flag line
-            let flag_part = line.trim();
-            let (flags, requires_value) = parse_flag_line(flag_part);
-
-            // Collect description from subsequent lines
-            let mut description = String::new();
-            i += 1;
-            while i < lines.len() && lines[i].starts_with("          ") {
-                description.push_str(lines[i].trim());
-                description.push(' ');
-                i += 1;
+async fn get_help_output(executable: &str, subcommand: &[String]) -> anyhow::Result<String> {
+    let timeout = help_timeout()?;
+    probe_help_output(Path::new(executable), subcommand, timeout)
+        .await
+        .map_err(|e| match e.downcast::<ProbeTimedOut>() {
+            Ok(timed_out) => {
+                if let Some(pid) = timed_out.pid {
+                    let _ = cancel::kill_process_group(pid);
+                }
+                anyhow::anyhow!(
+                    "{} (raise [package] help_timeout_secs if it is just slow)",
+                    timed_out
+                )
             }
+            Err(e) => e,
+        })
+}
 
-            if !flags.is_empty() {
-                options.push(OptionInfo {
-                    flags,
-                    description: description.trim().to_string(),
-                    requires_value,
-                });
+/// Let the user set any of the documented variables for this run, starting
+/// from the ones given with `--env`.
+fn prompt_env_vars(
+    env_vars: &[EnvVarInfo],
+    mut env: Vec<(String, String)>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let defaults: Vec<usize> = env_vars
+        .iter()
+        .enumerate()
+        .filter(|(_, var)| env.iter().any(|(name, _)| *name == var.name))
+        .map(|(i, _)| i)
+        .collect();
+    let chosen = ui::multi_select_with_defaults(
+        "Set any of these environment variables? (none to skip)",
+        env_vars.to_vec(),
+        &defaults,
+    )?;
+
+    for var in chosen {
+        let current = env
+            .iter()
+            .find(|(name, _)| *name == var.name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(&var.name).ok());
+        let value = ui::text(&format!("Value for {}:", var.name), current.as_deref())?;
+        env.retain(|(name, _)| *name != var.name);
+        env.push((var.name, value));
+    }
+    Ok(env)
+}
+
+enum SubcommandChoice {
+    TopLevel,
+    /// Stop descending and use the options of the subcommand chosen so far
+    Stop(String),
+    Subcommand(SubcommandInfo),
+}
+
+impl std::fmt::Display for SubcommandChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubcommandChoice::TopLevel => write!(f, "(none, use top-level options)"),
+            SubcommandChoice::Stop(chain) => write!(f, "(stop here, use {} options)", chain),
+            SubcommandChoice::Subcommand(sub) if sub.description.is_empty() => {
+                write!(f, "{}", sub.name)
             }
-        } else {
-            i += 1;
+            SubcommandChoice::Subcommand(sub) => write!(f, "{}: {}", sub.name, sub.description),
         }
     }
+}
+
+/// Identifies a level of subcommands, to notice help that repeats itself.
+fn subcommand_names(subcommands: &[SubcommandInfo]) -> Vec<String> {
+    let mut names: Vec<String> = subcommands.iter().map(|sub| sub.name.clone()).collect();
+    names.sort();
+    names
+}
 
-    // If no options found with new method, try fallback regex for single-line formats
-    if options.is_empty() {
-        options = parse_options_fallback(help_text)?;
+/// Options from `man <name>`, for tools whose help output has none. `None`
+/// when there is no man page or `man` isn't installed.
+fn man_page_options(name: &str) -> Option<Vec<OptionInfo>> {
+    let output = Command::new("man")
+        .args(["-P", "cat", name])
+        // Wide enough that descriptions don't wrap under the flag column
+        .env("MANWIDTH", "160")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
 
-    Ok(options)
+    Some(parse_man_page(&String::from_utf8_lossy(&output.stdout)))
 }
 
-fn parse_flag_line(line: &str) -> (Vec<String>, bool) {
-    // Examples: "-c, --config <CONFIG>" or "--init"
-    let mut flags = Vec::new();
-    let mut requires_value = false;
+/// What the example menu offers: the usual option list, or an example.
+enum ExampleChoice<'a> {
+    BuildCommand,
+    Example(&'a ExampleInfo),
+}
 
-    // Split by comma to handle multiple flags
-    for part in line.split(',') {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
+impl std::fmt::Display for ExampleChoice<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExampleChoice::BuildCommand => write!(f, "Build a command from options"),
+            ExampleChoice::Example(example) => write!(f, "Run example: {}", example),
         }
+    }
+}
+
+/// Offer the help's examples ahead of the option list, when it has any that
+/// can run without a shell.
+fn choose_example(examples: &[ExampleInfo]) -> anyhow::Result<Option<&ExampleInfo>> {
+    let runnable: Vec<&ExampleInfo> = examples
+        .iter()
+        .filter(|example| example_words(example).is_some())
+        .collect();
+    if runnable.is_empty() {
+        return Ok(None);
+    }
+
+    let mut choices = vec![ExampleChoice::BuildCommand];
+    choices.extend(runnable.into_iter().map(ExampleChoice::Example));
+    match ui::select("The help has examples. Run one instead?", choices)? {
+        ExampleChoice::BuildCommand => Ok(None),
+        ExampleChoice::Example(example) => Ok(Some(example)),
+    }
+}
 
-        // Split by space and take the flag part
-        let flag = part.split_whitespace().next().unwrap_or(part);
-        flags.push(flag.to_string());
+/// An example's words, or `None` when it needs a shell (pipes, redirects,
+/// `&&`, substitutions) and so can't be run directly.
+fn example_words(example: &ExampleInfo) -> Option<Vec<String>> {
+    const SHELL_WORDS: &[&str] = &["|", "||", "&&", ";", ">", ">>", "<", "2>&1", "&"];
+
+    let words = crate::tools::split_words(&example.command).ok()?;
+    let needs_shell = words.iter().any(|word| {
+        SHELL_WORDS.contains(&word.as_str()) || word.contains("$(") || word.contains('`')
+    });
+    (!needs_shell && words.len() > 1).then_some(words)
+}
 
-        // Check if this part indicates a value is required
-        if part.contains('<') {
-            requires_value = true;
+/// Run an example with the resolved executable in place of the tool's name,
+/// asking for a value for each placeholder such as `<file>` first.
+async fn run_example(
+    executable: &str,
+    example: &ExampleInfo,
+    env: Vec<(String, String)>,
+) -> anyhow::Result<()> {
+    let placeholder = regex::Regex::new(r"<([^<>\s]+)>").expect("placeholder pattern is valid");
+    let words = example_words(example).unwrap_or_default();
+
+    let mut values: HashMap<String, String> = HashMap::new();
+    let mut args = Vec::new();
+    for word in words.iter().skip(1) {
+        for captures in placeholder.captures_iter(word) {
+            let name = &captures[1];
+            if let Entry::Vacant(entry) = values.entry(name.to_string()) {
+                entry.insert(editor::prompt_value(
+                    &format!("Enter {}:", name),
+                    None,
+                    ValueType::infer(Some(name), ""),
+                )?);
+            }
         }
+        args.push(
+            placeholder
+                .replace_all(word, |captures: &regex::Captures| {
+                    values[&captures[1]].clone()
+                })
+                .into_owned(),
+        );
     }
 
-    (flags, requires_value)
+    capture::check_status(run_command(executable, &args, &env, None).await?)
 }
 
-fn parse_options_fallback(help_text: &str) -> anyhow::Result<Vec<OptionInfo>> {
-    let mut options = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bash_type_output_is_parsed() {
+        assert_eq!(parse_type_output("builtin\n"), Some(ShellKind::Builtin));
+        assert_eq!(parse_type_output("keyword\n"), Some(ShellKind::Keyword));
+        assert_eq!(parse_type_output("function\n"), Some(ShellKind::Function));
+        assert_eq!(
+            parse_type_output("alias\n"),
+            Some(ShellKind::Alias(String::new()))
+        );
+        assert_eq!(parse_type_output("file\n"), None);
+        assert_eq!(parse_type_output(""), None);
+    }
+
+    #[test]
+    fn zsh_whence_output_is_parsed() {
+        assert_eq!(parse_type_output("cd: builtin\n"), Some(ShellKind::Builtin));
+        assert_eq!(
+            parse_type_output("if: reserved\n"),
+            Some(ShellKind::Keyword)
+        );
+        assert_eq!(
+            parse_type_output("mkcd: function\n"),
+            Some(ShellKind::Function)
+        );
+        assert_eq!(parse_type_output("ls: command\n"), None);
+        assert_eq!(parse_type_output("nope: none\n"), None);
+    }
 
-    // Fallback regex for single-line formats (like grep)
-    let option_regex = Regex::new(r"^\s*([-\w\s,]+?)\s{2,}(.+)$")?;
+    #[test]
+    fn bash_alias_output_is_parsed() {
+        let aliases = parse_alias_output(
+            "alias ll='ls -alF'\n\
+             alias grep='grep --color=auto'\n\
+             alias say='echo '\\''hi'\\'''\n",
+        );
+        assert_eq!(aliases["ll"], "ls -alF");
+        assert_eq!(aliases["grep"], "grep --color=auto");
+        assert_eq!(aliases["say"], "echo 'hi'");
+    }
 
-    for line in help_text.lines() {
-        if let Some(captures) = option_regex.captures(line) {
-            let flags_str = captures.get(1).unwrap().as_str();
-            let description = captures.get(2).unwrap().as_str().trim();
+    #[test]
+    fn zsh_alias_output_is_parsed() {
+        let aliases =
+            parse_alias_output("g=git\nll='ls -l'\nwhich-command=whence\n\nnot an alias\n");
+        assert_eq!(aliases["g"], "git");
+        assert_eq!(aliases["ll"], "ls -l");
+        assert_eq!(aliases["which-command"], "whence");
+        assert_eq!(aliases.len(), 3);
+    }
 
-            // Split flags by comma and clean up
-            let flags: Vec<String> = flags_str
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
+    #[test]
+    fn alias_targets_skip_assignments_and_wrappers() {
+        assert_eq!(alias_target("ls -alF").as_deref(), Some("ls"));
+        assert_eq!(
+            alias_target("LC_ALL=C noglob command \\grep -n").as_deref(),
+            Some("grep")
+        );
+        assert_eq!(alias_target("").as_deref(), None);
+    }
 
-            if !flags.is_empty() {
-                // Simple heuristic: if description contains <value> or [value], it requires a value
-                let requires_value = description.contains('<') || description.contains('[');
+    #[test]
+    fn aliases_suggest_the_program_they_run() {
+        assert_eq!(
+            not_found_message("ll", Some(&ShellKind::Alias("ls -alF".to_string()))),
+            "'ll' is an alias for 'ls -alF' and can't be hoisted; try: hoist -p ls"
+        );
+        // An alias wrapping the same name has nothing better to suggest
+        assert_eq!(
+            not_found_message("grep", Some(&ShellKind::Alias("grep --color".to_string()))),
+            "'grep' is an alias for 'grep --color' and can't be hoisted"
+        );
+        assert!(
+            not_found_message("ll", Some(&ShellKind::Alias(String::new())))
+                .contains("~/.app-hoist/aliases")
+        );
+    }
 
-                options.push(OptionInfo {
-                    flags,
-                    description: description.to_string(),
-                    requires_value,
-                });
-            }
+    #[test]
+    fn other_kinds_explain_themselves() {
+        assert!(not_found_message("cd", Some(&ShellKind::Builtin)).contains("shell builtin"));
+        assert!(not_found_message("if", Some(&ShellKind::Keyword)).contains("shell keyword"));
+        assert!(not_found_message("mkcd", Some(&ShellKind::Function)).contains("shell function"));
+        assert_eq!(
+            not_found_message("nope", None),
+            "Executable 'nope' not found in PATH"
+        );
+    }
+
+    #[test]
+    fn shell_only_builtins_are_refused_before_searching() {
+        let err = find_executable("cd").unwrap_err();
+        assert!(err.to_string().contains("shell builtin"));
+    }
+
+    fn subcommand(name: &str, description: &str) -> SubcommandInfo {
+        SubcommandInfo {
+            name: name.to_string(),
+            description: description.to_string(),
         }
     }
 
-    Ok(options)
+    #[test]
+    fn subcommand_choices_read_naturally() {
+        assert_eq!(
+            SubcommandChoice::Subcommand(subcommand("build", "Compile")).to_string(),
+            "build: Compile"
+        );
+        assert_eq!(
+            SubcommandChoice::Subcommand(subcommand("container", "")).to_string(),
+            "container"
+        );
+        assert_eq!(
+            SubcommandChoice::Stop("remote add".to_string()).to_string(),
+            "(stop here, use remote add options)"
+        );
+    }
+
+    #[test]
+    fn repeated_subcommand_levels_are_recognised_in_any_order() {
+        let level = [subcommand("test", "Run"), subcommand("build", "")];
+        let again = [subcommand("build", "Compile"), subcommand("test", "")];
+        assert_eq!(subcommand_names(&level), subcommand_names(&again));
+        assert_eq!(subcommand_names(&level), ["build", "test"]);
+    }
 }
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Short names for the projects of one run, for progress lines and tables.
+/// Each project gets the fewest trailing path segments that tell it apart
+/// from the others, so `~/code/org/api` and `~/code/org/web` show as `api`
+/// and `web`, while two `api` directories keep a parent segment each.
+/// Reports and logs keep full paths.
+#[derive(Debug, Clone, Default)]
+pub struct PathDisplay {
+    names: HashMap<String, String>,
+}
+
+impl PathDisplay {
+    pub fn new(paths: &[String]) -> Self {
+        let absolute: Vec<PathBuf> = paths.iter().map(|p| absolute(p)).collect();
+        let Some(ancestor) = common_ancestor(&absolute) else {
+            return Self::default();
+        };
+
+        // Segments below the common ancestor, which are unique per distinct path
+        let relative: Vec<Vec<String>> = absolute
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&ancestor)
+                    .unwrap_or(path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .collect();
+
+        let mut names = HashMap::new();
+        for (i, path) in paths.iter().enumerate() {
+            let segments = &relative[i];
+            if segments.is_empty() {
+                // The ancestor itself is one of the projects
+                names.insert(path.clone(), shorten_home(&ancestor));
+                continue;
+            }
+
+            let mut depth = 1;
+            while depth < segments.len()
+                && relative.iter().enumerate().any(|(j, other)| {
+                    j != i
+                        && other != segments
+                        && other.ends_with(&segments[segments.len() - depth..])
+                })
+            {
+                depth += 1;
+            }
+            names.insert(path.clone(), segments[segments.len() - depth..].join("/"));
+        }
+
+        Self { names }
+    }
+
+    /// The short name for `path`, or its home-shortened form for a path that
+    /// wasn't part of the set.
+    pub fn show(&self, path: &str) -> String {
+        self.names
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| shorten_home(&absolute(path)))
+    }
+}
+
+/// The deepest directory containing every path, if they share one.
+pub fn common_ancestor(paths: &[PathBuf]) -> Option<PathBuf> {
+    let (first, rest) = paths.split_first()?;
+    let mut ancestor: Vec<Component> = first.components().collect();
+    for path in rest {
+        let shared = ancestor
+            .iter()
+            .zip(path.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        ancestor.truncate(shared);
+    }
+
+    // A single project is its own ancestor; its parent is more useful
+    if paths.len() == 1 {
+        ancestor.pop();
+    }
+
+    if ancestor.is_empty() {
+        None
+    } else {
+        Some(ancestor.iter().collect())
+    }
+}
+
+/// `path` with the home directory replaced by `~`.
+pub fn shorten_home(path: &Path) -> String {
+    shorten_home_in(path, dirs::home_dir().as_deref())
+}
+
+/// `shorten_home` with the home directory given.
+fn shorten_home_in(path: &Path, home: Option<&Path>) -> String {
+    if let Some(home) = home
+        && let Ok(rest) = path.strip_prefix(home)
+    {
+        if rest.as_os_str().is_empty() {
+            return "~".to_string();
+        }
+        return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+}
+
+/// An absolute form of a path given on the command line, without `.`
+/// segments, for comparing against other paths.
+fn absolute(path: &str) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+    absolute
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(paths: &[&str]) -> (PathDisplay, Vec<String>) {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        (PathDisplay::new(&paths), paths)
+    }
+
+    fn names(paths: &[&str]) -> Vec<String> {
+        let (display, paths) = display(paths);
+        paths.iter().map(|path| display.show(path)).collect()
+    }
+
+    #[test]
+    fn distinct_basenames_show_alone() {
+        assert_eq!(names(&["/code/org/api", "/code/org/web"]), ["api", "web"]);
+    }
+
+    #[test]
+    fn colliding_basenames_keep_a_parent_segment() {
+        assert_eq!(
+            names(&["/code/web/api", "/code/cli/api", "/code/worker"]),
+            ["web/api", "cli/api", "worker"]
+        );
+        // Only as far up as they differ
+        assert_eq!(
+            names(&["/code/a/svc/api", "/code/b/svc/api"]),
+            ["a/svc/api", "b/svc/api"]
+        );
+    }
+
+    #[test]
+    fn a_project_inside_another_is_named_below_it() {
+        let (display, paths) = display(&["/code/app", "/code/app/plugins/auth"]);
+        assert_eq!(display.show(&paths[1]), "auth");
+        // The outer project is the common ancestor, shown in full
+        assert_eq!(display.show(&paths[0]), shorten_home(Path::new(&paths[0])));
+    }
+
+    #[test]
+    fn a_single_path_shows_its_last_segment() {
+        assert_eq!(names(&["/code/org/api"]), ["api"]);
+        assert_eq!(
+            common_ancestor(&[PathBuf::from("/code/org/api")]),
+            Some(PathBuf::from("/code/org"))
+        );
+        assert_eq!(common_ancestor(&[]), None);
+    }
+
+    #[test]
+    fn paths_outside_the_set_are_shown_in_full() {
+        let (display, _) = display(&["/code/org/api", "/code/org/web"]);
+        assert_eq!(
+            display.show("/elsewhere/tool"),
+            shorten_home(&absolute("/elsewhere/tool"))
+        );
+    }
+
+    #[test]
+    fn home_is_shortened_to_a_tilde() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            shorten_home_in(&home.join("code/api"), Some(home)),
+            "~/code/api"
+        );
+        assert_eq!(shorten_home_in(home, Some(home)), "~");
+        // Only whole segments count
+        assert_eq!(
+            shorten_home_in(Path::new("/home/meg/api"), Some(home)),
+            Path::new("/home/meg/api").display().to_string()
+        );
+        assert_eq!(
+            shorten_home_in(Path::new("/srv/api"), None),
+            Path::new("/srv/api").display().to_string()
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cache::ProjectCache;
+use crate::capture::format_duration;
+use crate::detection::DetectionTrace;
+use crate::models::ProjectType;
+use crate::run_profile::config_path;
+use crate::ui;
+use std::io::IsTerminal;
+use std::time::Duration;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+/// The type pinned with `[project] type = "rust"` in the project's
+/// `.app-hoist.toml`, which wins over both detection and the cache.
+pub fn pinned_type(path: &str) -> anyhow::Result<Option<ProjectType>> {
+    let file = config_path(path);
+    if !file.exists() {
+        return Ok(None);
+    }
+
+    let document: DocumentMut = std::fs::read_to_string(&file)?.parse()?;
+    let Some(name) = document
+        .get("project")
+        .and_then(|project| project.get("type"))
+        .and_then(Item::as_str)
+    else {
+        return Ok(None);
+    };
+
+    match <ProjectType as clap::ValueEnum>::from_str(name, true) {
+        Ok(project_type) => Ok(Some(project_type)),
+        Err(_) => anyhow::bail!(
+            "Unknown [project] type = \"{}\" in {}; expected one of: {}",
+            name,
+            file.display(),
+            ProjectType::ALL
+                .iter()
+                .map(ProjectType::key)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Pin the project type in `.app-hoist.toml`, keeping the rest of the file intact.
+pub fn pin_type(path: &str, project_type: &ProjectType) -> anyhow::Result<()> {
+    let file = config_path(path);
+    let mut document: DocumentMut = if file.exists() {
+        std::fs::read_to_string(&file)?.parse()?
+    } else {
+        DocumentMut::new()
+    };
+
+    let project = document
+        .entry("project")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("[project] in {} is not a table", file.display()))?;
+    project["type"] = value(project_type.key());
+
+    std::fs::write(&file, document.to_string())?;
+    Ok(())
+}
+
+/// The type to use when a fresh detection disagrees with the cached entry.
+/// Interactive runs show both and ask, optionally pinning the answer;
+/// otherwise the fresh result wins and the change is logged.
+pub fn resolve_conflict(
+    path: &str,
+    cached: &ProjectCache,
+    fresh: &ProjectType,
+    trace: &DetectionTrace,
+) -> anyhow::Result<ProjectType> {
+    if !std::io::stdin().is_terminal() {
+        ui::status(&format!(
+            "💾 Project type changed from {} (cached) to {} (detected now), using the latter",
+            cached.project_type, fresh
+        ));
+        return Ok(fresh.clone());
+    }
+
+    ui::status(&format!(
+        "⚠️  Cached as a {} project {} ago, but detection now finds {}",
+        cached.project_type,
+        format_duration(Duration::from_secs(cached.age_seconds())),
+        fresh
+    ));
+    println!("Detection (checked in priority order):");
+    for line in trace.render() {
+        ui::status(&line);
+    }
+
+    let fresh_choice = format!("{} (detected now)", fresh);
+    let cached_choice = format!("{} (cached)", cached.project_type);
+    let choice = ui::select(
+        "Which project type should be used?",
+        vec![fresh_choice.clone(), cached_choice],
+    )?;
+    let chosen = if choice == fresh_choice {
+        fresh.clone()
+    } else {
+        cached.project_type.clone()
+    };
+
+    offer_pin(path, &chosen)?;
+    Ok(chosen)
+}
+
+/// The type to use when the project's files point at more than one, e.g. a
+/// pyproject.toml with both `[tool.uv]` and `[tool.poetry]`. Interactive runs
+/// ask, optionally pinning the answer; otherwise the first candidate wins.
+pub fn choose_type(path: &str, candidates: &[ProjectType]) -> anyhow::Result<ProjectType> {
+    let first = candidates[0].clone();
+    let names: Vec<String> = candidates.iter().map(ProjectType::to_string).collect();
+    if !std::io::stdin().is_terminal() {
+        ui::status(&format!(
+            "⚠️  Project could be any of {}, using {}; pin one in .app-hoist.toml to choose",
+            names.join(", "),
+            first
+        ));
+        return Ok(first);
+    }
+
+    ui::status(&format!(
+        "⚠️  This project is set up for {}",
+        names.join(" and ")
+    ));
+    let choice = ui::select("Which project type should be used?", names.clone())?;
+    let chosen = names
+        .iter()
+        .position(|name| *name == choice)
+        .map_or(first, |index| candidates[index].clone());
+
+    offer_pin(path, &chosen)?;
+    Ok(chosen)
+}
+
+/// Ask whether to pin the chosen type in `.app-hoist.toml`.
+fn offer_pin(path: &str, chosen: &ProjectType) -> anyhow::Result<()> {
+    let pin_choice = format!(
+        "Pin {} in {}",
+        chosen,
+        config_path(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+    let remember = ui::select(
+        "Remember this answer?",
+        vec!["Keep auto-detecting".to_string(), pin_choice.clone()],
+    )?;
+    if remember == pin_choice {
+        pin_type(path, chosen)?;
+        ui::status(&format!(
+            "💾 Pinned [project] type = \"{}\" in {}",
+            chosen.key(),
+            config_path(path).display()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinning_round_trips_and_keeps_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(pinned_type(path).unwrap(), None);
+
+        let original = "# ports for docker run\n[docker]\nports = [\"8080:80\"]\n\n[project]\nname = \"demo\"\n";
+        std::fs::write(config_path(path), original).unwrap();
+        assert_eq!(pinned_type(path).unwrap(), None);
+
+        pin_type(path, &ProjectType::Rust).unwrap();
+        assert_eq!(pinned_type(path).unwrap(), Some(ProjectType::Rust));
+        pin_type(path, &ProjectType::Go).unwrap();
+        assert_eq!(pinned_type(path).unwrap(), Some(ProjectType::Go));
+
+        let written = std::fs::read_to_string(config_path(path)).unwrap();
+        assert!(written.starts_with("# ports for docker run\n[docker]\nports = [\"8080:80\"]\n"));
+        assert!(
+            written.contains("name = \"demo\"\ntype = \"go\""),
+            "{}",
+            written
+        );
+    }
+
+    #[test]
+    fn a_new_file_gets_just_the_pin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        pin_type(path, &ProjectType::JavaScript).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(config_path(path)).unwrap(),
+            format!("[project]\ntype = \"{}\"\n", ProjectType::JavaScript.key())
+        );
+    }
+
+    #[test]
+    fn unknown_pinned_types_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        std::fs::write(config_path(path), "[project]\ntype = \"cobol\"\n").unwrap();
+        let err = pinned_type(path).unwrap_err().to_string();
+        assert!(
+            err.starts_with("Unknown [project] type = \"cobol\""),
+            "{}",
+            err
+        );
+        assert!(err.contains("rust"), "{}", err);
+    }
+}
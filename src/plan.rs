@@ -0,0 +1,61 @@
+use crate::editor::display_arg;
+use crate::utils::shell_quote;
+
+/// One command that is about to run, and what it runs against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub label: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl PlanStep {
+    pub fn command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(|word| display_arg(&shell_quote(word)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The commands a run will execute, shown before running them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+impl ExecutionPlan {
+    pub fn push(&mut self, label: &str, program: &str, args: &[String]) {
+        self.steps.push(PlanStep {
+            label: label.to_string(),
+            program: program.to_string(),
+            args: args.to_vec(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// One line per step with labels aligned in a column.
+    pub fn render(&self) -> Vec<String> {
+        let width = self
+            .steps
+            .iter()
+            .map(|step| console::measure_text_width(&step.label))
+            .max()
+            .unwrap_or(0);
+
+        self.steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "  {}  {}",
+                    console::pad_str(&step.label, width, console::Alignment::Left, None),
+                    step.command_line()
+                )
+            })
+            .collect()
+    }
+}
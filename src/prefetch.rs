@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+/// Work started ahead of time in background tasks, at most `concurrency` at
+/// once, keyed so the same work is never started twice. Dropping the
+/// prefetcher aborts whatever hasn't finished.
+pub struct Prefetcher<T> {
+    tasks: HashMap<Vec<String>, JoinHandle<anyhow::Result<T>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl<T: Send + 'static> Prefetcher<T> {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            tasks: HashMap::new(),
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+        }
+    }
+
+    /// Start `work` for `key` once a slot is free, unless it was already started.
+    pub fn spawn(
+        &mut self,
+        key: Vec<String>,
+        work: impl Future<Output = anyhow::Result<T>> + Send + 'static,
+    ) {
+        if self.tasks.contains_key(&key) {
+            return;
+        }
+        let permits = Arc::clone(&self.permits);
+        let task = tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            work.await
+        });
+        self.tasks.insert(key, task);
+    }
+
+    /// The result for `key`, waiting for it if it's still running. `None`
+    /// when it was never started or didn't run to completion.
+    pub async fn take(&mut self, key: &[String]) -> Option<anyhow::Result<T>> {
+        self.tasks.remove(key)?.await.ok()
+    }
+
+    /// Abort everything still queued or running.
+    pub fn cancel(&mut self) {
+        for (_, task) in self.tasks.drain() {
+            task.abort();
+        }
+    }
+}
+
+impl<T> Drop for Prefetcher<T> {
+    fn drop(&mut self) {
+        for task in self.tasks.values() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn key(name: &str) -> Vec<String> {
+        vec![name.to_string()]
+    }
+
+    /// Counts of fake help probes started, finished and running at once.
+    #[derive(Default)]
+    struct Probes {
+        started: AtomicUsize,
+        finished: AtomicUsize,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+    }
+
+    impl Probes {
+        /// A probe that takes `delay` to answer with `name`'s help.
+        fn probe(
+            self: &Arc<Self>,
+            name: &str,
+            delay: Duration,
+        ) -> impl Future<Output = anyhow::Result<String>> + Send + 'static {
+            let probes = Arc::clone(self);
+            let help = format!("help for {}", name);
+            async move {
+                probes.started.fetch_add(1, Ordering::SeqCst);
+                let running = probes.running.fetch_add(1, Ordering::SeqCst) + 1;
+                probes.most_running.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                probes.running.fetch_sub(1, Ordering::SeqCst);
+                probes.finished.fetch_add(1, Ordering::SeqCst);
+                Ok(help)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn at_most_the_concurrency_limit_runs_at_once() {
+        let probes = Arc::new(Probes::default());
+        let mut prefetcher = Prefetcher::new(2);
+        let names = ["build", "test", "run", "fmt", "doc"];
+        for name in names {
+            prefetcher.spawn(key(name), probes.probe(name, Duration::from_millis(20)));
+        }
+
+        for name in names {
+            let help = prefetcher.take(&key(name)).await.unwrap().unwrap();
+            assert_eq!(help, format!("help for {}", name));
+        }
+        assert_eq!(probes.finished.load(Ordering::SeqCst), names.len());
+        assert_eq!(probes.most_running.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn a_concurrency_of_zero_still_makes_progress() {
+        let probes = Arc::new(Probes::default());
+        let mut prefetcher = Prefetcher::new(0);
+        prefetcher.spawn(key("build"), probes.probe("build", Duration::ZERO));
+
+        assert!(prefetcher.take(&key("build")).await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn work_already_started_for_a_key_is_not_started_again() {
+        let probes = Arc::new(Probes::default());
+        let mut prefetcher = Prefetcher::new(4);
+        prefetcher.spawn(
+            key("build"),
+            probes.probe("build", Duration::from_millis(10)),
+        );
+        prefetcher.spawn(key("build"), probes.probe("other", Duration::ZERO));
+
+        let help = prefetcher.take(&key("build")).await.unwrap().unwrap();
+        assert_eq!(help, "help for build");
+        assert_eq!(probes.started.load(Ordering::SeqCst), 1);
+        // Taken results are gone
+        assert!(prefetcher.take(&key("build")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn keys_never_started_have_no_result() {
+        let mut prefetcher: Prefetcher<String> = Prefetcher::new(1);
+        assert!(prefetcher.take(&key("build")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn failures_are_handed_back() {
+        let mut prefetcher: Prefetcher<String> = Prefetcher::new(1);
+        prefetcher.spawn(key("build"), async { anyhow::bail!("probe failed") });
+
+        let error = prefetcher.take(&key("build")).await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "probe failed");
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_running_and_queued_work() {
+        let probes = Arc::new(Probes::default());
+        let mut prefetcher = Prefetcher::new(1);
+        prefetcher.spawn(key("build"), probes.probe("build", Duration::from_secs(30)));
+        prefetcher.spawn(key("test"), probes.probe("test", Duration::from_secs(30)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(probes.started.load(Ordering::SeqCst), 1);
+
+        prefetcher.cancel();
+
+        assert!(prefetcher.take(&key("build")).await.is_none());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // The queued probe never got a slot, and the running one never finished
+        assert_eq!(probes.started.load(Ordering::SeqCst), 1);
+        assert_eq!(probes.finished.load(Ordering::SeqCst), 0);
+        // Nothing holds on to the fakes once their tasks are gone
+        assert_eq!(Arc::strong_count(&probes), 1);
+    }
+
+    #[tokio::test]
+    async fn dropping_the_prefetcher_aborts_its_work() {
+        let probes = Arc::new(Probes::default());
+        let mut prefetcher = Prefetcher::new(1);
+        prefetcher.spawn(key("build"), probes.probe("build", Duration::from_secs(30)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        drop(prefetcher);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(probes.finished.load(Ordering::SeqCst), 0);
+        assert_eq!(Arc::strong_count(&probes), 1);
+    }
+}
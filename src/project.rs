@@ -1,34 +1,155 @@
-use crate::cache::{CacheManager, ProjectCache};
-use crate::models::{CliArg, OptionInfo, ProjectType};
-use crate::utils::{execute_project_command, select_options};
+use crate::alias;
+use crate::cache::{CacheManager, ProjectCache, markers_unchanged_since};
+use crate::capture;
+use crate::clean;
+use crate::config;
+use crate::detection::{self, DetectionTrace};
+use crate::docker::DockerMenu;
+use crate::editor::display_command;
+use crate::freshness::{self, Freshness};
+use crate::history::{self, HistoryEntry};
+use crate::installed::{self, InstallKind, InstalledEntry};
+use crate::lock;
+use crate::models::{BuildSettings, CliArg, Operation, OptionInfo, ProjectType};
+use crate::package;
+use crate::paths;
+use crate::pin;
+use crate::plan::ExecutionPlan;
+use crate::resolve;
+use crate::tools;
+use crate::ui;
+use crate::utils::{
+    SYSTEM_INSTALL_DIR, VENV_ACTIVATE, chain_commands, env_prefix, go_install_dir, on_path,
+    path_export_line, prompt_extra_args, run_command, run_dir, run_operation, select_from_flags,
+    select_option_indices, shell_command, temp_binary_path, venv_program,
+};
+use crate::value_sources::ValueSources;
+use std::path::Path;
+use std::process::ExitStatus;
 
-pub fn handle_project_mode(path: &str, dry_run: bool) -> anyhow::Result<()> {
-    println!("Managing project: {}", path);
+pub async fn handle_project_mode(
+    path: &str,
+    dry_run: bool,
+    version_check: bool,
+    selections: &[String],
+    env: &[String],
+    program_args: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut env = package::parse_env_assignments(env)?;
+    let program_args = program_args.map(tools::split_words).transpose()?;
+    println!(
+        "Managing project: {}",
+        paths::shorten_home(&std::path::absolute(path)?)
+    );
 
     // Initialize cache manager
     let mut cache_manager = CacheManager::new()?;
 
-    // Try to get cached project info
-    let (project_type, entry_point) = if let Some(cached) = cache_manager.get(path)? {
-        println!("Using cached project information");
-        (cached.project_type, cached.entry_point)
-    } else {
-        println!("Detecting project information...");
-        // Detect project type
-        let project_type = detect_project_type(path)?;
+    // A pinned type wins, then fresh detection, then the cache. The cache is
+    // trusted while none of the project's marker files changed since it was written
+    let previous = cache_manager.peek(path);
+    let pinned = match detection::only_type() {
+        Some(only_type) => Some(only_type),
+        None => pin::pinned_type(path)?,
+    };
+    let mut project_cache = match cache_manager.get(path)? {
+        Some(cached)
+            if markers_unchanged_since(path, cached.last_updated)
+                && pinned
+                    .as_ref()
+                    .is_none_or(|pinned| *pinned == cached.project_type) =>
+        {
+            println!("Using cached project information");
+            cached
+        }
+        _ => {
+            println!("Detecting project information...");
+            // Detect project type
+            let project_type = match pinned {
+                Some(pinned) => pinned,
+                None => {
+                    let mut trace = DetectionTrace::default();
+                    let fresh = detect_project_type_traced(path, &mut trace)?;
+                    let mut candidates =
+                        detection::competing_types(Path::new(path), &fresh, &trace);
+                    // One question also covers a pyproject.toml set up for both uv and Poetry
+                    if let Some(python) = candidates.iter().position(|candidate| {
+                        matches!(candidate, ProjectType::Uv | ProjectType::Poetry)
+                    }) && detection::claims_uv_and_poetry(Path::new(path))
+                    {
+                        candidates.splice(python..=python, [ProjectType::Uv, ProjectType::Poetry]);
+                    }
+                    match &previous {
+                        // The answer settles it, whatever the cache says
+                        _ if candidates.len() > 1 => pin::choose_type(path, &candidates)?,
+                        Some(previous) if previous.project_type != fresh => {
+                            pin::resolve_conflict(path, previous, &fresh, &trace)?
+                        }
+                        _ => fresh,
+                    }
+                }
+            };
 
-        // Detect entry point
-        let entry_point = detect_entry_point(path)?;
+            // Detect entry point
+            let entry_point = detect_entry_point(path)?;
 
-        // Cache the results
-        let cache = ProjectCache::new(project_type.clone(), entry_point.clone());
-        cache_manager.set(path.to_string(), cache)?;
+            // Cache the results
+            let mut cache = ProjectCache::new(project_type.clone(), entry_point.clone());
+            cache.package_manager = cached_package_manager(&project_type, path);
+            if project_type == ProjectType::Venv
+                && let Some(venv_dir) = find_venv(path)
+            {
+                cache.metadata.insert(VENV_DIR_KEY.to_string(), venv_dir);
+            }
+            if project_type == ProjectType::Go {
+                cache.metadata.insert(
+                    GO_GENERATE_KEY.to_string(),
+                    detect_go_generate(path).to_string(),
+                );
+            }
+            // A corrected entry point outlives re-detection of the same type
+            if let Some(entry) = previous
+                .as_ref()
+                .filter(|previous| previous.project_type == project_type)
+                .and_then(|previous| previous.metadata.get(ENTRY_POINT_KEY))
+            {
+                cache
+                    .metadata
+                    .insert(ENTRY_POINT_KEY.to_string(), entry.clone());
+            }
+            cache_manager.set(path.to_string(), cache.clone())?;
+            cache_manager.flush()?;
 
-        (project_type, entry_point)
+            cache
+        }
     };
+    if let Some(entry) = detection::entry_override() {
+        project_cache
+            .metadata
+            .insert(ENTRY_POINT_KEY.to_string(), entry);
+        cache_manager.set(path.to_string(), project_cache.clone())?;
+        cache_manager.flush()?;
+    }
+    let entry_override = project_cache.metadata.get(ENTRY_POINT_KEY).cloned();
+    if let Some(entry) = &entry_override {
+        project_cache.entry_point = entry.clone();
+    }
+    let project_type = project_cache.project_type.clone();
+    let entry_point = project_cache.entry_point.clone();
 
     // Get options based on type
-    let options = get_project_options(&project_type, &entry_point, path)?;
+    let mut operations = get_project_operations(&project_type, path);
+    // Python projects without a detected entry point still run a given one
+    if entry_override.is_some()
+        && !operations.contains(&Operation::Run)
+        && project_type.operations().contains(&Operation::Run)
+    {
+        operations.insert(0, Operation::Run);
+    }
+    let options: Vec<OptionInfo> = operations
+        .iter()
+        .map(|op| describe_operation(op, &project_type, &entry_point, path))
+        .collect();
 
     println!(
         "Detected {} project with {} options",
@@ -36,580 +157,2888 @@ pub fn handle_project_mode(path: &str, dry_run: bool) -> anyhow::Result<()> {
         options.len()
     );
 
-    let selected_options = if dry_run {
+    // A missing cargo or go is reported now rather than as a spawn error after prompting
+    if !dry_run {
+        tools::check_runtime(path, &mut project_cache)?;
+        cache_manager.set(path.to_string(), project_cache.clone())?;
+        cache_manager.flush()?;
+    }
+
+    if project_cache
+        .metadata
+        .get(GO_GENERATE_KEY)
+        .map(String::as_str)
+        == Some("true")
+    {
+        ui::status("💡 This project has //go:generate directives; run 'generate' before 'build'");
+    }
+
+    if version_check {
+        run_version_check(&project_type, path, dry_run).await?;
+    }
+
+    // Directories that are also Docker projects get one menu for both
+    let interactive = selections.is_empty();
+    let docker_menu = if dry_run || !interactive {
+        None
+    } else {
+        DockerMenu::detect(path)
+    };
+
+    let mut steps = Vec::new();
+    let selected_options = if !interactive {
+        select_from_flags(&options, selections, path)?
+            .into_iter()
+            .map(|(idx, value)| (operations[idx].clone(), value))
+            .collect()
+    } else if dry_run {
         println!("Dry run: skipping interactive selection, using no arguments.");
         Vec::new()
     } else if options.is_empty() {
         println!("No options available, proceeding with no arguments.");
         Vec::new()
+    } else if let Some(menu) = &docker_menu {
+        let section = section_label(&project_type, path);
+        let choices = select_combined(&section, &operations, &options, menu)?;
+        steps = combined_steps(&section, path, &choices, menu)?;
+        choices
+            .into_iter()
+            .filter_map(|choice| match choice {
+                MenuChoice::Project(op, value) => Some((op, value)),
+                MenuChoice::Docker(..) => None,
+            })
+            .collect()
     } else {
         // Interactive selection
-        select_options(&options)?
+        select_option_indices(&options, &[], &ValueSources::default())?
+            .into_iter()
+            .map(|(idx, value)| (operations[idx].clone(), value))
+            .collect()
+    };
+
+    let mut settings = BuildSettings {
+        package_manager: project_cache.package_manager.clone(),
+        venv_dir: project_cache.metadata.get(VENV_DIR_KEY).cloned(),
+        entry_point: entry_override,
+        ..BuildSettings::default()
     };
+    if selected_options.iter().any(|(op, _)| *op == Operation::Run) {
+        settings.program_args = match program_args {
+            Some(words) => words,
+            None if interactive => {
+                let words = prompt_program_args(&mut project_cache)?;
+                cache_manager.set(path.to_string(), project_cache.clone())?;
+                cache_manager.flush()?;
+                words
+            }
+            None => Vec::new(),
+        };
+    }
+    if interactive && !selected_options.is_empty() {
+        settings.extra_args = prompt_extra_args()?;
+    }
+    if project_type == ProjectType::Go
+        && selected_options
+            .iter()
+            .any(|(op, _)| matches!(op, Operation::Run | Operation::Build))
+    {
+        let packages = go_main_packages(path);
+        if packages.len() > 1 {
+            if interactive {
+                settings.go_package = Some(prompt_go_package(&packages, &mut project_cache)?);
+                cache_manager.set(path.to_string(), project_cache.clone())?;
+                cache_manager.flush()?;
+            } else {
+                settings.go_package = project_cache
+                    .metadata
+                    .get(GO_PACKAGE_KEY)
+                    .filter(|package| packages.contains(package))
+                    .cloned();
+            }
+        }
+    }
+    if project_type == ProjectType::Go
+        && selected_options
+            .iter()
+            .any(|(op, _)| *op == Operation::Build)
+        && !selected_options.iter().any(|(op, _)| *op == Operation::Run)
+    {
+        let env_value = |name: &str| {
+            env.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        settings.go_target = match (env_value("GOOS"), env_value("GOARCH")) {
+            (None, None) if interactive => prompt_go_target()?,
+            (None, None) => None,
+            (goos, goarch) => Some((
+                goos.unwrap_or_else(|| std::env::consts::OS.to_string()),
+                goarch.unwrap_or_else(|| go_arch(std::env::consts::ARCH).to_string()),
+            )),
+        };
+        if let Some((goos, goarch)) = &settings.go_target {
+            env.retain(|(key, _)| key != "GOOS" && key != "GOARCH");
+            env.push(("GOOS".to_string(), goos.clone()));
+            env.push(("GOARCH".to_string(), goarch.clone()));
+        }
+    }
+    if project_type == ProjectType::Go && uses_go_build_tags(&selected_options) {
+        if interactive {
+            settings.go_build_tags = prompt_go_build_tags(&mut project_cache)?;
+            cache_manager.set(path.to_string(), project_cache.clone())?;
+            cache_manager.flush()?;
+        } else {
+            settings.go_build_tags = cached_go_build_tags(&project_cache);
+        }
+    }
+    if project_type == ProjectType::Rust && !selected_options.is_empty() {
+        let members = workspace_members(path);
+        if let [member] = members.as_slice() {
+            settings.cargo_package = Some(member.name.clone());
+        } else if members.len() > 1 {
+            // Running or installing needs one crate; other operations may cover them all
+            let needs_one = selected_options.iter().any(|(op, _)| {
+                matches!(
+                    op,
+                    Operation::Run | Operation::RunWithArg { .. } | Operation::Install
+                )
+            });
+            if interactive {
+                settings.cargo_package =
+                    prompt_workspace_member(&members, !needs_one, &mut project_cache)?;
+                cache_manager.set(path.to_string(), project_cache.clone())?;
+                cache_manager.flush()?;
+            } else {
+                settings.cargo_package = cached_workspace_member(&project_cache, &members);
+            }
+        }
+    }
+    if project_type == ProjectType::Rust
+        && selected_options
+            .iter()
+            .any(|(op, _)| matches!(op, Operation::Run | Operation::RunWithArg { .. }))
+    {
+        let binaries = rust_binaries(&cargo_manifest_dir(path, settings.cargo_package.as_deref()));
+        if binaries.len() > 1 {
+            if interactive {
+                settings.cargo_bin = Some(prompt_rust_binary(&binaries, &mut project_cache)?);
+                cache_manager.set(path.to_string(), project_cache.clone())?;
+                cache_manager.flush()?;
+            } else {
+                settings.cargo_bin = project_cache
+                    .metadata
+                    .get(CARGO_BIN_KEY)
+                    .filter(|name| binaries.contains(name))
+                    .cloned();
+            }
+        }
+    }
+    if project_type == ProjectType::Rust
+        && selected_options.iter().any(|(op, _)| {
+            matches!(
+                op,
+                Operation::Run
+                    | Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Test
+                    | Operation::Target { .. }
+            )
+        })
+    {
+        let features = cargo_features(&cargo_manifest_dir(path, settings.cargo_package.as_deref()));
+        if interactive && !features.is_empty() {
+            prompt_cargo_features(&features, &mut settings, &mut project_cache)?;
+            cache_manager.set(path.to_string(), project_cache)?;
+            cache_manager.flush()?;
+        } else {
+            cached_cargo_features(&features, &mut settings, &project_cache);
+        }
+    }
+
+    if project_type == ProjectType::Rust && uses_cargo_profile(&selected_options) {
+        settings.cargo_profile = if interactive {
+            Some(prompt_cargo_profile(
+                cargo_profiles(path),
+                &selected_options,
+            )?)
+        } else {
+            config::user_string("rust", "profile")?
+        };
+    }
 
     // Build the command
-    let (executable, command_args) = build_project_command(&project_type, path, &selected_options)?;
+    let (executable, command_args) =
+        build_project_command(&project_type, path, &selected_options, &settings)?;
+
+    // Compare with the last run of the same operation here
+    let primary_operation = selected_options.first().map(|(op, _)| op.as_flag());
+    if let Some(operation) = &primary_operation
+        && !command_args.is_empty()
+    {
+        history::show_diff_against_last(path, operation, &executable, &command_args);
+    }
+
+    let operation_label = selected_options
+        .iter()
+        .map(|(op, _)| op.as_flag())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let project_step = ProjectStep {
+        project_type: &project_type,
+        path,
+        selected_options: &selected_options,
+        primary_operation: primary_operation.as_deref(),
+        executable: &executable,
+        args: &command_args,
+        env: &env,
+        cargo_package: settings.cargo_package.as_deref(),
+        go_package: settings.go_package.as_deref(),
+        go_install: settings.go_target.is_none(),
+    };
+
+    // Mixed project and Docker selections run as a sequence of steps
+    if steps
+        .iter()
+        .any(|step| matches!(step, CombinedStep::Docker(..)))
+    {
+        return run_combined_steps(&steps, &project_step, &operation_label).await;
+    }
 
     // Execute the command
     if command_args.is_empty() {
         println!("No command to execute. Select options to perform actions.");
     } else if dry_run {
-        println!("Dry run: {} {}", executable, command_args.join(" "));
+        println!(
+            "Dry run: {}{}",
+            env_prefix(&env),
+            shell_command(&executable, &command_args)
+        );
     } else {
+        let cleaning = selected_options
+            .iter()
+            .any(|(op, _)| *op == Operation::Clean);
+        if cleaning && !clean::confirm_destructive(&[(path, &project_type)])? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        if interactive {
+            alias::offer_save(&executable, &command_args, Some(path))?;
+        }
+        let _lock = lock::acquire(path, &operation_label).await?;
+        // Measured before anything is deleted
+        let artifacts = if cleaning {
+            clean::artifact_paths(&project_type, path)
+        } else {
+            Vec::new()
+        };
+        let artifacts_size = clean::size(&artifacts);
+        capture::check_status(project_step.execute().await?)?;
+        if cleaning && !artifacts.is_empty() {
+            println!("Reclaimed {}", clean::reclaimed(artifacts_size, &artifacts));
+        }
+    }
+
+    Ok(())
+}
+
+/// The project's own command, ready to run.
+struct ProjectStep<'a> {
+    project_type: &'a ProjectType,
+    path: &'a str,
+    selected_options: &'a [(Operation, Option<String>)],
+    primary_operation: Option<&'a str>,
+    executable: &'a str,
+    args: &'a [String],
+    env: &'a [(String, String)],
+    cargo_package: Option<&'a str>,
+    go_package: Option<&'a str>,
+    /// Whether a Go build is installed; cross-compiled ones stay in the project
+    go_install: bool,
+}
+
+impl ProjectStep<'_> {
+    /// Run the command and record it in history, returning how it exited.
+    async fn execute(&self) -> anyhow::Result<ExitStatus> {
         // Special handling for Go build command
-        if project_type == ProjectType::Go
-            && selected_options.iter().any(|(flag, _)| flag == "build")
+        if *self.project_type == ProjectType::Go
+            && self.go_install
+            && self
+                .selected_options
+                .iter()
+                .any(|(op, _)| *op == Operation::Build)
         {
-            execute_go_build_with_install(&executable, &command_args, path)?;
+            let started = std::time::Instant::now();
+            let install_name = go_binary_name(self.path, self.go_package)?;
+            let result =
+                execute_go_build_with_install(self.executable, self.args, self.path, &install_name)
+                    .await;
+            if let Some(operation) = self.primary_operation {
+                // A failed install after a successful build has no exit code of its own
+                let code = result.as_ref().ok().and_then(|status| status.code());
+                history::record(
+                    &HistoryEntry::new(self.path, operation, self.executable, self.args).finished(
+                        &run_dir(Some(self.path))?,
+                        code,
+                        started.elapsed(),
+                    ),
+                )?;
+            }
+            result
         } else {
-            execute_project_command(&executable, &command_args, path)?;
+            let status = run_operation(
+                self.primary_operation,
+                self.executable,
+                self.args,
+                self.env,
+                self.path,
+            )
+            .await?;
+            if status.success()
+                && *self.project_type == ProjectType::Rust
+                && self
+                    .selected_options
+                    .iter()
+                    .any(|(op, _)| *op == Operation::Install)
+            {
+                let crate_name = match self.cargo_package {
+                    Some(package) => package.to_string(),
+                    None => rust_package_name(self.path),
+                };
+                installed::record(InstalledEntry::cargo(&crate_name, self.path, self.args))?;
+            }
+            Ok(status)
+        }
+    }
+}
+
+/// One entry of a combined project and Docker menu, in the order chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuChoice {
+    Project(Operation, Option<String>),
+    Docker(usize, Option<String>),
+}
+
+/// A step of a combined run. All project selections become a single command,
+/// placed where the first of them was chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CombinedStep {
+    Project { label: String },
+    Docker(String, String, Vec<String>),
+}
+
+/// Heading for the project's options in a combined menu, e.g. "Cargo".
+fn section_label(project_type: &ProjectType, path: &str) -> String {
+    match project_type {
+        ProjectType::Rust => "Cargo".to_string(),
+        ProjectType::Go => "Go".to_string(),
+        ProjectType::Uv => "uv".to_string(),
+        ProjectType::Poetry => "Poetry".to_string(),
+        ProjectType::Venv | ProjectType::Generic => "Python".to_string(),
+        ProjectType::JavaScript | ProjectType::TypeScript => detect_package_manager(path),
+        ProjectType::Make => "Make".to_string(),
+        ProjectType::Just => "just".to_string(),
+        ProjectType::Deno => "Deno".to_string(),
+    }
+}
+
+/// Offer project and Docker operations in one menu, returning the choices in
+/// menu order.
+fn select_combined(
+    section: &str,
+    operations: &[Operation],
+    options: &[OptionInfo],
+    menu: &DockerMenu,
+) -> anyhow::Result<Vec<MenuChoice>> {
+    let labelled = |option: &OptionInfo, section: &str| OptionInfo {
+        section: Some(section.to_string()),
+        ..option.clone()
+    };
+    let combined: Vec<OptionInfo> = options
+        .iter()
+        .map(|option| labelled(option, section))
+        .chain(menu.options.iter().map(|option| labelled(option, "Docker")))
+        .collect();
+
+    Ok(
+        select_option_indices(&combined, &[], &ValueSources::default())?
+            .into_iter()
+            .map(|(idx, value)| match operations.get(idx) {
+                Some(op) => MenuChoice::Project(op.clone(), value),
+                None => MenuChoice::Docker(idx - operations.len(), value),
+            })
+            .collect(),
+    )
+}
+
+/// Turn combined menu choices into ordered steps.
+pub fn combined_steps(
+    section: &str,
+    path: &str,
+    choices: &[MenuChoice],
+    menu: &DockerMenu,
+) -> anyhow::Result<Vec<CombinedStep>> {
+    let mut steps = Vec::new();
+    for choice in choices {
+        match choice {
+            MenuChoice::Project(..) => {
+                if !steps
+                    .iter()
+                    .any(|step| matches!(step, CombinedStep::Project { .. }))
+                {
+                    steps.push(CombinedStep::Project {
+                        label: section.to_string(),
+                    });
+                }
+            }
+            MenuChoice::Docker(idx, value) => {
+                let flag = menu.options[*idx].preferred_flag();
+                let (program, args) = menu.command(path, flag, value.as_deref())?;
+                steps.push(CombinedStep::Docker(
+                    format!("Docker {}", flag),
+                    program,
+                    args,
+                ));
+            }
+        }
+    }
+    Ok(steps)
+}
+
+fn combined_plan(steps: &[CombinedStep], project: &ProjectStep) -> ExecutionPlan {
+    let mut plan = ExecutionPlan::default();
+    for step in steps {
+        match step {
+            CombinedStep::Project { label } => plan.push(label, project.executable, project.args),
+            CombinedStep::Docker(label, program, args) => plan.push(label, program, args),
+        }
+    }
+    plan
+}
+
+/// Show the steps, let the user reorder them, then run them one after
+/// another, stopping at the first failure.
+async fn run_combined_steps(
+    steps: &[CombinedStep],
+    project: &ProjectStep<'_>,
+    operation_label: &str,
+) -> anyhow::Result<()> {
+    // A project selection can still produce no command, e.g. run with no entry point
+    let mut steps: Vec<CombinedStep> = steps
+        .iter()
+        .filter(|step| !matches!(step, CombinedStep::Project { .. }) || !project.args.is_empty())
+        .cloned()
+        .collect();
+
+    println!("Plan:");
+    for line in combined_plan(&steps, project).render() {
+        println!("{}", line);
+    }
+    if steps.len() > 1 && !ui::confirm("Run in this order?", true)? {
+        steps = reorder_steps(steps, project)?;
+    }
+
+    let _lock = lock::acquire(project.path, operation_label).await?;
+    let total = steps.len();
+    for (i, step) in steps.iter().enumerate() {
+        let status = match step {
+            CombinedStep::Project { .. } => project.execute().await?,
+            CombinedStep::Docker(_, program, args) => {
+                run_command(program, args, &[], Some(project.path)).await?
+            }
+        };
+        if !status.success() {
+            let skipped = total - i - 1;
+            if skipped > 0 {
+                ui::status(&format!("⏭️  Skipping the remaining {} step(s)", skipped));
+            }
+            ui::status(&format!("❌ Step {} of {} failed", i + 1, total));
+            return capture::check_status(status);
         }
     }
 
     Ok(())
 }
 
-fn detect_project_type(path: &str) -> anyhow::Result<ProjectType> {
-    // Check for uv project
-    let pyproject_path = format!("{}/pyproject.toml", path);
-    let uv_lock_path = format!("{}/uv.lock", path);
-    if std::path::Path::new(&pyproject_path).exists() {
-        // Check for [tool.uv] section OR uv.lock file
-        let has_uv_section = std::fs::read_to_string(&pyproject_path)
-            .map(|content| content.contains("[tool.uv]"))
-            .unwrap_or(false);
-        let has_uv_lock = std::path::Path::new(&uv_lock_path).exists();
+/// Pick the steps one at a time in the order they should run.
+fn reorder_steps(
+    mut remaining: Vec<CombinedStep>,
+    project: &ProjectStep,
+) -> anyhow::Result<Vec<CombinedStep>> {
+    let mut ordered = Vec::new();
+    while remaining.len() > 1 {
+        let labels = combined_plan(&remaining, project).render();
+        let choice = ui::select(
+            &format!("Step {}:", ordered.len() + 1),
+            labels.iter().map(|l| l.trim().to_string()).collect(),
+        )?;
+        let idx = labels
+            .iter()
+            .position(|l| l.trim() == choice)
+            .unwrap_or_default();
+        ordered.push(remaining.remove(idx));
+    }
+    ordered.append(&mut remaining);
+    Ok(ordered)
+}
+
+const GO_GENERATE_KEY: &str = "go_generate";
+const GO_BUILD_TAGS_KEY: &str = "go_build_tags";
+const PROGRAM_ARGS_KEY: &str = "program_args";
+/// The `cmd/<name>` package chosen last when the module has several
+const GO_PACKAGE_KEY: &str = "go_package";
+/// Which of `VENV_DIRS` held the venv when the project was detected
+const VENV_DIR_KEY: &str = "venv_dir";
+/// An entry point given with `--entry`, kept until another one is given
+const ENTRY_POINT_KEY: &str = "entry_point";
+/// The Cargo workspace member chosen last, empty for the whole workspace
+const CARGO_PACKAGE_KEY: &str = "cargo_package";
+/// The binary chosen last when the package has several
+const CARGO_BIN_KEY: &str = "cargo_bin";
+const CARGO_FEATURES_KEY: &str = "cargo_features";
+const CARGO_NO_DEFAULT_FEATURES_KEY: &str = "cargo_no_default_features";
+
+/// Ask which main package to run and build, offering the last answer first
+/// and remembering the new one.
+fn prompt_go_package(
+    packages: &[String],
+    project_cache: &mut ProjectCache,
+) -> anyhow::Result<String> {
+    let mut choices = packages.to_vec();
+    if let Some(previous) = project_cache.metadata.get(GO_PACKAGE_KEY)
+        && let Some(idx) = choices.iter().position(|package| package == previous)
+    {
+        let previous = choices.remove(idx);
+        choices.insert(0, previous);
+    }
+
+    let package = ui::select("Which command?", choices)?;
+    project_cache
+        .metadata
+        .insert(GO_PACKAGE_KEY.to_string(), package.clone());
+    Ok(package)
+}
+
+/// GOOS/GOARCH pairs offered for cross-compiling, with a hint for each
+const GO_TARGETS: [(&str, &str, &str); 7] = [
+    ("linux", "amd64", ""),
+    ("linux", "arm64", " (Raspberry Pi, 64-bit OS)"),
+    ("linux", "arm", " (Raspberry Pi, 32-bit OS)"),
+    ("darwin", "arm64", " (Apple silicon)"),
+    ("darwin", "amd64", ""),
+    ("windows", "amd64", ""),
+    ("windows", "arm64", ""),
+];
+
+/// Ask which platform to build for. `None` builds for this machine and
+/// installs the result.
+fn prompt_go_target() -> anyhow::Result<Option<(String, String)>> {
+    const HOST: &str = "This machine (install to PATH)";
+    const OTHER: &str = "Other GOOS/GOARCH...";
+    let mut choices = vec![HOST.to_string()];
+    choices.extend(
+        GO_TARGETS
+            .iter()
+            .map(|(goos, goarch, hint)| format!("{}/{}{}", goos, goarch, hint)),
+    );
+    choices.push(OTHER.to_string());
+
+    let choice = ui::select("Build for:", choices)?;
+    let target = match choice.as_str() {
+        HOST => return Ok(None),
+        OTHER => ui::text("GOOS/GOARCH (e.g. freebsd/amd64):", None)?,
+        _ => choice
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+    parse_go_target(&target).map(Some)
+}
+
+/// A `GOOS/GOARCH` pair such as `linux/arm64`.
+fn parse_go_target(target: &str) -> anyhow::Result<(String, String)> {
+    match target.trim().split_once('/') {
+        Some((goos, goarch)) if !goos.is_empty() && !goarch.is_empty() => {
+            Ok((goos.to_string(), goarch.to_string()))
+        }
+        _ => anyhow::bail!("Expected GOOS/GOARCH, e.g. linux/arm64, not '{}'", target),
+    }
+}
+
+/// Go's name for a Rust `std::env::consts::ARCH`, for the architecture that
+/// `GOOS` alone implies.
+fn go_arch(arch: &str) -> &str {
+    match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    }
+}
+
+/// The output file of a cross-compiled build, e.g. `tool-linux-arm64` or
+/// `tool-windows-amd64.exe`.
+fn cross_binary_name(name: &str, goos: &str, goarch: &str) -> String {
+    let suffix = if goos == "windows" { ".exe" } else { "" };
+    format!("{}-{}-{}{}", name, goos, goarch, suffix)
+}
 
-        if has_uv_section || has_uv_lock {
-            return Ok(ProjectType::Uv);
+/// Ask for the arguments run passes to the program, defaulting to (and
+/// remembering) the project's last answer.
+fn prompt_program_args(project_cache: &mut ProjectCache) -> anyhow::Result<Vec<String>> {
+    let previous = project_cache
+        .metadata
+        .get(PROGRAM_ARGS_KEY)
+        .cloned()
+        .unwrap_or_default();
+    loop {
+        let input = ui::text("Arguments for the program (optional):", Some(&previous))?;
+        match tools::split_words(&input) {
+            Ok(words) => {
+                project_cache
+                    .metadata
+                    .insert(PROGRAM_ARGS_KEY.to_string(), input.trim().to_string());
+                return Ok(words);
+            }
+            Err(e) => println!("{}", e),
         }
     }
+}
+
+/// Ask for Go build tags, defaulting to (and remembering) the project's last answer.
+fn prompt_go_build_tags(project_cache: &mut ProjectCache) -> anyhow::Result<Vec<String>> {
+    let previous = project_cache
+        .metadata
+        .get(GO_BUILD_TAGS_KEY)
+        .cloned()
+        .unwrap_or_default();
+
+    let input = ui::text(
+        "Build tags (comma-separated, leave empty for none):",
+        Some(&previous),
+    )?;
+    let tags: Vec<String> = input
+        .split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    project_cache
+        .metadata
+        .insert(GO_BUILD_TAGS_KEY.to_string(), tags.join(","));
+
+    Ok(tags)
+}
+
+/// Ask which workspace member cargo should act on, offering the last answer
+/// first and remembering the new one. `None` means the whole workspace.
+fn prompt_workspace_member(
+    members: &[WorkspaceMember],
+    allow_all: bool,
+    project_cache: &mut ProjectCache,
+) -> anyhow::Result<Option<String>> {
+    const ALL_MEMBERS: &str = "All members";
+    let previous = project_cache
+        .metadata
+        .get(CARGO_PACKAGE_KEY)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut labels: Vec<String> = Vec::new();
+    if allow_all {
+        labels.push(ALL_MEMBERS.to_string());
+    }
+    labels.extend(members.iter().map(WorkspaceMember::to_string));
+    if let Some(idx) = members.iter().position(|member| member.name == previous) {
+        let label = labels.remove(idx + usize::from(allow_all));
+        labels.insert(0, label);
+    }
+
+    let choice = ui::select("Which workspace member?", labels)?;
+    let package = members
+        .iter()
+        .find(|member| member.to_string() == choice)
+        .map(|member| member.name.clone());
+
+    project_cache.metadata.insert(
+        CARGO_PACKAGE_KEY.to_string(),
+        package.clone().unwrap_or_default(),
+    );
+
+    Ok(package)
+}
+
+/// The workspace member chosen last, used when selecting with `--select`.
+fn cached_workspace_member(
+    project_cache: &ProjectCache,
+    members: &[WorkspaceMember],
+) -> Option<String> {
+    project_cache
+        .metadata
+        .get(CARGO_PACKAGE_KEY)
+        .filter(|name| members.iter().any(|member| member.name == **name))
+        .cloned()
+}
+
+/// Ask which of the package's binaries to run, offering the last answer first
+/// and remembering the new one.
+fn prompt_rust_binary(
+    binaries: &[String],
+    project_cache: &mut ProjectCache,
+) -> anyhow::Result<String> {
+    let mut choices = binaries.to_vec();
+    if let Some(previous) = project_cache.metadata.get(CARGO_BIN_KEY)
+        && let Some(idx) = choices.iter().position(|name| name == previous)
+    {
+        let previous = choices.remove(idx);
+        choices.insert(0, previous);
+    }
+
+    let binary = ui::select("Which binary should run?", choices)?;
+    project_cache
+        .metadata
+        .insert(CARGO_BIN_KEY.to_string(), binary.clone());
+    Ok(binary)
+}
+
+/// Whether any of the selected Go operations compiles with build tags.
+pub fn uses_go_build_tags(selected: &[(Operation, Option<String>)]) -> bool {
+    selected
+        .iter()
+        .any(|(op, _)| matches!(op, Operation::Run | Operation::Build | Operation::Test))
+}
+
+/// Whether any of the selected Rust operations builds with a Cargo profile.
+pub fn uses_cargo_profile(selected: &[(Operation, Option<String>)]) -> bool {
+    selected.iter().any(|(op, _)| {
+        matches!(
+            op,
+            Operation::Run
+                | Operation::RunWithArg { .. }
+                | Operation::Build
+                | Operation::Target { .. }
+        )
+    })
+}
+
+/// The profiles cargo can build with: debug, release and each custom
+/// `[profile.<name>]` in Cargo.toml.
+pub fn cargo_profiles(path: &str) -> Vec<String> {
+    let mut profiles = vec!["debug".to_string(), "release".to_string()];
+    if let Some(custom) = read_cargo_manifest(Path::new(path))
+        .as_ref()
+        .and_then(|manifest| manifest.get("profile")?.as_table().cloned())
+    {
+        profiles.extend(
+            custom
+                .into_iter()
+                .map(|(name, _)| name)
+                .filter(|name| !matches!(name.as_str(), "dev" | "release" | "test" | "bench")),
+        );
+    }
+    profiles
+}
+
+/// Ask which profile to build and run with. `[rust] profile` in the user
+/// config is offered first; without it, release when building and debug
+/// when only running.
+pub fn prompt_cargo_profile(
+    profiles: Vec<String>,
+    selected: &[(Operation, Option<String>)],
+) -> anyhow::Result<String> {
+    let configured = config::user_string("rust", "profile")?;
+    ui::select(
+        "Cargo profile:",
+        profiles_in_order(profiles, configured, selected),
+    )
+}
 
-    // Check for venv
-    let activate_path = format!("{}/bin/activate", path);
-    if std::path::Path::new(&activate_path).exists() {
-        return Ok(ProjectType::Venv);
+/// `profiles` with the one to offer first moved to the front.
+fn profiles_in_order(
+    mut profiles: Vec<String>,
+    configured: Option<String>,
+    selected: &[(Operation, Option<String>)],
+) -> Vec<String> {
+    let default = match configured {
+        Some(profile) => profile,
+        None if selected.iter().any(|(op, _)| *op == Operation::Build) => "release".to_string(),
+        None => "debug".to_string(),
+    };
+    if let Some(idx) = profiles.iter().position(|profile| *profile == default) {
+        let profile = profiles.remove(idx);
+        profiles.insert(0, profile);
     }
+    profiles
+}
 
-    // Check for Go project
-    let go_mod_path = format!("{}/go.mod", path);
-    if std::path::Path::new(&go_mod_path).exists() {
-        return Ok(ProjectType::Go);
+/// Cargo arguments selecting `profile`.
+fn cargo_profile_args(profile: &str) -> Vec<String> {
+    match profile {
+        "debug" | "dev" => Vec::new(),
+        "release" => vec!["--release".to_string()],
+        custom => vec!["--profile".to_string(), custom.to_string()],
     }
+}
 
-    // Check for Rust project
-    let cargo_toml_path = format!("{}/Cargo.toml", path);
-    if std::path::Path::new(&cargo_toml_path).exists() {
-        return Ok(ProjectType::Rust);
+/// Ask which Cargo features to enable and whether to keep the default ones,
+/// starting from (and remembering) the project's last answers.
+fn prompt_cargo_features(
+    features: &CargoFeatures,
+    settings: &mut BuildSettings,
+    project_cache: &mut ProjectCache,
+) -> anyhow::Result<()> {
+    cached_cargo_features(features, settings, project_cache);
+
+    if !features.names.is_empty() {
+        let defaults: Vec<usize> = features
+            .names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| settings.cargo_features.contains(name))
+            .map(|(idx, _)| idx)
+            .collect();
+        settings.cargo_features = ui::multi_select_with_defaults(
+            "Features to enable (empty for none):",
+            features.names.clone(),
+            &defaults,
+        )?;
+    }
+    if features.has_default {
+        settings.no_default_features =
+            !ui::confirm("Enable default features?", !settings.no_default_features)?;
     }
 
-    // Check for JavaScript/TypeScript project
-    let package_json_path = format!("{}/package.json", path);
-    if std::path::Path::new(&package_json_path).exists() {
-        // Check for TypeScript
-        let tsconfig_path = format!("{}/tsconfig.json", path);
-        if std::path::Path::new(&tsconfig_path).exists() {
-            return Ok(ProjectType::TypeScript);
-        } else {
-            return Ok(ProjectType::JavaScript);
+    project_cache.metadata.insert(
+        CARGO_FEATURES_KEY.to_string(),
+        settings.cargo_features.join(","),
+    );
+    project_cache.metadata.insert(
+        CARGO_NO_DEFAULT_FEATURES_KEY.to_string(),
+        settings.no_default_features.to_string(),
+    );
+
+    Ok(())
+}
+
+/// The features answered last time that the manifest still declares, used
+/// when selecting with `--select`.
+fn cached_cargo_features(
+    features: &CargoFeatures,
+    settings: &mut BuildSettings,
+    project_cache: &ProjectCache,
+) {
+    settings.cargo_features = project_cache
+        .metadata
+        .get(CARGO_FEATURES_KEY)
+        .map(|names| {
+            names
+                .split(',')
+                .filter(|name| features.names.iter().any(|feature| feature == name))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    settings.no_default_features = features.has_default
+        && project_cache
+            .metadata
+            .get(CARGO_NO_DEFAULT_FEATURES_KEY)
+            .map(String::as_str)
+            == Some("true");
+}
+
+/// The build tags answered last time, used when selecting with `--select`.
+pub fn cached_go_build_tags(project_cache: &ProjectCache) -> Vec<String> {
+    project_cache
+        .metadata
+        .get(GO_BUILD_TAGS_KEY)
+        .map(|tags| {
+            tags.split(',')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn run_version_check(
+    project_type: &ProjectType,
+    path: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let freshness = freshness::check_project(project_type, path);
+    println!("Dependencies: {}", freshness);
+
+    if let Freshness::Stale(_) = freshness
+        && let Some((executable, args)) = freshness::sync_command(project_type, path)?
+    {
+        ui::status("⚠️  Installed dependencies are out of date with the lockfile");
+
+        if dry_run {
+            println!("Dry run: {}", shell_command(&executable, &args));
+        } else if ui::confirm(
+            &format!("Run '{}' first?", display_command(&executable, &args)),
+            true,
+        )? {
+            capture::check_status(run_command(&executable, &args, &[], Some(path)).await?)?;
         }
     }
 
-    // Generic Python project
-    if std::path::Path::new(&pyproject_path).exists() {
-        return Ok(ProjectType::Generic);
+    Ok(())
+}
+
+pub fn detect_project_type(path: &str) -> anyhow::Result<ProjectType> {
+    detect_project_type_traced(path, &mut DetectionTrace::default())
+}
+
+/// Detect the project type, recording each marker and rule considered in `trace`.
+pub fn detect_project_type_traced(
+    path: &str,
+    trace: &mut DetectionTrace,
+) -> anyhow::Result<ProjectType> {
+    detection::detect_type(Path::new(path), trace)
+}
+
+/// The entry point run starts: the one given with `--entry`, or the
+/// detected one.
+fn run_entry_point(settings: &BuildSettings, path: &str) -> anyhow::Result<String> {
+    match &settings.entry_point {
+        Some(entry) => Ok(entry.clone()),
+        None => detect_entry_point(path),
     }
+}
 
-    Ok(ProjectType::Generic)
+pub fn detect_entry_point(path: &str) -> anyhow::Result<String> {
+    detect_entry_point_traced(path, &mut DetectionTrace::default())
 }
 
-fn detect_entry_point(path: &str) -> anyhow::Result<String> {
+pub fn detect_entry_point_traced(path: &str, trace: &mut DetectionTrace) -> anyhow::Result<String> {
+    let root = Path::new(path);
+
     // Check if this is a Go project
-    let go_mod_path = format!("{}/go.mod", path);
-    if std::path::Path::new(&go_mod_path).exists() {
+    if trace.check(root, "go.mod") {
         let go_candidates = ["main.go", "cmd/main.go"];
 
         for candidate in &go_candidates {
-            let full_path = format!("{}/{}", path, candidate);
-            if std::path::Path::new(&full_path).exists() {
+            if trace.check(root, candidate) {
+                trace.rule("go", true, &format!("{} exists", candidate));
                 return Ok(candidate.to_string());
             }
         }
 
+        let commands = go_command_packages(root);
+        if let [command] = commands.as_slice() {
+            trace.rule("go", true, &format!("{} is the only main package", command));
+            return Ok(command.clone());
+        }
+
         // Default to current directory for Go
+        trace.rule("go", true, "no main.go, building the module root");
         return Ok(".".to_string());
     }
 
     // Check if this is a Rust project
-    let cargo_toml_path = format!("{}/Cargo.toml", path);
-    if std::path::Path::new(&cargo_toml_path).exists() {
+    if trace.check(root, "Cargo.toml") {
+        trace.rule("rust", true, "cargo runs the package itself");
         return Ok(".".to_string()); // Run current directory for Rust
     }
 
     // Check if this is a JavaScript/TypeScript project
-    let package_json_path = format!("{}/package.json", path);
-    if std::path::Path::new(&package_json_path).exists() {
+    if trace.check(root, "package.json") {
+        trace.rule(
+            "javascript",
+            true,
+            "the package manager runs the start script",
+        );
         return Ok(".".to_string()); // Run with package manager
     }
 
+    // A single declared console script beats guessing at file names
+    let scripts = pyproject_scripts(path);
+    if let [(name, _)] = scripts.as_slice() {
+        trace.rule(
+            "python",
+            true,
+            &format!("[project.scripts] declares only {}", name),
+        );
+        return Ok(name.clone());
+    }
+
     // Python project detection
     let candidates = ["app.py", "main.py", "__main__.py"];
 
     for candidate in &candidates {
-        let full_path = format!("{}/{}", path, candidate);
-        if std::path::Path::new(&full_path).exists() {
+        if trace.check(root, candidate) {
+            trace.rule("python", true, &format!("{} exists", candidate));
             return Ok(candidate.to_string());
         }
     }
 
-    // Default to app.py if none found
-    Ok("app.py".to_string())
+    // Nothing to run; Python projects then offer no run option rather than one that fails
+    trace.rule(
+        "python",
+        false,
+        "no single [project.scripts] entry and no app.py, main.py or __main__.py",
+    );
+    Ok(String::new())
 }
 
-fn get_project_options(
-    project_type: &ProjectType,
-    entry_point: &str,
-    path: &str,
-) -> anyhow::Result<Vec<OptionInfo>> {
-    let mut options = Vec::new();
+/// Console scripts from pyproject.toml's `[project.scripts]`, as the script
+/// name and its `module:function` target.
+pub fn pyproject_scripts(path: &str) -> Vec<(String, String)> {
+    std::fs::read_to_string(Path::new(path).join("pyproject.toml"))
+        .ok()
+        .and_then(|content| toml::from_str::<toml::Value>(&content).ok())
+        .and_then(|value| value.get("project")?.get("scripts")?.as_table().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, target)| Some((name, target.as_str()?.to_string())))
+        .collect()
+}
 
-    match project_type {
-        ProjectType::Uv => {
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["sync".to_string()],
-                description: "Sync dependencies".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["add".to_string()],
-                description: "Install a package".to_string(),
-                requires_value: true,
-            });
-            options.push(OptionInfo {
-                flags: vec!["remove".to_string()],
-                description: "Uninstall a package".to_string(),
-                requires_value: true,
-            });
-        }
-        ProjectType::Venv => {
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["install".to_string()],
-                description: "Install a package".to_string(),
-                requires_value: true,
-            });
-            options.push(OptionInfo {
-                flags: vec!["uninstall".to_string()],
-                description: "Uninstall a package".to_string(),
-                requires_value: true,
-            });
-        }
-        ProjectType::Go => {
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: "Build and install the application".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: "Run tests".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["tidy".to_string()],
-                description: "Clean up dependencies".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["get".to_string()],
-                description: "Add a dependency".to_string(),
-                requires_value: true,
-            });
+/// `python` arguments that start an entry point: the file itself, or
+/// `-m <module>` for a `[project.scripts]` entry such as `pkg.cli:main`.
+fn python_entry_args(path: &str, entry_point: &str) -> Vec<String> {
+    match pyproject_scripts(path)
+        .into_iter()
+        .find(|(name, _)| name == entry_point)
+    {
+        Some((_, target)) => {
+            let module = target.split(':').next().unwrap_or(&target).trim();
+            vec!["-m".to_string(), module.to_string()]
         }
-        ProjectType::Rust => {
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: "Build the project".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["install".to_string()],
-                description: "Build and install to ~/.cargo/bin".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: "Run tests".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["check".to_string()],
-                description: "Check code without building".to_string(),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["clippy".to_string()],
-                description: "Run linter".to_string(),
-                requires_value: false,
-            });
+        None => vec![entry_point.to_string()],
+    }
+}
+
+fn is_python(project_type: &ProjectType) -> bool {
+    matches!(
+        project_type,
+        ProjectType::Uv | ProjectType::Poetry | ProjectType::Venv | ProjectType::Generic
+    )
+}
+
+pub fn get_project_operations(project_type: &ProjectType, path: &str) -> Vec<Operation> {
+    let mut operations = Vec::new();
+
+    let no_entry_point = is_python(project_type)
+        && detect_entry_point(path).is_ok_and(|entry_point| entry_point.is_empty());
+    for operation in project_type.operations() {
+        let is_run = operation == Operation::Run;
+        if is_run && no_entry_point {
+            continue;
         }
-        ProjectType::JavaScript | ProjectType::TypeScript => {
-            let pm = detect_package_manager(path);
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
+        operations.push(operation);
 
-            // Add detected CLI args as separate options
-            let cli_args = detect_cli_args(path);
-            for arg in cli_args {
-                if let Some(long) = &arg.long {
-                    options.push(OptionInfo {
-                        flags: vec![format!("run --{}", long)],
-                        description: format!("Run with --{} argument", long),
+        // Add detected CLI args as separate options right after run
+        if is_run
+            && matches!(
+                project_type,
+                ProjectType::JavaScript | ProjectType::TypeScript
+            )
+        {
+            for arg in detect_cli_args(path) {
+                if let Some(long) = arg.long {
+                    operations.push(Operation::RunWithArg {
+                        name: long,
                         requires_value: arg.requires_value,
                     });
                 }
             }
-            options.push(OptionInfo {
-                flags: vec!["install".to_string()],
-                description: format!("Install dependencies ({} install)", pm),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["add".to_string()],
-                description: format!("Add package ({} add)", pm),
-                requires_value: true,
-            });
-            options.push(OptionInfo {
-                flags: vec!["test".to_string()],
-                description: format!("Run tests ({} test)", pm),
-                requires_value: false,
-            });
-            options.push(OptionInfo {
-                flags: vec!["build".to_string()],
-                description: format!("Build project ({} run build)", pm),
-                requires_value: false,
-            });
-        }
-        ProjectType::Generic => {
-            options.push(OptionInfo {
-                flags: vec!["run".to_string()],
-                description: format!("Run the app ({})", entry_point),
-                requires_value: false,
-            });
         }
     }
 
-    Ok(options)
-}
+    match project_type {
+        ProjectType::Make => operations.extend(detect_make_targets(path)),
+        ProjectType::Just => operations.extend(detect_just_recipes(path)),
+        ProjectType::Deno => operations.extend(detect_deno_tasks(path)),
+        ProjectType::Rust => operations.extend(detect_rust_examples(path)),
+        // A single script is already the entry point that run starts
+        _ if is_python(project_type) => {
+            let scripts = pyproject_scripts(path);
+            if scripts.len() > 1 {
+                operations.extend(scripts.into_iter().map(|(name, target)| Operation::Target {
+                    name,
+                    description: Some(format!("Run the {} script", target)),
+                    parameters: Vec::new(),
+                }));
+            }
+        }
+        ProjectType::JavaScript | ProjectType::TypeScript => {
+            operations.extend(detect_package_scripts(path))
+        }
+        _ => {}
+    }
+
+    operations
+}
+
+/// What `--list-options` prints for a project.
+#[derive(serde::Serialize)]
+struct ProjectOptions {
+    path: String,
+    project_type: ProjectType,
+    entry_point: String,
+    options: Vec<OptionInfo>,
+}
+
+/// Print the operations offered for a project as JSON instead of prompting.
+/// Detection runs fresh (honoring a pinned type) and nothing is cached.
+pub fn print_options(path: &str) -> anyhow::Result<()> {
+    let project_type = match detection::only_type() {
+        Some(only_type) => only_type,
+        None => match pin::pinned_type(path)? {
+            Some(pinned) => pinned,
+            None => detect_project_type(path)?,
+        },
+    };
+    let entry_point = detect_entry_point(path)?;
+    let options = get_project_operations(&project_type, path)
+        .iter()
+        .map(|op| describe_operation(op, &project_type, &entry_point, path))
+        .collect();
+
+    let output = ProjectOptions {
+        path: path.to_string(),
+        project_type,
+        entry_point,
+        options,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+pub fn describe_operation(
+    operation: &Operation,
+    project_type: &ProjectType,
+    entry_point: &str,
+    path: &str,
+) -> OptionInfo {
+    let mut option = operation.to_option_info();
+
+    match (project_type, operation) {
+        (_, Operation::Run) => {
+            option.description = format!("Run the app ({})", entry_point);
+        }
+        (ProjectType::Go, Operation::Build) => {
+            option.description = "Build and install the application".to_string();
+        }
+        (
+            _,
+            Operation::Target {
+                name,
+                description,
+                parameters,
+            },
+        ) => {
+            if description.is_none() {
+                option.description = match project_type {
+                    ProjectType::Just => format!("Run just {}", name),
+                    ProjectType::Make => format!("Run make {}", name),
+                    ProjectType::Deno => format!("Run deno task {}", name),
+                    ProjectType::Rust => format!("Run the {} example", name),
+                    _ => format!("Run {} run {}", detect_package_manager(path), name),
+                };
+            }
+            if !parameters.is_empty() {
+                option.value_name = Some(parameters.join(" "));
+            }
+        }
+        (ProjectType::JavaScript | ProjectType::TypeScript, _) => {
+            let pm = detect_package_manager(path);
+            let command = match operation {
+                Operation::InstallDeps => Some(format!("{} install", pm)),
+                Operation::Add => Some(format!("{} add", pm)),
+                Operation::Test if pm == "bun" => Some("bun run test".to_string()),
+                Operation::Test => Some(format!("{} test", pm)),
+                Operation::Build => Some(format!("{} run build", pm)),
+                _ => None,
+            };
+            if let Some(command) = command {
+                option.description = format!("{} ({})", operation.description(), command);
+            }
+        }
+        _ => {}
+    }
+
+    option
+}
 
-fn build_project_command(
+pub fn build_project_command(
     project_type: &ProjectType,
     path: &str,
-    selected: &[(String, Option<String>)],
+    selected: &[(Operation, Option<String>)],
+    settings: &BuildSettings,
 ) -> anyhow::Result<(String, Vec<String>)> {
-    match project_type {
+    // Nothing selected means nothing to run, whatever wrapper the tool resolves to
+    if selected.is_empty() {
+        return Ok((String::new(), Vec::new()));
+    }
+
+    // Updates, formatters and auditors other than the project's own tool, and
+    // deleting artifacts run as commands of their own around the project's
+    // command. So do the tool's own fmt and clean next to anything else, as
+    // it takes one subcommand per invocation.
+    let alongside = selected.len() > 1;
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    let mut rest = Vec::new();
+    for (op, value) in selected {
+        match op {
+            Operation::Update => {
+                if let Some(commands) = update_commands(project_type, path, settings)? {
+                    before.extend(commands.into_iter().map(|command| (command, true)));
+                    continue;
+                }
+            }
+            Operation::Format => {
+                if let Some(formatter) = external_formatter(project_type, path, settings)? {
+                    after.push((formatter, false));
+                    continue;
+                }
+                if alongside {
+                    after.push((own_step(project_type, path, op, settings)?, true));
+                    continue;
+                }
+            }
+            Operation::Audit => {
+                if let Some(auditor) = external_auditor(project_type, path, settings)? {
+                    after.push((auditor, false));
+                    continue;
+                }
+            }
+            // Nothing to delete leaves nothing to run
+            Operation::Clean => {
+                if let Some(removal) = clean::removal_command(project_type, path) {
+                    if !removal.is_empty() {
+                        before.push((removal, false));
+                    }
+                    continue;
+                }
+                if alongside {
+                    before.push((own_step(project_type, path, op, settings)?, true));
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        rest.push((op.clone(), value.clone()));
+    }
+    if rest.len() < selected.len() {
+        let mut commands = before;
+        if !rest.is_empty() {
+            let (executable, args) = build_project_command(project_type, path, &rest, settings)?;
+            commands.push((std::iter::once(executable).chain(args).collect(), false));
+        }
+        commands.extend(after);
+        // Extra arguments are for the project's tool, never for rm or another
+        // tool's formatter or auditor
+        if rest.is_empty()
+            && let Some((first, _)) = commands.iter_mut().find(|(_, own)| *own)
+        {
+            first.extend(settings.extra_args.iter().cloned());
+        }
+        return Ok(chain_commands(
+            commands.into_iter().map(|(command, _)| command).collect(),
+        ));
+    }
+
+    let (executable, mut args) = match project_type {
         ProjectType::Uv => {
-            if selected.iter().any(|(flag, _)| flag == "run") {
-                // For run command, use uv run <entry_point>
-                let entry_point = detect_entry_point(path)?;
-                Ok(("uv".to_string(), vec!["run".to_string(), entry_point]))
+            let script = selected.iter().find_map(|(op, _)| match op {
+                Operation::Run => Some(run_entry_point(settings, path)),
+                Operation::Target { name, .. } => Some(Ok(name.clone())),
+                _ => None,
+            });
+            if let Some(script) = script {
+                // For run command, use uv run <entry_point or script name>
+                let mut args = vec!["run".to_string(), script?];
+                if selected.iter().any(|(op, _)| *op == Operation::Run) {
+                    args.extend(settings.program_args.iter().cloned());
+                }
+                tools::command("uv", path, args)
             } else {
                 // For other commands (sync, add, etc.), use uv --project <path> <command>
                 let mut args = vec!["--project".to_string(), path.to_string()];
-                for (flag, value) in selected {
-                    args.push(flag.clone());
-                    if let Some(val) = value {
-                        args.push(val.clone());
+                for (op, value) in selected {
+                    match op {
+                        Operation::Sync | Operation::Add | Operation::Remove => {
+                            args.push(op.as_flag());
+                            if let Some(val) = value {
+                                args.push(val.clone());
+                            }
+                        }
+                        Operation::Run
+                        | Operation::RunWithArg { .. }
+                        | Operation::Build
+                        | Operation::Install
+                        | Operation::InstallDeps
+                        | Operation::InstallPackage
+                        | Operation::UninstallPackage
+                        | Operation::Get
+                        | Operation::Test
+                        | Operation::Check
+                        | Operation::Clippy
+                        | Operation::Tidy
+                        | Operation::Generate
+                        | Operation::Format
+                        | Operation::Lint
+                        | Operation::Update
+                        | Operation::Audit
+                        | Operation::Clean
+                        | Operation::Target { .. } => unsupported(project_type, op)?,
+                    }
+                }
+                tools::command("uv", path, args)
+            }
+        }
+        ProjectType::Poetry => {
+            let mut args = Vec::new();
+            for (op, value) in selected {
+                match op {
+                    Operation::Run => {
+                        // Poetry installs declared scripts into its environment
+                        let entry_point = run_entry_point(settings, path)?;
+                        args.push("run".to_string());
+                        if entry_point.ends_with(".py") {
+                            args.push("python".to_string());
+                        }
+                        args.push(entry_point);
+                        args.extend(settings.program_args.iter().cloned());
+                    }
+                    Operation::Target { name, .. } => {
+                        args.push("run".to_string());
+                        args.push(name.clone());
+                    }
+                    Operation::InstallDeps => args.push("install".to_string()),
+                    Operation::Add | Operation::Remove => {
+                        args.push(op.as_flag());
+                        if let Some(packages) = value {
+                            args.extend(packages.split_whitespace().map(str::to_string));
+                        }
+                    }
+                    Operation::Test => {
+                        args.push("run".to_string());
+                        args.push("pytest".to_string());
                     }
+                    Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Get
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Format
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
                 }
-                Ok(("uv".to_string(), args))
             }
+            tools::command("poetry", path, args)
         }
         ProjectType::Venv => {
-            let mut command_parts = Vec::new();
-            for (flag, value) in selected {
-                match flag.as_str() {
-                    "run" => {
-                        command_parts.push(format!("python {}", detect_entry_point(path)?));
+            // The venv's own interpreter and pip, so nothing needs activating
+            let venv_dir = settings
+                .venv_dir
+                .clone()
+                .or_else(|| find_venv(path))
+                .unwrap_or_else(|| ".".to_string());
+            let venv = std::path::absolute(Path::new(path).join(venv_dir))?;
+            let python = venv_program(&venv, "python");
+            let pip = venv_program(&venv, "pip");
+
+            let mut commands = Vec::new();
+            for (op, value) in selected {
+                match op {
+                    Operation::Run => {
+                        let mut command = vec![python.clone()];
+                        command.extend(python_entry_args(path, &run_entry_point(settings, path)?));
+                        command.extend(settings.program_args.iter().cloned());
+                        commands.push(command);
+                    }
+                    Operation::Target { name, .. } => {
+                        let mut command = vec![python.clone()];
+                        command.extend(python_entry_args(path, name));
+                        commands.push(command);
                     }
-                    "install" => {
+                    Operation::InstallPackage => {
                         if let Some(pkg) = value {
-                            command_parts.push(format!("pip install {}", pkg));
+                            commands.push(pip_command(&pip, "install", pkg));
                         }
                     }
-                    "uninstall" => {
+                    Operation::UninstallPackage => {
                         if let Some(pkg) = value {
-                            command_parts.push(format!("pip uninstall {}", pkg));
+                            commands.push(pip_command(&pip, "uninstall", pkg));
                         }
                     }
-                    _ => {}
+                    Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Test
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Format
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
                 }
             }
-            let full_command = format!("source bin/activate && {}", command_parts.join(" && "));
-            Ok(("bash".to_string(), vec!["-c".to_string(), full_command]))
+            Ok(chain_commands(commands))
         }
         ProjectType::Go => {
+            let tag_args = go_tag_args(&settings.go_build_tags);
+            let job_args = jobs_args("-p", settings.jobs);
             let mut args = Vec::new();
-            for (flag, value) in selected {
-                match flag.as_str() {
-                    "run" => {
+            for (op, value) in selected {
+                match op {
+                    Operation::Run => {
                         args.push("run".to_string());
-                        args.push(detect_entry_point(path)?);
+                        args.extend(tag_args.clone());
+                        match &settings.go_package {
+                            Some(package) => args.push(package.clone()),
+                            None => args.push(run_entry_point(settings, path)?),
+                        }
+                        args.extend(settings.program_args.iter().cloned());
+                    }
+                    Operation::Generate => {
+                        args.push("generate".to_string());
+                        args.push("./...".to_string());
                     }
-                    "build" => {
+                    Operation::Build => {
                         // For build, we'll handle this specially in execution
                         args.push("build".to_string());
+                        args.extend(tag_args.clone());
+                        args.extend(job_args.clone());
                         args.push("-o".to_string());
-                        let binary_name = detect_binary_name(path)?;
-                        let temp_path = format!("/tmp/{}", binary_name);
-                        args.push(temp_path);
-                        args.push(".".to_string());
+                        let package = settings
+                            .go_package
+                            .clone()
+                            .or_else(|| single_go_command(path))
+                            .unwrap_or_else(|| ".".to_string());
+                        let binary_name = go_binary_name(path, Some(&package))?;
+                        match &settings.go_target {
+                            Some((goos, goarch)) => {
+                                args.push(cross_binary_name(&binary_name, goos, goarch))
+                            }
+                            None => {
+                                let temp_path = temp_binary_path(path, &binary_name);
+                                args.push(temp_path.to_string_lossy().into_owned());
+                            }
+                        }
+                        args.push(package);
                     }
-                    "test" => {
+                    Operation::Test => {
                         args.push("test".to_string());
+                        args.extend(tag_args.clone());
+                        args.extend(job_args.clone());
                         args.push("./...".to_string());
                     }
-                    "tidy" => {
+                    Operation::Tidy => {
                         args.push("mod".to_string());
                         args.push("tidy".to_string());
                     }
-                    "get" => {
+                    Operation::Get => {
                         if let Some(pkg) = value {
                             args.push("get".to_string());
                             args.push(pkg.clone());
                         }
                     }
-                    _ => {}
+                    Operation::Format => {
+                        args.push("fmt".to_string());
+                        args.push("./...".to_string());
+                    }
+                    Operation::Clean => {
+                        args.push("clean".to_string());
+                        args.push("./...".to_string());
+                    }
+                    Operation::RunWithArg { .. }
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Target { .. } => unsupported(project_type, op)?,
                 }
             }
-            Ok(("go".to_string(), args))
+            tools::command("go", path, args)
         }
         ProjectType::Rust => {
+            let job_args = jobs_args("--jobs", settings.jobs);
+            let package_args: Vec<String> = match &settings.cargo_package {
+                Some(package) => vec!["-p".to_string(), package.clone()],
+                None => Vec::new(),
+            };
+            // A package with one binary runs it without asking
+            let binary = settings.cargo_bin.clone().or_else(|| {
+                match rust_binaries(&cargo_manifest_dir(path, settings.cargo_package.as_deref()))
+                    .as_slice()
+                {
+                    [binary] => Some(binary.clone()),
+                    _ => None,
+                }
+            });
+            let build_profile =
+                cargo_profile_args(settings.cargo_profile.as_deref().unwrap_or("release"));
+            let mut run_args =
+                cargo_profile_args(settings.cargo_profile.as_deref().unwrap_or("debug"));
+            run_args.extend(package_args.clone());
+            if let Some(binary) = binary {
+                run_args.push("--bin".to_string());
+                run_args.push(binary);
+            }
+            let mut feature_args = Vec::new();
+            if settings.no_default_features {
+                feature_args.push("--no-default-features".to_string());
+            }
+            if !settings.cargo_features.is_empty() {
+                feature_args.push("--features".to_string());
+                feature_args.push(settings.cargo_features.join(","));
+            }
+            let install_path = settings
+                .cargo_package
+                .as_ref()
+                .and_then(|package| {
+                    workspace_members(path)
+                        .into_iter()
+                        .find(|member| member.name == *package)
+                })
+                .map_or_else(|| ".".to_string(), |member| member.dir);
             let mut args = Vec::new();
-            for (flag, value) in selected {
-                match flag.as_str() {
-                    "run" => {
+            for (op, value) in selected {
+                match op {
+                    Operation::Run => {
+                        args.push("run".to_string());
+                        args.extend(job_args.clone());
+                        args.extend(run_args.clone());
+                        args.extend(feature_args.clone());
+                        if !settings.program_args.is_empty() {
+                            args.push("--".to_string());
+                            args.extend(settings.program_args.iter().cloned());
+                        }
+                    }
+                    Operation::RunWithArg { name, .. } => {
                         args.push("run".to_string());
-                        args.push("--bin".to_string());
-                        args.push(detect_rust_binary_name(path)?);
+                        args.extend(job_args.clone());
+                        args.extend(run_args.clone());
+                        args.extend(feature_args.clone());
+                        args.push(format!("--{}", name));
+                        if let Some(val) = value {
+                            args.push(val.to_string());
+                        }
                     }
-                    "build" => {
+                    Operation::Build => {
                         args.push("build".to_string());
-                        args.push("--release".to_string());
+                        args.extend(build_profile.clone());
+                        args.extend(package_args.clone());
+                        args.extend(job_args.clone());
+                        args.extend(feature_args.clone());
                     }
-                    "install" => {
+                    Operation::Install => {
                         args.push("install".to_string());
                         args.push("--path".to_string());
-                        args.push(".".to_string());
+                        args.push(install_path.clone());
+                        args.extend(job_args.clone());
                     }
-                    "test" => {
+                    Operation::Test => {
                         args.push("test".to_string());
+                        args.extend(package_args.clone());
+                        args.extend(job_args.clone());
+                        args.extend(feature_args.clone());
                     }
-                    "check" => {
+                    Operation::Check => {
                         args.push("check".to_string());
+                        args.extend(package_args.clone());
+                        args.extend(job_args.clone());
                     }
-                    "clippy" => {
+                    Operation::Clippy => {
                         args.push("clippy".to_string());
+                        args.extend(package_args.clone());
+                        args.extend(job_args.clone());
                     }
-                    _ => {
-                        if let Some(arg_name) = flag.strip_prefix("run --") {
-                            args.push("run".to_string());
-                            args.push("--bin".to_string());
-                            args.push(detect_rust_binary_name(path)?);
-                            args.push(format!("--{}", arg_name));
-                            if let Some(val) = value {
-                                args.push(val.to_string());
-                            }
-                        }
+                    Operation::Target { name, .. } => {
+                        args.push("run".to_string());
+                        args.extend(job_args.clone());
+                        args.extend(cargo_profile_args(
+                            settings.cargo_profile.as_deref().unwrap_or("debug"),
+                        ));
+                        args.push("--example".to_string());
+                        args.push(name.clone());
+                        args.extend(feature_args.clone());
+                    }
+                    Operation::Format => {
+                        args.push("fmt".to_string());
+                        args.extend(package_args.clone());
                     }
+                    Operation::Clean => args.push("clean".to_string()),
+                    Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit => unsupported(project_type, op)?,
                 }
             }
-            Ok(("cargo".to_string(), args))
+            tools::command("cargo", path, args)
         }
         ProjectType::JavaScript | ProjectType::TypeScript => {
-            let pm = detect_package_manager(path);
-            let mut args = vec![pm];
+            let pm_name = settings
+                .package_manager
+                .clone()
+                .unwrap_or_else(|| detect_package_manager(path));
+            // `bun test` and `bun start` aren't the package's scripts, so bun gets `bun run`
+            let script = |name: &str| {
+                if pm_name == "bun" {
+                    vec!["run".to_string(), name.to_string()]
+                } else {
+                    vec![name.to_string()]
+                }
+            };
+            let mut args = Vec::new();
 
-            for (flag, value) in selected {
-                match flag.as_str() {
-                    "run" => {
-                        args.push("start".to_string());
+            for (op, value) in selected {
+                match op {
+                    Operation::Run => {
+                        args.extend(script("start"));
+                        // npm keeps arguments before `--` for itself
+                        if pm_name == "npm" && !settings.program_args.is_empty() {
+                            args.push("--".to_string());
+                        }
+                        args.extend(settings.program_args.iter().cloned());
+                    }
+                    Operation::RunWithArg { name, .. } => {
+                        args.extend(script("start"));
+                        args.push("--".to_string());
+                        args.push(format!("--{}", name));
+                        if let Some(val) = value {
+                            args.push(val.clone());
+                        }
                     }
-                    "install" => {
+                    Operation::InstallDeps => {
                         args.push("install".to_string());
                     }
-                    "add" => {
+                    Operation::Add => {
                         args.push("add".to_string());
                         if let Some(pkg) = value {
                             args.push(pkg.clone());
                         }
                     }
-                    "test" => {
-                        args.push("test".to_string());
+                    Operation::Test => {
+                        args.extend(script("test"));
                     }
-                    "build" => {
+                    Operation::Build => {
                         args.push("run".to_string());
                         args.push("build".to_string());
                     }
-                    _ => {}
+                    Operation::Target { name, .. } => {
+                        args.push("run".to_string());
+                        args.push(name.clone());
+                    }
+                    // Without a `format` script prettier runs on its own instead
+                    Operation::Format => {
+                        args.push("run".to_string());
+                        args.push("format".to_string());
+                    }
+                    Operation::Audit => args.push("audit".to_string()),
+                    Operation::Install
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Clean => unsupported(project_type, op)?,
+                }
+            }
+            package_manager_command(&pm_name, path, args)
+        }
+        ProjectType::Deno => {
+            let mut args = Vec::new();
+            for (op, _) in selected {
+                match op {
+                    Operation::Test | Operation::Format | Operation::Lint => {
+                        args.push(op.as_flag())
+                    }
+                    Operation::Target { name, .. } => {
+                        args.push("task".to_string());
+                        args.push(name.clone());
+                    }
+                    Operation::Run
+                    | Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
                 }
             }
-            Ok(("npx".to_string(), args))
+            tools::command("deno", path, args)
         }
         ProjectType::Generic => {
             let mut args = Vec::new();
-            for (flag, _) in selected {
-                if flag == "run" {
-                    args.push(detect_entry_point(path)?.to_string());
+            for (op, _) in selected {
+                match op {
+                    Operation::Run => {
+                        args.extend(python_entry_args(path, &run_entry_point(settings, path)?));
+                        args.extend(settings.program_args.iter().cloned());
+                    }
+                    Operation::Target { name, .. } => args.extend(python_entry_args(path, name)),
+                    Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Test
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Format
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
                 }
             }
-            Ok(("python".to_string(), args))
+            tools::command("python", path, args)
         }
-    }
-}
-
-fn detect_binary_name(path: &str) -> anyhow::Result<String> {
-    // Try to read from go.mod
-    let go_mod_path = format!("{}/go.mod", path);
-    if let Ok(content) = std::fs::read_to_string(&go_mod_path) {
-        // Parse module name from "module github.com/user/repo"
-        for line in content.lines() {
-            if line.starts_with("module ") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    // Extract repo name from github.com/user/repo
-                    let module_path = parts[1];
-                    if let Some(repo_name) = module_path.split('/').next_back() {
-                        return Ok(repo_name.to_string());
+        ProjectType::Make => {
+            let mut args = jobs_args("-j", settings.jobs);
+            for (op, _) in selected {
+                match op {
+                    Operation::Target { name, .. } => args.push(name.clone()),
+                    Operation::Run
+                    | Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Test
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Format
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
+                }
+            }
+            tools::command("make", path, args)
+        }
+        ProjectType::Just => {
+            let mut args = Vec::new();
+            for (op, value) in selected {
+                match op {
+                    Operation::Target { name, .. } => {
+                        args.push(name.clone());
+                        if let Some(value) = value {
+                            args.extend(tools::split_words(value)?);
+                        }
                     }
+                    Operation::Run
+                    | Operation::RunWithArg { .. }
+                    | Operation::Build
+                    | Operation::Install
+                    | Operation::InstallDeps
+                    | Operation::InstallPackage
+                    | Operation::UninstallPackage
+                    | Operation::Sync
+                    | Operation::Add
+                    | Operation::Remove
+                    | Operation::Get
+                    | Operation::Test
+                    | Operation::Check
+                    | Operation::Clippy
+                    | Operation::Tidy
+                    | Operation::Generate
+                    | Operation::Format
+                    | Operation::Lint
+                    | Operation::Update
+                    | Operation::Audit
+                    | Operation::Clean => unsupported(project_type, op)?,
                 }
             }
+            tools::command("just", path, args)
         }
-    }
+    }?;
 
-    // Fallback: use directory name
-    let dir_name = std::path::Path::new(path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("app");
+    // Arguments typed at the extra arguments prompt go last, as given
+    args.extend(settings.extra_args.iter().cloned());
+    Ok((executable, args))
+}
+
+/// The commands that update a project's dependencies. Go and uv take two:
+/// `go get -u ./...` then `go mod tidy`, and `uv lock --upgrade` then
+/// `uv sync`.
+fn update_commands(
+    project_type: &ProjectType,
+    path: &str,
+    settings: &BuildSettings,
+) -> anyhow::Result<Option<Vec<Vec<String>>>> {
+    let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+    let steps: Vec<(String, Vec<String>)> = match project_type {
+        ProjectType::Go => vec![
+            tools::command("go", path, words(&["get", "-u", "./..."]))?,
+            tools::command("go", path, words(&["mod", "tidy"]))?,
+        ],
+        ProjectType::Uv => vec![
+            tools::command("uv", path, words(&["--project", path, "lock", "--upgrade"]))?,
+            tools::command("uv", path, words(&["--project", path, "sync"]))?,
+        ],
+        ProjectType::Rust => vec![tools::command("cargo", path, words(&["update"]))?],
+        ProjectType::Poetry => vec![tools::command("poetry", path, words(&["update"]))?],
+        ProjectType::JavaScript | ProjectType::TypeScript => {
+            let pm_name = settings
+                .package_manager
+                .clone()
+                .unwrap_or_else(|| detect_package_manager(path));
+            // yarn calls it upgrade; its own update is an alias of install
+            let update = if pm_name == "yarn" {
+                "upgrade"
+            } else {
+                "update"
+            };
+            vec![package_manager_command(&pm_name, path, words(&[update]))?]
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(
+        steps
+            .into_iter()
+            .map(|(executable, args)| std::iter::once(executable).chain(args).collect())
+            .collect(),
+    ))
+}
 
-    Ok(dir_name.to_string())
+/// One of the project tool's own operations as a command of its own, without
+/// the extra arguments, which go to the main command.
+fn own_step(
+    project_type: &ProjectType,
+    path: &str,
+    op: &Operation,
+    settings: &BuildSettings,
+) -> anyhow::Result<Vec<String>> {
+    let settings = BuildSettings {
+        extra_args: Vec::new(),
+        ..settings.clone()
+    };
+    let (executable, args) =
+        build_project_command(project_type, path, &[(op.clone(), None)], &settings)?;
+    Ok(std::iter::once(executable).chain(args).collect())
 }
 
-fn extract_binary_path_from_args(args: &[String]) -> anyhow::Result<String> {
-    // Find the -o flag and get the next argument
-    for (i, arg) in args.iter().enumerate() {
-        if arg == "-o" && i + 1 < args.len() {
-            return Ok(args[i + 1].clone());
+/// The formatter command for projects whose own tool doesn't format: ruff
+/// or black for Python, prettier for JavaScript without a `format` script.
+fn external_formatter(
+    project_type: &ProjectType,
+    path: &str,
+    settings: &BuildSettings,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let words = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+    match project_type {
+        _ if is_python(project_type) => {
+            for (tool, args) in [("ruff", &["format"][..]), ("black", &["."][..])] {
+                if let Some(program) = python_tool(path, settings, tool) {
+                    let mut command = vec![program];
+                    command.extend(words(args));
+                    return Ok(Some(command));
+                }
+            }
+            anyhow::bail!(
+                "fmt needs ruff or black, and neither is in the project's venv or on PATH; install one with `pip install ruff`"
+            )
+        }
+        ProjectType::JavaScript | ProjectType::TypeScript
+            if !has_package_script(path, "format") =>
+        {
+            let pm_name = settings
+                .package_manager
+                .clone()
+                .unwrap_or_else(|| detect_package_manager(path));
+            Ok(Some(if pm_name == "bun" {
+                words(&["bun", "x", "prettier", "--write", "."])
+            } else {
+                words(&["npx", "prettier", "--write", "."])
+            }))
         }
+        _ => Ok(None),
     }
-    anyhow::bail!("Could not find output path in build arguments");
 }
 
-fn detect_package_manager(path: &str) -> String {
-    // Check for lock files to determine package manager
-    let yarn_lock = format!("{}/yarn.lock", path);
-    let pnpm_lock = format!("{}/pnpm-lock.yaml", path);
-    let _package_lock = format!("{}/package-lock.json", path);
+/// The command that scans a project's dependencies for known vulnerabilities,
+/// for ecosystems whose auditor is a tool of its own. Auditors that aren't
+/// installed fail with how to install them rather than an OS error.
+fn external_auditor(
+    project_type: &ProjectType,
+    path: &str,
+    settings: &BuildSettings,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let installed = |tool: &str| resolve::resolve(tool).selected().is_some();
+    let command = |tool: &str, args: &[&str]| -> anyhow::Result<Vec<String>> {
+        let args = args.iter().map(|word| word.to_string()).collect();
+        let (executable, args) = tools::command(tool, path, args)?;
+        Ok(std::iter::once(executable).chain(args).collect())
+    };
+    match project_type {
+        // cargo-deny is used when the project has configured it
+        ProjectType::Rust
+            if Path::new(path).join("deny.toml").is_file() && installed("cargo-deny") =>
+        {
+            command("cargo", &["deny", "check", "advisories"]).map(Some)
+        }
+        ProjectType::Rust if installed("cargo-audit") => command("cargo", &["audit"]).map(Some),
+        ProjectType::Rust => {
+            anyhow::bail!("audit needs cargo-audit; install it with `cargo install cargo-audit`")
+        }
+        ProjectType::Go => {
+            // `go install` puts it in GOBIN, which may not be on PATH
+            let in_go_bin =
+                go_install_dir()?.join(format!("govulncheck{}", std::env::consts::EXE_SUFFIX));
+            let program = if installed("govulncheck") {
+                "govulncheck".to_string()
+            } else if in_go_bin.is_file() {
+                in_go_bin.to_string_lossy().into_owned()
+            } else {
+                anyhow::bail!(
+                    "audit needs govulncheck; install it with `go install golang.org/x/vuln/cmd/govulncheck@latest`"
+                )
+            };
+            Ok(Some(vec![program, "./...".to_string()]))
+        }
+        _ if is_python(project_type) => {
+            // The venv's own pip-audit checks what's installed there
+            if let Some(program) = python_tool(path, settings, "pip-audit")
+                .filter(|program| program.as_str() != "pip-audit")
+            {
+                return Ok(Some(vec![program]));
+            }
+            if !installed("pip-audit") {
+                anyhow::bail!("audit needs pip-audit; install it with `pip install pip-audit`")
+            }
+            let mut command = vec!["pip-audit".to_string()];
+            if Path::new(path).join("requirements.txt").is_file() {
+                command.extend(["-r".to_string(), "requirements.txt".to_string()]);
+            } else {
+                command.push(".".to_string());
+            }
+            Ok(Some(command))
+        }
+        _ => Ok(None),
+    }
+}
 
-    if std::path::Path::new(&yarn_lock).exists() {
-        "yarn".to_string()
-    } else if std::path::Path::new(&pnpm_lock).exists() {
-        "pnpm".to_string()
+/// A Python tool from the project's virtualenv, or else its name when it's
+/// on `PATH`.
+fn python_tool(path: &str, settings: &BuildSettings, tool: &str) -> Option<String> {
+    settings
+        .venv_dir
+        .clone()
+        .or_else(|| find_venv(path))
+        .map(|dir| venv_program(&Path::new(path).join(dir), tool))
+        .filter(|program| Path::new(program).is_file())
+        .or_else(|| resolve::resolve(tool).selected().map(|_| tool.to_string()))
+}
+
+/// `pip install a b` for the packages typed at the prompt.
+fn pip_command(pip: &str, action: &str, packages: &str) -> Vec<String> {
+    [pip, action]
+        .into_iter()
+        .chain(packages.split_whitespace())
+        .map(str::to_string)
+        .collect()
+}
+
+fn go_tag_args(tags: &[String]) -> Vec<String> {
+    if tags.is_empty() {
+        Vec::new()
     } else {
-        "npm".to_string() // default
+        vec!["-tags".to_string(), tags.join(",")]
     }
 }
 
-fn detect_rust_binary_name(path: &str) -> anyhow::Result<String> {
-    // Parse from Cargo.toml [package] name
-    let cargo_toml_path = format!("{}/Cargo.toml", path);
-    if let Ok(content) = std::fs::read_to_string(&cargo_toml_path) {
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("name = ") {
-                let name_part = trimmed.split_once('=').unwrap().1.trim();
-                let name = name_part.trim_matches('"').trim_matches('\'');
-                return Ok(name.to_string());
-            }
-        }
+fn jobs_args(flag: &str, jobs: Option<usize>) -> Vec<String> {
+    match jobs {
+        Some(jobs) => vec![flag.to_string(), jobs.to_string()],
+        None => Vec::new(),
     }
+}
 
-    // Fallback to directory name
-    let dir_name = std::path::Path::new(path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("app");
+/// Scripts from package.json, described by their command. `start`, `test`,
+/// `build` and `format` are left out since run, test, build and fmt already
+/// cover them.
+fn detect_package_scripts(path: &str) -> Vec<Operation> {
+    package_scripts(path)
+        .into_iter()
+        .filter(|(name, _)| !["start", "test", "build", "format"].contains(&name.as_str()))
+        .map(|(name, body)| Operation::Target {
+            name,
+            description: body.as_str().map(str::to_string),
+            parameters: Vec::new(),
+        })
+        .collect()
+}
 
-    Ok(dir_name.to_string())
+fn package_scripts(path: &str) -> serde_json::Map<String, serde_json::Value> {
+    std::fs::read_to_string(Path::new(path).join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|json| json.get("scripts")?.as_object().cloned())
+        .unwrap_or_default()
 }
 
-fn detect_cli_args(path: &str) -> Vec<CliArg> {
-    let main_rs_path = format!("{}/src/main.rs", path);
-    let mut args = Vec::new();
+fn has_package_script(path: &str, name: &str) -> bool {
+    package_scripts(path).contains_key(name)
+}
 
-    if let Ok(content) = std::fs::read_to_string(&main_rs_path) {
-        if !content.contains("clap::Parser") && !content.contains("derive(Parser)") {
-            return args; // Not using clap
-        }
+/// Where a project's virtualenv may live, relative to its root: the root
+/// itself, or a `.venv`/`venv` directory from `python -m venv .venv`.
+pub const VENV_DIRS: &[&str] = &[".", ".venv", "venv"];
 
-        let lines: Vec<&str> = content.lines().collect();
-        let mut i = 0;
-        while i < lines.len() {
-            let line = lines[i].trim();
-            if line.starts_with("#[arg") {
-                // Parse the arg attribute
-                let mut long = None;
-                let short = None;
+/// The activation script that marks `venv_dir` as a venv, relative to the root.
+pub fn venv_marker(venv_dir: &str) -> String {
+    if venv_dir == "." {
+        VENV_ACTIVATE.to_string()
+    } else {
+        Path::new(venv_dir)
+            .join(VENV_ACTIVATE)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
 
-                // Simple parse: look for long = "..." or long
-                if line.contains("long") {
-                    if let Some(start) = line.find("long = \"") {
-                        if let Some(end) = line[start + 8..].find('"') {
-                            long = Some(line[start + 8..start + 8 + end].to_string());
-                        }
-                    } else if line.contains("long)") || line == "#[arg(long)]" {
-                        // Next field name
+/// The first of `VENV_DIRS` that holds a venv.
+pub fn find_venv(path: &str) -> Option<String> {
+    VENV_DIRS
+        .iter()
+        .find(|dir| Path::new(path).join(venv_marker(dir)).exists())
+        .map(|dir| dir.to_string())
+}
+
+/// The config files Deno reads, in the order it looks for them.
+pub const DENO_CONFIGS: &[&str] = &["deno.json", "deno.jsonc"];
+
+/// Tasks from deno.json, described by their command or, for tasks written
+/// as objects, their `description`.
+fn detect_deno_tasks(path: &str) -> Vec<Operation> {
+    let tasks = DENO_CONFIGS
+        .iter()
+        .find_map(|name| std::fs::read_to_string(Path::new(path).join(name)).ok())
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&strip_jsonc(&content)).ok())
+        .and_then(|json| json.get("tasks")?.as_object().cloned())
+        .unwrap_or_default();
+
+    tasks
+        .into_iter()
+        .map(|(name, task)| {
+            let description = match &task {
+                serde_json::Value::String(command) => Some(command.clone()),
+                serde_json::Value::Object(fields) => fields
+                    .get("description")
+                    .or_else(|| fields.get("command"))
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string),
+                _ => None,
+            };
+            Operation::Target {
+                name,
+                description,
+                parameters: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Turn JSONC into JSON by dropping `//` and `/* */` comments and trailing
+/// commas, leaving strings alone.
+fn strip_jsonc(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
                     }
+                    previous = next;
                 }
+            }
+            _ => output.push(c),
+        }
+    }
 
-                // Find the field name
-                i += 1;
-                while i < lines.len() {
-                    let field_line = lines[i].trim();
-                    if !field_line.starts_with("//") && !field_line.is_empty() {
-                        if let Some(colon_pos) = field_line.find(':') {
-                            let name = field_line[..colon_pos].trim().to_string();
-                            let type_part = field_line[colon_pos + 1..].trim();
+    let trailing_comma = regex::Regex::new(r",(\s*[}\]])").expect("comma pattern is valid");
+    trailing_comma.replace_all(&output, "$1").into_owned()
+}
 
-                            // Determine if requires value: if Option<T> or T, assume requires unless bool
-                            let requires_value = !type_part.starts_with("bool");
+/// The file names GNU make looks for, in the order it tries them.
+pub const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
 
-                            if let Some(l) = long.clone() {
-                                args.push(CliArg {
-                                    name,
+/// Targets defined in the project's Makefile, in file order, each with the
+/// `## comment` written after it. Special targets such as `.PHONY`, pattern
+/// rules and variable assignments are skipped.
+fn detect_make_targets(path: &str) -> Vec<Operation> {
+    let Some(content) = MAKEFILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(Path::new(path).join(name)).ok())
+    else {
+        return Vec::new();
+    };
+
+    // `name:` or `name::`, but not `name := value`
+    let rule =
+        regex::Regex::new(r"^([A-Za-z0-9_.-]+)::?(?:$|[^:=])").expect("rule pattern is valid");
+    let mut targets: Vec<Operation> = Vec::new();
+    for line in content.lines() {
+        let Some(captures) = rule.captures(line) else {
+            continue;
+        };
+        let name = &captures[1];
+        if name.starts_with('.') || targets.iter().any(|seen| seen.as_flag() == name) {
+            continue;
+        }
+        let description = line
+            .split_once("##")
+            .map(|(_, comment)| comment.trim().to_string())
+            .filter(|comment| !comment.is_empty());
+        targets.push(Operation::Target {
+            name: name.to_string(),
+            description,
+            parameters: Vec::new(),
+        });
+    }
+    targets
+}
+
+/// The file names just looks for. It matches them case-insensitively, so
+/// these are the common spellings.
+pub const JUSTFILES: &[&str] = &["justfile", "Justfile", ".justfile"];
+
+/// Recipes in the project's justfile, as `just --list` shows them, or read
+/// from the file itself when just isn't installed. Private recipes are left out.
+fn detect_just_recipes(path: &str) -> Vec<Operation> {
+    let listed = tools::command(
+        "just",
+        path,
+        vec!["--list".to_string(), "--unsorted".to_string()],
+    )
+    .ok()
+    .and_then(|(program, args)| {
+        std::process::Command::new(program)
+            .args(args)
+            .current_dir(path)
+            .output()
+            .ok()
+    })
+    .filter(|output| output.status.success());
+
+    match listed {
+        Some(output) => listed_recipes(&String::from_utf8_lossy(&output.stdout)),
+        None => parse_justfile(path),
+    }
+}
+
+/// Recipes from `just --list` output.
+fn listed_recipes(listing: &str) -> Vec<Operation> {
+    listing
+        .lines()
+        // Recipes are indented under "Available recipes:", between `[group]` headings
+        .filter(|line| line.starts_with(char::is_whitespace) && !line.trim_start().starts_with('['))
+        .filter_map(|line| {
+            let (signature, comment) = match line.split_once(" # ") {
+                Some((signature, comment)) => (signature, Some(comment.trim())),
+                None => (line, None),
+            };
+            // Older versions list aliases on their own line
+            if comment.is_some_and(|comment| comment.starts_with("alias for")) {
+                return None;
+            }
+            just_recipe(signature.trim(), comment.map(str::to_string))
+        })
+        .collect()
+}
+
+/// Recipes read straight from the justfile, each described by the comment
+/// on the line above it, as just does.
+fn parse_justfile(path: &str) -> Vec<Operation> {
+    let Some(content) = JUSTFILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(Path::new(path).join(name)).ok())
+    else {
+        return Vec::new();
+    };
+
+    // `name params...:`, but not `name := value` or an indented recipe body
+    let header = regex::Regex::new(r#"^@?([A-Za-z_][A-Za-z0-9_-]*)((?:\s+[^:]+)?)\s*:(?:$|[^=])"#)
+        .expect("recipe pattern is valid");
+    let mut recipes = Vec::new();
+    let mut comment: Option<String> = None;
+    let mut private = false;
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim().to_string()).filter(|text| !text.starts_with('!'));
+            continue;
+        }
+        if line.starts_with("[private") {
+            private = true;
+            continue;
+        }
+        if line.starts_with('[') {
+            continue;
+        }
+        if let Some(captures) = header.captures(line) {
+            let signature = format!("{}{}", &captures[1], &captures[2]);
+            if !private && !["alias", "set", "export"].contains(&&captures[1]) {
+                recipes.extend(just_recipe(&signature, comment.take()));
+            }
+        }
+        comment = None;
+        private = false;
+    }
+    recipes
+}
+
+/// A recipe from its signature, e.g. `build target="debug" *flags`. Names
+/// starting with `_` are private.
+fn just_recipe(signature: &str, description: Option<String>) -> Option<Operation> {
+    let parameter =
+        regex::Regex::new(r#"[+*]?\$?[A-Za-z_][A-Za-z0-9_-]*(?:=(?:"[^"]*"|'[^']*'|\S+))?"#)
+            .expect("parameter pattern is valid");
+    let (name, rest) = signature
+        .split_once(char::is_whitespace)
+        .unwrap_or((signature, ""));
+    if name.is_empty() || name.starts_with('_') {
+        return None;
+    }
+    Some(Operation::Target {
+        name: name.to_string(),
+        description: description.filter(|description| !description.is_empty()),
+        parameters: parameter
+            .find_iter(rest)
+            .map(|found| found.as_str().to_string())
+            .collect(),
+    })
+}
+
+/// Whether any Go source file carries a `//go:generate` directive.
+fn detect_go_generate(path: &str) -> bool {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_str().unwrap_or("");
+            entry.depth() == 0 || !(name.starts_with('.') || name == "vendor")
+        })
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "go"))
+        .any(|entry| {
+            std::fs::read_to_string(entry.path())
+                .map(|content| content.contains("//go:generate"))
+                .unwrap_or(false)
+        })
+}
+
+fn unsupported(project_type: &ProjectType, operation: &Operation) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "'{}' is not supported for {} projects",
+        operation.as_flag(),
+        project_type
+    )
+}
+
+/// Main packages of a Go module: the root when it has one, then each
+/// `cmd/<name>` directory that does, as `./cmd/<name>`.
+pub fn go_main_packages(path: &str) -> Vec<String> {
+    let root = Path::new(path);
+    let mut packages = Vec::new();
+    if is_go_main_package(root) {
+        packages.push(".".to_string());
+    }
+    packages.extend(go_command_packages(root));
+    packages
+}
+
+/// The `cmd/<name>` main packages, sorted by name.
+fn go_command_packages(root: &Path) -> Vec<String> {
+    let mut commands: Vec<String> = std::fs::read_dir(root.join("cmd"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| is_go_main_package(&entry.path()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    commands.sort();
+    commands
+        .into_iter()
+        .map(|name| format!("./cmd/{}", name))
+        .collect()
+}
+
+/// The module's only `cmd/<name>` package when the root isn't a main package.
+fn single_go_command(path: &str) -> Option<String> {
+    match go_main_packages(path).as_slice() {
+        [package] if package != "." => Some(package.clone()),
+        _ => None,
+    }
+}
+
+/// Whether a directory holds a Go file (other than a test) in `package main`.
+fn is_go_main_package(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| {
+            file.extension().is_some_and(|ext| ext == "go")
+                && !file.to_string_lossy().ends_with("_test.go")
+        })
+        .any(|file| {
+            std::fs::read_to_string(&file)
+                .is_ok_and(|content| content.lines().any(|line| line.trim() == "package main"))
+        })
+}
+
+/// The name a Go main package installs as: the `cmd/<name>` directory, or
+/// the module's last path segment for the root package.
+fn go_binary_name(path: &str, package: Option<&str>) -> anyhow::Result<String> {
+    let package = package
+        .map(str::to_string)
+        .or_else(|| single_go_command(path));
+    match package
+        .as_deref()
+        .and_then(|package| package.rsplit('/').next())
+    {
+        Some(name) if name != "." => Ok(name.to_string()),
+        _ => detect_binary_name(path),
+    }
+}
+
+fn detect_binary_name(path: &str) -> anyhow::Result<String> {
+    // Try to read from go.mod
+    let go_mod_path = Path::new(path).join("go.mod");
+    if let Ok(content) = std::fs::read_to_string(&go_mod_path) {
+        // Parse module name from "module github.com/user/repo"
+        for line in content.lines() {
+            if line.starts_with("module ") {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 2 {
+                    // Extract repo name from github.com/user/repo
+                    let module_path = parts[1];
+                    if let Some(repo_name) = module_path.split('/').next_back() {
+                        return Ok(repo_name.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // Fallback: use directory name
+    let dir_name = Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("app");
+
+    Ok(sanitize_binary_name(dir_name))
+}
+
+/// Turn a directory name into something usable as an executable name, so a
+/// project at `My App (2024)` builds `My-App-2024`.
+fn sanitize_binary_name(dir_name: &str) -> String {
+    let mut name = String::new();
+    for c in dir_name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+
+    let name = name.trim_matches(['-', '.']);
+    if name.is_empty() {
+        "app".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn extract_binary_path_from_args(args: &[String]) -> anyhow::Result<String> {
+    // Find the -o flag and get the next argument
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "-o" && i + 1 < args.len() {
+            return Ok(args[i + 1].clone());
+        }
+    }
+    anyhow::bail!("Could not find output path in build arguments");
+}
+
+pub fn detect_package_manager(path: &str) -> String {
+    detect_package_manager_traced(path, &mut DetectionTrace::default())
+}
+
+pub fn detect_package_manager_traced(path: &str, trace: &mut DetectionTrace) -> String {
+    // Check for lock files to determine package manager
+    let root = Path::new(path);
+
+    if trace.check(root, "yarn.lock") {
+        trace.rule("yarn", true, "yarn.lock is checked first");
+        "yarn".to_string()
+    } else if trace.check(root, "pnpm-lock.yaml") {
+        trace.rule("pnpm", true, "pnpm-lock.yaml");
+        "pnpm".to_string()
+    } else if let Some(lockfile) = ["bun.lock", "bun.lockb"]
+        .into_iter()
+        .find(|lockfile| trace.check(root, lockfile))
+    {
+        trace.rule("bun", true, lockfile);
+        "bun".to_string()
+    } else {
+        trace.rule(
+            "npm",
+            true,
+            "no yarn, pnpm or bun lockfile, using the default",
+        );
+        "npm".to_string() // default
+    }
+}
+
+/// The package manager's command, started through `npx` unless it's bun or
+/// overridden.
+fn package_manager_command(
+    pm_name: &str,
+    path: &str,
+    args: Vec<String>,
+) -> anyhow::Result<(String, Vec<String>)> {
+    let pm = tools::resolve(pm_name, path)?;
+    if pm.overridden || !runs_through_npx(pm_name) {
+        Ok(pm.command(args))
+    } else {
+        let mut npx_args = vec![pm.program];
+        npx_args.extend(args);
+        Ok(("npx".to_string(), npx_args))
+    }
+}
+
+/// Whether a package manager is started through `npx`, which fetches it if
+/// needed. bun is a runtime of its own and is run directly.
+pub fn runs_through_npx(package_manager: &str) -> bool {
+    package_manager != "bun"
+}
+
+/// The package manager to remember in a project's cache entry, for
+/// JavaScript and TypeScript projects.
+pub fn cached_package_manager(project_type: &ProjectType, path: &str) -> Option<String> {
+    matches!(
+        project_type,
+        ProjectType::JavaScript | ProjectType::TypeScript
+    )
+    .then(|| detect_package_manager(path))
+}
+
+/// The package name from Cargo.toml, which `cargo install` records the
+/// install under, falling back to the directory name.
+fn rust_package_name(path: &str) -> String {
+    read_cargo_manifest(Path::new(path))
+        .and_then(|manifest| package_name(&manifest))
+        .unwrap_or_else(|| {
+            let dir_name = Path::new(path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("app");
+            sanitize_binary_name(dir_name)
+        })
+}
+
+/// The binaries a Cargo package builds: its `[[bin]]` entries plus, unless
+/// `autobins = false`, `src/main.rs` (named after the package) and each
+/// file or directory under `src/bin/`.
+pub fn rust_binaries(dir: &Path) -> Vec<String> {
+    rust_targets(dir, "bin", "autobins", Some("src/main.rs"), "src/bin")
+}
+
+/// The examples of the package at `dir`, from `[[example]]` and `examples/`.
+fn rust_examples(dir: &Path) -> Vec<String> {
+    rust_targets(dir, "example", "autoexamples", None, "examples")
+}
+
+fn rust_targets(
+    dir: &Path,
+    kind: &str,
+    auto_key: &str,
+    main_file: Option<&str>,
+    auto_dir: &str,
+) -> Vec<String> {
+    let Some(manifest) = read_cargo_manifest(dir) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    let mut declared_paths = Vec::new();
+    for target in manifest
+        .get(kind)
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(name) = target.get("name").and_then(toml::Value::as_str) {
+            names.push(name.to_string());
+        }
+        if let Some(path) = target.get("path").and_then(toml::Value::as_str) {
+            declared_paths.push(path.trim_start_matches("./").to_string());
+        }
+    }
+
+    let auto = manifest
+        .get("package")
+        .and_then(|package| package.get(auto_key))
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(true);
+    if !auto {
+        return names;
+    }
+    if let Some(main_file) = main_file
+        && dir.join(main_file).is_file()
+        && !declared_paths.iter().any(|path| path == main_file)
+        && let Some(name) = package_name(&manifest)
+    {
+        names.push(name);
+    }
+    let mut discovered: Vec<(String, String)> = std::fs::read_dir(dir.join(auto_dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                entry_path.join("main.rs").is_file().then(|| {
+                    (
+                        file_name.clone(),
+                        format!("{}/{}/main.rs", auto_dir, file_name),
+                    )
+                })
+            } else {
+                let stem = file_name.strip_suffix(".rs")?;
+                Some((stem.to_string(), format!("{}/{}", auto_dir, file_name)))
+            }
+        })
+        .filter(|(_, path)| !declared_paths.contains(path))
+        .collect();
+    discovered.sort();
+    for (name, _) in discovered {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// `cargo run --example` operations for the examples of the project, or of
+/// every workspace member (cargo finds an example by name across them).
+fn detect_rust_examples(path: &str) -> Vec<Operation> {
+    let root = Path::new(path);
+    let members = workspace_members(path);
+    let dirs: Vec<std::path::PathBuf> = if members.is_empty() {
+        vec![root.to_path_buf()]
+    } else {
+        members
+            .iter()
+            .map(|member| root.join(&member.dir))
+            .collect()
+    };
+
+    let mut examples: Vec<String> = Vec::new();
+    for dir in dirs {
+        for name in rust_examples(&dir) {
+            if !examples.contains(&name) {
+                examples.push(name);
+            }
+        }
+    }
+    examples
+        .into_iter()
+        .map(|name| Operation::Target {
+            name,
+            description: None,
+            parameters: Vec::new(),
+        })
+        .collect()
+}
+
+/// A package of a Cargo workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceMember {
+    pub name: String,
+    /// Directory relative to the workspace root, e.g. `crates/cli`
+    pub dir: String,
+}
+
+impl std::fmt::Display for WorkspaceMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.dir)
+    }
+}
+
+/// The packages of the Cargo workspace at `path`, from `[workspace] members`
+/// minus `exclude`, plus the root package if there is one. Empty when
+/// Cargo.toml has no `[workspace]`.
+pub fn workspace_members(path: &str) -> Vec<WorkspaceMember> {
+    let root = Path::new(path);
+    let Some(manifest) = read_cargo_manifest(root) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(|item| item.trim_end_matches('/').to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let excluded = patterns("exclude");
+
+    let mut members = Vec::new();
+    if let Some(name) = package_name(&manifest) {
+        members.push(WorkspaceMember {
+            name,
+            dir: ".".to_string(),
+        });
+    }
+    for pattern in patterns("members") {
+        for dir in expand_member_glob(root, &pattern) {
+            if excluded.contains(&dir) || members.iter().any(|member| member.dir == dir) {
+                continue;
+            }
+            if let Some(name) = read_cargo_manifest(&root.join(&dir)).and_then(|m| package_name(&m))
+            {
+                members.push(WorkspaceMember { name, dir });
+            }
+        }
+    }
+    members
+}
+
+/// The directory holding the Cargo.toml of `package`, or the project root
+/// when no workspace member is chosen.
+fn cargo_manifest_dir(path: &str, package: Option<&str>) -> std::path::PathBuf {
+    let root = Path::new(path);
+    package
+        .and_then(|package| {
+            workspace_members(path)
+                .into_iter()
+                .find(|member| member.name == package)
+        })
+        .map_or_else(|| root.to_path_buf(), |member| root.join(member.dir))
+}
+
+/// The `[features]` a Cargo.toml declares.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CargoFeatures {
+    /// Every feature except `default`, in manifest order
+    pub names: Vec<String>,
+    pub has_default: bool,
+}
+
+impl CargoFeatures {
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && !self.has_default
+    }
+}
+
+pub fn cargo_features(dir: &Path) -> CargoFeatures {
+    let Some(table) = std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml_edit::DocumentMut>().ok())
+        .and_then(|doc| {
+            doc.get("features")?.as_table_like().map(|t| {
+                t.iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect::<Vec<_>>()
+            })
+        })
+    else {
+        return CargoFeatures::default();
+    };
+    CargoFeatures {
+        has_default: table.iter().any(|name| name == "default"),
+        names: table.into_iter().filter(|name| name != "default").collect(),
+    }
+}
+
+fn read_cargo_manifest(dir: &Path) -> Option<toml::Value> {
+    let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    toml::from_str(&content).ok()
+}
+
+fn package_name(manifest: &toml::Value) -> Option<String> {
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Directories matching a workspace members entry such as `crates/*`, where
+/// `*` and `?` match within a single path component.
+fn expand_member_glob(root: &Path, pattern: &str) -> Vec<String> {
+    let mut dirs = vec![String::new()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        let join = |dir: &str, name: &str| {
+            if dir.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", dir, name)
+            }
+        };
+        if !component.contains(['*', '?']) {
+            dirs = dirs.iter().map(|dir| join(dir, component)).collect();
+            continue;
+        }
+        let wildcard = regex::escape(component)
+            .replace(r"\*", "[^/]*")
+            .replace(r"\?", "[^/]");
+        let Ok(matcher) = regex::Regex::new(&format!("^{}$", wildcard)) else {
+            return Vec::new();
+        };
+        let mut matched = Vec::new();
+        for dir in &dirs {
+            let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| matcher.is_match(name))
+                .collect();
+            names.sort();
+            matched.extend(names.iter().map(|name| join(dir, name)));
+        }
+        dirs = matched;
+    }
+    dirs.retain(|dir| !dir.is_empty() && root.join(dir).join("Cargo.toml").is_file());
+    dirs
+}
+
+fn detect_cli_args(path: &str) -> Vec<CliArg> {
+    let main_rs_path = Path::new(path).join("src/main.rs");
+    let mut args = Vec::new();
+
+    if let Ok(content) = std::fs::read_to_string(&main_rs_path) {
+        if !content.contains("clap::Parser") && !content.contains("derive(Parser)") {
+            return args; // Not using clap
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].trim();
+            if line.starts_with("#[arg") {
+                // Parse the arg attribute
+                let mut long = None;
+                let short = None;
+
+                // Simple parse: look for long = "..." or long
+                if line.contains("long") {
+                    if let Some(start) = line.find("long = \"") {
+                        if let Some(end) = line[start + 8..].find('"') {
+                            long = Some(line[start + 8..start + 8 + end].to_string());
+                        }
+                    } else if line.contains("long)") || line == "#[arg(long)]" {
+                        // Next field name
+                    }
+                }
+
+                // Find the field name
+                i += 1;
+                while i < lines.len() {
+                    let field_line = lines[i].trim();
+                    if !field_line.starts_with("//") && !field_line.is_empty() {
+                        if let Some(colon_pos) = field_line.find(':') {
+                            let name = field_line[..colon_pos].trim().to_string();
+                            let type_part = field_line[colon_pos + 1..].trim();
+
+                            // Determine if requires value: if Option<T> or T, assume requires unless bool
+                            let requires_value = !type_part.starts_with("bool");
+
+                            if let Some(l) = long.clone() {
+                                args.push(CliArg {
+                                    name,
                                     long: Some(l),
                                     short,
                                     requires_value,
@@ -636,56 +3065,1812 @@ fn detect_cli_args(path: &str) -> Vec<CliArg> {
     args
 }
 
-fn execute_go_build_with_install(
+/// Build, then move the binary into place. A failed build is returned as
+/// its exit status; a failed install is an error.
+async fn execute_go_build_with_install(
     executable: &str,
     args: &[String],
     path: &str,
-) -> anyhow::Result<()> {
-    use std::process::Command;
+    install_name: &str,
+) -> anyhow::Result<ExitStatus> {
+    use tokio::process::Command;
 
-    // Step 1: Build the binary
+    // Step 1: Build the binary into its own temp directory
+    let binary_path = extract_binary_path_from_args(args)?;
+    let temp_dir = Path::new(&binary_path).parent().map(Path::to_path_buf);
+    if let Some(dir) = &temp_dir {
+        std::fs::create_dir_all(dir)?;
+    }
     println!("Building Go application...");
     let mut build_cmd = Command::new(executable);
     build_cmd.args(args).current_dir(path);
 
-    let build_status = build_cmd.status()?;
+    let build_status = capture::run(&mut build_cmd, &display_command(executable, args)).await?;
     if !build_status.success() {
-        anyhow::bail!("Build failed with exit code: {:?}", build_status.code());
+        println!("Build failed with exit code: {:?}", build_status.code());
+        return Ok(build_status);
     }
 
-    // Step 2: Detect the binary path from the build command
-    let binary_path = extract_binary_path_from_args(args)?;
-
-    // Step 3: Determine final installation name
-    let install_name = detect_binary_name(path)?;
-    let install_path = format!("/usr/bin/{}", install_name);
+    // Step 2: Choose the install directory; the system one is opt-in
+    let system_dir = match SYSTEM_INSTALL_DIR {
+        Some(dir) if config::user_flag("go", "system_install", false)? => ui::confirm(
+            &format!("Install {} to {} with sudo?", install_name, dir),
+            false,
+        )?
+        .then(|| std::path::PathBuf::from(dir)),
+        _ => None,
+    };
+    let use_sudo = system_dir.is_some();
+    let install_dir = match system_dir {
+        Some(dir) => dir,
+        None => go_install_dir()?,
+    };
+    let install_path = install_dir
+        .join(format!("{}{}", install_name, std::env::consts::EXE_SUFFIX))
+        .to_string_lossy()
+        .into_owned();
 
-    // Step 4: Check if binary exists before moving
-    if !std::path::Path::new(&binary_path).exists() {
+    // Step 3: Check if binary exists before moving
+    if !Path::new(&binary_path).exists() {
         anyhow::bail!("Built binary not found at: {}", binary_path);
     }
 
-    // Step 5: Move to /usr/bin (requires sudo)
+    // Step 4: Move into the install directory
     println!("Installing {} to {}...", install_name, install_path);
-    let install_status = Command::new("sudo")
-        .args(["mv", &binary_path, &install_path])
-        .status()?;
-
-    if !install_status.success() {
-        anyhow::bail!("Installation failed. You may need to run with sudo or check permissions.");
+    if use_sudo {
+        let install_status = Command::new("sudo")
+            .args(["mv", &binary_path, &install_path])
+            .status()
+            .await?;
+        if !install_status.success() {
+            anyhow::bail!(
+                "Installation failed. You may need to run with sudo or check permissions."
+            );
+        }
+    } else {
+        std::fs::create_dir_all(&install_dir)?;
+        // The temp directory may be on another drive, where a rename fails
+        if std::fs::rename(&binary_path, &install_path).is_err() {
+            std::fs::copy(&binary_path, &install_path)?;
+            std::fs::remove_file(&binary_path)?;
+        }
+    }
+    if let Some(dir) = &temp_dir {
+        let _ = std::fs::remove_dir(dir);
     }
 
-    // Step 6: Verify installation
-    let which_output = Command::new("which").arg(&install_name).output()?;
-    if which_output.status.success() {
-        println!(
-            "✅ Successfully installed {} and added to PATH!",
-            install_name
-        );
+    installed::record(InstalledEntry::new(
+        install_name,
+        InstallKind::Go,
+        &install_path,
+        path,
+    ))?;
+
+    // Step 5: Verify installation
+    if on_path(&install_dir) {
+        ui::status(&format!("✅ Successfully installed {}", install_name));
         println!("You can now run: {}", install_name);
     } else {
-        println!("⚠️  Binary installed but may not be in PATH. Try: export PATH=$PATH:/usr/bin");
+        ui::status(&format!(
+            "⚠️  Installed {}, but {} isn't on PATH. Add it with:",
+            install_name,
+            install_dir.display()
+        ));
+        println!("  {}", path_export_line(&install_dir));
     }
 
-    Ok(())
+    Ok(build_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary project holding the given files.
+    fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        write_files(dir.path(), files);
+        dir
+    }
+
+    fn write_files(root: &Path, files: &[(&str, &str)]) {
+        for (relative, content) in files {
+            let file = root.join(relative);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+        }
+    }
+
+    /// The smallest project of each type that every operation can run in.
+    fn fixture_for(project_type: &ProjectType) -> tempfile::TempDir {
+        fixture(&fixture_files(project_type))
+    }
+
+    fn fixture_files(project_type: &ProjectType) -> Vec<(&'static str, &'static str)> {
+        match project_type {
+            ProjectType::Uv => vec![
+                (
+                    "pyproject.toml",
+                    "[project]\nname = \"demo\"\n[project.scripts]\ndemo = \"demo:main\"\n",
+                ),
+                ("uv.lock", ""),
+            ],
+            ProjectType::Poetry => vec![
+                ("pyproject.toml", "[tool.poetry]\nname = \"demo\"\n"),
+                ("main.py", ""),
+            ],
+            ProjectType::Venv => vec![
+                (".venv/pyvenv.cfg", ""),
+                (
+                    if cfg!(windows) {
+                        ".venv/Scripts/activate.bat"
+                    } else {
+                        ".venv/bin/activate"
+                    },
+                    "",
+                ),
+                ("main.py", ""),
+            ],
+            ProjectType::Generic => vec![("main.py", "")],
+            ProjectType::Go => vec![
+                ("go.mod", "module demo\n"),
+                ("main.go", "package main\nfunc main() {}\n"),
+            ],
+            ProjectType::Rust => vec![
+                ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+                ("src/main.rs", "fn main() {}\n"),
+            ],
+            ProjectType::JavaScript => {
+                vec![("package.json", "{\"scripts\": {\"start\": \"node .\"}}")]
+            }
+            ProjectType::TypeScript => vec![
+                ("package.json", "{\"scripts\": {\"start\": \"node .\"}}"),
+                ("tsconfig.json", "{}"),
+            ],
+            ProjectType::Make => vec![("Makefile", "build:\n\techo build\n")],
+            ProjectType::Just => vec![("justfile", "build:\n    echo build\n")],
+            ProjectType::Deno => {
+                vec![("deno.json", "{\"tasks\": {\"dev\": \"deno run main.ts\"}}")]
+            }
+        }
+    }
+
+    fn build(
+        project_type: &ProjectType,
+        path: &str,
+        operation: Operation,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        build_project_command(
+            project_type,
+            path,
+            &[(operation, None)],
+            &BuildSettings::default(),
+        )
+    }
+
+    /// Each operation built on its own, chained in the given order.
+    fn chained(
+        project_type: &ProjectType,
+        path: &str,
+        operations: &[Operation],
+    ) -> (String, Vec<String>) {
+        chain_commands(
+            operations
+                .iter()
+                .map(|op| {
+                    let (executable, args) = build(project_type, path, op.clone()).unwrap();
+                    std::iter::once(executable).chain(args).collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn selection(operations: &[Operation]) -> Vec<(Operation, Option<String>)> {
+        operations.iter().map(|op| (op.clone(), None)).collect()
+    }
+
+    #[test]
+    fn every_offered_operation_is_handled() {
+        for project_type in &ProjectType::ALL {
+            let dir = fixture_for(project_type);
+            let path = dir.path().to_str().unwrap();
+            for operation in project_type.operations() {
+                // Tools missing from this machine may fail the build; an
+                // operation the command builder doesn't know must not
+                if let Err(e) = build(project_type, path, operation.clone()) {
+                    assert!(
+                        !e.to_string().contains("is not supported"),
+                        "{:?} {:?}: {}",
+                        project_type,
+                        operation,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn operations_not_offered_are_rejected() {
+        let dir = fixture_for(&ProjectType::Go);
+        let path = dir.path().to_str().unwrap();
+        let err = build(&ProjectType::Go, path, Operation::Clippy).unwrap_err();
+        assert_eq!(err.to_string(), "'clippy' is not supported for Go projects");
+
+        let dir = fixture_for(&ProjectType::Venv);
+        let path = dir.path().to_str().unwrap();
+        assert!(build(&ProjectType::Venv, path, Operation::Tidy).is_err());
+    }
+
+    #[test]
+    fn offered_flags_are_unique_per_project_type() {
+        for project_type in &ProjectType::ALL {
+            let flags: Vec<String> = project_type
+                .operations()
+                .iter()
+                .map(Operation::as_flag)
+                .collect();
+            let mut unique = flags.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(flags.len(), unique.len(), "{:?}: {:?}", project_type, flags);
+        }
+    }
+
+    #[test]
+    fn option_info_follows_the_operation() {
+        for project_type in &ProjectType::ALL {
+            for operation in project_type.operations() {
+                let option = operation.to_option_info();
+                assert_eq!(option.flags, vec![operation.as_flag()]);
+                assert_eq!(
+                    option.value == crate::models::ValueArity::Required,
+                    operation.requires_value()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_selected_builds_nothing() {
+        let dir = fixture_for(&ProjectType::Rust);
+        let command = build_project_command(
+            &ProjectType::Rust,
+            dir.path().to_str().unwrap(),
+            &[],
+            &BuildSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(command, (String::new(), Vec::new()));
+    }
+
+    fn go_project() -> tempfile::TempDir {
+        fixture_for(&ProjectType::Go)
+    }
+
+    fn go_settings(tags: &[&str]) -> BuildSettings {
+        BuildSettings {
+            go_build_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..BuildSettings::default()
+        }
+    }
+
+    #[test]
+    fn go_build_tags_follow_the_subcommand() {
+        let dir = go_project();
+        let path = dir.path().to_str().unwrap();
+        let settings = go_settings(&["integration", "sqlite"]);
+
+        let (executable, args) = build_project_command(
+            &ProjectType::Go,
+            path,
+            &[(Operation::Test, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(executable, "go");
+        assert_eq!(args, ["test", "-tags", "integration,sqlite", "./..."]);
+
+        let (_, args) = build_project_command(
+            &ProjectType::Go,
+            path,
+            &[(Operation::Build, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(&args[..3], ["build", "-tags", "integration,sqlite"]);
+        assert_eq!(args.last().unwrap(), ".");
+    }
+
+    #[test]
+    fn go_without_tags_adds_no_flag() {
+        let dir = go_project();
+        let path = dir.path().to_str().unwrap();
+        let (_, args) = build(&ProjectType::Go, path, Operation::Test).unwrap();
+        assert_eq!(args, ["test", "./..."]);
+
+        let (_, args) = build(&ProjectType::Go, path, Operation::Generate).unwrap();
+        assert_eq!(args, ["generate", "./..."]);
+    }
+
+    #[test]
+    fn go_build_tags_only_apply_to_compiling_operations() {
+        assert!(uses_go_build_tags(&[(Operation::Run, None)]));
+        assert!(uses_go_build_tags(&[
+            (Operation::Tidy, None),
+            (Operation::Build, None)
+        ]));
+        assert!(!uses_go_build_tags(&[(Operation::Generate, None)]));
+        assert!(!uses_go_build_tags(&[]));
+    }
+
+    #[test]
+    fn cached_go_build_tags_skip_empty_entries() {
+        let mut cache = ProjectCache::new(ProjectType::Go, String::new());
+        assert!(cached_go_build_tags(&cache).is_empty());
+        cache
+            .metadata
+            .insert(GO_BUILD_TAGS_KEY.to_string(), "a,,b".to_string());
+        assert_eq!(cached_go_build_tags(&cache), ["a", "b"]);
+    }
+
+    #[test]
+    fn go_generate_directives_are_found_in_source() {
+        let dir = fixture(&[
+            ("go.mod", "module demo\n"),
+            ("main.go", "package main\n"),
+            (
+                "internal/gen/gen.go",
+                "package gen\n//go:generate stringer -type=Kind\n",
+            ),
+        ]);
+        assert!(detect_go_generate(dir.path().to_str().unwrap()));
+    }
+
+    #[test]
+    fn go_generate_directives_in_vendor_and_hidden_dirs_are_ignored() {
+        let dir = fixture(&[
+            ("go.mod", "module demo\n"),
+            ("main.go", "package main\n// go generate is run by hand\n"),
+            ("vendor/dep/dep.go", "//go:generate mockgen\n"),
+            (".cache/old.go", "//go:generate mockgen\n"),
+            ("notes.txt", "//go:generate mockgen\n"),
+        ]);
+        assert!(!detect_go_generate(dir.path().to_str().unwrap()));
+    }
+
+    /// Directory names that have broken path handling before.
+    const AWKWARD_NAMES: [&str; 3] = ["My App (2024)", "café ünïcode", "it's a [test]"];
+
+    #[test]
+    fn awkward_directory_names_are_detected() {
+        for name in AWKWARD_NAMES {
+            for project_type in &ProjectType::ALL {
+                let dir = tempfile::tempdir().unwrap();
+                let root = dir.path().join(name);
+                write_files(&root, &fixture_files(project_type));
+                assert_eq!(
+                    detect_project_type(root.to_str().unwrap()).unwrap(),
+                    *project_type,
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn awkward_directory_names_stay_whole_in_commands() {
+        for name in AWKWARD_NAMES {
+            for project_type in &ProjectType::ALL {
+                let dir = tempfile::tempdir().unwrap();
+                let root = dir.path().join(name);
+                write_files(&root, &fixture_files(project_type));
+                let path = root.to_str().unwrap();
+
+                for operation in project_type.operations() {
+                    if operation.requires_value() {
+                        continue;
+                    }
+                    // Tools missing from this machine are covered elsewhere
+                    let Ok((executable, args)) = build(project_type, path, operation.clone())
+                    else {
+                        continue;
+                    };
+                    // Cleaning a project with nothing to delete runs nothing
+                    assert!(
+                        !executable.is_empty() || args.is_empty(),
+                        "{:?} {:?}",
+                        project_type,
+                        operation
+                    );
+                    for arg in &args {
+                        assert!(
+                            !arg.is_empty(),
+                            "{:?} {:?}: {:?}",
+                            project_type,
+                            operation,
+                            args
+                        );
+                        // A path in a plain argument is whole; a chained shell
+                        // line carries it quoted
+                        if arg.contains(name.rsplit(' ').next().unwrap()) {
+                            let whole = if executable == "bash" {
+                                crate::utils::shell_quote(path)
+                            } else {
+                                path.to_string()
+                            };
+                            assert!(
+                                arg.contains(&whole),
+                                "{:?} {:?}: {:?}",
+                                project_type,
+                                operation,
+                                args
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn go_binary_names_fall_back_to_a_clean_directory_name() {
+        assert_eq!(sanitize_binary_name("My App (2024)"), "My-App-2024");
+        assert_eq!(sanitize_binary_name("café ünïcode"), "café-ünïcode");
+        assert_eq!(sanitize_binary_name("(())"), "app");
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("My App (2024)");
+        write_files(&root, &[("main.go", "package main\n")]);
+        assert_eq!(
+            detect_binary_name(root.to_str().unwrap()).unwrap(),
+            "My-App-2024"
+        );
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn target_names(operations: &[Operation]) -> Vec<String> {
+        operations.iter().map(|op| op.as_flag()).collect()
+    }
+
+    #[test]
+    fn make_targets_are_read_from_rules() {
+        let dir = fixture(&[(
+            "Makefile",
+            "CC := gcc\n\
+             FLAGS = -O2\n\
+             .PHONY: build test\n\
+             build: deps ## Compile everything\n\
+             \t$(CC) main.c\n\
+             test:: ## \n\
+             %.o: %.c\n\
+             build: extra\n\
+             dist-clean:\n",
+        )]);
+        let targets = detect_make_targets(dir.path().to_str().unwrap());
+        assert_eq!(target_names(&targets), ["build", "test", "dist-clean"]);
+        match &targets[0] {
+            Operation::Target { description, .. } => {
+                assert_eq!(description.as_deref(), Some("Compile everything"))
+            }
+            other => panic!("{:?}", other),
+        }
+        match &targets[1] {
+            Operation::Target { description, .. } => assert_eq!(*description, None),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn make_prefers_gnumakefile_and_runs_the_target() {
+        let dir = fixture(&[("GNUmakefile", "gnu:\n"), ("Makefile", "plain:\n")]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(detect_project_type(path).unwrap(), ProjectType::Make);
+        let targets = detect_make_targets(path);
+        assert_eq!(target_names(&targets), ["gnu"]);
+
+        let (executable, args) = build(&ProjectType::Make, path, targets[0].clone()).unwrap();
+        assert!(executable.ends_with("make"), "{}", executable);
+        assert_eq!(args.last().map(String::as_str), Some("gnu"));
+        assert!(build(&ProjectType::Make, path, Operation::Test).is_err());
+    }
+
+    #[test]
+    fn justfile_recipes_are_read_when_just_is_missing() {
+        let dir = fixture(&[(
+            "justfile",
+            "set shell := [\"bash\", \"-c\"]\n\
+             version := \"1.0\"\n\
+             alias b := build\n\
+             \n\
+             # Compile the project\n\
+             build target=\"debug\" *flags:\n\
+             \x20   cargo build --profile {{target}} {{flags}}\n\
+             \n\
+             #!/usr/bin/env just\n\
+             @test:\n\
+             \x20   cargo test\n\
+             \n\
+             [private]\n\
+             helper:\n\
+             \x20   echo hidden\n\
+             \n\
+             _internal:\n\
+             \x20   echo hidden\n\
+             \n\
+             [group('ci')]\n\
+             # Everything CI runs\n\
+             ci: build test\n",
+        )]);
+        let recipes = parse_justfile(dir.path().to_str().unwrap());
+        assert_eq!(target_names(&recipes), ["build", "test", "ci"]);
+        assert_eq!(
+            recipes[0],
+            Operation::Target {
+                name: "build".to_string(),
+                description: Some("Compile the project".to_string()),
+                parameters: vec!["target=\"debug\"".to_string(), "*flags".to_string()],
+            }
+        );
+        assert!(recipes[0].requires_value());
+        assert!(!recipes[1].requires_value());
+        assert_eq!(
+            recipes[2],
+            Operation::Target {
+                name: "ci".to_string(),
+                description: Some("Everything CI runs".to_string()),
+                parameters: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn just_recipe_signatures_carry_their_parameters() {
+        assert_eq!(
+            just_recipe("deploy env $TOKEN +hosts='a b'", None),
+            Some(Operation::Target {
+                name: "deploy".to_string(),
+                description: None,
+                parameters: vec![
+                    "env".to_string(),
+                    "$TOKEN".to_string(),
+                    "+hosts='a b'".to_string()
+                ],
+            })
+        );
+        assert_eq!(just_recipe("_setup", Some("private".to_string())), None);
+    }
+
+    #[test]
+    fn just_runs_the_recipe_with_its_arguments() {
+        let dir = fixture_for(&ProjectType::Just);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(detect_project_type(path).unwrap(), ProjectType::Just);
+
+        let recipe = Operation::Target {
+            name: "build".to_string(),
+            description: None,
+            parameters: vec!["*flags".to_string()],
+        };
+        let (executable, args) = build_project_command(
+            &ProjectType::Just,
+            path,
+            &[(recipe, Some("release 'two words'".to_string()))],
+            &BuildSettings::default(),
+        )
+        .unwrap();
+        assert!(executable.ends_with("just"), "{}", executable);
+        assert_eq!(args, ["build", "release", "two words"]);
+    }
+
+    #[test]
+    fn just_list_output_is_parsed() {
+        let listing = "Available recipes:\n\
+                       \x20   build target=\"debug\" # Compile the project\n\
+                       \x20   b # alias for `build`\n\
+                       \x20   [ci]\n\
+                       \x20   lint\n";
+        let recipes = listed_recipes(listing);
+        assert_eq!(target_names(&recipes), ["build", "lint"]);
+        assert_eq!(
+            recipes[0],
+            Operation::Target {
+                name: "build".to_string(),
+                description: Some("Compile the project".to_string()),
+                parameters: vec!["target=\"debug\"".to_string()],
+            }
+        );
+    }
+
+    const PACKAGE_JSON: &str = r#"{
+        "scripts": {
+            "start": "node .",
+            "test": "vitest",
+            "build": "tsc",
+            "format": "prettier -w .",
+            "lint": "eslint .",
+            "db:migrate": "prisma migrate dev"
+        }
+    }"#;
+
+    #[test]
+    fn package_scripts_become_targets_without_duplicating_run_and_test() {
+        let dir = fixture(&[("package.json", PACKAGE_JSON)]);
+        let path = dir.path().to_str().unwrap();
+        let scripts = detect_package_scripts(path);
+        assert_eq!(target_names(&scripts), ["db:migrate", "lint"]);
+        assert_eq!(
+            scripts[1],
+            Operation::Target {
+                name: "lint".to_string(),
+                description: Some("eslint .".to_string()),
+                parameters: Vec::new(),
+            }
+        );
+
+        let operations = get_project_operations(&ProjectType::JavaScript, path);
+        assert!(operations.contains(&scripts[0]));
+        assert_eq!(
+            operations
+                .iter()
+                .filter(|op| matches!(op, Operation::Run | Operation::Test))
+                .count(),
+            2
+        );
+        assert!(
+            !operations
+                .iter()
+                .any(|op| matches!(op, Operation::Target { name, .. } if name == "start"))
+        );
+    }
+
+    #[test]
+    fn package_scripts_run_through_the_package_manager() {
+        let lint = Operation::Target {
+            name: "lint".to_string(),
+            description: None,
+            parameters: Vec::new(),
+        };
+        for (lockfile, manager) in [
+            ("package-lock.json", "npm"),
+            ("yarn.lock", "yarn"),
+            ("pnpm-lock.yaml", "pnpm"),
+            ("bun.lock", "bun"),
+        ] {
+            let dir = fixture(&[("package.json", PACKAGE_JSON), (lockfile, "")]);
+            let (executable, args) = build(
+                &ProjectType::JavaScript,
+                dir.path().to_str().unwrap(),
+                lint.clone(),
+            )
+            .unwrap();
+            let command = format!("{} {}", executable, args.join(" "));
+            assert!(
+                command.ends_with(&format!("{} run lint", manager)),
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn scripts_shared_by_every_project_are_the_same_batch_action() {
+        let script = |name: &str, body: &str| Operation::Target {
+            name: name.to_string(),
+            description: Some(body.to_string()),
+            parameters: Vec::new(),
+        };
+        let lint = script("lint", "eslint .");
+        assert!(lint.is_batch_safe());
+        assert!(lint.same_action(&script("lint", "biome lint")));
+        assert!(!lint.same_action(&script("db:migrate", "eslint .")));
+    }
+
+    #[test]
+    fn bun_lockfiles_pick_bun_without_npx() {
+        for lockfile in ["bun.lock", "bun.lockb"] {
+            let dir = fixture(&[("package.json", PACKAGE_JSON), (lockfile, "")]);
+            let path = dir.path().to_str().unwrap();
+            assert_eq!(detect_package_manager(path), "bun");
+            assert_eq!(
+                cached_package_manager(&ProjectType::JavaScript, path).as_deref(),
+                Some("bun")
+            );
+
+            let (executable, args) =
+                build(&ProjectType::JavaScript, path, Operation::Test).unwrap();
+            assert_eq!(executable, "bun");
+            assert_eq!(args, ["run", "test"]);
+            let (executable, args) = build(&ProjectType::JavaScript, path, Operation::Add).unwrap();
+            assert_eq!(executable, "bun");
+            assert_eq!(args, ["add"]);
+        }
+        assert!(!runs_through_npx("bun"));
+        assert!(runs_through_npx("pnpm"));
+    }
+
+    #[test]
+    fn a_recorded_package_manager_is_used_instead_of_detecting_it() {
+        let dir = fixture(&[("package.json", PACKAGE_JSON), ("yarn.lock", "")]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(cached_package_manager(&ProjectType::Deno, path), None);
+
+        let settings = BuildSettings {
+            package_manager: Some("bun".to_string()),
+            ..Default::default()
+        };
+        let (executable, args) = build_project_command(
+            &ProjectType::JavaScript,
+            path,
+            &[(Operation::Test, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(executable, "bun");
+        assert_eq!(args, ["run", "test"]);
+    }
+
+    #[test]
+    fn deno_configs_without_package_json_are_deno_projects() {
+        let config = r#"{
+            // Tasks deno runs
+            "tasks": {
+                "dev": "deno run --watch main.ts",
+                "check": { "description": "Type-check everything", "command": "deno check ." },
+                "url": "echo http://localhost/*not a comment*/",
+            },
+        }"#;
+        let dir = fixture(&[("deno.jsonc", config)]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(detect_project_type(path).unwrap(), ProjectType::Deno);
+
+        let tasks = detect_deno_tasks(path);
+        assert_eq!(target_names(&tasks), ["check", "dev", "url"]);
+        let descriptions: Vec<Option<String>> = tasks
+            .iter()
+            .map(|task| match task {
+                Operation::Target { description, .. } => description.clone(),
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                Some("Type-check everything".to_string()),
+                Some("deno run --watch main.ts".to_string()),
+                Some("echo http://localhost/*not a comment*/".to_string())
+            ]
+        );
+
+        let (executable, args) = build(&ProjectType::Deno, path, tasks[1].clone()).unwrap();
+        assert_eq!(executable, "deno");
+        assert_eq!(args, ["task", "dev"]);
+        for operation in [Operation::Test, Operation::Format, Operation::Lint] {
+            let (_, args) = build(&ProjectType::Deno, path, operation.clone()).unwrap();
+            assert_eq!(args, [operation.as_flag()]);
+        }
+
+        write_files(dir.path(), &[("package.json", PACKAGE_JSON)]);
+        assert_eq!(detect_project_type(path).unwrap(), ProjectType::JavaScript);
+    }
+
+    #[test]
+    fn poetry_projects_are_found_by_section_or_lockfile() {
+        for files in [
+            vec![("pyproject.toml", "[tool.poetry]\nname = \"demo\"\n")],
+            vec![
+                ("pyproject.toml", "[project]\nname = \"demo\"\n"),
+                ("poetry.lock", ""),
+            ],
+        ] {
+            let dir = fixture(&files);
+            let path = dir.path().to_str().unwrap();
+            assert_eq!(detect_project_type(path).unwrap(), ProjectType::Poetry);
+            assert!(!detection::claims_uv_and_poetry(dir.path()));
+        }
+
+        let dir = fixture(&[(
+            "pyproject.toml",
+            "[tool.uv]\ndev-dependencies = []\n[tool.poetry]\nname = \"demo\"\n",
+        )]);
+        assert!(detection::claims_uv_and_poetry(dir.path()));
+    }
+
+    #[test]
+    fn poetry_operations_run_through_poetry() {
+        let dir = fixture_for(&ProjectType::Poetry);
+        let path = dir.path().to_str().unwrap();
+        let poetry = |operation: Operation, value: Option<&str>| {
+            let (executable, args) = build_project_command(
+                &ProjectType::Poetry,
+                path,
+                &[(operation, value.map(str::to_string))],
+                &BuildSettings::default(),
+            )
+            .unwrap();
+            assert_eq!(executable, "poetry");
+            args
+        };
+        assert_eq!(poetry(Operation::InstallDeps, None), ["install"]);
+        assert_eq!(
+            poetry(Operation::Add, Some("requests httpx[http2]")),
+            ["add", "requests", "httpx[http2]"]
+        );
+        assert_eq!(
+            poetry(Operation::Remove, Some("requests")),
+            ["remove", "requests"]
+        );
+        assert_eq!(poetry(Operation::Run, None), ["run", "python", "main.py"]);
+        assert_eq!(poetry(Operation::Test, None), ["run", "pytest"]);
+    }
+
+    #[test]
+    fn venvs_are_found_at_the_root_or_in_a_subdirectory() {
+        assert_eq!(venv_marker("."), VENV_ACTIVATE);
+        for venv_dir in [".", ".venv", "venv"] {
+            let dir = fixture(&[(&venv_marker(venv_dir), ""), ("main.py", "")]);
+            let path = dir.path().to_str().unwrap();
+            assert_eq!(find_venv(path).as_deref(), Some(venv_dir));
+            assert_eq!(detect_project_type(path).unwrap(), ProjectType::Venv);
+        }
+
+        let dir = fixture(&[(&venv_marker("venv"), ""), (&venv_marker(".venv"), "")]);
+        assert_eq!(
+            find_venv(dir.path().to_str().unwrap()).as_deref(),
+            Some(".venv")
+        );
+        // A directory named like a venv isn't one without its activation script
+        let dir = fixture(&[(".venv/pyvenv.cfg", ""), ("main.py", "")]);
+        assert_eq!(find_venv(dir.path().to_str().unwrap()), None);
+    }
+
+    #[test]
+    fn venv_commands_use_the_venvs_own_programs() {
+        let dir = fixture(&[
+            (&venv_marker(".venv"), ""),
+            (&venv_marker("venv"), ""),
+            ("main.py", ""),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        let venv = |name: &str| std::path::absolute(dir.path().join(name)).unwrap();
+
+        let (executable, args) = build_project_command(
+            &ProjectType::Venv,
+            path,
+            &[(
+                Operation::InstallPackage,
+                Some("package[extra] other".to_string()),
+            )],
+            &BuildSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(executable, venv_program(&venv(".venv"), "pip"));
+        assert_eq!(args, ["install", "package[extra]", "other"]);
+
+        // The directory recorded in the cache wins over looking again
+        let settings = BuildSettings {
+            venv_dir: Some("venv".to_string()),
+            ..Default::default()
+        };
+        let (executable, args) = build_project_command(
+            &ProjectType::Venv,
+            path,
+            &[(Operation::Run, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(executable, venv_program(&venv("venv"), "python"));
+        assert_eq!(args.last().map(String::as_str), Some("main.py"));
+    }
+
+    const TWO_SCRIPTS: &str = "[project]\nname = \"demo\"\n\
+                               [project.scripts]\n\
+                               serve = \"demo.server:main\"\n\
+                               migrate = \"demo.db : run\"\n\
+                               broken = 3\n";
+
+    #[test]
+    fn project_scripts_are_read_from_pyproject() {
+        let dir = fixture(&[("pyproject.toml", TWO_SCRIPTS)]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(
+            pyproject_scripts(path),
+            [
+                ("migrate".to_string(), "demo.db : run".to_string()),
+                ("serve".to_string(), "demo.server:main".to_string())
+            ]
+        );
+        assert_eq!(python_entry_args(path, "serve"), ["-m", "demo.server"]);
+        assert_eq!(python_entry_args(path, "migrate"), ["-m", "demo.db"]);
+        assert_eq!(python_entry_args(path, "tool.py"), ["tool.py"]);
+    }
+
+    #[test]
+    fn a_single_project_script_is_the_entry_point() {
+        let dir = fixture_for(&ProjectType::Uv);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(detect_entry_point(path).unwrap(), "demo");
+        let (_, args) = build(&ProjectType::Uv, path, Operation::Run).unwrap();
+        assert_eq!(args, ["run", "demo"]);
+    }
+
+    #[test]
+    fn several_project_scripts_become_run_targets() {
+        let dir = fixture(&[("pyproject.toml", TWO_SCRIPTS)]);
+        let path = dir.path().to_str().unwrap();
+        // Neither script is the obvious one and there is no file to guess at
+        assert_eq!(detect_entry_point(path).unwrap(), "");
+
+        for project_type in [ProjectType::Uv, ProjectType::Generic] {
+            let operations = get_project_operations(&project_type, path);
+            assert!(!operations.contains(&Operation::Run), "{:?}", project_type);
+            let targets: Vec<String> = operations
+                .iter()
+                .filter(|op| matches!(op, Operation::Target { .. }))
+                .map(|op| op.as_flag())
+                .collect();
+            assert_eq!(targets, ["migrate", "serve"], "{:?}", project_type);
+        }
+
+        let serve = Operation::Target {
+            name: "serve".to_string(),
+            description: None,
+            parameters: Vec::new(),
+        };
+        let (_, args) = build(&ProjectType::Uv, path, serve.clone()).unwrap();
+        assert_eq!(args, ["run", "serve"]);
+        let (_, args) = build(&ProjectType::Generic, path, serve).unwrap();
+        assert_eq!(args, ["-m", "demo.server"]);
+    }
+
+    /// A workspace with a root package, two members matched by a glob, one
+    /// excluded and a directory without a manifest.
+    fn workspace() -> tempfile::TempDir {
+        fixture(&[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"crates/*\", \"tools/gen/\"]\n\
+                 exclude = [\"crates/old\"]\n\
+                 [package]\nname = \"app\"\n",
+            ),
+            ("src/main.rs", "fn main() {}\n"),
+            ("crates/cli/Cargo.toml", "[package]\nname = \"app-cli\"\n"),
+            ("crates/core/Cargo.toml", "[package]\nname = \"app-core\"\n"),
+            ("crates/old/Cargo.toml", "[package]\nname = \"app-old\"\n"),
+            ("crates/notes/README.md", ""),
+            ("tools/gen/Cargo.toml", "[package]\nname = \"gen\"\n"),
+        ])
+    }
+
+    #[test]
+    fn workspace_members_follow_globs_and_excludes() {
+        let dir = workspace();
+        let members: Vec<(String, String)> = workspace_members(dir.path().to_str().unwrap())
+            .into_iter()
+            .map(|member| (member.name, member.dir))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("app".to_string(), ".".to_string()),
+                ("app-cli".to_string(), "crates/cli".to_string()),
+                ("app-core".to_string(), "crates/core".to_string()),
+                ("gen".to_string(), "tools/gen".to_string()),
+            ]
+        );
+        assert_eq!(
+            expand_member_glob(dir.path(), "./crates/c?re"),
+            ["crates/core"]
+        );
+
+        let single = fixture_for(&ProjectType::Rust);
+        assert!(workspace_members(single.path().to_str().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn the_chosen_member_is_passed_to_cargo() {
+        let dir = workspace();
+        let path = dir.path().to_str().unwrap();
+        let settings = BuildSettings {
+            cargo_package: Some("app-cli".to_string()),
+            ..Default::default()
+        };
+        for operation in [
+            Operation::Build,
+            Operation::Test,
+            Operation::Check,
+            Operation::Clippy,
+            Operation::Run,
+        ] {
+            let (_, args) = build_project_command(
+                &ProjectType::Rust,
+                path,
+                &[(operation.clone(), None)],
+                &settings,
+            )
+            .unwrap();
+            assert!(
+                args.windows(2).any(|pair| pair == ["-p", "app-cli"]),
+                "{:?}: {:?}",
+                operation,
+                args
+            );
+        }
+        let (_, args) = build_project_command(
+            &ProjectType::Rust,
+            path,
+            &[(Operation::Install, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(args, ["install", "--path", "crates/cli"]);
+
+        // Without a member cargo acts on the workspace as it would by itself
+        let (_, args) = build(&ProjectType::Rust, path, Operation::Test).unwrap();
+        assert_eq!(args, ["test"]);
+    }
+
+    #[test]
+    fn a_remembered_member_is_only_used_while_it_exists() {
+        let members = workspace_members(workspace().path().to_str().unwrap());
+        let mut cache = ProjectCache::new(ProjectType::Rust, ".".to_string());
+        assert_eq!(cached_workspace_member(&cache, &members), None);
+
+        cache
+            .metadata
+            .insert(CARGO_PACKAGE_KEY.to_string(), "gen".to_string());
+        assert_eq!(
+            cached_workspace_member(&cache, &members).as_deref(),
+            Some("gen")
+        );
+        cache
+            .metadata
+            .insert(CARGO_PACKAGE_KEY.to_string(), "removed".to_string());
+        assert_eq!(cached_workspace_member(&cache, &members), None);
+    }
+
+    #[test]
+    fn cargo_features_keep_manifest_order() {
+        let dir = fixture(&[(
+            "Cargo.toml",
+            "[package]\nname = \"demo\"\n\
+             [features]\nzstd = []\ndefault = [\"tls\"]\ntls = []\n\"serde-json\" = []\n",
+        )]);
+        assert_eq!(
+            cargo_features(dir.path()),
+            CargoFeatures {
+                names: strings(&["zstd", "tls", "serde-json"]),
+                has_default: true,
+            }
+        );
+
+        let plain = fixture_for(&ProjectType::Rust);
+        assert!(cargo_features(plain.path()).is_empty());
+    }
+
+    #[test]
+    fn remembered_features_are_dropped_once_undeclared() {
+        let features = CargoFeatures {
+            names: strings(&["tls", "zstd"]),
+            has_default: false,
+        };
+        let mut cache = ProjectCache::new(ProjectType::Rust, ".".to_string());
+        cache
+            .metadata
+            .insert(CARGO_FEATURES_KEY.to_string(), "zstd,gone".to_string());
+        cache.metadata.insert(
+            CARGO_NO_DEFAULT_FEATURES_KEY.to_string(),
+            "true".to_string(),
+        );
+
+        let mut settings = BuildSettings::default();
+        cached_cargo_features(&features, &mut settings, &cache);
+        assert_eq!(settings.cargo_features, ["zstd"]);
+        // Without a default feature set there is nothing to turn off
+        assert!(!settings.no_default_features);
+
+        let with_default = CargoFeatures {
+            has_default: true,
+            ..features
+        };
+        cached_cargo_features(&with_default, &mut settings, &cache);
+        assert!(settings.no_default_features);
+    }
+
+    #[test]
+    fn chosen_features_are_passed_to_cargo() {
+        let dir = fixture_for(&ProjectType::Rust);
+        let path = dir.path().to_str().unwrap();
+        let settings = BuildSettings {
+            cargo_features: strings(&["tls", "zstd"]),
+            no_default_features: true,
+            ..Default::default()
+        };
+        let cargo = |operation: Operation| {
+            build_project_command(&ProjectType::Rust, path, &[(operation, None)], &settings)
+                .unwrap()
+                .1
+        };
+        assert_eq!(
+            cargo(Operation::Build),
+            [
+                "build",
+                "--release",
+                "--no-default-features",
+                "--features",
+                "tls,zstd"
+            ]
+        );
+        assert_eq!(
+            cargo(Operation::Test),
+            ["test", "--no-default-features", "--features", "tls,zstd"]
+        );
+        assert_eq!(
+            cargo(Operation::Run),
+            [
+                "run",
+                "--bin",
+                "demo",
+                "--no-default-features",
+                "--features",
+                "tls,zstd"
+            ]
+        );
+        // Checks and lints build with the manifest's own features
+        assert_eq!(cargo(Operation::Check), ["check"]);
+    }
+
+    #[test]
+    fn rust_binaries_come_from_the_manifest_and_src_bin() {
+        let dir = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"demo\"\n\
+                 [[bin]]\nname = \"server\"\npath = \"src/bin/serve.rs\"\n",
+            ),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/bin/serve.rs", "fn main() {}\n"),
+            ("src/bin/admin.rs", "fn main() {}\n"),
+            ("src/bin/worker/main.rs", "fn main() {}\n"),
+            ("src/bin/helpers/mod.rs", ""),
+            ("examples/hello.rs", "fn main() {}\n"),
+            ("examples/notes.txt", ""),
+        ]);
+        assert_eq!(
+            rust_binaries(dir.path()),
+            ["server", "demo", "admin", "worker"]
+        );
+        assert_eq!(rust_examples(dir.path()), ["hello"]);
+
+        write_files(
+            dir.path(),
+            &[(
+                "Cargo.toml",
+                "[package]\nname = \"demo\"\nautobins = false\n\
+                 [[bin]]\nname = \"server\"\npath = \"src/bin/serve.rs\"\n",
+            )],
+        );
+        assert_eq!(rust_binaries(dir.path()), ["server"]);
+    }
+
+    #[test]
+    fn examples_are_offered_across_the_workspace() {
+        let dir = workspace();
+        write_files(
+            dir.path(),
+            &[
+                ("examples/demo.rs", ""),
+                ("crates/cli/examples/demo.rs", ""),
+                ("crates/core/examples/bench/main.rs", ""),
+            ],
+        );
+        let path = dir.path().to_str().unwrap();
+        let examples = detect_rust_examples(path);
+        assert_eq!(target_names(&examples), ["demo", "bench"]);
+
+        let (_, args) = build(&ProjectType::Rust, path, examples[1].clone()).unwrap();
+        assert_eq!(args, ["run", "--example", "bench"]);
+    }
+
+    #[test]
+    fn run_names_the_binary_only_when_it_is_known() {
+        let dir = fixture(&[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/bin/admin.rs", "fn main() {}\n"),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        // Several binaries and no answer: cargo reports the choice itself
+        let (_, args) = build(&ProjectType::Rust, path, Operation::Run).unwrap();
+        assert_eq!(args, ["run"]);
+
+        let settings = BuildSettings {
+            cargo_bin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let (_, args) = build_project_command(
+            &ProjectType::Rust,
+            path,
+            &[(Operation::Run, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(args, ["run", "--bin", "admin"]);
+    }
+
+    #[test]
+    fn custom_profiles_are_offered_after_debug_and_release() {
+        let dir = fixture(&[(
+            "Cargo.toml",
+            "[package]\nname = \"demo\"\n\
+             [profile.release]\nlto = true\n\
+             [profile.ci]\ninherits = \"dev\"\n\
+             [profile.dist]\ninherits = \"release\"\n",
+        )]);
+        let profiles = cargo_profiles(dir.path().to_str().unwrap());
+        assert_eq!(profiles, ["debug", "release", "ci", "dist"]);
+
+        let run = [(Operation::Run, None)];
+        let build = [(Operation::Run, None), (Operation::Build, None)];
+        assert_eq!(profiles_in_order(profiles.clone(), None, &run)[0], "debug");
+        assert_eq!(
+            profiles_in_order(profiles.clone(), None, &build)[0],
+            "release"
+        );
+        assert_eq!(
+            profiles_in_order(profiles.clone(), Some("dist".to_string()), &run),
+            ["dist", "debug", "release", "ci"]
+        );
+        // A configured profile this project doesn't have changes nothing
+        assert_eq!(
+            profiles_in_order(profiles.clone(), Some("gone".to_string()), &run),
+            profiles
+        );
+    }
+
+    #[test]
+    fn the_profile_applies_to_build_run_and_examples() {
+        let dir = fixture_for(&ProjectType::Rust);
+        let path = dir.path().to_str().unwrap();
+        let example = Operation::Target {
+            name: "hello".to_string(),
+            description: None,
+            parameters: Vec::new(),
+        };
+        assert!(uses_cargo_profile(&[(example.clone(), None)]));
+        assert!(!uses_cargo_profile(&[(Operation::Test, None)]));
+
+        let cargo = |operation: Operation, profile: Option<&str>| {
+            let settings = BuildSettings {
+                cargo_profile: profile.map(str::to_string),
+                ..Default::default()
+            };
+            build_project_command(&ProjectType::Rust, path, &[(operation, None)], &settings)
+                .unwrap()
+                .1
+        };
+        // Unset, builds are release and runs are debug
+        assert_eq!(cargo(Operation::Build, None), ["build", "--release"]);
+        assert_eq!(cargo(Operation::Run, None), ["run", "--bin", "demo"]);
+        assert_eq!(cargo(Operation::Build, Some("debug")), ["build"]);
+        assert_eq!(
+            cargo(Operation::Run, Some("release")),
+            ["run", "--release", "--bin", "demo"]
+        );
+        assert_eq!(
+            cargo(example, Some("ci")),
+            ["run", "--profile", "ci", "--example", "hello"]
+        );
+    }
+
+    const GO_MAIN: &str = "package main\n\nfunc main() {}\n";
+
+    #[test]
+    fn go_commands_are_found_under_cmd() {
+        let dir = fixture(&[
+            ("go.mod", "module github.com/acme/tools\n"),
+            ("cmd/server/main.go", GO_MAIN),
+            ("cmd/cli/root.go", GO_MAIN),
+            ("cmd/shared/shared.go", "package shared\n"),
+            ("cmd/only_tests/main_test.go", GO_MAIN),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(go_main_packages(path), ["./cmd/cli", "./cmd/server"]);
+        assert_eq!(single_go_command(path), None);
+        assert_eq!(
+            go_binary_name(path, Some("./cmd/server")).unwrap(),
+            "server"
+        );
+        assert_eq!(go_binary_name(path, Some(".")).unwrap(), "tools");
+
+        // A root main package is offered first and runs as the module
+        write_files(dir.path(), &[("main.go", GO_MAIN)]);
+        assert_eq!(go_main_packages(path), [".", "./cmd/cli", "./cmd/server"]);
+    }
+
+    #[test]
+    fn a_lone_go_command_is_the_entry_point() {
+        let dir = fixture(&[
+            ("go.mod", "module github.com/acme/tools\n"),
+            ("cmd/server/main.go", GO_MAIN),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(single_go_command(path).as_deref(), Some("./cmd/server"));
+        assert_eq!(detect_entry_point(path).unwrap(), "./cmd/server");
+        assert_eq!(go_binary_name(path, None).unwrap(), "server");
+    }
+
+    #[test]
+    fn the_chosen_go_package_is_run_and_built() {
+        let dir = fixture(&[
+            ("go.mod", "module github.com/acme/tools\n"),
+            ("cmd/server/main.go", GO_MAIN),
+            ("cmd/cli/main.go", GO_MAIN),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        let settings = BuildSettings {
+            go_package: Some("./cmd/cli".to_string()),
+            ..Default::default()
+        };
+        let go = |operation: Operation| {
+            build_project_command(&ProjectType::Go, path, &[(operation, None)], &settings)
+                .unwrap()
+                .1
+        };
+        assert_eq!(go(Operation::Run), ["run", "./cmd/cli"]);
+        let args = go(Operation::Build);
+        assert_eq!(args.last().map(String::as_str), Some("./cmd/cli"));
+        let output = extract_binary_path_from_args(&args).unwrap();
+        assert_eq!(
+            Path::new(&output).file_name().unwrap(),
+            format!("cli{}", std::env::consts::EXE_SUFFIX).as_str()
+        );
+    }
+
+    #[test]
+    fn go_targets_name_their_binaries() {
+        assert_eq!(
+            parse_go_target(" freebsd/amd64 ").unwrap(),
+            ("freebsd".to_string(), "amd64".to_string())
+        );
+        assert!(parse_go_target("linux").is_err());
+        assert!(parse_go_target("/arm64").is_err());
+        assert_eq!(
+            cross_binary_name("tool", "linux", "arm64"),
+            "tool-linux-arm64"
+        );
+        assert_eq!(
+            cross_binary_name("tool", "windows", "amd64"),
+            "tool-windows-amd64.exe"
+        );
+        assert_eq!(go_arch("x86_64"), "amd64");
+        assert_eq!(go_arch("riscv64"), "riscv64");
+    }
+
+    #[test]
+    fn cross_compiled_binaries_stay_in_the_project() {
+        let dir = fixture_for(&ProjectType::Go);
+        let path = dir.path().to_str().unwrap();
+        let settings = BuildSettings {
+            go_target: Some(("windows".to_string(), "arm64".to_string())),
+            ..Default::default()
+        };
+        let (_, args) = build_project_command(
+            &ProjectType::Go,
+            path,
+            &[(Operation::Build, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(args, ["build", "-o", "demo-windows-arm64.exe", "."]);
+    }
+
+    #[test]
+    fn program_args_follow_each_tools_separator() {
+        let program_args = tools::split_words("--port 8080 'two words'").unwrap();
+        let settings = BuildSettings {
+            program_args: program_args.clone(),
+            ..Default::default()
+        };
+        let run = |project_type: ProjectType, files: &[(&str, &str)]| {
+            let dir = if files.is_empty() {
+                fixture_for(&project_type)
+            } else {
+                fixture(files)
+            };
+            build_project_command(
+                &project_type,
+                dir.path().to_str().unwrap(),
+                &[(Operation::Run, None)],
+                &settings,
+            )
+            .unwrap()
+            .1
+        };
+        let with_args = |words: &[&str]| {
+            let mut expected = strings(words);
+            expected.extend(program_args.clone());
+            expected
+        };
+
+        assert_eq!(
+            run(ProjectType::Rust, &[]),
+            with_args(&["run", "--bin", "demo", "--"])
+        );
+        assert_eq!(run(ProjectType::Go, &[]), with_args(&["run", "main.go"]));
+        assert_eq!(run(ProjectType::Uv, &[]), with_args(&["run", "demo"]));
+        assert_eq!(
+            run(ProjectType::Poetry, &[]),
+            with_args(&["run", "python", "main.py"])
+        );
+        assert_eq!(run(ProjectType::Generic, &[]), with_args(&["main.py"]));
+        assert!(
+            run(
+                ProjectType::JavaScript,
+                &[("package.json", PACKAGE_JSON), ("yarn.lock", "")]
+            )
+            .ends_with(&with_args(&["yarn", "start"]))
+        );
+        // npm keeps anything before `--` for itself
+        assert!(
+            run(ProjectType::JavaScript, &[("package.json", PACKAGE_JSON)])
+                .ends_with(&with_args(&["start", "--"]))
+        );
+    }
+
+    #[test]
+    fn program_args_are_left_off_other_operations() {
+        let dir = fixture_for(&ProjectType::Rust);
+        let settings = BuildSettings {
+            program_args: strings(&["--port", "8080"]),
+            ..Default::default()
+        };
+        let (_, args) = build_project_command(
+            &ProjectType::Rust,
+            dir.path().to_str().unwrap(),
+            &[(Operation::Test, None)],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(args, ["test"]);
+    }
+
+    #[test]
+    fn fmt_uses_each_ecosystems_formatter() {
+        let command = |project_type: ProjectType, files: &[(&str, &str)]| {
+            let dir = if files.is_empty() {
+                fixture_for(&project_type)
+            } else {
+                fixture(files)
+            };
+            let (executable, args) = build(
+                &project_type,
+                dir.path().to_str().unwrap(),
+                Operation::Format,
+            )
+            .unwrap();
+            std::iter::once(executable).chain(args).collect::<Vec<_>>()
+        };
+
+        assert_eq!(command(ProjectType::Rust, &[]), ["cargo", "fmt"]);
+        assert_eq!(command(ProjectType::Go, &[]), ["go", "fmt", "./..."]);
+        assert_eq!(command(ProjectType::Deno, &[]), ["deno", "fmt"]);
+        assert!(
+            command(ProjectType::JavaScript, &[("package.json", PACKAGE_JSON)])
+                .ends_with(&strings(&["npm", "run", "format"]))
+        );
+        // Without a format script prettier formats instead
+        let no_script = "{\"scripts\": {\"start\": \"node .\"}}";
+        assert_eq!(
+            command(ProjectType::JavaScript, &[("package.json", no_script)]),
+            ["npx", "prettier", "--write", "."]
+        );
+        assert_eq!(
+            command(
+                ProjectType::TypeScript,
+                &[("package.json", no_script), ("bun.lock", "")]
+            ),
+            ["bun", "x", "prettier", "--write", "."]
+        );
+    }
+
+    #[test]
+    fn python_formats_with_the_venvs_ruff() {
+        let ruff = venv_program(Path::new(".venv"), "ruff");
+        let dir = fixture(&[(&venv_marker(".venv"), ""), (&ruff, ""), ("main.py", "")]);
+        let path = dir.path().to_str().unwrap();
+        let (executable, args) = build(&ProjectType::Venv, path, Operation::Format).unwrap();
+        assert_eq!(
+            executable,
+            dir.path().join(&ruff).to_string_lossy().into_owned()
+        );
+        assert_eq!(args, ["format"]);
+    }
+
+    #[test]
+    fn two_step_updates_run_in_order() {
+        let go = fixture_for(&ProjectType::Go);
+        let go_path = go.path().to_str().unwrap();
+        assert_eq!(
+            update_commands(&ProjectType::Go, go_path, &BuildSettings::default())
+                .unwrap()
+                .unwrap(),
+            [
+                strings(&["go", "get", "-u", "./..."]),
+                strings(&["go", "mod", "tidy"])
+            ]
+        );
+        let uv = fixture_for(&ProjectType::Uv);
+        let uv_path = uv.path().to_str().unwrap();
+        assert_eq!(
+            update_commands(&ProjectType::Uv, uv_path, &BuildSettings::default())
+                .unwrap()
+                .unwrap(),
+            [
+                strings(&["uv", "--project", uv_path, "lock", "--upgrade"]),
+                strings(&["uv", "--project", uv_path, "sync"])
+            ]
+        );
+        assert!(
+            update_commands(&ProjectType::Deno, go_path, &BuildSettings::default())
+                .unwrap()
+                .is_none()
+        );
+
+        // The project's own command follows the update
+        let (executable, args) = build_project_command(
+            &ProjectType::Go,
+            go_path,
+            &[(Operation::Update, None), (Operation::Test, None)],
+            &BuildSettings::default(),
+        )
+        .unwrap();
+        let (expected, expected_args) = chain_commands(vec![
+            strings(&["go", "get", "-u", "./..."]),
+            strings(&["go", "mod", "tidy"]),
+            strings(&["go", "test", "./..."]),
+        ]);
+        assert_eq!((executable, args), (expected, expected_args));
+    }
+
+    #[test]
+    fn single_step_updates_use_the_projects_tool() {
+        let update = |project_type: ProjectType, files: &[(&str, &str)]| {
+            let dir = if files.is_empty() {
+                fixture_for(&project_type)
+            } else {
+                fixture(files)
+            };
+            let (executable, args) = build(
+                &project_type,
+                dir.path().to_str().unwrap(),
+                Operation::Update,
+            )
+            .unwrap();
+            std::iter::once(executable).chain(args).collect::<Vec<_>>()
+        };
+        assert_eq!(update(ProjectType::Rust, &[]), ["cargo", "update"]);
+        assert_eq!(update(ProjectType::Poetry, &[]), ["poetry", "update"]);
+        for (lockfile, command) in [
+            ("package-lock.json", ["npm", "update"]),
+            ("yarn.lock", ["yarn", "upgrade"]),
+            ("pnpm-lock.yaml", ["pnpm", "update"]),
+        ] {
+            let words = update(
+                ProjectType::JavaScript,
+                &[("package.json", PACKAGE_JSON), (lockfile, "")],
+            );
+            assert!(words.ends_with(&strings(&command)), "{:?}", words);
+        }
+    }
+
+    #[test]
+    fn audits_use_the_package_manager_or_the_venvs_pip_audit() {
+        for (lockfile, manager) in [("package-lock.json", "npm"), ("pnpm-lock.yaml", "pnpm")] {
+            let dir = fixture(&[("package.json", PACKAGE_JSON), (lockfile, "")]);
+            let (_, args) = build(
+                &ProjectType::JavaScript,
+                dir.path().to_str().unwrap(),
+                Operation::Audit,
+            )
+            .unwrap();
+            assert!(args.ends_with(&strings(&[manager, "audit"])), "{:?}", args);
+        }
+
+        let pip_audit = venv_program(Path::new(".venv"), "pip-audit");
+        let dir = fixture(&[
+            (&venv_marker(".venv"), ""),
+            (&pip_audit, ""),
+            ("main.py", ""),
+        ]);
+        let (executable, args) = build(
+            &ProjectType::Venv,
+            dir.path().to_str().unwrap(),
+            Operation::Audit,
+        )
+        .unwrap();
+        assert_eq!(
+            executable,
+            dir.path().join(&pip_audit).to_string_lossy().into_owned()
+        );
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn fmt_runs_as_a_command_of_its_own() {
+        for (project_type, files, other) in [
+            (
+                ProjectType::Rust,
+                fixture_files(&ProjectType::Rust),
+                Operation::Build,
+            ),
+            (
+                ProjectType::Go,
+                fixture_files(&ProjectType::Go),
+                Operation::Test,
+            ),
+            (
+                ProjectType::JavaScript,
+                vec![("package.json", PACKAGE_JSON)],
+                Operation::Build,
+            ),
+            (
+                ProjectType::Deno,
+                fixture_files(&ProjectType::Deno),
+                Operation::Test,
+            ),
+        ] {
+            let dir = fixture(&files);
+            let path = dir.path().to_str().unwrap();
+            let command = build_project_command(
+                &project_type,
+                path,
+                &selection(&[Operation::Format, other.clone()]),
+                &BuildSettings::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                command,
+                chained(&project_type, path, &[other, Operation::Format]),
+                "{:?}",
+                project_type
+            );
+        }
+    }
+
+    #[test]
+    fn updates_run_before_other_operations() {
+        for (project_type, files) in [
+            (ProjectType::Rust, fixture_files(&ProjectType::Rust)),
+            (ProjectType::Poetry, fixture_files(&ProjectType::Poetry)),
+            (
+                ProjectType::JavaScript,
+                vec![("package.json", PACKAGE_JSON), ("yarn.lock", "")],
+            ),
+        ] {
+            let dir = fixture(&files);
+            let path = dir.path().to_str().unwrap();
+            let command = build_project_command(
+                &project_type,
+                path,
+                &selection(&[Operation::Update, Operation::Test]),
+                &BuildSettings::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                command,
+                chained(&project_type, path, &[Operation::Update, Operation::Test]),
+                "{:?}",
+                project_type
+            );
+        }
+
+        let dir = fixture_for(&ProjectType::Rust);
+        let path = dir.path().to_str().unwrap();
+        let command = build_project_command(
+            &ProjectType::Rust,
+            path,
+            &selection(&[Operation::Build, Operation::Update]),
+            &BuildSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            chained(
+                &ProjectType::Rust,
+                path,
+                &[Operation::Update, Operation::Build]
+            )
+        );
+    }
+
+    #[test]
+    fn clean_runs_before_other_operations() {
+        for (project_type, other) in [
+            (ProjectType::Rust, Operation::Build),
+            (ProjectType::Go, Operation::Test),
+        ] {
+            let dir = fixture_for(&project_type);
+            let path = dir.path().to_str().unwrap();
+            let command = build_project_command(
+                &project_type,
+                path,
+                &selection(&[other.clone(), Operation::Clean]),
+                &BuildSettings::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                command,
+                chained(&project_type, path, &[Operation::Clean, other]),
+                "{:?}",
+                project_type
+            );
+        }
+    }
+
+    #[test]
+    fn extra_args_never_reach_removal_commands() {
+        let dir = fixture(&[
+            ("package.json", PACKAGE_JSON),
+            ("node_modules/x/index.js", ""),
+        ]);
+        let path = dir.path().to_str().unwrap();
+        let settings = BuildSettings {
+            extra_args: strings(&["--verbose"]),
+            ..Default::default()
+        };
+        let removal = clean::removal_command(&ProjectType::JavaScript, path).unwrap();
+
+        // Cleaning alone runs the removal as it is
+        let (executable, args) = build_project_command(
+            &ProjectType::JavaScript,
+            path,
+            &selection(&[Operation::Clean]),
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(
+            std::iter::once(executable).chain(args).collect::<Vec<_>>(),
+            removal
+        );
+
+        // Next to another operation they go to the package manager's command
+        let (_, test) = build_project_command(
+            &ProjectType::JavaScript,
+            path,
+            &selection(&[Operation::Test]),
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(test.last().unwrap(), "--verbose");
+        let command = build_project_command(
+            &ProjectType::JavaScript,
+            path,
+            &selection(&[Operation::Clean, Operation::Test]),
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            chain_commands(vec![
+                removal,
+                std::iter::once("npx".to_string()).chain(test).collect()
+            ])
+        );
+
+        // Tools that clean up themselves get them when cleaning alone
+        let dir = fixture_for(&ProjectType::Rust);
+        let (_, args) = build_project_command(
+            &ProjectType::Rust,
+            dir.path().to_str().unwrap(),
+            &selection(&[Operation::Clean]),
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(args, ["clean", "--verbose"]);
+    }
 }
@@ -0,0 +1,50 @@
+use crate::models::SelectedOption;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Where the last selection for each tool is kept.
+pub fn selections_file() -> anyhow::Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home_dir.join(".app-hoist").join("selections.json"))
+}
+
+fn key(executable: &str, subcommand: Option<&str>) -> String {
+    match subcommand {
+        Some(subcommand) => format!("{} {}", executable, subcommand),
+        None => executable.to_string(),
+    }
+}
+
+fn load_all() -> anyhow::Result<BTreeMap<String, Vec<SelectedOption>>> {
+    let path = selections_file()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// The options chosen the last time this tool (and subcommand) was hoisted.
+/// A missing or unreadable file just means there is nothing to recall.
+pub fn load(executable: &str, subcommand: Option<&str>) -> Vec<SelectedOption> {
+    load_all()
+        .ok()
+        .and_then(|mut all| all.remove(&key(executable, subcommand)))
+        .unwrap_or_default()
+}
+
+pub fn save(
+    executable: &str,
+    subcommand: Option<&str>,
+    selected: &[SelectedOption],
+) -> anyhow::Result<()> {
+    let mut all = load_all().unwrap_or_default();
+    all.insert(key(executable, subcommand), selected.to_vec());
+
+    let path = selections_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&all)?)?;
+    Ok(())
+}
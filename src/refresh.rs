@@ -0,0 +1,212 @@
+use crate::cache::{CacheManager, ProjectCache, Revalidation};
+use crate::project::{cached_package_manager, detect_entry_point, detect_project_type};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+// Keep startup refresh cheap: a few paths, a couple of seconds at most
+pub const REFRESH_LIMIT: usize = 5;
+pub const REFRESH_BUDGET: Duration = Duration::from_secs(2);
+
+/// A cache refresh running alongside the interactive menu. Dropping it stops
+/// the refresh before the next project.
+pub struct BackgroundRefresh {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl BackgroundRefresh {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BackgroundRefresh {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Start re-validating the cache entries for `paths` without blocking the caller.
+pub fn spawn_refresh(paths: Vec<String>, budget: Duration) -> BackgroundRefresh {
+    spawn_refresh_with(paths, budget, CacheManager::new).0
+}
+
+/// `spawn_refresh` with the cache `open` returns, and a handle resolving to
+/// how many entries ended up warm.
+fn spawn_refresh_with(
+    paths: Vec<String>,
+    budget: Duration,
+    open: impl FnOnce() -> anyhow::Result<CacheManager> + Send + 'static,
+) -> (BackgroundRefresh, JoinHandle<usize>) {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&cancelled);
+
+    let handle = tokio::task::spawn_blocking(move || {
+        let Ok(mut cache_manager) = open() else {
+            return 0;
+        };
+        refresh_entries(&mut cache_manager, &paths, Instant::now() + budget, &flag)
+    });
+
+    (BackgroundRefresh { cancelled }, handle)
+}
+
+pub fn refresh_entries(
+    cache_manager: &mut CacheManager,
+    paths: &[String],
+    deadline: Instant,
+    cancelled: &AtomicBool,
+) -> usize {
+    let mut warm = 0;
+
+    for path in paths {
+        if cancelled.load(Ordering::Relaxed) || Instant::now() >= deadline {
+            break;
+        }
+
+        let previous = match cache_manager.revalidate(path) {
+            Revalidation::Warm => {
+                warm += 1;
+                continue;
+            }
+            Revalidation::Changed(previous) => Some(previous),
+            Revalidation::Missing => None,
+        };
+
+        // Detect again, keeping what earlier runs remembered about the project
+        let (Ok(project_type), Ok(entry_point)) =
+            (detect_project_type(path), detect_entry_point(path))
+        else {
+            continue;
+        };
+        // A changed type is left for the next foreground run to settle with the user
+        if previous
+            .as_ref()
+            .is_some_and(|previous| previous.project_type != project_type)
+        {
+            continue;
+        }
+        let mut cache = ProjectCache::new(project_type.clone(), entry_point);
+        // The lockfile that decides the package manager may be what changed
+        cache.package_manager = cached_package_manager(&project_type, path);
+        if let Some(previous) = previous {
+            cache.metadata = previous.metadata;
+        }
+        let refreshed = cache_manager.set(path.clone(), cache).is_ok();
+
+        if refreshed {
+            warm += 1;
+        }
+    }
+
+    let _ = cache_manager.flush();
+    warm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::sync::mpsc;
+
+    /// A Rust project and a cache holding an expired entry for it.
+    fn expired_project(dir: &Path) -> (String, std::path::PathBuf) {
+        let project = dir.join("project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(project.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let project = project.to_str().unwrap().to_string();
+
+        let cache_dir = dir.join("cache");
+        let mut cache_manager = CacheManager::in_dir(cache_dir.clone()).unwrap();
+        cache_manager
+            .set(
+                project.clone(),
+                ProjectCache::new(crate::models::ProjectType::Rust, "src/main.rs".to_string()),
+            )
+            .unwrap();
+        cache_manager.flush().unwrap();
+        drop(cache_manager);
+
+        // Backdate the entry on disk, as if written a week ago
+        for entry in walkdir::WalkDir::new(&cache_dir).into_iter().flatten() {
+            if entry.file_type().is_file() {
+                let text = std::fs::read_to_string(entry.path()).unwrap();
+                let mut json: serde_json::Value = serde_json::from_str(&text).unwrap();
+                json["last_updated"] = serde_json::json!(1);
+                std::fs::write(entry.path(), json.to_string()).unwrap();
+            }
+        }
+        (project, cache_dir)
+    }
+
+    /// The project's entry as written to disk, however old.
+    fn last_updated(cache_dir: &Path, project: &str) -> u64 {
+        let file = cache_dir.join(format!("{:016x}.json", crate::cache::path_hash(project)));
+        let text = std::fs::read_to_string(file).unwrap();
+        serde_json::from_str::<ProjectCache>(&text)
+            .unwrap()
+            .last_updated
+    }
+
+    #[tokio::test]
+    async fn refresh_warms_expired_entries_while_the_menu_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, cache_dir) = expired_project(dir.path());
+        assert_eq!(last_updated(&cache_dir, &project), 1);
+
+        // The refresh can't start until the menu has shown, so a menu that
+        // waited on it would never get there
+        let (menu_shown, wait_for_menu) = mpsc::channel::<()>();
+        let open_dir = cache_dir.clone();
+        let (_refresh, handle) =
+            spawn_refresh_with(vec![project.clone()], REFRESH_BUDGET, move || {
+                wait_for_menu.recv().ok();
+                CacheManager::in_dir(open_dir)
+            });
+
+        let menu = || "selected";
+        assert_eq!(menu(), "selected");
+        menu_shown.send(()).unwrap();
+
+        assert_eq!(handle.await.unwrap(), 1);
+        assert!(last_updated(&cache_dir, &project) > 1);
+    }
+
+    #[test]
+    fn cancelled_refreshes_leave_entries_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, cache_dir) = expired_project(dir.path());
+        let mut cache_manager = CacheManager::in_dir(cache_dir.clone()).unwrap();
+
+        let warm = refresh_entries(
+            &mut cache_manager,
+            std::slice::from_ref(&project),
+            Instant::now() + REFRESH_BUDGET,
+            &AtomicBool::new(true),
+        );
+        drop(cache_manager);
+        assert_eq!(warm, 0);
+        assert_eq!(last_updated(&cache_dir, &project), 1);
+    }
+
+    #[test]
+    fn refreshes_stop_at_the_deadline() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, cache_dir) = expired_project(dir.path());
+        let mut cache_manager = CacheManager::in_dir(cache_dir).unwrap();
+
+        let warm = refresh_entries(
+            &mut cache_manager,
+            &[project],
+            Instant::now(),
+            &AtomicBool::new(false),
+        );
+        assert_eq!(warm, 0);
+    }
+}
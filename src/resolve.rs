@@ -0,0 +1,323 @@
+use crate::cli::OutputFormat;
+use crate::installed;
+use crate::ui;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where a candidate executable was found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Source {
+    /// Given as a path rather than a name
+    Explicit,
+    /// An entry of $PATH, with its position
+    Path { index: usize, dir: String },
+    /// Cargo's bin directory when it isn't on $PATH
+    CargoBin { dir: String },
+    /// A binary app-hoist installed, wherever it ended up
+    Installed,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Explicit => write!(f, "explicit path"),
+            Source::Path { index, dir } => write!(f, "PATH[{}] {}", index, dir),
+            Source::CargoBin { dir } => write!(f, "cargo bin {} (not on PATH)", dir),
+            Source::Installed => write!(f, "install manifest"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub path: String,
+    pub source: Source,
+    /// Project it was installed from, when app-hoist installed it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_from: Option<String>,
+    /// First line of `--version`, filled in by `probe_versions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether this is the one app-hoist runs
+    pub selected: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Resolution {
+    pub name: String,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Resolution {
+    pub fn selected(&self) -> Option<&Candidate> {
+        self.candidates.iter().find(|candidate| candidate.selected)
+    }
+}
+
+/// Every executable `name` could mean, in the order app-hoist prefers them:
+/// $PATH entries first, then cargo's bin directory, then binaries from the
+/// install manifest that are in neither. The first is marked selected.
+pub fn resolve(name: &str) -> Resolution {
+    let mut candidates = Vec::new();
+
+    if is_path(name) {
+        if let Some(file) = with_extensions(Path::new(name))
+            .into_iter()
+            .find(|file| is_executable(file))
+        {
+            candidates.push(candidate(
+                file.to_string_lossy().into_owned(),
+                Source::Explicit,
+            ));
+        }
+    } else {
+        let path_dirs: Vec<PathBuf> = std::env::var_os("PATH")
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+
+        for (index, dir) in path_dirs.iter().enumerate() {
+            let Some(file) = with_extensions(&dir.join(name))
+                .into_iter()
+                .find(|file| is_executable(file))
+            else {
+                continue;
+            };
+            candidates.push(candidate(
+                file.to_string_lossy().into_owned(),
+                Source::Path {
+                    index,
+                    dir: dir.to_string_lossy().into_owned(),
+                },
+            ));
+        }
+
+        if let Some(dir) = cargo_bin_dir()
+            && !path_dirs.iter().any(|entry| same_dir(entry, &dir))
+        {
+            let file = dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+            if is_executable(&file) {
+                candidates.push(candidate(
+                    file.to_string_lossy().into_owned(),
+                    Source::CargoBin {
+                        dir: dir.to_string_lossy().into_owned(),
+                    },
+                ));
+            }
+        }
+
+        for entry in installed::load().unwrap_or_default() {
+            if entry.name != name {
+                continue;
+            }
+            match candidates
+                .iter_mut()
+                .find(|c| same_file(&c.path, &entry.path))
+            {
+                Some(existing) => existing.installed_from = Some(entry.project.clone()),
+                None if is_executable(Path::new(&entry.path)) => {
+                    let mut installed = candidate(entry.path.clone(), Source::Installed);
+                    installed.installed_from = Some(entry.project.clone());
+                    candidates.push(installed);
+                }
+                None => {}
+            }
+        }
+    }
+
+    // PATH may list a directory twice; the later entry never wins
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|c| seen.insert(c.path.clone()));
+
+    if let Some(first) = candidates.first_mut() {
+        first.selected = true;
+    }
+
+    Resolution {
+        name: name.to_string(),
+        candidates,
+    }
+}
+
+fn candidate(path: String, source: Source) -> Candidate {
+    Candidate {
+        path,
+        source,
+        installed_from: None,
+        version: None,
+        selected: false,
+    }
+}
+
+/// Whether `name` is a path such as `./target/release/tool` rather than a
+/// bare command name.
+pub fn is_path(name: &str) -> bool {
+    name.contains(std::path::MAIN_SEPARATOR) || name.contains('/')
+}
+
+/// The names of every executable on $PATH, each once and sorted. Only the
+/// directory listings are read, plus the executable bit of names not seen
+/// yet, so this stays quick with thousands of entries.
+pub fn path_executables() -> Vec<String> {
+    let mut names = std::collections::BTreeSet::new();
+    let dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(name) = command_name(&path)
+                && !names.contains(&name)
+                && is_executable(&path)
+            {
+                names.insert(name);
+            }
+        }
+    }
+
+    names.into_iter().collect()
+}
+
+/// The name a file on PATH is run by: its file name, without the
+/// %PATHEXT% extension on Windows.
+#[cfg(windows)]
+fn command_name(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| has_path_extension(ext))?;
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
+
+#[cfg(not(windows))]
+fn command_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+#[cfg(windows)]
+fn path_extensions() -> Vec<String> {
+    std::env::var("PATHEXT")
+        .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(windows)]
+fn has_path_extension(ext: &str) -> bool {
+    path_extensions()
+        .iter()
+        .any(|known| known.trim_start_matches('.').eq_ignore_ascii_case(ext))
+}
+
+/// The files `path` may refer to. On Windows a name without an extension
+/// tries each of %PATHEXT%, the way cmd.exe does.
+#[cfg(windows)]
+fn with_extensions(path: &Path) -> Vec<PathBuf> {
+    let has_known_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(has_path_extension);
+    if has_known_extension {
+        return vec![path.to_path_buf()];
+    }
+
+    path_extensions()
+        .iter()
+        .map(|ext| {
+            let mut file = path.as_os_str().to_owned();
+            file.push(ext);
+            PathBuf::from(file)
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn with_extensions(path: &Path) -> Vec<PathBuf> {
+    vec![path.to_path_buf()]
+}
+
+fn cargo_bin_dir() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+        .map(|home| home.join("bin"))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    same_dir(Path::new(a), Path::new(b))
+}
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// How long `--version` gets before a candidate is shown without one.
+const VERSION_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Fill in each candidate's version from `--version`, skipping any that fail,
+/// hang or print something that doesn't look like a version line.
+pub async fn probe_versions(resolution: &mut Resolution) {
+    for candidate in &mut resolution.candidates {
+        candidate.version = probe_version(&candidate.path).await;
+    }
+}
+
+async fn probe_version(path: &str) -> Option<String> {
+    let output = tokio::process::Command::new(path)
+        .arg("--version")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(VERSION_PROBE_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    (line.len() <= 120 && line.chars().any(|c| c.is_ascii_digit())).then(|| line.to_string())
+}
+
+pub fn print_resolution(resolution: &Resolution, format: OutputFormat) -> anyhow::Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(resolution)?);
+        return Ok(());
+    }
+
+    for candidate in &resolution.candidates {
+        let marker = if candidate.selected { "*" } else { " " };
+        println!("{} {}", marker, candidate.path);
+        println!("    from:    {}", candidate.source);
+        if let Some(project) = &candidate.installed_from {
+            println!("    install: app-hoist, from {}", project);
+        }
+        if let Some(version) = &candidate.version {
+            println!("    version: {}", version);
+        }
+    }
+    if resolution.candidates.len() > 1 {
+        ui::status("🎯 * marks the one app-hoist runs");
+    }
+    Ok(())
+}
@@ -0,0 +1,441 @@
+use crate::ui;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike, value};
+
+const PROJECT_CONFIG_FILE: &str = ".app-hoist.toml";
+
+/// Saved answers to the `docker run` prompts for one project.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunProfile {
+    pub ports: Vec<String>,
+    pub env: Vec<String>,
+    pub volumes: Vec<String>,
+    pub extra_args: Vec<String>,
+    pub image_tag: Option<String>,
+}
+
+impl RunProfile {
+    pub fn to_run_args(&self, image_name: &str) -> Vec<String> {
+        let mut args = vec!["run".to_string(), "-it".to_string(), "--rm".to_string()];
+
+        for port in &self.ports {
+            args.push("-p".to_string());
+            args.push(port.clone());
+        }
+        for env in &self.env {
+            args.push("-e".to_string());
+            args.push(env.clone());
+        }
+        for volume in &self.volumes {
+            args.push("-v".to_string());
+            args.push(volume.clone());
+        }
+        args.extend(self.extra_args.iter().cloned());
+
+        match &self.image_tag {
+            Some(tag) => args.push(format!("{}:{}", image_name, tag)),
+            None => args.push(image_name.to_string()),
+        }
+
+        args
+    }
+}
+
+pub fn config_path(project_path: &str) -> PathBuf {
+    Path::new(project_path).join(PROJECT_CONFIG_FILE)
+}
+
+pub fn load_profiles(project_path: &str) -> anyhow::Result<BTreeMap<String, RunProfile>> {
+    let path = config_path(project_path);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let document: DocumentMut = std::fs::read_to_string(&path)?.parse()?;
+    Ok(profiles_from_document(&document))
+}
+
+pub fn profiles_from_document(document: &DocumentMut) -> BTreeMap<String, RunProfile> {
+    let mut profiles = BTreeMap::new();
+
+    let Some(table) = document
+        .get("docker")
+        .and_then(|docker| docker.get("profiles"))
+        .and_then(Item::as_table_like)
+    else {
+        return profiles;
+    };
+
+    for (name, item) in table.iter() {
+        let string_list = |key: &str| -> Vec<String> {
+            item.get(key)
+                .and_then(Item::as_array)
+                .map(|array| {
+                    array
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        profiles.insert(
+            name.to_string(),
+            RunProfile {
+                ports: string_list("ports"),
+                env: string_list("env"),
+                volumes: string_list("volumes"),
+                extra_args: string_list("extra_args"),
+                image_tag: item
+                    .get("image_tag")
+                    .and_then(Item::as_str)
+                    .map(str::to_string),
+            },
+        );
+    }
+
+    profiles
+}
+
+/// Write a profile into the document, keeping everything else (including comments) intact.
+/// Profiles written as inline tables stay inline.
+pub fn set_profile_in_document(
+    document: &mut DocumentMut,
+    name: &str,
+    profile: &RunProfile,
+) -> anyhow::Result<()> {
+    let implicit = || {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+    };
+    let docker = child_table(document.as_table_mut(), "docker", implicit)?;
+    let profiles = child_table(docker, "profiles", implicit)?;
+    let entry = child_table(profiles, name, || Item::Table(Table::new()))?;
+
+    let to_array = |values: &[String]| -> Item {
+        let mut array = Array::new();
+        for v in values {
+            array.push(v.as_str());
+        }
+        value(array)
+    };
+
+    // Replacing values in place keeps the comments above their keys
+    let mut set = |key: &str, item: Item| match entry.get_mut(key) {
+        Some(existing) => *existing = item,
+        None => {
+            entry.insert(key, item);
+        }
+    };
+    set("ports", to_array(&profile.ports));
+    set("env", to_array(&profile.env));
+    set("volumes", to_array(&profile.volumes));
+    set("extra_args", to_array(&profile.extra_args));
+    match &profile.image_tag {
+        Some(tag) => set("image_tag", value(tag.as_str())),
+        None => {
+            entry.remove("image_tag");
+        }
+    }
+
+    Ok(())
+}
+
+/// The table under `key`, added with `new` when missing. Inline parents
+/// turn the new table into an inline one.
+fn child_table<'a>(
+    parent: &'a mut dyn TableLike,
+    key: &str,
+    new: impl FnOnce() -> Item,
+) -> anyhow::Result<&'a mut dyn TableLike> {
+    if !parent.contains_key(key) {
+        parent.insert(key, new());
+    }
+    parent
+        .get_mut(key)
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| anyhow::anyhow!("'{}' in {} is not a table", key, PROJECT_CONFIG_FILE))
+}
+
+pub fn save_profile(project_path: &str, name: &str, profile: &RunProfile) -> anyhow::Result<()> {
+    let path = config_path(project_path);
+    let mut document: DocumentMut = if path.exists() {
+        std::fs::read_to_string(&path)?.parse()?
+    } else {
+        DocumentMut::new()
+    };
+
+    set_profile_in_document(&mut document, name, profile)?;
+    std::fs::write(&path, document.to_string())?;
+
+    Ok(())
+}
+
+/// Pick the profile for a `docker run`: an explicit `--docker-profile` wins,
+/// otherwise the user chooses among saved profiles or answers the prompts.
+pub fn resolve_run_profile(
+    project_path: &str,
+    requested: Option<&str>,
+    interactive: bool,
+) -> anyhow::Result<RunProfile> {
+    let profiles = load_profiles(project_path)?;
+
+    if let Some(name) = requested {
+        return profiles.get(name).cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Docker profile '{}' not found in {}",
+                name,
+                config_path(project_path).display()
+            )
+        });
+    }
+
+    if !interactive {
+        return Ok(RunProfile::default());
+    }
+
+    let mut choices: Vec<String> = Vec::new();
+    for name in profiles.keys() {
+        choices.push(format!("Use profile {}", name));
+        choices.push(format!("Edit profile {}", name));
+    }
+    choices.push("New profile".to_string());
+    choices.push("Run without a profile".to_string());
+
+    let choice = if profiles.is_empty() {
+        if ui::confirm(
+            "Configure ports, environment, or volumes for this run?",
+            false,
+        )? {
+            "New profile".to_string()
+        } else {
+            "Run without a profile".to_string()
+        }
+    } else {
+        ui::select("Select a run profile:", choices)?
+    };
+
+    if let Some(name) = choice.strip_prefix("Use profile ") {
+        return Ok(profiles[name].clone());
+    }
+
+    if let Some(name) = choice.strip_prefix("Edit profile ") {
+        let profile = prompt_profile(&profiles[name])?;
+        save_profile(project_path, name, &profile)?;
+        ui::status(&format!("✅ Updated profile '{}'", name));
+        return Ok(profile);
+    }
+
+    if choice == "New profile" {
+        let profile = prompt_profile(&RunProfile::default())?;
+        let name = ui::text("Save as profile (leave empty to skip):", None)?;
+        if !name.trim().is_empty() {
+            save_profile(project_path, name.trim(), &profile)?;
+            ui::status(&format!("✅ Saved profile '{}'", name.trim()));
+        }
+        return Ok(profile);
+    }
+
+    Ok(RunProfile::default())
+}
+
+fn prompt_profile(current: &RunProfile) -> anyhow::Result<RunProfile> {
+    let prompt_list = |message: &str, values: &[String]| -> anyhow::Result<Vec<String>> {
+        let input = ui::text(message, Some(&values.join(", ")))?;
+        Ok(input
+            .split(',')
+            .map(|part| part.trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect())
+    };
+
+    let ports = prompt_list(
+        "Port mappings (e.g. 8080:80, comma-separated):",
+        &current.ports,
+    )?;
+    let env = prompt_list(
+        "Environment variables (KEY=VALUE, comma-separated):",
+        &current.env,
+    )?;
+    let volumes = prompt_list(
+        "Volumes (host:container, comma-separated):",
+        &current.volumes,
+    )?;
+    let extra_args = ui::text(
+        "Extra docker run arguments:",
+        Some(&current.extra_args.join(" ")),
+    )?
+    .split_whitespace()
+    .map(str::to_string)
+    .collect();
+    let image_tag = ui::text(
+        "Image tag (leave empty for latest):",
+        Some(current.image_tag.as_deref().unwrap_or("")),
+    )?;
+
+    Ok(RunProfile {
+        ports,
+        env,
+        volumes,
+        extra_args,
+        image_tag: Some(image_tag.trim().to_string()).filter(|tag| !tag.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dev_profile() -> RunProfile {
+        RunProfile {
+            ports: vec!["8080:80".to_string()],
+            env: vec!["RUST_LOG=debug".to_string()],
+            volumes: vec!["./data:/data".to_string()],
+            extra_args: vec!["--init".to_string()],
+            image_tag: Some("dev".to_string()),
+        }
+    }
+
+    fn parse(text: &str) -> DocumentMut {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn profiles_round_trip_through_the_document() {
+        let mut document = DocumentMut::new();
+        set_profile_in_document(&mut document, "dev", &dev_profile()).unwrap();
+        set_profile_in_document(&mut document, "ci", &RunProfile::default()).unwrap();
+
+        let reparsed = parse(&document.to_string());
+        let profiles = profiles_from_document(&reparsed);
+        assert_eq!(profiles["dev"], dev_profile());
+        assert_eq!(profiles["ci"], RunProfile::default());
+        assert!(document.to_string().contains("[docker.profiles.dev]"));
+    }
+
+    #[test]
+    fn editing_keeps_comments_and_other_settings() {
+        let mut document = parse(
+            "# project settings\n\
+             [build]\n\
+             jobs = 2 # keep this\n\
+             \n\
+             [docker.profiles.dev]\n\
+             # the usual ports\n\
+             ports = [\"3000:3000\"]\n",
+        );
+        set_profile_in_document(&mut document, "dev", &dev_profile()).unwrap();
+
+        let text = document.to_string();
+        assert!(text.starts_with("# project settings\n[build]\njobs = 2 # keep this\n"));
+        assert!(text.contains("# the usual ports\nports = [\"8080:80\"]"));
+        assert_eq!(profiles_from_document(&parse(&text))["dev"], dev_profile());
+    }
+
+    #[test]
+    fn clearing_the_image_tag_removes_it() {
+        let mut document = DocumentMut::new();
+        set_profile_in_document(&mut document, "dev", &dev_profile()).unwrap();
+        let untagged = RunProfile {
+            image_tag: None,
+            ..dev_profile()
+        };
+        set_profile_in_document(&mut document, "dev", &untagged).unwrap();
+        assert!(!document.to_string().contains("image_tag"));
+    }
+
+    #[test]
+    fn inline_profiles_are_updated_in_place() {
+        let mut document = parse("docker = { profiles = { dev = { ports = [\"1:1\"] } } }\n");
+        set_profile_in_document(&mut document, "dev", &dev_profile()).unwrap();
+        set_profile_in_document(&mut document, "ci", &RunProfile::default()).unwrap();
+
+        let text = document.to_string();
+        assert!(text.starts_with("docker = {"), "{}", text);
+        let profiles = profiles_from_document(&parse(&text));
+        assert_eq!(profiles["dev"], dev_profile());
+        assert_eq!(profiles["ci"], RunProfile::default());
+    }
+
+    #[test]
+    fn non_table_keys_are_an_error() {
+        let mut document = parse("docker = \"compose\"\n");
+        let err = set_profile_in_document(&mut document, "dev", &dev_profile()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'docker' in .app-hoist.toml is not a table"
+        );
+
+        let mut document = parse("[docker]\nprofiles = [1, 2]\n");
+        assert!(set_profile_in_document(&mut document, "dev", &dev_profile()).is_err());
+    }
+
+    #[test]
+    fn run_args_put_the_image_last() {
+        assert_eq!(
+            dev_profile().to_run_args("api-app"),
+            [
+                "run",
+                "-it",
+                "--rm",
+                "-p",
+                "8080:80",
+                "-e",
+                "RUST_LOG=debug",
+                "-v",
+                "./data:/data",
+                "--init",
+                "api-app:dev"
+            ]
+        );
+        assert_eq!(
+            RunProfile::default().to_run_args("api-app"),
+            ["run", "-it", "--rm", "api-app"]
+        );
+    }
+
+    #[test]
+    fn a_requested_profile_wins_over_prompting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        save_profile(path, "dev", &dev_profile()).unwrap();
+
+        // Interactive or not, the named profile is used without asking
+        assert_eq!(
+            resolve_run_profile(path, Some("dev"), true).unwrap(),
+            dev_profile()
+        );
+        assert_eq!(
+            resolve_run_profile(path, Some("dev"), false).unwrap(),
+            dev_profile()
+        );
+    }
+
+    #[test]
+    fn a_missing_requested_profile_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        save_profile(path, "dev", &dev_profile()).unwrap();
+
+        let err = resolve_run_profile(path, Some("prod"), false).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Docker profile 'prod' not found")
+        );
+    }
+
+    #[test]
+    fn unattended_runs_without_a_request_use_no_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        save_profile(path, "dev", &dev_profile()).unwrap();
+
+        assert_eq!(
+            resolve_run_profile(path, None, false).unwrap(),
+            RunProfile::default()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ui;
 use anyhow::anyhow;
 use std::collections::HashMap;
 use std::fs;
@@ -58,17 +59,24 @@ pub fn init_project_from_template(template_name: &str, target_path: &str) -> any
         }
     };
 
+    for (first, second) in case_collisions(&config.variables) {
+        ui::status(&format!(
+            "⚠️  Variables '{}' and '{}' differ only by case",
+            first, second
+        ));
+    }
+
     // Collect variable values
     let variables = collect_template_variables(&config)?;
 
     // Copy and process template files
     copy_template_files(&template_dir, target_path, &variables)?;
 
-    println!(
+    ui::status(&format!(
         "✅ Successfully initialized project from template '{}'",
         template_name
-    );
-    println!("📁 Project created at: {}", target_path);
+    ));
+    ui::status(&format!("📁 Project created at: {}", target_path));
 
     Ok(())
 }
@@ -85,32 +93,60 @@ pub fn create_template_from_project(project_path: &str, template_name: &str) ->
     // Copy project files (excluding common ignore patterns)
     copy_project_to_template(project_path, &template_dir)?;
 
+    // Offer to turn detected names into variables
+    let mut variables = HashMap::new();
+    let candidates = detect_variable_candidates(project_path);
+    if !candidates.is_empty() {
+        let labels: Vec<String> = candidates.iter().map(|c| c.to_string()).collect();
+        let chosen =
+            ui::multi_select("Turn any of these into template variables?", labels.clone())?;
+
+        for candidate in candidates
+            .into_iter()
+            .zip(&labels)
+            .filter(|(_, label)| chosen.contains(label))
+            .map(|(candidate, _)| candidate)
+        {
+            parameterize_files(&template_dir, &candidate.value, &candidate.name)?;
+            variables.insert(
+                candidate.name,
+                TemplateVariable {
+                    description: candidate.description,
+                    default: candidate.value,
+                },
+            );
+        }
+    }
+
     // Create basic template config
     let config = TemplateConfig {
         name: template_name.to_string(),
         description: format!("Template created from {}", project_path),
         language: detect_project_language(project_path)?,
         tags: vec!["custom".to_string()],
-        variables: HashMap::new(),
+        variables,
     };
 
     save_template_config(&template_dir.join("template.toml"), &config)?;
 
-    println!(
+    ui::status(&format!(
         "✅ Successfully created template '{}' from project",
         template_name
-    );
-    println!("📁 Template stored at: {}", template_dir.display());
+    ));
+    ui::status(&format!(
+        "📁 Template stored at: {}",
+        template_dir.display()
+    ));
 
     Ok(())
 }
 
-fn get_template_dir() -> anyhow::Result<PathBuf> {
+pub fn get_template_dir() -> anyhow::Result<PathBuf> {
     let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not find home directory"))?;
     Ok(home_dir.join(".app-hoist").join("templates"))
 }
 
-fn load_template_config(path: &Path) -> anyhow::Result<TemplateConfig> {
+pub fn load_template_config(path: &Path) -> anyhow::Result<TemplateConfig> {
     let content = fs::read_to_string(path)?;
     let value: toml::Value = toml::from_str(&content)?;
 
@@ -171,6 +207,7 @@ fn load_template_config(path: &Path) -> anyhow::Result<TemplateConfig> {
     } else {
         HashMap::new()
     };
+    validate_variable_names(&variables).map_err(|err| anyhow!("{} in {}", err, path.display()))?;
 
     Ok(TemplateConfig {
         name,
@@ -222,6 +259,64 @@ fn save_template_config(path: &Path, config: &TemplateConfig) -> anyhow::Result<
     Ok(())
 }
 
+/// Variables every template can reference without declaring them.
+pub const BUILTIN_VARIABLES: [&str; 2] = ["project_name", "year"];
+
+/// Declared names must be identifiers, the only thing `{{name}}` is matched
+/// against, and must not shadow a built-in.
+pub fn validate_variable_names(
+    variables: &HashMap<String, TemplateVariable>,
+) -> anyhow::Result<()> {
+    let mut names: Vec<&String> = variables.keys().collect();
+    names.sort();
+
+    let mut problems = Vec::new();
+    for name in names {
+        if !is_identifier(name) {
+            problems.push(format!(
+                "variable '{}' is not a valid name (use letters, digits and '_', not starting with a digit)",
+                name
+            ));
+        } else if BUILTIN_VARIABLES.contains(&name.as_str()) {
+            problems.push(format!(
+                "variable '{}' collides with a built-in (built-ins: {})",
+                name,
+                BUILTIN_VARIABLES.join(", ")
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(anyhow!("{}", problems.join("; ")));
+    }
+    Ok(())
+}
+
+/// Pairs of declared names that differ only by case, which are easy to mix
+/// up in `{{...}}` references.
+pub fn case_collisions(variables: &HashMap<String, TemplateVariable>) -> Vec<(String, String)> {
+    let mut names: Vec<&String> = variables.keys().collect();
+    names.sort();
+
+    let mut collisions = Vec::new();
+    for (i, first) in names.iter().enumerate() {
+        for second in &names[i + 1..] {
+            if first.eq_ignore_ascii_case(second) {
+                collisions.push((first.to_string(), second.to_string()));
+            }
+        }
+    }
+    collisions
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 fn collect_template_variables(config: &TemplateConfig) -> anyhow::Result<HashMap<String, String>> {
     let mut variables = HashMap::new();
 
@@ -234,14 +329,16 @@ fn collect_template_variables(config: &TemplateConfig) -> anyhow::Result<HashMap
 
     // Collect user-defined variables
     for (key, var_config) in &config.variables {
-        let value = inquire::Text::new(&var_config.description)
-            .with_default(&var_config.default)
-            .prompt()
-            .unwrap_or_else(|_| {
+        let value = match ui::text(&var_config.description, Some(&var_config.default)) {
+            Ok(value) => value,
+            // An unattended prompt ends the run rather than taking every default
+            Err(e) if e.is::<ui::PromptTimedOut>() => return Err(e),
+            Err(_) => {
                 // Fallback to default value if interactive prompt fails
                 println!("Using default value for '{}': {}", key, var_config.default);
                 var_config.default.clone()
-            });
+            }
+        };
         variables.insert(key.clone(), value);
     }
 
@@ -271,16 +368,20 @@ fn copy_template_files(
         if path.is_dir() {
             fs::create_dir_all(&target_file)?;
         } else {
-            // Read and process template file
-            let content = fs::read_to_string(path)?;
-            let processed_content = process_template_content(&content, variables)?;
-
             // Ensure parent directory exists
             if let Some(parent) = target_file.parent() {
                 fs::create_dir_all(parent)?;
             }
 
-            fs::write(&target_file, processed_content)?;
+            // Process text files; copy binary files through untouched
+            let bytes = fs::read(path)?;
+            match String::from_utf8(bytes) {
+                Ok(content) => {
+                    let processed_content = process_template_content(&content, variables)?;
+                    fs::write(&target_file, processed_content)?;
+                }
+                Err(err) => fs::write(&target_file, err.into_bytes())?,
+            }
         }
     }
 
@@ -313,28 +414,26 @@ fn copy_project_to_template(project_path: &str, template_dir: &Path) -> anyhow::
         ".env",
     ];
 
-    for entry in walkdir::WalkDir::new(project_path) {
+    // Ignored and hidden directories are skipped along with everything in them
+    let ignored = |entry: &walkdir::DirEntry| {
+        let file_name = entry.file_name().to_str().unwrap_or("");
+        entry.depth() > 0
+            && (ignore_patterns.iter().any(|pattern| {
+                if pattern.starts_with("*.") {
+                    file_name.ends_with(&pattern[1..])
+                } else {
+                    file_name == *pattern
+                }
+            }) || (file_name.starts_with('.') && file_name != ".gitignore"))
+    };
+
+    for entry in walkdir::WalkDir::new(project_path)
+        .into_iter()
+        .filter_entry(|entry| !ignored(entry))
+    {
         let entry = entry?;
         let path = entry.path();
 
-        // Skip ignored files/directories
-        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-        if ignore_patterns.iter().any(|pattern| {
-            if pattern.starts_with("*.") {
-                file_name.ends_with(&pattern[1..])
-            } else {
-                file_name == *pattern
-            }
-        }) {
-            continue;
-        }
-
-        // Skip hidden files/directories
-        if file_name.starts_with('.') && file_name != ".gitignore" {
-            continue;
-        }
-
         // Calculate relative path and target
         let relative_path = path.strip_prefix(project_path)?;
         let target_path = template_dir.join(relative_path);
@@ -349,6 +448,125 @@ fn copy_project_to_template(project_path: &str, template_dir: &Path) -> anyhow::
     Ok(())
 }
 
+/// A literal in the project that is a good candidate for a template variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableCandidate {
+    pub name: String,
+    pub value: String,
+    pub description: String,
+}
+
+impl std::fmt::Display for VariableCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = \"{}\" ({})",
+            self.name, self.value, self.description
+        )
+    }
+}
+
+/// Names from the project's manifests that usually need changing per project.
+pub fn detect_variable_candidates(project_path: &str) -> Vec<VariableCandidate> {
+    let root = Path::new(project_path);
+    let mut candidates = Vec::new();
+    let mut add = |name: &str, value: Option<String>, description: &str| {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            candidates.push(VariableCandidate {
+                name: name.to_string(),
+                value,
+                description: description.to_string(),
+            });
+        }
+    };
+
+    let toml_string = |file: &str, table: &str, key: &str| -> Option<String> {
+        let content = fs::read_to_string(root.join(file)).ok()?;
+        let value: toml::Value = toml::from_str(&content).ok()?;
+        value.get(table)?.get(key)?.as_str().map(str::to_string)
+    };
+
+    add(
+        "crate_name",
+        toml_string("Cargo.toml", "package", "name"),
+        "Cargo package name",
+    );
+    add(
+        "module_path",
+        fs::read_to_string(root.join("go.mod"))
+            .ok()
+            .and_then(|content| {
+                content
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix("module "))
+                    .map(|module| module.trim().to_string())
+            }),
+        "Go module path",
+    );
+    add(
+        "package_name",
+        fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|json| json.get("name")?.as_str().map(str::to_string)),
+        "package.json name",
+    );
+    add(
+        "python_package",
+        toml_string("pyproject.toml", "project", "name"),
+        "pyproject project name",
+    );
+
+    candidates
+}
+
+/// Replace whole-word occurrences of `literal` with `{{variable}}` in every
+/// text file under `dir`. Binary files are left alone.
+fn parameterize_files(dir: &Path, literal: &str, variable: &str) -> anyhow::Result<()> {
+    let placeholder = format!("{{{{{}}}}}", variable);
+
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+
+        let replaced = replace_whole_word(&content, literal, &placeholder);
+        if replaced != content {
+            fs::write(entry.path(), replaced)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace `literal` only where it isn't part of a longer identifier, so
+/// `app` doesn't rewrite `apple` or `my-app`.
+pub fn replace_whole_word(content: &str, literal: &str, replacement: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut result = String::with_capacity(content.len());
+    let mut copied = 0;
+
+    for (start, _) in content.match_indices(literal) {
+        let end = start + literal.len();
+        let before = content[..start].chars().next_back();
+        let after = content[end..].chars().next();
+
+        if start >= copied && !before.is_some_and(is_word) && !after.is_some_and(is_word) {
+            result.push_str(&content[copied..start]);
+            result.push_str(replacement);
+            copied = end;
+        }
+    }
+
+    result.push_str(&content[copied..]);
+    result
+}
+
 fn detect_project_language(project_path: &str) -> anyhow::Result<String> {
     // Simple language detection based on files present
     let project_path = Path::new(project_path);
@@ -372,3 +590,257 @@ fn detect_project_language(project_path: &str) -> anyhow::Result<String> {
         Ok("unknown".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_TOML: &str = "[package]\nname = \"hoist_demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nhoist_demo_macros = \"1\"\n";
+    const MAIN_RS: &str = "fn main() {\n    println!(\"hoist_demo {}\", hoist_demo::VERSION);\n}\n";
+
+    fn cargo_project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), CARGO_TOML).unwrap();
+        fs::write(dir.path().join("src/main.rs"), MAIN_RS).unwrap();
+        fs::write(dir.path().join("logo.bin"), [0xff, 0xfe, 0x00, 0x01]).unwrap();
+        fs::write(dir.path().join("target/debug/hoist_demo"), "build output").unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        dir
+    }
+
+    /// Make a template from `project` the way `template create` does, with
+    /// every detected candidate chosen.
+    fn make_template(project: &Path, template: &Path) -> TemplateConfig {
+        let project = project.to_str().unwrap();
+        copy_project_to_template(project, template).unwrap();
+
+        let mut variables = HashMap::new();
+        for candidate in detect_variable_candidates(project) {
+            parameterize_files(template, &candidate.value, &candidate.name).unwrap();
+            variables.insert(
+                candidate.name,
+                TemplateVariable {
+                    description: candidate.description,
+                    default: candidate.value,
+                },
+            );
+        }
+        let config = TemplateConfig {
+            name: "demo".to_string(),
+            description: String::new(),
+            language: detect_project_language(project).unwrap(),
+            tags: Vec::new(),
+            variables,
+        };
+        save_template_config(&template.join("template.toml"), &config).unwrap();
+        config
+    }
+
+    fn defaults(config: &TemplateConfig) -> HashMap<String, String> {
+        config
+            .variables
+            .iter()
+            .map(|(name, variable)| (name.clone(), variable.default.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn cargo_package_name_is_a_candidate() {
+        let project = cargo_project();
+        assert_eq!(
+            detect_variable_candidates(project.path().to_str().unwrap()),
+            [VariableCandidate {
+                name: "crate_name".to_string(),
+                value: "hoist_demo".to_string(),
+                description: "Cargo package name".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn other_manifests_give_their_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("go.mod"),
+            "module github.com/me/svc\n\ngo 1.22\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"name": "web"}"#).unwrap();
+        fs::write(
+            dir.path().join("pyproject.toml"),
+            "[project]\nname = \"tool\"\n",
+        )
+        .unwrap();
+
+        let values: Vec<(String, String)> =
+            detect_variable_candidates(dir.path().to_str().unwrap())
+                .into_iter()
+                .map(|candidate| (candidate.name, candidate.value))
+                .collect();
+        assert_eq!(
+            values,
+            [
+                ("module_path".to_string(), "github.com/me/svc".to_string()),
+                ("package_name".to_string(), "web".to_string()),
+                ("python_package".to_string(), "tool".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn template_files_use_placeholders() {
+        let project = cargo_project();
+        let template = tempfile::tempdir().unwrap();
+        make_template(project.path(), template.path());
+
+        let cargo_toml = fs::read_to_string(template.path().join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("name = \"{{crate_name}}\""));
+        // Longer names that merely start with the literal are left alone
+        assert!(cargo_toml.contains("hoist_demo_macros = \"1\""));
+        assert!(!template.path().join("target").exists());
+        assert!(!template.path().join("HEAD").exists());
+        assert!(!template.path().join(".git").exists());
+        assert_eq!(
+            fs::read(template.path().join("logo.bin")).unwrap(),
+            [0xff, 0xfe, 0x00, 0x01]
+        );
+
+        let config = load_template_config(&template.path().join("template.toml")).unwrap();
+        assert_eq!(config.variables["crate_name"].default, "hoist_demo");
+        assert_eq!(config.language, "rust");
+    }
+
+    #[test]
+    fn init_with_defaults_round_trips_to_the_original_project() {
+        let project = cargo_project();
+        let template = tempfile::tempdir().unwrap();
+        let config = make_template(project.path(), template.path());
+
+        let target = tempfile::tempdir().unwrap();
+        copy_template_files(
+            template.path(),
+            target.path().to_str().unwrap(),
+            &defaults(&config),
+        )
+        .unwrap();
+
+        for file in ["Cargo.toml", "src/main.rs", "logo.bin"] {
+            assert_eq!(
+                fs::read(target.path().join(file)).unwrap(),
+                fs::read(project.path().join(file)).unwrap(),
+                "{}",
+                file
+            );
+        }
+        assert!(!target.path().join("template.toml").exists());
+    }
+
+    #[test]
+    fn init_with_a_new_name_renames_the_project() {
+        let project = cargo_project();
+        let template = tempfile::tempdir().unwrap();
+        let config = make_template(project.path(), template.path());
+
+        let mut variables = defaults(&config);
+        variables.insert("crate_name".to_string(), "billing".to_string());
+        let target = tempfile::tempdir().unwrap();
+        copy_template_files(template.path(), target.path().to_str().unwrap(), &variables).unwrap();
+
+        assert_eq!(
+            detect_variable_candidates(target.path().to_str().unwrap())[0].value,
+            "billing"
+        );
+        assert_eq!(
+            fs::read_to_string(target.path().join("src/main.rs")).unwrap(),
+            MAIN_RS.replace("hoist_demo", "billing")
+        );
+    }
+
+    #[test]
+    fn whole_words_only_are_replaced() {
+        assert_eq!(
+            replace_whole_word("app apple my-app app_x (app)", "app", "{{name}}"),
+            "{{name}} apple my-app app_x ({{name}})"
+        );
+        assert_eq!(replace_whole_word("no match", "app", "x"), "no match");
+        assert_eq!(replace_whole_word("aaa", "aa", "x"), "aaa");
+    }
+
+    fn declared(names: &[&str]) -> HashMap<String, TemplateVariable> {
+        names
+            .iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    TemplateVariable {
+                        description: format!("The {}", name),
+                        default: String::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identifiers_are_valid_variable_names() {
+        assert!(validate_variable_names(&declared(&["author", "_private", "api_v2", "X"])).is_ok());
+        for name in ["2fa", "kebab-case", "with space", "", "naïve"] {
+            let err = validate_variable_names(&declared(&[name]))
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                err,
+                format!(
+                    "variable '{}' is not a valid name (use letters, digits and '_', not starting with a digit)",
+                    name
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn built_in_names_cant_be_declared() {
+        let err = validate_variable_names(&declared(&["year", "author"]))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "variable 'year' collides with a built-in (built-ins: project_name, year)"
+        );
+
+        // Every problem is reported at once, in name order
+        let err = validate_variable_names(&declared(&["project_name", "1st"]))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("variable '1st' is not a valid name"),
+            "{}",
+            err
+        );
+        assert!(
+            err.ends_with(
+                "; variable 'project_name' collides with a built-in (built-ins: project_name, year)"
+            ),
+            "{}",
+            err
+        );
+        // Built-ins are matched exactly
+        assert!(validate_variable_names(&declared(&["Year"])).is_ok());
+    }
+
+    #[test]
+    fn names_differing_only_by_case_are_paired() {
+        assert_eq!(
+            case_collisions(&declared(&["apiKey", "author", "APIKEY", "ApiKey"])),
+            [
+                ("APIKEY".to_string(), "ApiKey".to_string()),
+                ("APIKEY".to_string(), "apiKey".to_string()),
+                ("ApiKey".to_string(), "apiKey".to_string()),
+            ]
+        );
+        assert!(case_collisions(&declared(&["author", "authors"])).is_empty());
+    }
+}
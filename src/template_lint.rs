@@ -0,0 +1,505 @@
+use crate::template::{self, BUILTIN_VARIABLES, TemplateConfig};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Files above this size are almost always build output that slipped into a template
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub rule: &'static str,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{} [{}]", severity, self.rule)?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, " {}:{}", file, line)?,
+            (Some(file), None) => write!(f, " {}", file)?,
+            _ => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// A template's files, loaded once and shared by every rule.
+pub struct TemplateTree {
+    pub root: PathBuf,
+    pub config: Option<TemplateConfig>,
+    pub config_error: Option<String>,
+    pub text_files: Vec<(String, String)>,
+    pub binary_files: Vec<String>,
+    pub file_sizes: Vec<(String, u64)>,
+    pub symlinks: Vec<(String, PathBuf)>,
+}
+
+impl TemplateTree {
+    pub fn load(root: &Path) -> anyhow::Result<Self> {
+        let config_path = root.join("template.toml");
+        let (config, config_error) = if config_path.exists() {
+            match template::load_template_config(&config_path) {
+                Ok(config) => (Some(config), None),
+                Err(err) => (None, Some(err.to_string())),
+            }
+        } else {
+            (None, None)
+        };
+
+        let mut tree = TemplateTree {
+            root: root.to_path_buf(),
+            config,
+            config_error,
+            text_files: Vec::new(),
+            binary_files: Vec::new(),
+            file_sizes: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        for entry in walkdir::WalkDir::new(root).follow_links(false) {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path.strip_prefix(root)?.to_string_lossy().to_string();
+
+            if entry.path_is_symlink() {
+                tree.symlinks.push((relative, fs::read_link(path)?));
+                continue;
+            }
+            if !entry.file_type().is_file() || relative == "template.toml" {
+                continue;
+            }
+
+            tree.file_sizes
+                .push((relative.clone(), entry.metadata()?.len()));
+            match String::from_utf8(fs::read(path)?) {
+                Ok(content) => tree.text_files.push((relative, content)),
+                Err(_) => tree.binary_files.push(relative),
+            }
+        }
+
+        Ok(tree)
+    }
+}
+
+pub fn lint_template(name: &str) -> anyhow::Result<Vec<LintIssue>> {
+    let template_dir = template::get_template_dir()?.join(name);
+    if !template_dir.exists() {
+        anyhow::bail!("Template '{}' not found", name);
+    }
+
+    let tree = TemplateTree::load(&template_dir)?;
+    Ok(run_rules(&tree))
+}
+
+pub fn run_rules(tree: &TemplateTree) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    issues.extend(check_config(tree));
+    issues.extend(check_undeclared_variables(tree));
+    issues.extend(check_unused_variables(tree));
+    issues.extend(check_variable_case(tree));
+    issues.extend(check_binary_files(tree));
+    issues.extend(check_large_files(tree));
+    issues.extend(check_absolute_symlinks(tree));
+    issues.extend(check_hook_commands(tree));
+    issues
+}
+
+pub fn check_config(tree: &TemplateTree) -> Vec<LintIssue> {
+    if let Some(err) = &tree.config_error {
+        return vec![issue(
+            Severity::Error,
+            "config",
+            Some("template.toml"),
+            None,
+            format!("template.toml is invalid: {}", err),
+        )];
+    }
+
+    if tree.config.is_none() {
+        return vec![issue(
+            Severity::Warning,
+            "config",
+            None,
+            None,
+            "template.toml is missing; init will fall back to defaults".to_string(),
+        )];
+    }
+
+    Vec::new()
+}
+
+pub fn check_undeclared_variables(tree: &TemplateTree) -> Vec<LintIssue> {
+    let declared = declared_variables(tree);
+    let mut issues = Vec::new();
+
+    for (file, line, variable) in variable_references(tree) {
+        if !declared.contains(&variable) && !BUILTIN_VARIABLES.contains(&variable.as_str()) {
+            issues.push(issue(
+                Severity::Error,
+                "undeclared-variable",
+                Some(&file),
+                Some(line),
+                format!("'{{{{{}}}}}' is not declared in template.toml", variable),
+            ));
+        }
+    }
+
+    issues
+}
+
+pub fn check_unused_variables(tree: &TemplateTree) -> Vec<LintIssue> {
+    let used: BTreeSet<String> = variable_references(tree)
+        .into_iter()
+        .map(|(_, _, variable)| variable)
+        .collect();
+
+    declared_variables(tree)
+        .into_iter()
+        .filter(|variable| !used.contains(variable))
+        .map(|variable| {
+            issue(
+                Severity::Warning,
+                "unused-variable",
+                Some("template.toml"),
+                None,
+                format!("variable '{}' is declared but never used", variable),
+            )
+        })
+        .collect()
+}
+
+pub fn check_variable_case(tree: &TemplateTree) -> Vec<LintIssue> {
+    let Some(config) = &tree.config else {
+        return Vec::new();
+    };
+
+    template::case_collisions(&config.variables)
+        .into_iter()
+        .map(|(first, second)| {
+            issue(
+                Severity::Warning,
+                "variable-case",
+                Some("template.toml"),
+                None,
+                format!("variables '{}' and '{}' differ only by case", first, second),
+            )
+        })
+        .collect()
+}
+
+pub fn check_binary_files(tree: &TemplateTree) -> Vec<LintIssue> {
+    tree.binary_files
+        .iter()
+        .map(|file| {
+            issue(
+                Severity::Warning,
+                "binary-file",
+                Some(file),
+                None,
+                "binary file will be copied without variable substitution".to_string(),
+            )
+        })
+        .collect()
+}
+
+pub fn check_large_files(tree: &TemplateTree) -> Vec<LintIssue> {
+    tree.file_sizes
+        .iter()
+        .filter(|(_, size)| *size > MAX_FILE_SIZE)
+        .map(|(file, size)| {
+            issue(
+                Severity::Warning,
+                "large-file",
+                Some(file),
+                None,
+                format!(
+                    "file is {} KB (limit {} KB)",
+                    size / 1024,
+                    MAX_FILE_SIZE / 1024
+                ),
+            )
+        })
+        .collect()
+}
+
+pub fn check_absolute_symlinks(tree: &TemplateTree) -> Vec<LintIssue> {
+    tree.symlinks
+        .iter()
+        .filter(|(_, target)| target.is_absolute())
+        .map(|(file, target)| {
+            issue(
+                Severity::Error,
+                "absolute-symlink",
+                Some(file),
+                None,
+                format!(
+                    "symlink points to absolute path {}, which won't exist for other users",
+                    target.display()
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Commands listed under an optional `[hooks]` table must name a binary on PATH.
+pub fn check_hook_commands(tree: &TemplateTree) -> Vec<LintIssue> {
+    let Ok(content) = fs::read_to_string(tree.root.join("template.toml")) else {
+        return Vec::new();
+    };
+    let Ok(value) = toml::from_str::<toml::Value>(&content) else {
+        return Vec::new();
+    };
+    let Some(hooks) = value.get("hooks").and_then(|h| h.as_table()) else {
+        return Vec::new();
+    };
+
+    let mut issues = Vec::new();
+    for (hook, command) in hooks {
+        let program = command
+            .as_str()
+            .and_then(|c| c.split_whitespace().next())
+            .unwrap_or("");
+        if !program.is_empty() && !binary_on_path(program) {
+            issues.push(issue(
+                Severity::Warning,
+                "hook-command",
+                Some("template.toml"),
+                None,
+                format!("hook '{}' runs '{}', which is not installed", hook, program),
+            ));
+        }
+    }
+
+    issues
+}
+
+fn declared_variables(tree: &TemplateTree) -> BTreeSet<String> {
+    tree.config
+        .as_ref()
+        .map(|config| config.variables.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Every `{{variable}}` reference as (file, line, variable).
+fn variable_references(tree: &TemplateTree) -> Vec<(String, usize, String)> {
+    let placeholder = Regex::new(r"\{\{([A-Za-z_][A-Za-z0-9_]*)\}\}").unwrap();
+    let mut references = Vec::new();
+
+    for (file, content) in &tree.text_files {
+        for (index, line) in content.lines().enumerate() {
+            for captures in placeholder.captures_iter(line) {
+                references.push((file.clone(), index + 1, captures[1].to_string()));
+            }
+        }
+    }
+
+    references
+}
+
+fn binary_on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).exists();
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+fn issue(
+    severity: Severity,
+    rule: &'static str,
+    file: Option<&str>,
+    line: Option<usize>,
+    message: String,
+) -> LintIssue {
+    LintIssue {
+        severity,
+        rule,
+        file: file.map(str::to_string),
+        line,
+        message,
+    }
+}
+
+pub fn has_errors(issues: &[LintIssue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "name = \"svc\"\n\
+                          [variables.author]\n\
+                          description = \"Author\"\n\
+                          default = \"me\"\n";
+
+    /// A template directory holding the given files.
+    fn template(files: &[(&str, &[u8])]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (relative, content) in files {
+            let file = dir.path().join(relative);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        dir
+    }
+
+    fn lint(files: &[(&str, &[u8])], rule: fn(&TemplateTree) -> Vec<LintIssue>) -> Vec<LintIssue> {
+        let dir = template(files);
+        rule(&TemplateTree::load(dir.path()).unwrap())
+    }
+
+    fn rules(issues: &[LintIssue]) -> Vec<&'static str> {
+        issues.iter().map(|issue| issue.rule).collect()
+    }
+
+    #[test]
+    fn a_clean_template_has_no_issues() {
+        let dir = template(&[
+            ("template.toml", CONFIG.as_bytes()),
+            ("README.md", b"# {{project_name}} by {{author}}\n"),
+        ]);
+        assert!(run_rules(&TemplateTree::load(dir.path()).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn missing_config_is_a_warning() {
+        let issues = lint(&[("README.md", b"hi\n")], check_config);
+        assert_eq!(rules(&issues), ["config"]);
+        assert_eq!(issues[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn unparseable_config_is_an_error() {
+        let issues = lint(&[("template.toml", b"name = \n")], check_config);
+        assert_eq!(rules(&issues), ["config"]);
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(has_errors(&issues));
+    }
+
+    #[test]
+    fn undeclared_variables_are_errors_with_their_location() {
+        let issues = lint(
+            &[
+                ("template.toml", CONFIG.as_bytes()),
+                ("src/main.rs", b"// {{author}}\nfn main() { {{licence}} }\n"),
+            ],
+            check_undeclared_variables,
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            format!(
+                "error [undeclared-variable] {}:2: '{{{{licence}}}}' is not declared in template.toml",
+                Path::new("src").join("main.rs").display()
+            )
+        );
+    }
+
+    #[test]
+    fn builtins_need_no_declaration() {
+        let issues = lint(
+            &[("LICENSE", b"(c) {{year}} {{project_name}}\n")],
+            check_undeclared_variables,
+        );
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn declared_but_unused_variables_are_warnings() {
+        let issues = lint(
+            &[
+                ("template.toml", CONFIG.as_bytes()),
+                ("README.md", b"plain\n"),
+            ],
+            check_unused_variables,
+        );
+        assert_eq!(rules(&issues), ["unused-variable"]);
+        assert!(issues[0].message.contains("'author'"));
+        assert!(!has_errors(&issues));
+    }
+
+    #[test]
+    fn variables_differing_by_case_are_warnings() {
+        let config = format!(
+            "{}[variables.Author]\ndescription = \"\"\ndefault = \"\"\n",
+            CONFIG
+        );
+        let issues = lint(&[("template.toml", config.as_bytes())], check_variable_case);
+        assert_eq!(rules(&issues), ["variable-case"]);
+    }
+
+    #[test]
+    fn binary_files_are_reported() {
+        let issues = lint(
+            &[
+                ("logo.png", &[0x89, b'P', b'N', b'G', 0xff, 0xfe]),
+                ("a.txt", b"text"),
+            ],
+            check_binary_files,
+        );
+        assert_eq!(rules(&issues), ["binary-file"]);
+        assert_eq!(issues[0].file.as_deref(), Some("logo.png"));
+    }
+
+    #[test]
+    fn files_over_the_limit_are_reported() {
+        let big = vec![b'a'; MAX_FILE_SIZE as usize + 1];
+        let issues = lint(&[("big.txt", &big), ("small.txt", b"a")], check_large_files);
+        assert_eq!(rules(&issues), ["large-file"]);
+        assert_eq!(issues[0].file.as_deref(), Some("big.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolute_symlinks_are_errors() {
+        let dir = template(&[("target.txt", b"x")]);
+        std::os::unix::fs::symlink("target.txt", dir.path().join("relative")).unwrap();
+        std::os::unix::fs::symlink("/etc/hosts", dir.path().join("absolute")).unwrap();
+
+        let issues = check_absolute_symlinks(&TemplateTree::load(dir.path()).unwrap());
+        assert_eq!(rules(&issues), ["absolute-symlink"]);
+        assert_eq!(issues[0].file.as_deref(), Some("absolute"));
+    }
+
+    #[test]
+    fn hooks_naming_missing_programs_are_warnings() {
+        let config = format!(
+            "{}[hooks]\nmissing = \"app-hoist-no-such-program --flag\"\n",
+            CONFIG
+        );
+        let issues = lint(&[("template.toml", config.as_bytes())], check_hook_commands);
+        assert_eq!(rules(&issues), ["hook-command"]);
+        assert_eq!(issues[0].severity, Severity::Warning);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_with_a_path_to_an_existing_program_pass() {
+        let dir = template(&[("bin/setup", b"")]);
+        let setup = dir.path().join("bin").join("setup");
+        let config = format!("[hooks]\nsetup = \"{} --quiet\"\n", setup.display());
+        fs::write(dir.path().join("template.toml"), config).unwrap();
+
+        assert!(check_hook_commands(&TemplateTree::load(dir.path()).unwrap()).is_empty());
+    }
+}
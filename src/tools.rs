@@ -0,0 +1,410 @@
+use crate::cache::ProjectCache;
+use crate::config;
+use crate::models::ProjectType;
+use crate::project::{detect_package_manager, runs_through_npx};
+use crate::resolve;
+use crate::run_profile;
+
+/// The executable to use for an ecosystem tool such as `cargo` or `go`, after
+/// environment and config overrides have been applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTool {
+    pub program: String,
+    pub leading_args: Vec<String>,
+    pub overridden: bool,
+}
+
+impl ResolvedTool {
+    /// Program and arguments for running this tool with `args`.
+    pub fn command(self, args: Vec<String>) -> (String, Vec<String>) {
+        let mut full_args = self.leading_args;
+        full_args.extend(args);
+        (self.program, full_args)
+    }
+}
+
+/// Resolve `tool` for the project at `path`. Precedence is the `HOIST_<TOOL>`
+/// environment variable, then `[tools]` in the project's `.app-hoist.toml`,
+/// then `[tools]` in `~/.app-hoist/config.toml`, then the bare tool name.
+pub fn resolve(tool: &str, path: &str) -> anyhow::Result<ResolvedTool> {
+    resolve_with(
+        tool,
+        std::env::var(env_var_name(tool)).ok(),
+        &run_profile::config_path(path),
+        config::user_config_path().as_deref(),
+    )
+}
+
+/// `resolve` with the environment value and config files given.
+fn resolve_with(
+    tool: &str,
+    env_value: Option<String>,
+    project_config: &std::path::Path,
+    user_config: Option<&std::path::Path>,
+) -> anyhow::Result<ResolvedTool> {
+    let project_value = configured_tool(project_config, tool)?;
+    let user_value = match user_config {
+        Some(config) => configured_tool(config, tool)?,
+        None => None,
+    };
+
+    resolve_from(
+        tool,
+        [env_value, project_value, user_value]
+            .into_iter()
+            .flatten()
+            .find(|value| !value.trim().is_empty()),
+    )
+}
+
+/// Shorthand for `resolve(tool, path)?.command(args)`.
+pub fn command(tool: &str, path: &str, args: Vec<String>) -> anyhow::Result<(String, Vec<String>)> {
+    Ok(resolve(tool, path)?.command(args))
+}
+
+pub fn env_var_name(tool: &str) -> String {
+    format!("HOIST_{}", tool.to_uppercase().replace('-', "_"))
+}
+
+pub fn resolve_from(tool: &str, override_value: Option<String>) -> anyhow::Result<ResolvedTool> {
+    let Some(value) = override_value else {
+        return Ok(ResolvedTool {
+            program: tool.to_string(),
+            leading_args: Vec::new(),
+            overridden: false,
+        });
+    };
+
+    let mut words = split_words(&value)?.into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Override for '{}' is empty", tool))?;
+
+    Ok(ResolvedTool {
+        program,
+        leading_args: words.collect(),
+        overridden: true,
+    })
+}
+
+/// Project cache metadata naming the runtime found for the project, so later
+/// runs skip looking for it.
+const RUNTIME_KEY: &str = "runtime";
+
+/// A project's runtime that isn't installed, e.g. `cargo` for a Rust project.
+#[derive(Debug)]
+pub struct MissingRuntime {
+    pub program: String,
+    /// What needs it, e.g. "Rust projects"
+    pub needed_for: String,
+    /// Set through `HOIST_<TOOL>` or `[tools]` rather than the default name
+    pub overridden: bool,
+}
+
+impl std::fmt::Display for MissingRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} isn't installed or isn't on PATH; it's needed for {}",
+            self.program, self.needed_for
+        )?;
+        if self.overridden {
+            write!(
+                f,
+                "\n  It was set by a HOIST_* variable or [tools] in a config file"
+            )
+        } else if let Some(hint) = install_hint(&self.program) {
+            write!(f, "\n  Install it: {}", hint)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::error::Error for MissingRuntime {}
+
+/// The program a project type's commands start, after overrides: `cargo`,
+/// `go`, `uv`, `poetry`, `python`, `make`, `just`, `deno`, or for JavaScript `npx` (or
+/// bun, or the overriding package manager). Venv commands use the venv's own
+/// interpreter and aren't checked.
+pub fn runtime(project_type: &ProjectType, path: &str) -> anyhow::Result<Option<ResolvedTool>> {
+    let tool = match project_type {
+        ProjectType::Rust => resolve("cargo", path)?,
+        ProjectType::Go => resolve("go", path)?,
+        ProjectType::Uv => resolve("uv", path)?,
+        ProjectType::Poetry => resolve("poetry", path)?,
+        ProjectType::Generic => resolve("python", path)?,
+        ProjectType::JavaScript | ProjectType::TypeScript => {
+            let pm_name = detect_package_manager(path);
+            let pm = resolve(&pm_name, path)?;
+            if pm.overridden || !runs_through_npx(&pm_name) {
+                pm
+            } else {
+                // The package manager is run through npx, which fetches it if needed
+                resolve_from("npx", None)?
+            }
+        }
+        ProjectType::Make => resolve("make", path)?,
+        ProjectType::Just => resolve("just", path)?,
+        ProjectType::Deno => resolve("deno", path)?,
+        ProjectType::Venv => return Ok(None),
+    };
+    Ok(Some(tool))
+}
+
+/// Check that the runtime for the project at `path` is installed before any prompting,
+/// failing with a `MissingRuntime` naming how to install it. A runtime found
+/// once is remembered in the project's cache entry and not looked for again.
+pub fn check_runtime(path: &str, cache: &mut ProjectCache) -> anyhow::Result<()> {
+    let Some(tool) = runtime(&cache.project_type, path)? else {
+        return Ok(());
+    };
+    if cache.metadata.get(RUNTIME_KEY) == Some(&tool.program) {
+        return Ok(());
+    }
+
+    require(&tool, &format!("{} projects", cache.project_type))?;
+    cache.metadata.insert(RUNTIME_KEY.to_string(), tool.program);
+    Ok(())
+}
+
+/// Fail with a `MissingRuntime` unless `tool` is on PATH (or an existing
+/// path), where starting it will find it.
+pub fn require(tool: &ResolvedTool, needed_for: &str) -> anyhow::Result<()> {
+    let runnable = resolve::resolve(&tool.program)
+        .candidates
+        .iter()
+        .any(|candidate| {
+            matches!(
+                candidate.source,
+                resolve::Source::Path { .. } | resolve::Source::Explicit
+            )
+        });
+    if runnable {
+        return Ok(());
+    }
+    Err(MissingRuntime {
+        program: tool.program.clone(),
+        needed_for: needed_for.to_string(),
+        overridden: tool.overridden,
+    }
+    .into())
+}
+
+/// Where to get a runtime app-hoist knows about.
+fn install_hint(program: &str) -> Option<&'static str> {
+    Some(match program {
+        "cargo" => "https://rustup.rs",
+        "go" => "https://go.dev/doc/install",
+        "uv" => "https://docs.astral.sh/uv/getting-started/installation/",
+        "poetry" => "https://python-poetry.org/docs/#installation",
+        "python" | "python3" => "https://www.python.org/downloads/",
+        "npx" | "npm" => "Node.js from https://nodejs.org/en/download",
+        "yarn" => "corepack enable, or https://yarnpkg.com/getting-started/install",
+        "pnpm" => "https://pnpm.io/installation",
+        "docker" => "https://docs.docker.com/get-docker/",
+        "make" => "https://www.gnu.org/software/make/",
+        "just" => "https://just.systems/man/en/packages.html",
+        "bun" => "https://bun.sh/docs/installation",
+        "deno" => "https://docs.deno.com/runtime/getting_started/installation/",
+        _ => return None,
+    })
+}
+
+/// Split a command line into words the way a POSIX shell would, honouring
+/// single quotes, double quotes and backslash escapes.
+pub fn split_words(input: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("Unterminated single quote in '{}'", input),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Inside double quotes a backslash only escapes these
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => anyhow::bail!("Unterminated double quote in '{}'", input),
+                        },
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("Unterminated double quote in '{}'", input),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.next() {
+                    Some(c) => current.push(c),
+                    None => anyhow::bail!("Trailing backslash in '{}'", input),
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+
+    Ok(words)
+}
+
+fn configured_tool(config: &std::path::Path, tool: &str) -> anyhow::Result<Option<String>> {
+    if !config.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(config)?;
+    let value: toml::Value = toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", config.display(), e))?;
+
+    Ok(value
+        .get("tools")
+        .and_then(|tools| tools.get(tool))
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn words(input: &str) -> Vec<String> {
+        split_words(input).unwrap()
+    }
+
+    /// A project config and a user config, each with `[tools] cargo` when given.
+    fn configs(project: Option<&str>, user: Option<&str>) -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let project_config = dir.path().join(".app-hoist.toml");
+        let user_config = dir.path().join("config.toml");
+        for (file, cargo) in [(&project_config, project), (&user_config, user)] {
+            if let Some(cargo) = cargo {
+                std::fs::write(file, format!("[tools]\ncargo = '{}'\n", cargo)).unwrap();
+            }
+        }
+        (dir, project_config, user_config)
+    }
+
+    fn resolved(env: Option<&str>, project: &Path, user: &Path) -> ResolvedTool {
+        resolve_with("cargo", env.map(str::to_string), project, Some(user)).unwrap()
+    }
+
+    #[test]
+    fn environment_wins_over_both_configs() {
+        let (_dir, project, user) = configs(Some("/project/cargo"), Some("/user/cargo"));
+        assert_eq!(
+            resolved(Some("/env/cargo"), &project, &user).program,
+            "/env/cargo"
+        );
+    }
+
+    #[test]
+    fn project_config_wins_over_user_config() {
+        let (_dir, project, user) = configs(Some("/project/cargo"), Some("/user/cargo"));
+        assert_eq!(resolved(None, &project, &user).program, "/project/cargo");
+
+        // An empty environment value doesn't count as an override
+        assert_eq!(
+            resolved(Some("  "), &project, &user).program,
+            "/project/cargo"
+        );
+    }
+
+    #[test]
+    fn user_config_applies_without_a_project_override() {
+        let (_dir, project, user) = configs(None, Some("/user/cargo"));
+        assert_eq!(resolved(None, &project, &user).program, "/user/cargo");
+    }
+
+    #[test]
+    fn bare_name_without_any_override() {
+        let (_dir, project, user) = configs(None, None);
+        assert_eq!(
+            resolved(None, &project, &user),
+            ResolvedTool {
+                program: "cargo".to_string(),
+                leading_args: Vec::new(),
+                overridden: false,
+            }
+        );
+    }
+
+    #[test]
+    fn unparseable_config_is_an_error() {
+        let (_dir, project, user) = configs(None, None);
+        std::fs::write(&project, "[tools\n").unwrap();
+        assert!(resolve_with("cargo", None, &project, Some(&user)).is_err());
+    }
+
+    #[test]
+    fn wrappers_become_leading_args() {
+        let tool = resolve_from("go", Some("mise exec go --".to_string())).unwrap();
+        assert!(tool.overridden);
+        assert_eq!(
+            tool.command(vec!["build".to_string()]),
+            ("mise".to_string(), words("exec go -- build"))
+        );
+    }
+
+    #[test]
+    fn empty_override_is_an_error() {
+        assert!(resolve_from("go", Some(String::new())).is_err());
+    }
+
+    #[test]
+    fn words_split_like_a_shell() {
+        assert_eq!(words("  a   b "), ["a", "b"]);
+        assert_eq!(
+            words("'/opt/my tools/cargo' +nightly"),
+            ["/opt/my tools/cargo", "+nightly"]
+        );
+        assert_eq!(words(r#""say \"hi\"" \$HOME"#), [r#"say "hi""#, "$HOME"]);
+        assert_eq!(words(r#""a\nb""#), [r"a\nb"]);
+        assert_eq!(words(r"one\ word"), ["one word"]);
+        assert_eq!(words("''"), [""]);
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn unterminated_quotes_are_errors() {
+        assert!(split_words("'open").is_err());
+        assert!(split_words("\"open").is_err());
+        assert!(split_words("trailing\\").is_err());
+    }
+
+    #[test]
+    fn env_var_names_are_upper_snake_case() {
+        assert_eq!(env_var_name("cargo"), "HOIST_CARGO");
+        assert_eq!(env_var_name("cargo-audit"), "HOIST_CARGO_AUDIT");
+    }
+}
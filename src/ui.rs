@@ -0,0 +1,1142 @@
+use crate::events::{self, Event};
+use crate::models::ProjectType;
+use crate::utils::complete_path;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use inquire::validator::Validation;
+use inquire::{Confirm, MultiSelect, Select, Text};
+use regex::Regex;
+use std::borrow::Cow;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::runtime::RuntimeFlavor;
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+static GLYPHS: AtomicU8 = AtomicU8::new(GlyphMode::Emoji as u8);
+static PROMPT_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(0);
+static PAGE_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_PAGE_SIZE as u64);
+
+/// How status glyphs are shown.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum GlyphMode {
+    /// Emoji such as ✅ and ❌
+    Emoji = 0,
+    /// Text labels such as [ok] and [error]
+    Ascii = 1,
+    /// No glyphs at all
+    None = 2,
+}
+
+// Text labels used in place of emoji in ascii mode
+const EMOJI_LABELS: &[(&str, &str)] = &[
+    ("✅", "[ok]"),
+    ("❌", "[error]"),
+    ("⚠️", "[warning]"),
+    ("💡", "[hint]"),
+    ("📦", "[package]"),
+    ("🏗️", "[project]"),
+    ("🐳", "[docker]"),
+    ("🔄", "[multi]"),
+    ("📋", "[list]"),
+    ("💾", "[cache]"),
+    ("❓", "[help]"),
+    ("🚪", "[exit]"),
+    ("🚀", ""),
+    ("👋", ""),
+    ("📂", "[projects]"),
+    ("📁", "[path]"),
+    ("🎯", ""),
+    ("📚", ""),
+    ("➖", "[-]"),
+    ("⏭️", "[skip]"),
+];
+
+/// Enable plain mode when requested or when the terminal cannot handle rich output,
+/// and turn off colors when asked to. Glyphs come from `--glyphs`, then
+/// `[ui] glyphs` in the user config; without either, plain output and
+/// terminals whose locale isn't UTF-8 get ascii labels. Prompts give up
+/// after `[ui] prompt_timeout_secs`, when set to more than zero, and lists
+/// show up to `[ui] page_size` rows.
+pub fn init(plain: bool, no_color: bool, glyphs: Option<GlyphMode>) {
+    let dumb_terminal = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    PLAIN.store(plain || dumb_terminal, Ordering::Relaxed);
+
+    let mode =
+        glyphs
+            .or_else(configured_glyphs)
+            .unwrap_or(if plain || dumb_terminal || !utf8_locale() {
+                GlyphMode::Ascii
+            } else {
+                GlyphMode::Emoji
+            });
+    GLYPHS.store(mode as u8, Ordering::Relaxed);
+
+    match crate::config::user_integer("ui", "prompt_timeout_secs") {
+        Ok(secs) => PROMPT_TIMEOUT_SECS.store(secs.unwrap_or(0), Ordering::Relaxed),
+        Err(e) => eprintln!("Ignoring {}", e),
+    }
+    match crate::config::user_integer("ui", "page_size") {
+        Ok(Some(0)) => eprintln!("Ignoring [ui] page_size, which must be at least 1"),
+        Ok(rows) => PAGE_SIZE.store(rows.unwrap_or(DEFAULT_PAGE_SIZE as u64), Ordering::Relaxed),
+        Err(e) => eprintln!("Ignoring {}", e),
+    }
+
+    if no_color || std::env::var_os("NO_COLOR").is_some() {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+pub fn glyph_mode() -> GlyphMode {
+    match GLYPHS.load(Ordering::Relaxed) {
+        1 => GlyphMode::Ascii,
+        2 => GlyphMode::None,
+        _ => GlyphMode::Emoji,
+    }
+}
+
+fn configured_glyphs() -> Option<GlyphMode> {
+    let value = crate::config::user_string("ui", "glyphs").ok()??;
+    match <GlyphMode as clap::ValueEnum>::from_str(&value, true) {
+        Ok(mode) => Some(mode),
+        Err(_) => {
+            eprintln!("Ignoring unknown [ui] glyphs = \"{}\" in config", value);
+            None
+        }
+    }
+}
+
+/// Whether the locale advertises UTF-8, going by the first of LC_ALL,
+/// LC_CTYPE and LANG that is set. Windows terminals don't use these.
+fn utf8_locale() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+/// Render text for the current glyph mode, replacing emoji with text labels
+/// in ascii mode and dropping them in none mode.
+pub fn render(text: &str) -> String {
+    render_with(text, glyph_mode())
+}
+
+/// Render text for the given glyph mode rather than the current one.
+pub fn render_with(text: &str, mode: GlyphMode) -> String {
+    if mode == GlyphMode::Emoji {
+        return text.to_string();
+    }
+
+    let mut result = text.to_string();
+    for (emoji, label) in EMOJI_LABELS {
+        let label = if mode == GlyphMode::None { "" } else { label };
+        // Emoji are followed by padding that a text label doesn't need
+        let replacement = if label.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", label)
+        };
+        result = result.replace(&format!("{}  ", emoji), &replacement);
+        result = result.replace(&format!("{} ", emoji), &replacement);
+        result = result.replace(emoji, label);
+    }
+
+    result
+}
+
+/// How prompts and progress should be laid out for a given terminal size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub page_size: usize,
+    pub label_width: usize,
+    pub aggregate_progress: bool,
+}
+
+// Below these sizes the default menus and per-project spinners stop fitting
+const SMALL_HEIGHT: u16 = 15;
+const DEFAULT_PAGE_SIZE: usize = 7;
+const PROMPT_CHROME_ROWS: u16 = 3;
+const PROMPT_CHROME_COLUMNS: u16 = 6;
+
+pub fn layout_for(width: u16, height: u16) -> Layout {
+    layout_with(width, height, PAGE_SIZE.load(Ordering::Relaxed) as usize)
+}
+
+/// Layout for a terminal size, showing at most `max_page_size` rows.
+fn layout_with(width: u16, height: u16, max_page_size: usize) -> Layout {
+    let available_rows = height.saturating_sub(PROMPT_CHROME_ROWS) as usize;
+
+    Layout {
+        page_size: available_rows.clamp(1, max_page_size),
+        label_width: width.saturating_sub(PROMPT_CHROME_COLUMNS).max(10) as usize,
+        aggregate_progress: height < SMALL_HEIGHT,
+    }
+}
+
+/// Layout for the current terminal, re-queried on every call so resizes are picked up.
+pub fn terminal_layout() -> Layout {
+    let (height, width) = console::Term::stdout().size();
+    layout_for(width, height)
+}
+
+pub fn truncate_label(label: &str, width: usize) -> Cow<'_, str> {
+    console::truncate_str(label, width, "…")
+}
+
+pub fn status(text: &str) {
+    println!("{}", render(text));
+}
+
+pub fn select<T: std::fmt::Display>(message: &str, options: Vec<T>) -> anyhow::Result<T> {
+    prompt_required(message);
+    let message = message.to_string();
+    let index = if !is_plain() {
+        let layout = terminal_layout();
+        let labels: Vec<String> = options
+            .iter()
+            .map(|o| truncate_label(&render(&o.to_string()), layout.label_width).into_owned())
+            .collect();
+        with_idle_timeout(move || {
+            let filter =
+                |input: &str, _: &String, label: &str, _: usize| fuzzy_matches(input, label);
+            Ok(Select::new(&message, labels)
+                .with_page_size(layout.page_size)
+                .with_filter(&filter)
+                .raw_prompt()?
+                .index)
+        })?
+    } else {
+        let labels: Vec<String> = options.iter().map(|o| render(&o.to_string())).collect();
+        with_idle_timeout(move || {
+            plain_select_index(
+                &message,
+                &labels,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )
+        })?
+    };
+
+    Ok(options
+        .into_iter()
+        .nth(index)
+        .expect("index validated against options"))
+}
+
+/// Whether the characters of `input` appear in `label` in order, ignoring
+/// case, so typing "gco" finds "git-checkout".
+fn fuzzy_matches(input: &str, label: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+    input
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|wanted| label.any(|c| c == wanted))
+}
+
+pub fn multi_select<T: std::fmt::Display>(
+    message: &str,
+    options: Vec<T>,
+) -> anyhow::Result<Vec<T>> {
+    multi_select_with_defaults(message, options, &[])
+}
+
+/// A multi-select with the entries at `defaults` checked to begin with.
+/// Typing filters the list on each entry's full text, including any part cut
+/// off to fit the terminal.
+pub fn multi_select_with_defaults<T: std::fmt::Display>(
+    message: &str,
+    options: Vec<T>,
+    defaults: &[usize],
+) -> anyhow::Result<Vec<T>> {
+    prompt_required(message);
+    let message = message.to_string();
+    let defaults: Vec<usize> = defaults
+        .iter()
+        .copied()
+        .filter(|&i| i < options.len())
+        .collect();
+    let texts: Vec<String> = options.iter().map(|o| render(&o.to_string())).collect();
+    let indices = if !is_plain() {
+        let layout = terminal_layout();
+        let labels: Vec<String> = texts
+            .iter()
+            .map(|text| truncate_label(text, layout.label_width).into_owned())
+            .collect();
+        let searchable: Vec<String> = texts.iter().map(|text| text.to_lowercase()).collect();
+        with_idle_timeout(move || {
+            let filter = |input: &str, _: &String, _: &str, index: usize| {
+                searchable[index].contains(&input.to_lowercase())
+            };
+            Ok(MultiSelect::new(&message, labels)
+                .with_page_size(layout.page_size)
+                .with_default(&defaults)
+                .with_filter(&filter)
+                .raw_prompt()?
+                .into_iter()
+                .map(|choice| choice.index)
+                .collect::<Vec<_>>())
+        })?
+    } else {
+        with_idle_timeout(move || {
+            plain_multi_select_indices(
+                &message,
+                &texts,
+                &defaults,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )
+        })?
+    };
+
+    // Taken in the order chosen, which plain mode lets differ from the list's
+    let mut options: Vec<Option<T>> = options.into_iter().map(Some).collect();
+    Ok(indices
+        .into_iter()
+        .filter_map(|i| options[i].take())
+        .collect())
+}
+
+fn plain_multi_select_indices(
+    message: &str,
+    labels: &[String],
+    defaults: &[usize],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> anyhow::Result<Vec<usize>> {
+    write_plain_list(output, message, labels)?;
+
+    // With defaults, empty input keeps them and '-' chooses nothing
+    let prompt = if defaults.is_empty() {
+        "Enter numbers separated by commas (empty for none): ".to_string()
+    } else {
+        let numbers: Vec<String> = defaults.iter().map(|i| (i + 1).to_string()).collect();
+        format!(
+            "Enter numbers separated by commas (empty for {}, - for none): ",
+            numbers.join(",")
+        )
+    };
+
+    loop {
+        let answer = read_line_from(input, output, &prompt)?;
+        if answer.trim().is_empty() {
+            return Ok(defaults.to_vec());
+        }
+        if answer.trim() == "-" {
+            return Ok(Vec::new());
+        }
+
+        let indices: Option<Vec<usize>> = answer
+            .split(',')
+            .map(|part| part.trim().parse::<usize>().ok())
+            .map(|n| n.filter(|n| (1..=labels.len()).contains(n)))
+            .collect();
+
+        match indices {
+            Some(mut indices) => {
+                // Keep the order the numbers were typed in, which is the order to run them
+                let mut seen = std::collections::HashSet::new();
+                indices.retain(|n| seen.insert(*n));
+                return Ok(indices.into_iter().map(|n| n - 1).collect());
+            }
+            None => writeln!(
+                output,
+                "Please enter numbers between 1 and {}",
+                labels.len()
+            )?,
+        }
+    }
+}
+
+pub fn confirm(message: &str, default: bool) -> anyhow::Result<bool> {
+    prompt_required(message);
+    let message = message.to_string();
+    if !is_plain() {
+        return with_idle_timeout(move || {
+            Ok(Confirm::new(&message).with_default(default).prompt()?)
+        });
+    }
+
+    let hint = if default { "Y/n" } else { "y/N" };
+    with_idle_timeout(move || {
+        loop {
+            let input = read_plain_line(&format!("{} [{}]: ", message, hint))?;
+            match input.trim().to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => println!("Please answer y or n"),
+            }
+        }
+    })
+}
+
+pub fn text(message: &str, default: Option<&str>) -> anyhow::Result<String> {
+    prompt_required(message);
+    let message = message.to_string();
+    let default = default.map(str::to_string);
+    if !is_plain() {
+        return with_idle_timeout(move || {
+            let mut prompt = Text::new(&message);
+            if let Some(default) = &default {
+                prompt = prompt.with_default(default);
+            }
+            Ok(prompt.prompt()?)
+        });
+    }
+
+    with_idle_timeout(move || {
+        let input = match &default {
+            Some(default) => read_plain_line(&format!("{} [{}]: ", message, default))?,
+            None => read_plain_line(&format!("{} ", message))?,
+        };
+
+        match (input.trim().is_empty(), default) {
+            (true, Some(default)) => Ok(default),
+            _ => Ok(input.trim().to_string()),
+        }
+    })
+}
+
+/// A free-form prompt with a hint line. The answer is returned as typed,
+/// without trimming, since it becomes an argument value. Answers `validate`
+/// rejects are asked for again, with its message shown. With
+/// `complete_paths`, Tab completes file and directory names (not in plain mode).
+pub fn text_with_help(
+    message: &str,
+    default: Option<&str>,
+    help: &str,
+    complete_paths: bool,
+    validate: impl Fn(&str) -> Result<(), String> + Clone + Send + 'static,
+) -> anyhow::Result<String> {
+    prompt_required(message);
+    let message = message.to_string();
+    let default = default.map(str::to_string);
+    let help = help.to_string();
+    if !is_plain() {
+        return with_idle_timeout(move || {
+            let mut prompt =
+                Text::new(&message)
+                    .with_help_message(&help)
+                    .with_validator(move |input: &str| {
+                        Ok(match validate(input) {
+                            Ok(()) => Validation::Valid,
+                            Err(reason) => Validation::Invalid(reason.into()),
+                        })
+                    });
+            if let Some(default) = &default {
+                prompt = prompt.with_default(default);
+            }
+            if complete_paths {
+                prompt = prompt.with_autocomplete(|input: &str| Ok(complete_path(input)));
+            }
+            Ok(prompt.prompt()?)
+        });
+    }
+
+    with_idle_timeout(move || {
+        println!("({})", help);
+        loop {
+            let input = match &default {
+                Some(default) => read_plain_line(&format!("{} [{}]: ", message, default))?,
+                None => read_plain_line(&format!("{} ", message))?,
+            };
+
+            let input = match &default {
+                Some(default) if input.is_empty() => default.clone(),
+                _ => input,
+            };
+            match validate(&input) {
+                Ok(()) => return Ok(input),
+                Err(reason) => println!("{}", reason),
+            }
+        }
+    })
+}
+
+fn plain_select_index(
+    message: &str,
+    labels: &[String],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> anyhow::Result<usize> {
+    if labels.is_empty() {
+        anyhow::bail!("Nothing to choose from for '{}'", message);
+    }
+    write_plain_list(output, message, labels)?;
+
+    loop {
+        let answer = read_line_from(input, output, "Enter a number: ")?;
+        match answer.trim().parse::<usize>() {
+            Ok(n) if (1..=labels.len()).contains(&n) => return Ok(n - 1),
+            _ => writeln!(
+                output,
+                "Please enter a number between 1 and {}",
+                labels.len()
+            )?,
+        }
+    }
+}
+
+/// The numbered list a plain prompt answers by number.
+fn write_plain_list(
+    output: &mut impl Write,
+    message: &str,
+    labels: &[String],
+) -> std::io::Result<()> {
+    writeln!(output, "{}", message)?;
+    for (i, label) in labels.iter().enumerate() {
+        writeln!(output, "  {}) {}", i + 1, label)?;
+    }
+    Ok(())
+}
+
+/// A prompt that got no answer within `[ui] prompt_timeout_secs`.
+#[derive(Debug)]
+pub struct PromptTimedOut;
+
+impl std::fmt::Display for PromptTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out waiting for input")
+    }
+}
+
+impl std::error::Error for PromptTimedOut {}
+
+/// Exit code after a prompt timed out, the same as timeout(1) uses.
+pub const PROMPT_TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Let progress listeners know the run is waiting on an answer.
+fn prompt_required(message: &str) {
+    events::emit(Event::PromptRequired {
+        message: message.to_string(),
+    });
+}
+
+/// Run a prompt, giving up once it has waited `prompt_timeout_secs`. The
+/// timeout surfaces as `PromptTimedOut`, so callers unwind and release what
+/// they hold (locks, temp files) on the way out.
+fn with_idle_timeout<T: Send + 'static>(
+    prompt: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    match PROMPT_TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => prompt(),
+        secs => block_on(with_timeout(prompt, Duration::from_secs(secs)))?,
+    }
+}
+
+/// Run a prompt on the blocking pool and wait for it or `timeout`, whichever
+/// comes first. A prompt that times out is left reading and never answered.
+async fn with_timeout<T: Send + 'static>(
+    prompt: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+    timeout: Duration,
+) -> anyhow::Result<T> {
+    let terminal = TerminalState::save();
+    tokio::select! {
+        result = tokio::task::spawn_blocking(prompt) => result?,
+        _ = tokio::time::sleep(timeout) => {
+            // The abandoned prompt may have left the terminal in raw mode
+            terminal.restore();
+            println!();
+            Err(PromptTimedOut.into())
+        }
+    }
+}
+
+/// Wait for `future` from synchronous code: on the runtime already running,
+/// or else on one of its own.
+fn block_on<F: Future + Send>(future: F) -> anyhow::Result<F::Output>
+where
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        // A single-threaded runtime can't be blocked in place
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| block_on_own_runtime(future))
+                .join()
+                .map_err(|_| anyhow::anyhow!("prompt thread panicked"))?
+        }),
+        Err(_) => block_on_own_runtime(future),
+    }
+}
+
+/// `block_on` with a runtime of its own, which doesn't wait for an abandoned
+/// prompt when it shuts down.
+fn block_on_own_runtime<F: Future>(future: F) -> anyhow::Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()?;
+    let output = runtime.block_on(future);
+    runtime.shutdown_background();
+    Ok(output)
+}
+
+/// Terminal settings from before a prompt, to put back if it is abandoned.
+struct TerminalState {
+    #[cfg(unix)]
+    termios: Option<libc::termios>,
+}
+
+impl TerminalState {
+    fn save() -> Self {
+        #[cfg(unix)]
+        {
+            // SAFETY: tcgetattr only writes into the termios it is given
+            let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+            let saved = unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } == 0;
+            Self {
+                termios: saved.then_some(termios),
+            }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    fn restore(&self) {
+        #[cfg(unix)]
+        if let Some(termios) = &self.termios {
+            // SAFETY: the termios came from tcgetattr on the same descriptor
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) };
+        }
+        // Show the cursor again, which rich prompts hide
+        if !is_plain() {
+            print!("\x1b[?25h");
+            let _ = std::io::stdout().flush();
+        }
+    }
+}
+
+fn read_plain_line(prompt: &str) -> anyhow::Result<String> {
+    read_line_from(&mut std::io::stdin().lock(), &mut std::io::stdout(), prompt)
+}
+
+fn read_line_from(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    prompt: &str,
+) -> anyhow::Result<String> {
+    write!(output, "{}", prompt)?;
+    output.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        anyhow::bail!("Input closed");
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Progress display shared by a set of concurrent tasks. Small terminals get a
+/// single aggregated bar instead of one spinner per task.
+pub struct ProgressGroup {
+    multi: MultiProgress,
+    aggregate: Option<ProgressBar>,
+}
+
+impl ProgressGroup {
+    pub fn new(total: usize) -> Self {
+        let multi = MultiProgress::new();
+        let aggregate = if !is_plain() && terminal_layout().aggregate_progress {
+            let pb = multi.add(ProgressBar::new(total as u64));
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("[{elapsed_precise}] {pos}/{len} done {wide_msg}")
+                    .unwrap(),
+            );
+            Some(pb)
+        } else {
+            None
+        };
+
+        Self { multi, aggregate }
+    }
+
+    pub fn task(&self, message: String) -> Progress {
+        if is_plain() {
+            status(&message);
+            return Progress::Plain;
+        }
+
+        if let Some(pb) = &self.aggregate {
+            pb.set_message(render(&message));
+            return Progress::Aggregated(pb.clone());
+        }
+
+        let pb = self.multi.add(ProgressBar::new_spinner());
+        let mut style = ProgressStyle::default_spinner()
+            .template("{spinner:.green} [{elapsed_precise}] {wide_msg}")
+            .unwrap();
+        // The default spinner is drawn with braille characters
+        if glyph_mode() != GlyphMode::Emoji {
+            style = style.tick_chars("|/-\\ ");
+        }
+        pb.set_style(style);
+        pb.set_message(render(&message));
+        Progress::Spinner(pb)
+    }
+
+    /// Hide the progress display while `f` runs, e.g. to show a prompt.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.multi.suspend(f)
+    }
+
+    pub fn finish(&self) {
+        if let Some(pb) = &self.aggregate {
+            pb.finish();
+        }
+    }
+}
+
+/// Progress display for a single task: an indicatif spinner normally, a share
+/// of an aggregated bar on small terminals, or one status line per update in
+/// plain mode.
+pub enum Progress {
+    Spinner(ProgressBar),
+    Aggregated(ProgressBar),
+    Plain,
+}
+
+impl Progress {
+    /// Print a line of child output above the progress display.
+    pub fn println(&self, line: &str) {
+        match self {
+            Progress::Spinner(pb) | Progress::Aggregated(pb) => pb.println(render(line)),
+            Progress::Plain => println!("{}", line),
+        }
+    }
+
+    pub fn set_message(&self, message: String) {
+        match self {
+            Progress::Spinner(pb) | Progress::Aggregated(pb) => pb.set_message(render(&message)),
+            Progress::Plain => status(&message),
+        }
+    }
+
+    pub fn finish_with_message(&self, message: String) {
+        match self {
+            Progress::Spinner(pb) => pb.finish_with_message(render(&message)),
+            Progress::Aggregated(pb) => {
+                pb.set_message(render(&message));
+                pb.inc(1);
+            }
+            Progress::Plain => status(&message),
+        }
+    }
+}
+
+/// Counts warnings in child command output as it is forwarded, using the
+/// conventions of each ecosystem's tools. Output in an unrecognised format
+/// simply counts as zero.
+pub struct WarningCounter {
+    line_pattern: Option<Regex>,
+    summary_pattern: Option<Regex>,
+    ignore_pattern: Option<Regex>,
+    lines: usize,
+    summary: Option<usize>,
+}
+
+impl WarningCounter {
+    pub fn new(project_type: &ProjectType) -> Self {
+        let (line, summary, ignore) = match project_type {
+            // rustc: `warning: unused variable`, minus cargo's per-crate totals
+            ProjectType::Rust => (
+                Some(r"^warning(\[\w+\])?:"),
+                None,
+                Some(r"generated \d+ warnings?"),
+            ),
+            // eslint/tsc: per-line `12:5  warning  ...`, or a `(N warnings)` summary
+            ProjectType::JavaScript | ProjectType::TypeScript => (
+                Some(r"^\s*\d+:\d+\s+[Ww]arning\b"),
+                Some(r"(\d+) [Ww]arnings?\b"),
+                None,
+            ),
+            ProjectType::Go => (Some(r"WARNING"), None, None),
+            ProjectType::Uv | ProjectType::Poetry | ProjectType::Venv | ProjectType::Deno => {
+                (Some(r"(?i)^warning\b"), None, None)
+            }
+            // Compilers a Makefile or justfile calls print `file.c:3:5: warning: ...`
+            ProjectType::Make | ProjectType::Just => (Some(r"\bwarning:"), None, None),
+            ProjectType::Generic => (None, None, None),
+        };
+
+        let compile = |pattern: Option<&str>| pattern.map(|p| Regex::new(p).unwrap());
+        Self {
+            line_pattern: compile(line),
+            summary_pattern: compile(summary),
+            ignore_pattern: compile(ignore),
+            lines: 0,
+            summary: None,
+        }
+    }
+
+    pub fn observe(&mut self, line: &str) {
+        if let Some(pattern) = &self.summary_pattern
+            && let Some(count) = pattern
+                .captures(line)
+                .and_then(|c| c[1].parse::<usize>().ok())
+        {
+            *self.summary.get_or_insert(0) += count;
+            return;
+        }
+
+        if self
+            .ignore_pattern
+            .as_ref()
+            .is_some_and(|p| p.is_match(line))
+        {
+            return;
+        }
+
+        if self.line_pattern.as_ref().is_some_and(|p| p.is_match(line)) {
+            self.lines += 1;
+        }
+    }
+
+    /// A tool's own summary wins over counting individual warning lines.
+    pub fn count(&self) -> usize {
+        self.summary.unwrap_or(self.lines)
+    }
+}
+
+/// " with N warnings" suffix for reports, empty when there were none.
+pub fn warning_suffix(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        1 => " with 1 warning".to_string(),
+        n => format!(" with {} warnings", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(texts: &[&str]) -> Vec<String> {
+        texts
+            .iter()
+            .map(|text| render_with(text, GlyphMode::Ascii))
+            .collect()
+    }
+
+    #[test]
+    fn plain_select_lists_numbered_labels_and_retries() {
+        let labels = labels(&["Build", "Test"]);
+        let mut output = Vec::new();
+        let index = plain_select_index(
+            "Which operation?",
+            &labels,
+            &mut "7\n2\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(index, 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Which operation?\n  1) Build\n  2) Test\n\
+             Enter a number: Please enter a number between 1 and 2\n\
+             Enter a number: "
+        );
+    }
+
+    #[test]
+    fn plain_select_errors_on_an_empty_list() {
+        let mut output = Vec::new();
+        let result = plain_select_index("Pick one", &[], &mut "1\n".as_bytes(), &mut output);
+        assert!(result.is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn plain_select_errors_when_input_closes() {
+        let labels = labels(&["a"]);
+        let result = plain_select_index("Pick one", &labels, &mut "".as_bytes(), &mut Vec::new());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn plain_multi_select_keeps_typed_order_and_defaults() {
+        let labels = labels(&["build", "test", "lint"]);
+        let pick = |answer: &str, defaults: &[usize]| {
+            let mut output = Vec::new();
+            let indices = plain_multi_select_indices(
+                "Steps?",
+                &labels,
+                defaults,
+                &mut answer.as_bytes(),
+                &mut output,
+            )
+            .unwrap();
+            (indices, String::from_utf8(output).unwrap())
+        };
+
+        assert_eq!(pick("3, 1, 3\n", &[]).0, vec![2, 0]);
+        assert_eq!(pick("\n", &[1]).0, vec![1]);
+        assert_eq!(pick("-\n", &[1]).0, Vec::<usize>::new());
+
+        let (indices, output) = pick("0\n2\n", &[0, 2]);
+        assert_eq!(indices, vec![1]);
+        assert!(output.contains("(empty for 1,3, - for none)"));
+        assert!(output.contains("Please enter numbers between 1 and 3"));
+    }
+
+    #[test]
+    fn ascii_rendering_replaces_emoji_with_labels() {
+        assert_eq!(
+            render_with(
+                "⚠️  Installed dependencies are out of date",
+                GlyphMode::Ascii
+            ),
+            "[warning] Installed dependencies are out of date"
+        );
+        assert_eq!(
+            render_with("✅ Successfully installed rg", GlyphMode::Ascii),
+            "[ok] Successfully installed rg"
+        );
+        assert_eq!(
+            render_with("🚀 Welcome to app-hoist", GlyphMode::Ascii),
+            "Welcome to app-hoist"
+        );
+        assert_eq!(
+            render_with("❌ Step 2 of 3 failed", GlyphMode::None),
+            "Step 2 of 3 failed"
+        );
+        assert_eq!(render_with("✅ done", GlyphMode::Emoji), "✅ done");
+    }
+
+    #[test]
+    fn plain_dry_run_flow_has_no_escape_sequences() {
+        let steps = labels(&["📦 Install dependencies", "🏗️  Build"]);
+        let mut output = Vec::new();
+        let index = plain_select_index(
+            "Select an operation",
+            &steps,
+            &mut "2\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "{}",
+            render_with(
+                "⚠️  Installed dependencies are out of date with the lockfile",
+                GlyphMode::Ascii
+            )
+        )
+        .unwrap();
+        writeln!(output, "Dry run: cargo build").unwrap();
+
+        assert_eq!(index, 1);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "Select an operation\n  1) [package] Install dependencies\n  2) [project] Build\n\
+             Enter a number: [warning] Installed dependencies are out of date with the lockfile\n\
+             Dry run: cargo build\n"
+        );
+        assert!(!output.contains('\x1b'));
+    }
+
+    #[test]
+    fn roomy_terminals_show_a_full_page() {
+        assert_eq!(
+            layout_with(120, 40, DEFAULT_PAGE_SIZE),
+            Layout {
+                page_size: DEFAULT_PAGE_SIZE,
+                label_width: 114,
+                aggregate_progress: false,
+            }
+        );
+    }
+
+    #[test]
+    fn short_terminals_page_fewer_rows_and_aggregate_progress() {
+        let layout = layout_with(80, 8, DEFAULT_PAGE_SIZE);
+        assert_eq!(layout.page_size, 5);
+        assert!(layout.aggregate_progress);
+
+        // Never fewer than one row, however small the pane
+        assert_eq!(layout_with(80, 2, DEFAULT_PAGE_SIZE).page_size, 1);
+        assert_eq!(layout_with(80, 0, DEFAULT_PAGE_SIZE).page_size, 1);
+    }
+
+    #[test]
+    fn progress_aggregates_below_the_small_height() {
+        assert!(layout_with(80, SMALL_HEIGHT - 1, DEFAULT_PAGE_SIZE).aggregate_progress);
+        assert!(!layout_with(80, SMALL_HEIGHT, DEFAULT_PAGE_SIZE).aggregate_progress);
+    }
+
+    #[test]
+    fn configured_page_size_caps_tall_terminals() {
+        assert_eq!(layout_with(80, 100, 20).page_size, 20);
+        assert_eq!(layout_with(80, 10, 20).page_size, 7);
+    }
+
+    #[test]
+    fn narrow_terminals_keep_a_minimum_label_width() {
+        assert_eq!(layout_with(30, 40, DEFAULT_PAGE_SIZE).label_width, 24);
+        assert_eq!(layout_with(12, 40, DEFAULT_PAGE_SIZE).label_width, 10);
+        assert_eq!(layout_with(0, 40, DEFAULT_PAGE_SIZE).label_width, 10);
+    }
+
+    #[test]
+    fn labels_are_truncated_to_the_width() {
+        assert_eq!(truncate_label("build", 10), "build");
+        assert_eq!(truncate_label("Run the project tests", 10), "Run the p…");
+    }
+
+    fn count(project_type: ProjectType, output: &str) -> usize {
+        let mut counter = WarningCounter::new(&project_type);
+        for line in output.lines() {
+            counter.observe(line);
+        }
+        counter.count()
+    }
+
+    #[test]
+    fn rustc_warnings_are_counted_without_cargo_totals() {
+        let output = "   Compiling demo v0.1.0 (/work/demo)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 1;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+warning[E0170]: pattern binding `A` is named the same as one of the variants
+warning: `demo` (bin \"demo\") generated 2 warnings
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.41s";
+        assert_eq!(count(ProjectType::Rust, output), 2);
+    }
+
+    #[test]
+    fn eslint_summary_wins_over_counted_lines() {
+        let output = "/work/app/src/index.ts
+  3:7   warning  'unused' is assigned a value but never used  no-unused-vars
+  9:1   warning  Unexpected console statement                 no-console
+
+\u{2716} 5 problems (2 errors, 3 warnings)";
+        assert_eq!(count(ProjectType::TypeScript, output), 3);
+    }
+
+    #[test]
+    fn eslint_lines_are_counted_without_a_summary() {
+        let output = "  3:7   warning  'unused' is assigned a value  no-unused-vars
+  4:1   error    Parsing error
+  9:1   Warning  Unexpected console statement  no-console";
+        assert_eq!(count(ProjectType::JavaScript, output), 2);
+    }
+
+    #[test]
+    fn go_vet_warnings_are_counted() {
+        let output = "# demo
+WARNING: ./main.go:5:2: fmt.Printf format %d has arg s of wrong type string
+ok  \tdemo\t0.002s";
+        assert_eq!(count(ProjectType::Go, output), 1);
+    }
+
+    #[test]
+    fn python_and_compiler_warnings_are_counted() {
+        assert_eq!(
+            count(
+                ProjectType::Uv,
+                "warning: `VIRTUAL_ENV=.venv` does not match\nResolved 3 packages"
+            ),
+            1
+        );
+        assert_eq!(
+            count(
+                ProjectType::Make,
+                "cc -c main.c\nmain.c:3:5: warning: unused variable 'x' [-Wunused-variable]"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn unknown_formats_count_as_zero() {
+        assert_eq!(count(ProjectType::Generic, "warning: something"), 0);
+        assert_eq!(count(ProjectType::Rust, "all good\nnothing to see"), 0);
+    }
+
+    #[test]
+    fn warning_suffix_reads_naturally() {
+        assert_eq!(warning_suffix(0), "");
+        assert_eq!(warning_suffix(1), " with 1 warning");
+        assert_eq!(warning_suffix(14), " with 14 warnings");
+    }
+
+    /// A prompt that waits until `sender` is dropped and is never answered.
+    fn unanswered() -> (
+        std::sync::mpsc::Sender<()>,
+        impl FnOnce() -> anyhow::Result<String> + Send + 'static,
+    ) {
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        (sender, move || {
+            let _ = receiver.recv();
+            Ok("late".to_string())
+        })
+    }
+
+    fn timed_out<T>(result: anyhow::Result<T>) -> bool {
+        result.is_err_and(|e| e.is::<PromptTimedOut>())
+    }
+
+    #[test]
+    fn unanswered_prompts_time_out() {
+        let (sender, prompt) = unanswered();
+        assert!(timed_out(
+            block_on(with_timeout(prompt, Duration::from_millis(10))).unwrap()
+        ));
+        drop(sender);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn prompts_time_out_inside_the_runtime() {
+        let (sender, prompt) = unanswered();
+        assert!(timed_out(
+            with_timeout(prompt, Duration::from_millis(10)).await
+        ));
+        drop(sender);
+
+        // Synchronous callers block in place
+        let (sender, prompt) = unanswered();
+        assert!(timed_out(
+            block_on(with_timeout(prompt, Duration::from_millis(10))).unwrap()
+        ));
+        drop(sender);
+    }
+
+    #[tokio::test]
+    async fn single_threaded_runtimes_wait_on_a_thread_of_their_own() {
+        let (sender, prompt) = unanswered();
+        assert!(timed_out(
+            block_on(with_timeout(prompt, Duration::from_millis(10))).unwrap()
+        ));
+        drop(sender);
+    }
+
+    #[test]
+    fn answered_prompts_return_the_answer() {
+        let answer = block_on(with_timeout(
+            || Ok("yes".to_string()),
+            Duration::from_secs(60),
+        ))
+        .unwrap();
+        assert_eq!(answer.unwrap(), "yes");
+    }
+}
@@ -1,124 +1,1207 @@
-use crate::models::OptionInfo;
-use indicatif::ProgressBar;
-use inquire::{MultiSelect, Text};
-use std::process::{Command, Stdio};
+use crate::cancel::{Cancelled, TaskRegistry};
+use crate::capture::{self, Capture, TailBuffer};
+use crate::config;
+use crate::editor::{self, display_command};
+use crate::history::{self, HistoryEntry};
+use crate::models::{OptionInfo, ProjectType, SelectedOption, ValueArity};
+use crate::ui::{self, Progress, WarningCounter, warning_suffix};
+use crate::value_sources::ValueSources;
+use std::collections::HashMap;
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as AsyncCommand;
 
-pub fn select_options(options: &[OptionInfo]) -> anyhow::Result<Vec<(String, Option<String>)>> {
-    // Create a list of option descriptions for selection
-    let option_texts: Vec<String> = options
+/// Let the user choose options, starting from `previous`, the last selection
+/// for the same tool.
+pub fn select_options(
+    options: &[OptionInfo],
+    previous: &[SelectedOption],
+    sources: &ValueSources,
+    tool: Option<&str>,
+) -> anyhow::Result<Vec<SelectedOption>> {
+    group_selected(
+        options,
+        select_option_indices(options, previous, sources)?,
+        tool,
+    )
+}
+
+/// Turn chosen option indices and values into the options to pass, with
+/// values passed the way `tool`'s configured value style says.
+pub fn group_selected(
+    options: &[OptionInfo],
+    chosen: Vec<(usize, Option<String>)>,
+    tool: Option<&str>,
+) -> anyhow::Result<Vec<SelectedOption>> {
+    let value_style = ValueStyle::load(tool)?;
+    let mut selected: Vec<(usize, SelectedOption)> = Vec::new();
+    for (idx, value) in chosen {
+        match selected.last_mut() {
+            // Repeated options come back as consecutive entries for the same index
+            Some((last, option)) if *last == idx => option.occurrences.push(value),
+            _ => selected.push((
+                idx,
+                SelectedOption {
+                    flag: options[idx].preferred_flag().to_string(),
+                    occurrences: vec![value],
+                    joined: value_style.joins(&options[idx]),
+                },
+            )),
+        }
+    }
+    Ok(selected.into_iter().map(|(_, option)| option).collect())
+}
+
+/// How option values are passed: `--file value`, `--file=value`, or as the
+/// tool's help writes them. Set with `[package] value_style` in
+/// `~/.app-hoist/config.toml`, or per tool under `[package.value_styles]`,
+/// for tools that only accept one form or whose help shows the wrong one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueStyle {
+    Auto,
+    Separate,
+    Joined,
+}
+
+impl ValueStyle {
+    fn load(tool: Option<&str>) -> anyhow::Result<Self> {
+        Self::from_config(config::load_user_config()?.as_ref(), tool)
+    }
+
+    fn from_config(config: Option<&toml::Value>, tool: Option<&str>) -> anyhow::Result<Self> {
+        let package = config.and_then(|config| config.get("package"));
+        let per_tool = tool.and_then(|tool| {
+            package?
+                .get("value_styles")?
+                .get(tool)
+                .map(|style| (format!("[package.value_styles] {}", tool), style))
+        });
+        let (key, style) = match per_tool {
+            Some((key, style)) => {
+                let style = style
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("{} must be a string", key))?;
+                (key, Some(style))
+            }
+            None => (
+                "[package] value_style".to_string(),
+                package
+                    .and_then(|package| package.get("value_style")?.as_str())
+                    .map(str::to_string),
+            ),
+        };
+
+        match style.as_deref() {
+            None | Some("auto") => Ok(ValueStyle::Auto),
+            Some("separate") => Ok(ValueStyle::Separate),
+            Some("joined") => Ok(ValueStyle::Joined),
+            Some(other) => anyhow::bail!(
+                "{} must be \"auto\", \"separate\" or \"joined\", not \"{}\"",
+                key,
+                other
+            ),
+        }
+    }
+
+    /// Only long flags can take a value after `=`. An optional value always
+    /// goes there, since a separate word would be read as an argument.
+    /// Windows `/X:value` flags and short flags the help writes with their
+    /// value attached (`-Dkey=value`, `-I<dir>`) are passed that way.
+    fn joins(self, option: &OptionInfo) -> bool {
+        let flag = option.preferred_flag();
+        if flag.starts_with('/') || !flag.starts_with("--") {
+            return option.joined_value;
+        }
+        option.value == ValueArity::Optional
+            || match self {
+                ValueStyle::Auto => option.joined_value,
+                ValueStyle::Separate => false,
+                ValueStyle::Joined => true,
+            }
+    }
+}
+
+/// Let the user choose options, returning the index of each chosen option and its value.
+/// A repeatable option appears once per time it should be passed. Options in
+/// `previous` start out checked, with their values as defaults; ones the tool
+/// no longer has are ignored. Values come from a list where `sources` has a
+/// command for the option.
+pub fn select_option_indices(
+    options: &[OptionInfo],
+    previous: &[SelectedOption],
+    sources: &ValueSources,
+) -> anyhow::Result<Vec<(usize, Option<String>)>> {
+    let recalled: HashMap<usize, &SelectedOption> = previous
+        .iter()
+        .filter_map(|prev| {
+            options
+                .iter()
+                .position(|opt| opt.preferred_flag() == prev.flag)
+                .map(|idx| (idx, prev))
+        })
+        .collect();
+    let mut defaults: Vec<usize> = recalled.keys().copied().collect();
+    defaults.sort_unstable();
+
+    // Options from more than one section are labelled with theirs, e.g.
+    // "Display Options │ --color <WHEN>: ..."
+    let grouped = options
+        .iter()
+        .any(|opt| opt.section.is_some() && opt.section != options[0].section);
+
+    let choices: Vec<OptionChoice> = options
         .iter()
         .enumerate()
-        .map(|(i, opt)| format!("[{}] {}: {}", i, opt.flags.join(", "), opt.description))
+        .map(|(index, opt)| OptionChoice {
+            index,
+            section: opt.section.as_deref().filter(|_| grouped),
+            option: opt,
+        })
         .collect();
 
-    // Use MultiSelect to let user choose options
-    let selected_texts = MultiSelect::new("Select options to include:", option_texts).prompt()?;
+    let chosen = ui::multi_select_with_defaults("Select options to include:", choices, &defaults)?;
+    let chosen = drop_conflicts(chosen)?;
 
     let mut selected = Vec::new();
 
-    for text in selected_texts {
-        // Extract the index from [idx]
-        if let Some(start) = text.find('[')
-            && let Some(end) = text.find(']')
-            && let Ok(idx) = text[start + 1..end].parse::<usize>()
-            && let Some(opt) = options.get(idx)
-        {
-            let flag = opt.flags[0].clone(); // Use the first flag
-
-            let value = if opt.requires_value {
-                // Ask for value
-                Some(Text::new(&format!("Enter value for {}:", flag)).prompt()?)
-            } else {
-                None
-            };
+    for OptionChoice {
+        index: idx,
+        option: opt,
+        ..
+    } in chosen
+    {
+        // Values entered last time, in order
+        let previous_values: Vec<Option<&str>> = recalled
+            .get(&idx)
+            .map(|prev| prev.occurrences.iter().map(Option::as_deref).collect())
+            .unwrap_or_default();
+        let previous_value = |i: usize| previous_values.get(i).copied().flatten();
 
-            selected.push((flag, value));
+        if opt.repeatable && opt.value.takes_value() {
+            for i in 0.. {
+                selected.push((idx, prompt_option_value(opt, previous_value(i), sources)?));
+                if !ui::confirm(
+                    &format!("Add another value for {}?", opt.preferred_flag()),
+                    i + 1 < previous_values.len(),
+                )? {
+                    break;
+                }
+            }
+        } else if opt.repeatable {
+            for _ in 0..prompt_repeat_count(opt, previous_values.len().max(1))? {
+                selected.push((idx, None));
+            }
+        } else if opt.value.takes_value() {
+            selected.push((idx, prompt_option_value(opt, previous_value(0), sources)?));
+        } else {
+            selected.push((idx, None));
         }
     }
 
     Ok(selected)
 }
 
-pub fn build_command(selected: &[(String, Option<String>)]) -> anyhow::Result<Vec<String>> {
-    let mut args = Vec::new();
+/// Warn about chosen options whose help says they can't be combined, and
+/// offer to drop one of each pair. Options without such hints are left alone.
+fn drop_conflicts(mut chosen: Vec<OptionChoice>) -> anyhow::Result<Vec<OptionChoice>> {
+    let mut kept_both: Vec<(usize, usize)> = Vec::new();
+    loop {
+        let Some((a, b)) = first_conflict(&chosen, &kept_both) else {
+            return Ok(chosen);
+        };
 
-    for (flag, value) in selected {
-        args.push(flag.clone());
-        if let Some(val) = value {
-            args.push(val.clone());
+        let (first, second) = (
+            chosen[a].option.preferred_flag().to_string(),
+            chosen[b].option.preferred_flag().to_string(),
+        );
+        ui::status(&format!(
+            "⚠️  {} can't be used with {}, according to its help",
+            first, second
+        ));
+        let drop_second = format!("Drop {}", second);
+        let drop_first = format!("Drop {}", first);
+        let choice = ui::select(
+            "Which one should go?",
+            vec![drop_second.clone(), drop_first, "Keep both".to_string()],
+        )?;
+        if choice == drop_second {
+            chosen.remove(b);
+        } else if choice == "Keep both" {
+            kept_both.push((chosen[a].index, chosen[b].index));
+        } else {
+            chosen.remove(a);
         }
     }
+}
 
-    Ok(args)
+/// Positions of the first two chosen options that can't be combined, other
+/// than pairs of option indices in `kept_both`.
+fn first_conflict(chosen: &[OptionChoice], kept_both: &[(usize, usize)]) -> Option<(usize, usize)> {
+    chosen.iter().enumerate().find_map(|(a, first)| {
+        chosen[a + 1..]
+            .iter()
+            .position(|second| {
+                first
+                    .option
+                    .conflicts
+                    .iter()
+                    .any(|flag| flag == second.option.preferred_flag())
+                    && !kept_both.contains(&(first.index, second.index))
+            })
+            .map(|offset| (a, a + 1 + offset))
+    })
 }
 
-pub fn execute_command(executable: &str, args: &[String]) -> anyhow::Result<()> {
-    println!("Executing: {} {}", executable, args.join(" "));
+/// An entry in the option list. Its text is what typing filters on: the
+/// section, flags, description and default.
+struct OptionChoice<'a> {
+    index: usize,
+    section: Option<&'a str>,
+    option: &'a OptionInfo,
+}
+
+impl std::fmt::Display for OptionChoice<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(section) = self.section {
+            write!(f, "{} │ ", section)?;
+        }
+        write!(
+            f,
+            "{}: {}",
+            self.option.flag_label(),
+            self.option.description
+        )?;
+        if let Some(default) = &self.option.default {
+            write!(f, " [default: {}]", default)?;
+        }
+        Ok(())
+    }
+}
 
-    let mut command = Command::new(executable);
-    command.args(args);
+/// Options named with `--select name` or `--select name=value`, in the
+/// shape `select_option_indices` returns, for runs that can't prompt. A name
+/// matches any of an option's flags, with or without its leading dashes.
+pub fn select_from_flags(
+    options: &[OptionInfo],
+    selections: &[String],
+    target: &str,
+) -> anyhow::Result<Vec<(usize, Option<String>)>> {
+    let mut selected = Vec::new();
 
-    let status = command.status()?;
+    for selection in selections {
+        let (name, value) = match selection.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (selection.as_str(), None),
+        };
+        let wanted = name.trim_start_matches('-');
+        let Some(idx) = options.iter().position(|opt| {
+            opt.flags
+                .iter()
+                .any(|flag| flag == name || flag.trim_start_matches('-') == wanted)
+        }) else {
+            anyhow::bail!(
+                "Unknown option '{}' for {}; valid choices: {}",
+                name,
+                target,
+                options
+                    .iter()
+                    .map(OptionInfo::preferred_flag)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
 
-    if status.success() {
-        println!("Command executed successfully");
+        let opt = &options[idx];
+        match (&value, opt.value) {
+            (None, ValueArity::Required) => anyhow::bail!(
+                "{} needs a value: --select {}=VALUE",
+                opt.preferred_flag(),
+                name
+            ),
+            (Some(_), ValueArity::None) => {
+                anyhow::bail!("{} doesn't take a value", opt.preferred_flag())
+            }
+            (Some(value), _) => {
+                opt.value_type
+                    .check(value)
+                    .map_err(|reason| anyhow::anyhow!("{}: {}", opt.preferred_flag(), reason))?;
+            }
+            (None, _) => {}
+        }
+        selected.push((idx, value));
+    }
+
+    Ok(selected)
+}
+
+/// The value to pass with an option, or `None` to pass an optional-value
+/// flag on its own. `previous` is the value given last time, offered as the
+/// default in place of the help's.
+fn prompt_option_value(
+    opt: &OptionInfo,
+    previous: Option<&str>,
+    sources: &ValueSources,
+) -> anyhow::Result<Option<String>> {
+    if !opt.possible_values.is_empty() {
+        return select_possible_value(opt, &opt.possible_values, previous);
+    }
+    if let Some(values) = sources.values(opt) {
+        return select_possible_value(opt, &values, previous);
+    }
+    let optional = opt.value == ValueArity::Optional;
+    let message = match &opt.value_name {
+        Some(name) => format!("Enter {} for {}", name, opt.preferred_flag()),
+        None => format!("Enter value for {}", opt.preferred_flag()),
+    };
+    let message = if optional {
+        format!("{} (empty to pass the flag alone):", message)
     } else {
-        println!("Command failed with exit code: {:?}", status.code());
+        format!("{}:", message)
+    };
+    let default = previous.or(opt.default.as_deref());
+    let value = editor::prompt_value(&message, default, opt.value_type)?;
+    Ok(Some(value).filter(|value| !(optional && value.is_empty())))
+}
+
+/// How many times to pass a repeatable flag such as `-v`.
+fn prompt_repeat_count(opt: &OptionInfo, default: usize) -> anyhow::Result<usize> {
+    loop {
+        let input = ui::text(
+            &format!("How many times to pass {}?", opt.preferred_flag()),
+            Some(&default.to_string()),
+        )?;
+        match input.trim().parse::<usize>() {
+            Ok(count) if count > 0 => return Ok(count),
+            _ => println!("Please enter a number of at least 1"),
+        }
+    }
+}
+
+const OTHER_VALUE: &str = "(other, type a value)";
+const NO_VALUE: &str = "(none, pass the flag alone)";
+
+/// Choose among the values an option lists, or a value command printed, with
+/// free text as a fallback in case the list is incomplete.
+fn select_possible_value(
+    opt: &OptionInfo,
+    values: &[String],
+    previous: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let choice = ui::select(
+        &format!("Value for {}:", opt.preferred_flag()),
+        value_choices(opt, values, previous),
+    )?;
+    match choice.as_str() {
+        NO_VALUE => Ok(None),
+        OTHER_VALUE => Ok(Some(editor::prompt_value(
+            &format!("Enter value for {}:", opt.preferred_flag()),
+            None,
+            opt.value_type,
+        )?)),
+        _ => Ok(Some(choice)),
+    }
+}
+
+/// The entries offered for an option's value, in order.
+fn value_choices(opt: &OptionInfo, values: &[String], previous: Option<&str>) -> Vec<String> {
+    // The default, or last time's value, goes first so Enter picks it
+    let mut choices = values.to_vec();
+    if let Some(default) = previous.or(opt.default.as_deref())
+        && let Some(pos) = choices.iter().position(|value| value == default)
+    {
+        let value = choices.remove(pos);
+        choices.insert(0, value);
+    }
+    // An optional value is left out unless one is picked
+    if opt.value == ValueArity::Optional {
+        choices.insert(0, NO_VALUE.to_string());
+    }
+    choices.push(OTHER_VALUE.to_string());
+    choices
+}
+
+/// Replace a leading `~` with the home directory.
+pub fn expand_tilde(path: &str) -> anyhow::Result<String> {
+    if path.starts_with("~") {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        Ok(path.replacen("~", &home, 1))
+    } else {
+        Ok(path.to_string())
+    }
+}
+
+/// Entries of the directory `input` is in that start with its last
+/// component, as `input` would continue to them: directories end in `/`,
+/// and hidden entries only show once a `.` is typed.
+pub fn complete_path(input: &str) -> Vec<String> {
+    const MAX_SUGGESTIONS: usize = 100;
+
+    let (typed_dir, prefix) = match input.rfind('/') {
+        Some(slash) => input.split_at(slash + 1),
+        None => ("", input),
+    };
+    let dir = match typed_dir {
+        "" => ".".to_string(),
+        dir => expand_tilde(dir).unwrap_or_else(|_| dir.to_string()),
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut suggestions: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            // Follows symlinks, so a link to a directory completes like one
+            let is_dir = entry.path().is_dir();
+            Some(format!(
+                "{}{}{}",
+                typed_dir,
+                name,
+                if is_dir { "/" } else { "" }
+            ))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// The name a tool goes by in the user config, e.g. `java` for
+/// `/usr/bin/java` or `C:\\...\\java.exe`.
+pub fn tool_name(executable: &str) -> String {
+    std::path::Path::new(executable)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| executable.to_string())
+}
+
+/// Environment assignments as a shell would write them before a command,
+/// e.g. `RUST_LOG=debug `, or nothing when there are none.
+pub fn env_prefix(env: &[(String, String)]) -> String {
+    env.iter()
+        .map(|(name, value)| format!("{}={} ", name, shell_quote(value)))
+        .collect()
+}
+
+/// Quote a string so a POSIX shell reads it back as exactly one word.
+pub fn shell_quote(value: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// A program and its arguments quoted for a POSIX shell, so the line can be
+/// pasted into a terminal and run as is.
+pub fn shell_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A venv's activation script, relative to the venv directory.
+#[cfg(windows)]
+pub const VENV_ACTIVATE: &str = "Scripts\\activate.bat";
+#[cfg(not(windows))]
+pub const VENV_ACTIVATE: &str = "bin/activate";
+
+/// Where Go builds from project mode are installed: `$GOBIN`, else the
+/// first `$GOPATH` entry's `bin`, else `~/go/bin`, the same place
+/// `go install` uses. No sudo is needed.
+pub fn go_install_dir() -> anyhow::Result<std::path::PathBuf> {
+    go_install_dir_from(
+        std::env::var_os("GOBIN"),
+        std::env::var_os("GOPATH"),
+        dirs::home_dir(),
+    )
+}
+
+/// `go_install_dir` with the environment and home directory given.
+fn go_install_dir_from(
+    gobin: Option<std::ffi::OsString>,
+    gopath: Option<std::ffi::OsString>,
+    home: Option<std::path::PathBuf>,
+) -> anyhow::Result<std::path::PathBuf> {
+    if let Some(gobin) = gobin.filter(|dir| !dir.is_empty()) {
+        return Ok(std::path::PathBuf::from(gobin));
+    }
+    if let Some(gopath) = gopath
+        .as_deref()
+        .and_then(|gopath| std::env::split_paths(gopath).next())
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        return Ok(gopath.join("bin"));
+    }
+    let home = home.ok_or_else(|| anyhow::anyhow!("Could not find the home directory"))?;
+    Ok(home.join("go").join("bin"))
+}
+
+/// The system-wide install directory, used only when asked for with
+/// `system_install = true` under `[go]`. Installing into or removing from it
+/// needs sudo.
+#[cfg(not(windows))]
+pub const SYSTEM_INSTALL_DIR: Option<&str> = Some("/usr/bin");
+#[cfg(windows)]
+pub const SYSTEM_INSTALL_DIR: Option<&str> = None;
+
+/// Whether an installed file lives in `SYSTEM_INSTALL_DIR`, so removing it
+/// needs sudo.
+pub fn in_system_install_dir(path: &str) -> bool {
+    SYSTEM_INSTALL_DIR.is_some_and(|dir| std::path::Path::new(path).starts_with(dir))
+}
+
+/// Whether `dir` is one of the directories on `$PATH`.
+pub fn on_path(dir: &std::path::Path) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|entry| entry == dir))
+}
+
+/// The shell line that adds `dir` to `PATH`.
+pub fn path_export_line(dir: &std::path::Path) -> String {
+    if cfg!(windows) {
+        format!("$env:Path += \";{}\"", dir.display())
+    } else {
+        format!("export PATH=\"$PATH:{}\"", dir.display())
+    }
+}
+
+/// A scratch location for a project's binary before it is installed, in a
+/// directory of its own under the system temp directory, e.g.
+/// `/tmp/app-hoist-3f2a…/tool` or `%TEMP%\app-hoist-3f2a…\tool.exe`.
+pub fn temp_binary_path(project: &str, name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!(
+            "app-hoist-{:016x}",
+            crate::cache::path_hash(project)
+        ))
+        .join(format!("{}{}", name, std::env::consts::EXE_SUFFIX))
+}
+
+/// A program inside a venv: `<venv>/bin/python`, or `<venv>\Scripts\python.exe`
+/// on Windows.
+pub fn venv_program(venv_dir: &std::path::Path, program: &str) -> String {
+    let bin = if cfg!(windows) { "Scripts" } else { "bin" };
+    venv_dir
+        .join(bin)
+        .join(format!("{}{}", program, std::env::consts::EXE_SUFFIX))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Run command lines one after another, stopping at the first failure: a
+/// single one directly, several through `bash -c` (`cmd /C` on Windows).
+pub fn chain_commands(commands: Vec<Vec<String>>) -> (String, Vec<String>) {
+    if commands.len() <= 1 {
+        let mut words = commands.into_iter().flatten();
+        let program = words.next().unwrap_or_default();
+        return (program, words.collect());
+    }
+
+    let script = commands
+        .iter()
+        .map(|words| {
+            words
+                .iter()
+                .map(|word| platform_quote(word))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" && ");
+
+    if cfg!(windows) {
+        ("cmd".to_string(), vec!["/C".to_string(), script])
+    } else {
+        ("bash".to_string(), vec!["-c".to_string(), script])
+    }
+}
+
+/// Quote a word for the platform's shell: POSIX quoting, or double quotes for cmd.
+fn platform_quote(word: &str) -> String {
+    if !cfg!(windows) {
+        return shell_quote(word);
+    }
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./\\=:,@+".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("\"{}\"", word.replace('"', "\"\""))
     }
+}
+
+/// What joins a value to its flag: `--file=value`, `/LEV:3` for Windows
+/// flags, or nothing for short flags such as `-Dkey=value`.
+fn value_separator(flag: &str) -> &'static str {
+    if flag.starts_with('/') {
+        ":"
+    } else if flag.starts_with("--") {
+        "="
+    } else {
+        ""
+    }
+}
+
+/// Arguments for the selected options, followed by `extra_args` as given.
+pub fn build_command(
+    selected: &[SelectedOption],
+    extra_args: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+
+    for option in selected {
+        for value in &option.occurrences {
+            match value {
+                Some(val) if option.joined => args.push(format!(
+                    "{}{}{}",
+                    option.flag,
+                    value_separator(&option.flag),
+                    val
+                )),
+                Some(val) => {
+                    args.push(option.flag.clone());
+                    args.push(val.clone());
+                }
+                None => args.push(option.flag.clone()),
+            }
+        }
+    }
+    args.extend(extra_args.iter().cloned());
+
+    Ok(args)
+}
+
+/// Ask for anything the option list missed, split like a shell would so
+/// quoted arguments keep their spaces. Empty input adds nothing.
+pub fn prompt_extra_args() -> anyhow::Result<Vec<String>> {
+    loop {
+        let input = ui::text("Additional arguments (optional):", None)?;
+        match crate::tools::split_words(&input) {
+            Ok(words) => return Ok(words),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
 
-    Ok(())
+/// Run a command attached to the terminal, or captured in quiet-child mode,
+/// with `env` added to its environment and in `path` when given. The run is
+/// recorded in history. Pass the status to `capture::check_status` to fail
+/// with the command's exit code.
+pub async fn run_command(
+    executable: &str,
+    args: &[String],
+    env: &[(String, String)],
+    path: Option<&str>,
+) -> anyhow::Result<ExitStatus> {
+    run_recorded(
+        HistoryEntry::command(executable, args, env),
+        executable,
+        args,
+        env,
+        path,
+    )
+    .await
 }
 
-pub fn execute_project_command(
+/// Run a project operation's command in the project, recording it in
+/// history under `operation` so later runs can be compared with it.
+pub async fn run_operation(
+    operation: Option<&str>,
     executable: &str,
     args: &[String],
+    env: &[(String, String)],
     path: &str,
-) -> anyhow::Result<()> {
-    println!("Executing: {} {}", executable, args.join(" "));
+) -> anyhow::Result<ExitStatus> {
+    let entry = match operation {
+        Some(operation) => HistoryEntry {
+            env: env.to_vec(),
+            ..HistoryEntry::new(path, operation, executable, args)
+        },
+        None => HistoryEntry::command(executable, args, env),
+    };
+    run_recorded(entry, executable, args, env, Some(path)).await
+}
+
+async fn run_recorded(
+    entry: HistoryEntry,
+    executable: &str,
+    args: &[String],
+    env: &[(String, String)],
+    path: Option<&str>,
+) -> anyhow::Result<ExitStatus> {
+    println!(
+        "Executing: {}{}",
+        env_prefix(env),
+        display_command(executable, args)
+    );
 
-    let mut command = Command::new(executable);
-    command.args(args);
-    command.current_dir(path);
+    let mut command = AsyncCommand::new(executable);
+    command.args(args).envs(env.iter().cloned());
+    if let Some(path) = path {
+        command.current_dir(path);
+    }
 
-    let status = command.status()?;
+    let started = std::time::Instant::now();
+    let status = capture::run(&mut command, &display_command(executable, args)).await?;
+    history::record(&entry.finished(&run_dir(path)?, status.code(), started.elapsed()))?;
 
     if status.success() {
-        println!("Command executed successfully");
+        // Quiet runs have already printed a summary line
+        if !capture::is_quiet() {
+            println!("Command executed successfully");
+        }
     } else {
         println!("Command failed with exit code: {:?}", status.code());
     }
 
-    Ok(())
+    Ok(status)
+}
+
+/// The absolute directory a command runs in, for its history entry.
+pub fn run_dir(path: Option<&str>) -> anyhow::Result<std::path::PathBuf> {
+    Ok(match path {
+        Some(path) => std::path::absolute(path)?,
+        None => std::env::current_dir()?,
+    })
 }
 
+/// A command that ran but exited unsuccessfully, with the end of its stderr.
+#[derive(Debug)]
+pub struct CommandFailed {
+    pub code: Option<i32>,
+    pub warnings: usize,
+    pub stderr_tail: Vec<String>,
+}
+
+impl std::fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Command failed with exit code: {:?}{}",
+            self.code,
+            warning_suffix(self.warnings)
+        )
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+/// The line read into `buf`, without its line ending, leaving `buf` empty.
+fn take_line(buf: &mut Vec<u8>) -> String {
+    let line = String::from_utf8_lossy(buf)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    buf.clear();
+    line
+}
+
+/// Run a project command with its output forwarded through the progress
+/// display, returning how many warnings the tool reported.
 pub async fn execute_project_command_async(
     executable: &str,
     args: &[String],
     path: &str,
-    pb: &ProgressBar,
-) -> anyhow::Result<()> {
-    pb.set_message(format!("Running: {} {}", executable, args.join(" ")));
+    project_type: &ProjectType,
+    pb: &Progress,
+    registry: &TaskRegistry,
+) -> anyhow::Result<usize> {
+    if registry.is_cancelled(path) {
+        return Err(Cancelled.into());
+    }
+    pb.set_message(format!("Running: {}", display_command(executable, args)));
+    let started = std::time::Instant::now();
 
     let mut command = AsyncCommand::new(executable);
     command
         .args(args)
         .current_dir(path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so cancelling also stops anything it started
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command.spawn()?;
+    registry.set_child(path, child.id());
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut stderr = BufReader::new(child.stderr.take().expect("stderr is piped"));
+    let (mut stdout_buf, mut stderr_buf) = (Vec::new(), Vec::new());
+    let (mut stdout_done, mut stderr_done) = (false, false);
+    let mut warnings = WarningCounter::new(project_type);
+    let mut capture = if capture::is_quiet() {
+        Some(Capture::new(&format!("{} {}", path, executable))?)
+    } else {
+        None
+    };
 
-    let status = command.status().await?;
+    // Forward both streams as they arrive, counting warnings on the way through.
+    // Lines are read as bytes, since tools don't always print valid UTF-8; a
+    // read cut short by the other stream's line resumes into the same buffer
+    let mut stderr_tail = TailBuffer::new(capture::tail_lines());
+    loop {
+        let (read, from_stderr) = tokio::select! {
+            read = stdout.read_until(b'\n', &mut stdout_buf), if !stdout_done => (read?, false),
+            read = stderr.read_until(b'\n', &mut stderr_buf), if !stderr_done => (read?, true),
+            else => break,
+        };
+
+        let buf = if from_stderr {
+            &mut stderr_buf
+        } else {
+            &mut stdout_buf
+        };
+        if read == 0 && buf.is_empty() {
+            if from_stderr {
+                stderr_done = true;
+            } else {
+                stdout_done = true;
+            }
+            continue;
+        }
+        let line = take_line(buf);
+
+        warnings.observe(&line);
+        if from_stderr {
+            stderr_tail.push(line.clone());
+        }
+        match &mut capture {
+            Some(capture) => capture.write_line(&line),
+            None => pb.println(&line),
+        }
+    }
+
+    let status = child.wait().await?;
+    let warning_count = warnings.count();
+    history::record(&HistoryEntry::command(executable, args, &[]).finished(
+        &run_dir(Some(path))?,
+        status.code(),
+        started.elapsed(),
+    ))?;
+
+    if registry.is_cancelled(path) {
+        pb.set_message(format!(
+            "⏭️  Cancelled: {}",
+            display_command(executable, args)
+        ));
+        return Err(Cancelled.into());
+    }
 
     if status.success() {
-        pb.set_message(format!("✅ Completed: {} {}", executable, args.join(" ")));
-        Ok(())
+        let duration = if capture.is_some() {
+            format!(" in {}", capture::format_duration(started.elapsed()))
+        } else {
+            String::new()
+        };
+        pb.set_message(format!(
+            "✅ Completed: {}{}{}",
+            display_command(executable, args),
+            duration,
+            warning_suffix(warning_count)
+        ));
+        Ok(warning_count)
     } else {
         pb.set_message(format!(
-            "❌ Failed: {} {} (exit code: {:?})",
-            executable,
-            args.join(" "),
+            "❌ Failed: {} (exit code: {:?})",
+            display_command(executable, args),
             status.code()
         ));
-        anyhow::bail!("Command failed with exit code: {:?}", status.code());
+        if let Some(capture) = capture {
+            for line in capture.finish().failure_report() {
+                pb.println(&line);
+            }
+        }
+        Err(CommandFailed {
+            code: status.code(),
+            warnings: warning_count,
+            stderr_tail: stderr_tail.lines().cloned().collect(),
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_lose_their_line_ending() {
+        let mut buf = b"warning: unused\r\n".to_vec();
+        assert_eq!(take_line(&mut buf), "warning: unused");
+        assert!(buf.is_empty());
+
+        let mut buf = b"no newline at the end".to_vec();
+        assert_eq!(take_line(&mut buf), "no newline at the end");
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced_rather_than_failing() {
+        let mut buf = b"caf\xe9 warning: latin-1\n".to_vec();
+        assert_eq!(take_line(&mut buf), "caf\u{fffd} warning: latin-1");
+    }
+
+    fn parsed(help: &str) -> OptionInfo {
+        crate::help_parser::parse_help(help)
+            .into_iter()
+            .next()
+            .expect("an option")
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn listed_values_keep_an_escape_hatch() {
+        let opt = parsed("Options:\n  --format <F>  Output [possible values: json, yaml, table]\n");
+        assert_eq!(
+            value_choices(&opt, &opt.possible_values, None),
+            ["json", "yaml", "table", OTHER_VALUE]
+        );
+    }
+
+    #[test]
+    fn optional_values_can_be_left_out() {
+        let opt = parsed("Options:\n  --color[=WHEN]  Colorize {always,never}\n");
+        assert_eq!(opt.value, ValueArity::Optional);
+        assert_eq!(
+            value_choices(&opt, &strings(&["always", "never"]), None),
+            [NO_VALUE, "always", "never", OTHER_VALUE]
+        );
+    }
+
+    #[test]
+    fn defaults_are_offered_first_and_shown_in_the_list() {
+        let opt = parsed(
+            "Options:\n  --level <L>  Log level [possible values: debug, info] [default: info]\n",
+        );
+        assert_eq!(
+            value_choices(&opt, &opt.possible_values, None),
+            ["info", "debug", OTHER_VALUE]
+        );
+        // Last time's value wins over the help's default
+        assert_eq!(
+            value_choices(&opt, &opt.possible_values, Some("debug")),
+            ["debug", "info", OTHER_VALUE]
+        );
+
+        let choice = OptionChoice {
+            index: 0,
+            section: None,
+            option: &opt,
+        };
+        assert!(choice.to_string().ends_with("[default: info]"));
+    }
+
+    fn selected(flag: &str, occurrences: &[Option<&str>], joined: bool) -> SelectedOption {
+        SelectedOption {
+            flag: flag.to_string(),
+            occurrences: occurrences.iter().map(|v| v.map(str::to_string)).collect(),
+            joined,
+        }
+    }
+
+    #[test]
+    fn repeated_options_are_passed_once_per_occurrence() {
+        let args = build_command(
+            &[
+                selected("-v", &[None, None, None], false),
+                selected("--include", &[Some("src"), Some("lib")], false),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            ["-v", "-v", "-v", "--include", "src", "--include", "lib"]
+        );
+    }
+
+    #[test]
+    fn value_styles_decide_how_long_flags_take_values() {
+        let joined = parsed("Options:\n  --file=FILE  read from FILE\n");
+        let separate = parsed("Options:\n  --file <FILE>  read from FILE\n");
+        assert!(ValueStyle::Auto.joins(&joined));
+        assert!(!ValueStyle::Auto.joins(&separate));
+        assert!(!ValueStyle::Separate.joins(&joined));
+        assert!(ValueStyle::Joined.joins(&separate));
+
+        // Short flags can't take `=value`
+        let short = parsed("Options:\n  -f FILE  read from FILE\n");
+        assert!(!ValueStyle::Joined.joins(&short));
+    }
+
+    #[test]
+    fn joined_values_use_the_flag_style_separator() {
+        let args = build_command(
+            &[
+                selected("--file", &[Some("a.txt")], true),
+                selected("/LEV", &[Some("3")], true),
+                selected("-D", &[Some("key=value")], true),
+                selected("--out", &[Some("b.txt")], false),
+            ],
+            &["extra".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            [
+                "--file=a.txt",
+                "/LEV:3",
+                "-Dkey=value",
+                "--out",
+                "b.txt",
+                "extra"
+            ]
+        );
+    }
+
+    #[test]
+    fn optional_values_may_be_left_out_but_required_ones_may_not() {
+        let options = crate::help_parser::parse_help(
+            "Options:\n      --color [WHEN]  colorize\n  -c, --config <FILE>  config file\n",
+        );
+        // A separate word would be read as an argument, so optional values join
+        assert!(ValueStyle::Separate.joins(&options[0]));
+
+        assert_eq!(
+            select_from_flags(&options, &strings(&["--color"]), "tool").unwrap(),
+            [(0, None)]
+        );
+        let err = select_from_flags(&options, &strings(&["config"]), "tool").unwrap_err();
+        assert!(err.to_string().contains("needs a value"));
+        assert_eq!(
+            build_command(&[selected("--color", &[None], true)], &[]).unwrap(),
+            ["--color"]
+        );
+    }
+
+    #[test]
+    fn selected_values_are_checked_against_their_type() {
+        let options =
+            crate::help_parser::parse_help("Options:\n  -p, --port <PORT>  Port to listen on\n");
+        let err = select_from_flags(&options, &strings(&["port=banana"]), "tool").unwrap_err();
+        assert_eq!(err.to_string(), "--port: 'banana' is not a whole number");
+        assert!(select_from_flags(&options, &strings(&["-p=8080"]), "tool").is_ok());
+    }
+
+    #[test]
+    fn conflicting_choices_are_found_until_kept() {
+        let options = crate::help_parser::parse_help(
+            "Options:\n  --json  JSON output [conflicts with --quiet]\n  -v  Verbose\n  -q, --quiet  Less output\n",
+        );
+        let chosen: Vec<OptionChoice> = [2, 1, 0]
+            .into_iter()
+            .map(|index| OptionChoice {
+                index,
+                section: None,
+                option: &options[index],
+            })
+            .collect();
+
+        // Declared on --json only, but found from --quiet's side too
+        assert_eq!(first_conflict(&chosen, &[]), Some((0, 2)));
+        assert_eq!(first_conflict(&chosen, &[(2, 0)]), None);
+        assert_eq!(first_conflict(&chosen[..2], &[]), None);
+    }
+
+    fn style(config: &str, tool: &str) -> anyhow::Result<ValueStyle> {
+        ValueStyle::from_config(Some(&toml::from_str(config).unwrap()), Some(tool))
+    }
+
+    #[test]
+    fn value_styles_come_from_the_tool_then_the_package_table() {
+        assert_eq!(
+            ValueStyle::from_config(None, Some("ls")).unwrap(),
+            ValueStyle::Auto
+        );
+        let config = r#"
+[package]
+value_style = "separate"
+
+[package.value_styles]
+java = "joined"
+"#;
+        assert_eq!(style(config, "java").unwrap(), ValueStyle::Joined);
+        assert_eq!(style(config, "ls").unwrap(), ValueStyle::Separate);
+    }
+
+    #[test]
+    fn unknown_value_styles_are_rejected() {
+        let err = style("[package]\nvalue_style = \"glued\"", "ls").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[package] value_style must be \"auto\", \"separate\" or \"joined\", not \"glued\""
+        );
+        let err = style("[package.value_styles]\nls = 1", "ls").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[package.value_styles] ls must be a string"
+        );
+    }
+
+    #[test]
+    fn attached_short_flags_keep_their_value_attached() {
+        let options = crate::help_parser::parse_help(
+            "Options:\n  -Dkey=value  Set a property\n  -I<dir>  Include path\n",
+        );
+        for option in &options {
+            assert!(ValueStyle::Separate.joins(option), "{:?}", option.flags);
+        }
+        let args = build_command(&[selected("-D", &[Some("mode=fast")], true)], &[]).unwrap();
+        assert_eq!(args, ["-Dmode=fast"]);
+    }
+
+    #[test]
+    fn go_installs_follow_gobin_then_gopath_then_home() {
+        let dir = |path: &str| Some(std::ffi::OsString::from(path));
+        let home = Some(std::path::PathBuf::from("/home/me"));
+        let gopath = std::env::join_paths(["/opt/go", "/srv/go"]).unwrap();
+
+        assert_eq!(
+            go_install_dir_from(dir("/opt/bin"), Some(gopath.clone()), home.clone()).unwrap(),
+            std::path::Path::new("/opt/bin")
+        );
+        assert_eq!(
+            go_install_dir_from(dir(""), Some(gopath), home.clone()).unwrap(),
+            std::path::Path::new("/opt/go").join("bin")
+        );
+        assert_eq!(
+            go_install_dir_from(None, dir(""), home).unwrap(),
+            std::path::Path::new("/home/me").join("go").join("bin")
+        );
+        assert!(go_install_dir_from(None, None, None).is_err());
+    }
+
+    #[test]
+    fn go_builds_go_to_a_temp_directory_per_project() {
+        let first = temp_binary_path("/work/a", "tool");
+        let second = temp_binary_path("/work/b", "tool");
+        assert!(first.starts_with(std::env::temp_dir()));
+        assert_ne!(first.parent(), second.parent());
+        assert_eq!(first, temp_binary_path("/work/a", "tool"));
+        assert_eq!(
+            first.file_name().unwrap(),
+            format!("tool{}", std::env::consts::EXE_SUFFIX).as_str()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn only_the_system_directory_needs_sudo() {
+        assert!(in_system_install_dir("/usr/bin/tool"));
+        assert!(!in_system_install_dir("/home/me/go/bin/tool"));
+        assert_eq!(
+            path_export_line(std::path::Path::new("/home/me/go/bin")),
+            "export PATH=\"$PATH:/home/me/go/bin\""
+        );
     }
 }
@@ -0,0 +1,171 @@
+use crate::config;
+use crate::models::OptionInfo;
+use crate::ui;
+use crate::utils::tool_name;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a value command may run before its values are given up on.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Commands that ship with app-hoist, as examples of what `[value_sources.<tool>]`
+/// in `~/.app-hoist/config.toml` can add: tool, flag, then the command whose
+/// output lines are the values.
+const BUILTIN: &[(&str, &str, &str)] = &[
+    (
+        "docker",
+        "--network",
+        "docker network ls --format '{{.Name}}'",
+    ),
+    (
+        "docker",
+        "--volumes-from",
+        "docker ps -a --format '{{.Names}}'",
+    ),
+    (
+        "docker",
+        "--context",
+        "docker context ls --format '{{.Name}}'",
+    ),
+    ("git", "--branch", "git branch --format='%(refname:short)'"),
+    ("git", "--onto", "git branch --format='%(refname:short)'"),
+    (
+        "git",
+        "--set-upstream-to",
+        "git branch -r --format='%(refname:short)'",
+    ),
+    (
+        "kubectl",
+        "--namespace",
+        "kubectl get namespaces -o custom-columns=:metadata.name --no-headers",
+    ),
+    (
+        "kubectl",
+        "--context",
+        "kubectl config get-contexts -o name",
+    ),
+];
+
+/// Shell commands that list the values a tool's flags accept, e.g. the
+/// networks for `docker --network`, keyed by flag.
+#[derive(Debug, Default)]
+pub struct ValueSources {
+    commands: HashMap<String, String>,
+}
+
+impl ValueSources {
+    /// The commands for `executable`: the built-in ones, then the user's
+    /// `[value_sources.<tool>]` entries, where an empty command turns a
+    /// built-in off.
+    pub fn load(executable: &str) -> anyhow::Result<Self> {
+        let tool = tool_name(executable);
+
+        let mut commands: HashMap<String, String> = BUILTIN
+            .iter()
+            .filter(|(name, _, _)| *name == tool)
+            .map(|(_, flag, command)| (flag.to_string(), command.to_string()))
+            .collect();
+
+        if let Some(table) = config::load_user_config()?
+            .and_then(|config| config.get("value_sources")?.get(&tool).cloned())
+        {
+            let table = table.as_table().ok_or_else(|| {
+                anyhow::anyhow!("[value_sources.{}] must be a table of flag = command", tool)
+            })?;
+            for (flag, command) in table {
+                let command = command.as_str().ok_or_else(|| {
+                    anyhow::anyhow!("[value_sources.{}] {} must be a string", tool, flag)
+                })?;
+                if command.trim().is_empty() {
+                    commands.remove(flag);
+                } else {
+                    commands.insert(flag.clone(), command.to_string());
+                }
+            }
+        }
+
+        Ok(ValueSources { commands })
+    }
+
+    /// The values to offer for `opt`, or `None` when no command is configured
+    /// for it or the command fails, in which case the value is typed instead.
+    pub fn values(&self, opt: &OptionInfo) -> Option<Vec<String>> {
+        let (flag, command) = opt
+            .flags
+            .iter()
+            .find_map(|flag| Some((flag, self.commands.get(flag)?)))?;
+
+        match run_value_command(command) {
+            Ok(values) if !values.is_empty() => Some(values),
+            Ok(_) => None,
+            Err(e) => {
+                ui::status(&format!("💡 Couldn't list values for {}: {}", flag, e));
+                None
+            }
+        }
+    }
+}
+
+/// Run `command` through the shell and return its non-empty output lines.
+fn run_value_command(command: &str) -> anyhow::Result<Vec<String>> {
+    let mut child = shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("could not run `{}`: {}", command, e))?;
+
+    // Read on another thread so a command with lots of output can't fill the
+    // pipe and stall while we wait for it
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > COMMAND_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!(
+                "`{}` took longer than {}s",
+                command,
+                COMMAND_TIMEOUT.as_secs()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    if !status.success() {
+        anyhow::bail!("`{}` exited with {}", command, status);
+    }
+
+    let output = reader
+        .join()
+        .map_err(|_| anyhow::anyhow!("reading `{}` failed", command))??;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
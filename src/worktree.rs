@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Cache metadata keys for the repository a project belongs to.
+pub const REPOSITORY_KEY: &str = "repository";
+pub const WORKTREE_KEY: &str = "worktree";
+
+/// The git repository a project directory is checked out from. Every
+/// worktree of a repository shares its `repository` (the common git
+/// directory) while keeping its own path, so worktrees stay separate
+/// projects that are still known to be related.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkout {
+    pub repository: PathBuf,
+    /// The linked worktree's name, `None` for the main checkout
+    pub worktree: Option<String>,
+}
+
+impl Checkout {
+    /// Find the checkout containing `path`, from the nearest `.git`: a
+    /// directory for the main checkout, or for a linked worktree a file
+    /// holding `gitdir: <repo>/.git/worktrees/<name>`.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let start = std::fs::canonicalize(path).ok()?;
+        let dot_git = start
+            .ancestors()
+            .map(|dir| dir.join(".git"))
+            .find(|candidate| candidate.exists())?;
+
+        if dot_git.is_dir() {
+            return Some(Self {
+                repository: dot_git,
+                worktree: None,
+            });
+        }
+
+        let content = std::fs::read_to_string(&dot_git).ok()?;
+        let gitdir = content
+            .lines()
+            .find_map(|line| line.strip_prefix("gitdir:"))?;
+        let gitdir = dot_git.parent()?.join(gitdir.trim());
+
+        // The worktree's admin directory names the shared one in `commondir`
+        let common = std::fs::read_to_string(gitdir.join("commondir")).ok()?;
+        let repository = std::fs::canonicalize(gitdir.join(common.trim())).ok()?;
+        let worktree = gitdir.file_name()?.to_string_lossy().into_owned();
+
+        Some(Self {
+            repository,
+            worktree: Some(worktree),
+        })
+    }
+
+    /// The repository's directory name, e.g. `service-x` for
+    /// `service-x/.git` or a bare `service-x.git`.
+    pub fn repository_name(&self) -> String {
+        let dir = if self
+            .repository
+            .file_name()
+            .is_some_and(|name| name == ".git")
+        {
+            self.repository.parent().unwrap_or(&self.repository)
+        } else {
+            &self.repository
+        };
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        name.strip_suffix(".git")
+            .map(str::to_string)
+            .unwrap_or(name)
+    }
+
+    /// E.g. `service-x (worktree: feature-y)`.
+    pub fn label(&self) -> String {
+        match &self.worktree {
+            Some(worktree) => format!("{} (worktree: {})", self.repository_name(), worktree),
+            None => self.repository_name(),
+        }
+    }
+
+    /// Record the checkout in a project's cache metadata.
+    pub fn annotate(&self, metadata: &mut std::collections::HashMap<String, String>) {
+        metadata.insert(
+            REPOSITORY_KEY.to_string(),
+            self.repository.to_string_lossy().into_owned(),
+        );
+        match &self.worktree {
+            Some(worktree) => metadata.insert(WORKTREE_KEY.to_string(), worktree.clone()),
+            None => metadata.remove(WORKTREE_KEY),
+        };
+    }
+}
+
+/// Projects that are checkouts of the same repository, keyed by repository.
+/// Projects outside git, or alone in their repository, are left out.
+pub fn shared_repositories(paths: &[String]) -> BTreeMap<PathBuf, Vec<(String, Checkout)>> {
+    let mut groups: BTreeMap<PathBuf, Vec<(String, Checkout)>> = BTreeMap::new();
+    for path in paths {
+        if let Some(checkout) = Checkout::detect(Path::new(path)) {
+            groups
+                .entry(checkout.repository.clone())
+                .or_default()
+                .push((path.clone(), checkout));
+        }
+    }
+    groups.retain(|_, checkouts| checkouts.len() > 1);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A main checkout at `root/service-x` with linked worktrees under
+    /// `root/wt/<name>`, laid out the way `git worktree add` leaves them.
+    fn repository(root: &Path, worktrees: &[&str]) -> PathBuf {
+        let main = root.join("service-x");
+        std::fs::create_dir_all(main.join(".git")).unwrap();
+        for name in worktrees {
+            let admin = main.join(".git/worktrees").join(name);
+            std::fs::create_dir_all(&admin).unwrap();
+            std::fs::write(admin.join("commondir"), "../..\n").unwrap();
+
+            let checkout = root.join("wt").join(name);
+            std::fs::create_dir_all(&checkout).unwrap();
+            std::fs::write(
+                checkout.join(".git"),
+                format!("gitdir: {}\n", admin.display()),
+            )
+            .unwrap();
+        }
+        std::fs::canonicalize(main.join(".git")).unwrap()
+    }
+
+    fn paths(root: &Path, dirs: &[&str]) -> Vec<String> {
+        dirs.iter()
+            .map(|dir| root.join(dir).to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn a_git_directory_is_the_main_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = repository(dir.path(), &[]);
+
+        let checkout = Checkout::detect(&dir.path().join("service-x")).unwrap();
+        assert_eq!(checkout.repository, repository);
+        assert_eq!(checkout.worktree, None);
+        assert_eq!(checkout.label(), "service-x");
+    }
+
+    #[test]
+    fn a_subdirectory_belongs_to_the_nearest_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = repository(dir.path(), &["feature-y"]);
+        let nested = dir.path().join("wt/feature-y/crates/core");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let checkout = Checkout::detect(&nested).unwrap();
+        assert_eq!(checkout.repository, repository);
+        assert_eq!(checkout.worktree.as_deref(), Some("feature-y"));
+    }
+
+    #[test]
+    fn a_git_file_names_the_worktree_and_its_shared_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = repository(dir.path(), &["feature-y"]);
+
+        let checkout = Checkout::detect(&dir.path().join("wt/feature-y")).unwrap();
+        assert_eq!(checkout.repository, repository);
+        assert_eq!(checkout.label(), "service-x (worktree: feature-y)");
+    }
+
+    #[test]
+    fn a_relative_gitdir_is_taken_from_the_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = repository(dir.path(), &["feature-y"]);
+        std::fs::write(
+            dir.path().join("wt/feature-y/.git"),
+            "gitdir: ../../service-x/.git/worktrees/feature-y\n",
+        )
+        .unwrap();
+
+        let checkout = Checkout::detect(&dir.path().join("wt/feature-y")).unwrap();
+        assert_eq!(checkout.repository, repository);
+    }
+
+    #[test]
+    fn malformed_git_files_are_not_checkouts() {
+        let dir = tempfile::tempdir().unwrap();
+        repository(dir.path(), &["feature-y", "no-commondir"]);
+        std::fs::remove_file(
+            dir.path()
+                .join("service-x/.git/worktrees/no-commondir/commondir"),
+        )
+        .unwrap();
+        let stray = dir.path().join("stray");
+        std::fs::create_dir(&stray).unwrap();
+        std::fs::write(stray.join(".git"), "not a gitdir line\n").unwrap();
+
+        assert_eq!(Checkout::detect(&dir.path().join("wt/no-commondir")), None);
+        assert_eq!(Checkout::detect(&stray), None);
+        assert_eq!(Checkout::detect(&dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn a_bare_repository_is_named_without_its_suffix() {
+        let checkout = Checkout {
+            repository: PathBuf::from("/srv/git/service-x.git"),
+            worktree: Some("feature-y".to_string()),
+        };
+        assert_eq!(checkout.repository_name(), "service-x");
+    }
+
+    #[test]
+    fn annotating_the_main_checkout_drops_a_stale_worktree() {
+        let mut metadata = HashMap::from([(WORKTREE_KEY.to_string(), "old".to_string())]);
+        Checkout {
+            repository: PathBuf::from("/src/service-x/.git"),
+            worktree: None,
+        }
+        .annotate(&mut metadata);
+
+        assert_eq!(metadata[REPOSITORY_KEY], "/src/service-x/.git");
+        assert!(!metadata.contains_key(WORKTREE_KEY));
+    }
+
+    #[test]
+    fn checkouts_of_one_repository_are_grouped() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = repository(dir.path(), &["feature-y", "hotfix"]);
+        let other = dir.path().join("other");
+        std::fs::create_dir_all(other.join(".git")).unwrap();
+        std::fs::create_dir(dir.path().join("loose")).unwrap();
+
+        let projects = paths(
+            dir.path(),
+            &["service-x", "wt/feature-y", "other", "loose", "wt/hotfix"],
+        );
+        let groups = shared_repositories(&projects);
+
+        assert_eq!(groups.len(), 1);
+        let grouped: Vec<_> = groups[&repository]
+            .iter()
+            .map(|(path, checkout)| (path.as_str(), checkout.worktree.as_deref()))
+            .collect();
+        assert_eq!(
+            grouped,
+            vec![
+                (projects[0].as_str(), None),
+                (projects[1].as_str(), Some("feature-y")),
+                (projects[4].as_str(), Some("hotfix")),
+            ]
+        );
+    }
+}
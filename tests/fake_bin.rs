@@ -0,0 +1,785 @@
+//! End-to-end runs against fake tools: small shell scripts written into a
+//! temp directory that is the only thing on PATH besides the system shell
+//! utilities, so tool lookup, the help probe's fallbacks and exit codes are
+//! exercised without touching real tools or the user's home.
+#![cfg(unix)]
+
+use app_hoist::help_parser::{ProbeTimedOut, probe_help_output};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+
+const HELP: &str = "Usage: tool [OPTIONS]
+
+Options:
+  -q, --quiet          Say less
+  -n, --count <COUNT>  How many times
+";
+
+/// A directory of fake tools and a home directory for app-hoist to use.
+struct FakeBin {
+    dir: tempfile::TempDir,
+}
+
+impl FakeBin {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("bin")).unwrap();
+        std::fs::create_dir_all(dir.path().join("home")).unwrap();
+        Self { dir }
+    }
+
+    fn bin(&self) -> PathBuf {
+        self.dir.path().join("bin")
+    }
+
+    /// Write an executable `sh` script named `name`.
+    fn tool(&self, name: &str, body: &str) -> PathBuf {
+        let path = self.bin().join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// A tool that prints `HELP` when asked with `help_args`, rejects any
+    /// other way of asking, and otherwise echoes its arguments and exits
+    /// with `code`.
+    fn tool_with_help(&self, name: &str, help_args: &str, code: i32) -> PathBuf {
+        self.tool(
+            name,
+            &format!(
+                r#"if [ "$*" = "{help_args}" ]; then
+  cat <<'EOF'
+{HELP}EOF
+  exit 0
+fi
+case "$1" in
+  --help|-h|help|/?) echo "unrecognized option '$1'" >&2; exit 2 ;;
+esac
+echo "ran: $*"
+exit {code}"#
+            ),
+        )
+    }
+
+    /// Run app-hoist with only the fake tools (and the shell) on PATH.
+    fn hoist(&self, args: &[&str]) -> Output {
+        self.hoist_with_env(args, &[])
+    }
+
+    fn hoist_with_env(&self, args: &[&str], env: &[(&str, &Path)]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_app-hoist"))
+            .args(args)
+            .env_clear()
+            .envs(env.iter().copied())
+            .env("PATH", format!("{}:/usr/bin:/bin", self.bin().display()))
+            .env("HOME", self.dir.path().join("home"))
+            .env("TERM", "dumb")
+            .stdin(Stdio::null())
+            .output()
+            .unwrap()
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+async fn probe(path: &Path) -> anyhow::Result<String> {
+    probe_help_output(path, &[], Duration::from_secs(5)).await
+}
+
+#[tokio::test]
+async fn help_is_found_whichever_way_the_tool_answers() {
+    let bin = FakeBin::new();
+    for help_args in ["--help", "-h", "help", "/?"] {
+        let tool = bin.tool_with_help("tool", help_args, 0);
+        let help = probe(&tool).await.unwrap();
+        assert!(help.contains("--count <COUNT>"), "{}: {}", help_args, help);
+    }
+}
+
+#[tokio::test]
+async fn help_printed_to_stderr_with_a_failing_status_still_counts() {
+    let bin = FakeBin::new();
+    let tool = bin.tool("tool", &format!("cat >&2 <<'EOF'\n{}EOF\nexit 2", HELP));
+    assert!(probe(&tool).await.unwrap().contains("--quiet"));
+}
+
+#[tokio::test]
+async fn tools_without_help_list_every_attempt() {
+    let bin = FakeBin::new();
+    let tool = bin.tool("tool", "exit 1");
+    let err = probe(&tool).await.unwrap_err().to_string();
+    assert!(
+        err.ends_with("(tried --help, -h, help, /?, no arguments)"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn slow_help_probes_time_out() {
+    let bin = FakeBin::new();
+    let tool = bin.tool("tool", "sleep 5");
+    let err = probe_help_output(&tool, &[], Duration::from_millis(200))
+        .await
+        .unwrap_err();
+    let timed_out = err.downcast_ref::<ProbeTimedOut>().unwrap();
+    assert!(timed_out.command.ends_with("tool --help"));
+}
+
+#[test]
+fn tools_are_found_on_path_and_their_options_listed() {
+    let bin = FakeBin::new();
+    bin.tool_with_help("tool", "-h", 0);
+
+    let output = bin.hoist(&["-p", "tool", "--list-options"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let listed: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(
+        listed["executable"],
+        bin.bin().join("tool").display().to_string()
+    );
+    let flags: Vec<&serde_json::Value> = listed["options"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|option| &option["flags"])
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            &serde_json::json!(["-q", "--quiet"]),
+            &serde_json::json!(["-n", "--count"])
+        ]
+    );
+}
+
+#[test]
+fn missing_tools_are_reported() {
+    let bin = FakeBin::new();
+    let output = bin.hoist(&["-p", "no-such-tool", "--list-options"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("Executable 'no-such-tool' not found in PATH"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn selected_options_are_passed_to_the_tool() {
+    let bin = FakeBin::new();
+    bin.tool_with_help("tool", "--help", 0);
+
+    let output = bin.hoist(&["-p", "tool", "--select", "quiet", "--select", "count=2"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("ran: --quiet --count 2"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn the_tool_exit_code_is_passed_on() {
+    let bin = FakeBin::new();
+    bin.tool_with_help("tool", "--help", 3);
+
+    let output = bin.hoist(&["-p", "tool", "--select", "quiet"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+
+    let output = bin.hoist(&["-p", "tool", "--select", "quiet", "--ignore-exit-code"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[test]
+fn dry_runs_print_the_command_without_running_it() {
+    let bin = FakeBin::new();
+    bin.tool_with_help("tool", "--help", 3);
+
+    let output = bin.hoist(&["-p", "tool", "--select", "quiet", "--dry-run"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("ran:"));
+    assert!(stdout(&output).contains("Dry run:"), "{}", stdout(&output));
+}
+
+#[test]
+fn usage_lines_are_only_used_without_an_options_section() {
+    let bin = FakeBin::new();
+    bin.tool(
+        "script",
+        "echo 'Usage: script [-v] [-o output] input'; exit 1",
+    );
+    bin.tool(
+        "tool",
+        &format!(
+            "cat <<'EOF'\nUsage: tool [-x] [-y]\n\n{}EOF",
+            &HELP[HELP.find("Options").unwrap()..]
+        ),
+    );
+
+    let flags = |name: &str| {
+        let output = bin.hoist(&["-p", name, "--list-options"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let listed: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+        listed["options"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|option| option["flags"][0].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(flags("script"), ["-v", "-o"]);
+    assert_eq!(flags("tool"), ["-q", "-n"]);
+}
+
+/// A `go` that writes a file wherever `-o` points.
+const FAKE_GO: &str = r#"out=""
+prev=""
+for arg in "$@"; do
+  [ "$prev" = "-o" ] && out="$arg"
+  prev="$arg"
+done
+echo "go $*"
+[ -n "$out" ] && echo built > "$out"
+exit 0"#;
+
+fn go_project(bin: &FakeBin) -> PathBuf {
+    bin.tool("go", FAKE_GO);
+    let project = bin.dir.path().join("hello");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("go.mod"), "module example.com/hello\n").unwrap();
+    std::fs::write(project.join("main.go"), "package main\nfunc main() {}\n").unwrap();
+    project
+}
+
+#[test]
+fn go_builds_install_into_gobin_without_sudo() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+    let gobin = bin.dir.path().join("gobin");
+
+    let output = bin.hoist_with_env(
+        &["--path", project.to_str().unwrap(), "--select", "build"],
+        &[("GOBIN", &gobin)],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(gobin.join("hello").is_file());
+    assert!(!stdout(&output).contains("sudo"), "{}", stdout(&output));
+    // gobin isn't on PATH, so the line to add it is printed
+    assert!(
+        stdout(&output).contains(&format!("export PATH=\"$PATH:{}\"", gobin.display())),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn go_builds_fall_back_to_go_bin_in_home() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "build"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(bin.dir.path().join("home/go/bin/hello").is_file());
+}
+
+#[test]
+fn cross_compiled_go_builds_are_left_in_the_project() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+    let gobin = bin.dir.path().join("gobin");
+    let build = |extra: &[&str]| {
+        let mut args = vec![
+            "--path",
+            project.to_str().unwrap(),
+            "--select",
+            "build",
+            "--env",
+            "GOOS=windows",
+            "--env",
+            "GOARCH=amd64",
+        ];
+        args.extend(extra);
+        bin.hoist_with_env(&args, &[("GOBIN", &gobin)])
+    };
+
+    let output = build(&["--dry-run"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output)
+            .contains("Dry run: GOOS=windows GOARCH=amd64 go build -o hello-windows-amd64.exe ."),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = build(&[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(project.join("hello-windows-amd64.exe").is_file());
+    assert!(!gobin.exists());
+}
+
+#[test]
+fn program_args_keep_their_quoting_in_dry_runs() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+
+    let output = bin.hoist(&[
+        "--path",
+        project.to_str().unwrap(),
+        "--select",
+        "run",
+        "--args",
+        "serve --name 'two words'",
+        "--dry-run",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("Dry run: go run main.go serve --name 'two words'"),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--args", "'unclosed"]);
+    assert!(!output.status.success());
+}
+
+/// A project of `files`, dated an hour back so a cache entry written now
+/// counts as newer than every marker file.
+fn project(bin: &FakeBin, files: &[(&str, &str)]) -> PathBuf {
+    let project = bin.dir.path().join("project");
+    for (name, content) in files {
+        let file = project.join(name);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, content).unwrap();
+    }
+    let an_hour_ago = std::time::SystemTime::now() - Duration::from_secs(3600);
+    for name in files.iter().map(|(name, _)| *name).chain([""]) {
+        std::fs::File::open(project.join(name))
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+    }
+    project
+}
+
+#[test]
+fn a_project_type_given_on_the_command_line_is_remembered() {
+    let bin = FakeBin::new();
+    let project = project(
+        &bin,
+        &[
+            (
+                "package.json",
+                r#"{"scripts": {"docs": "vitepress build"}}"#,
+            ),
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ],
+    );
+    let path = project.to_str().unwrap();
+    let dry_build = |extra: &[&str]| {
+        let mut args = vec!["--path", path, "--select", "build", "--dry-run"];
+        args.extend(extra);
+        let output = bin.hoist(&args);
+        assert!(output.status.success(), "{}", stderr(&output));
+        stdout(&output)
+    };
+
+    // Rust is checked before JavaScript
+    let detected = dry_build(&[]);
+    assert!(
+        detected.contains("Dry run: cargo build --release"),
+        "{}",
+        detected
+    );
+
+    let overridden = dry_build(&["--project-type", "js"]);
+    assert!(
+        overridden.contains("Detected JavaScript project"),
+        "{}",
+        overridden
+    );
+    assert!(overridden.contains("npm run build"), "{}", overridden);
+    // The correction sticks without the flag
+    let remembered = dry_build(&[]);
+    assert!(
+        remembered.contains("Using cached project information"),
+        "{}",
+        remembered
+    );
+    assert!(
+        remembered.contains("Detected JavaScript project"),
+        "{}",
+        remembered
+    );
+}
+
+#[test]
+fn an_entry_point_given_on_the_command_line_is_remembered() {
+    let bin = FakeBin::new();
+    let project = project(&bin, &[("tools/serve.py", ""), ("requirements.txt", "")]);
+    let path = project.to_str().unwrap();
+    let dry_run = |extra: &[&str]| {
+        let mut args = vec!["--path", path, "--select", "run", "--dry-run"];
+        args.extend(extra);
+        let output = bin.hoist(&args);
+        (output.status.success(), stdout(&output), stderr(&output))
+    };
+
+    // Nothing to guess at, so there is no run option until one is given
+    let (ok, _, _) = dry_run(&[]);
+    assert!(!ok);
+
+    let (ok, out, err) = dry_run(&["--entry", "tools/serve.py"]);
+    assert!(ok, "{}", err);
+    assert!(out.contains("Dry run: python tools/serve.py"), "{}", out);
+    let (ok, out, err) = dry_run(&[]);
+    assert!(ok, "{}", err);
+    assert!(out.contains("Dry run: python tools/serve.py"), "{}", out);
+}
+
+#[test]
+fn a_missing_formatter_fails_with_how_to_install_one() {
+    let bin = FakeBin::new();
+    bin.tool("python", "echo \"python $*\"");
+    let project = project(&bin, &[("main.py", "")]);
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "fmt"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("fmt needs ruff or black")
+            && stderr(&output).contains("pip install ruff"),
+        "{}",
+        stderr(&output)
+    );
+
+    // Once one is installed it's used
+    bin.tool("black", "echo \"black $*\"");
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "fmt"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("black ."), "{}", stdout(&output));
+}
+
+#[test]
+fn go_updates_fetch_then_tidy() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "update"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let out = stdout(&output);
+    let fetched = out.find("go get -u ./...").expect(&out);
+    let tidied = out.find("go mod tidy").expect(&out);
+    assert!(fetched < tidied, "{}", out);
+}
+
+#[test]
+fn audits_name_the_missing_auditor_and_use_it_once_installed() {
+    let bin = FakeBin::new();
+    bin.tool("cargo", "echo \"cargo $*\"");
+    let project = project(
+        &bin,
+        &[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ],
+    );
+    let audit = || bin.hoist(&["--path", project.to_str().unwrap(), "--select", "audit"]);
+
+    let output = audit();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("cargo install cargo-audit"),
+        "{}",
+        stderr(&output)
+    );
+
+    bin.tool("cargo-audit", "exit 0");
+    let output = audit();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("cargo audit"),
+        "{}",
+        stdout(&output)
+    );
+
+    // A configured cargo-deny is preferred
+    bin.tool("cargo-deny", "exit 0");
+    std::fs::write(project.join("deny.toml"), "").unwrap();
+    let output = audit();
+    assert!(
+        stdout(&output).contains("cargo deny check advisories"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn govulncheck_is_found_in_gobin_off_path() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+    let gobin = bin.dir.path().join("gobin");
+    let audit = || {
+        bin.hoist_with_env(
+            &["--path", project.to_str().unwrap(), "--select", "audit"],
+            &[("GOBIN", &gobin)],
+        )
+    };
+
+    let output = audit();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("go install golang.org/x/vuln/cmd/govulncheck@latest"),
+        "{}",
+        stderr(&output)
+    );
+
+    std::fs::create_dir_all(&gobin).unwrap();
+    let govulncheck = gobin.join("govulncheck");
+    std::fs::write(&govulncheck, "#!/bin/sh\necho \"govulncheck $*\"\n").unwrap();
+    std::fs::set_permissions(&govulncheck, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let output = audit();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("govulncheck ./..."),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn clean_removes_artifacts_whose_paths_look_like_options() {
+    let bin = FakeBin::new();
+    bin.tool("python", "echo \"python $*\"");
+    let project = project(
+        &bin,
+        &[
+            ("main.py", ""),
+            ("-pkg/__init__.py", ""),
+            ("-pkg/__pycache__/mod.pyc", "cached"),
+        ],
+    );
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "clean"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!project.join("-pkg/__pycache__").exists());
+    assert!(project.join("-pkg/__init__.py").is_file());
+    assert!(
+        stdout(&output).contains("Reclaimed 6 B"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn go_clean_claims_no_reclaimed_space() {
+    let bin = FakeBin::new();
+    let project = go_project(&bin);
+
+    let output = bin.hoist(&["--path", project.to_str().unwrap(), "--select", "clean"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("go clean ./..."),
+        "{}",
+        stdout(&output)
+    );
+    assert!(
+        !stdout(&output).contains("Reclaimed"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+/// Go modules named `names` under the fake bin's directory.
+fn go_projects(bin: &FakeBin, names: &[&str]) -> Vec<String> {
+    bin.tool("go", FAKE_GO);
+    names
+        .iter()
+        .map(|name| {
+            let project = bin.dir.path().join(name);
+            std::fs::create_dir_all(&project).unwrap();
+            std::fs::write(
+                project.join("go.mod"),
+                format!("module example.com/{name}\n"),
+            )
+            .unwrap();
+            std::fs::write(project.join("main.go"), "package main\nfunc main() {}\n").unwrap();
+            project.to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn multi_project_dry_runs_show_the_plan_for_the_selection() {
+    let bin = FakeBin::new();
+    let projects = go_projects(&bin, &["api", "web"]);
+
+    let mut args = vec!["--dry-run", "--select", "test"];
+    for project in &projects {
+        args.extend(["--multi-path", project]);
+    }
+    let output = bin.hoist(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let out = stdout(&output);
+    let plan: Vec<&str> = out
+        .lines()
+        .skip_while(|line| *line != "Plan:")
+        .skip(1)
+        .take(2)
+        .collect();
+    assert!(plan[0].starts_with("  api  go test"), "{}", out);
+    assert!(plan[1].starts_with("  web  go test"), "{}", out);
+    assert_eq!(out.matches("Dry run - go test").count(), 2, "{}", out);
+}
+
+#[test]
+fn multi_project_dry_runs_report_progress_events() {
+    let bin = FakeBin::new();
+    let projects = go_projects(&bin, &["api", "web"]);
+    let progress = bin.dir.path().join("progress.jsonl");
+
+    let mut args = vec![
+        "--dry-run",
+        "--select",
+        "test",
+        "--progress-file",
+        progress.to_str().unwrap(),
+    ];
+    for project in &projects {
+        args.extend(["--multi-path", project]);
+    }
+    let output = bin.hoist(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let events: Vec<serde_json::Value> = std::fs::read_to_string(&progress)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(
+        events.iter().all(|event| event["version"] == 1),
+        "{:?}",
+        events
+    );
+    let names: Vec<&str> = events
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect();
+
+    // Projects run side by side, so only their own events keep their order
+    let (outer, running) = names.split_at(4);
+    assert_eq!(
+        outer,
+        ["phase_started", "phase_finished", "plan", "phase_started"]
+    );
+    assert_eq!(running.len(), 5, "{:?}", names);
+    assert_eq!(running[4], "phase_finished");
+    assert_eq!(events[0]["phase"], "detect");
+    assert_eq!(events[3]["phase"], "run");
+    assert_eq!(events[8]["phase"], "run");
+
+    let steps = events[2]["steps"].as_array().unwrap();
+    let labels: Vec<_> = steps.iter().map(|step| &step["label"]).collect();
+    assert_eq!(labels, ["api", "web"]);
+    assert!(
+        steps
+            .iter()
+            .all(|step| step["program"] == "go" && step["args"][0] == "test")
+    );
+
+    for project in &projects {
+        let own: Vec<&str> = events
+            .iter()
+            .filter(|event| event["project"] == project.as_str())
+            .map(|event| event["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(own, ["project_started", "project_succeeded"], "{}", project);
+    }
+}
+
+#[test]
+fn choosing_between_competing_types_settles_a_changed_cache_entry() {
+    let bin = FakeBin::new();
+    let project = project(
+        &bin,
+        &[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ],
+    );
+    let path = project.to_str().unwrap();
+    let dry_test = || {
+        let output = bin.hoist(&["--path", path, "--select", "test", "--dry-run"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        format!("{}{}", stdout(&output), stderr(&output))
+    };
+
+    let rust = dry_test();
+    assert!(!rust.contains("could be any of"), "{}", rust);
+
+    // A go.mod next to Cargo.toml asks once, and the cached Rust isn't asked about again
+    std::fs::write(project.join("go.mod"), "module example.com/demo\n").unwrap();
+    let both = dry_test();
+    assert!(
+        both.contains("Project could be any of Go, Rust, using Go"),
+        "{}",
+        both
+    );
+    assert!(!both.contains("Project type changed"), "{}", both);
+}
+
+#[test]
+fn a_pin_wins_over_the_cache_and_fresh_detection_over_a_stale_cache() {
+    let bin = FakeBin::new();
+    let project = project(&bin, &[("Cargo.toml", "[package]\nname = \"demo\"\n")]);
+    let path = project.to_str().unwrap();
+    let dry_test = || {
+        let output = bin.hoist(&["--path", path, "--select", "test", "--dry-run"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        format!("{}{}", stdout(&output), stderr(&output))
+    };
+    let backdate = |name: &str| {
+        std::fs::File::open(project.join(name))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+    };
+
+    assert!(dry_test().contains("Detected Rust project"));
+
+    // Changed markers outweigh the cached Rust
+    std::fs::remove_file(project.join("Cargo.toml")).unwrap();
+    std::fs::write(project.join("go.mod"), "module example.com/demo\n").unwrap();
+    let fresh = dry_test();
+    assert!(fresh.contains("Detecting project information"), "{}", fresh);
+    assert!(fresh.contains("Detected Go project"), "{}", fresh);
+
+    // Unchanged markers keep the cache
+    backdate("go.mod");
+    backdate("");
+    let cached = dry_test();
+    assert!(
+        cached.contains("Using cached project information"),
+        "{}",
+        cached
+    );
+    assert!(cached.contains("Detected Go project"), "{}", cached);
+
+    // A pin outweighs both
+    std::fs::write(
+        project.join(".app-hoist.toml"),
+        "[project]\ntype = \"rust\"\n",
+    )
+    .unwrap();
+    backdate(".app-hoist.toml");
+    backdate("");
+    let pinned = dry_test();
+    assert!(pinned.contains("Detected Rust project"), "{}", pinned);
+}
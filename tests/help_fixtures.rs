@@ -0,0 +1,211 @@
+//! Replays captured help output through the parser, so a change that helps
+//! one tool's format can't quietly break another's. Each fixture is a real
+//! tool's `--help`, checked for how many options it yields, a few of their
+//! flags, and whether those take a value.
+
+use app_hoist::help_parser::{help_text, parse_help, parse_subcommands, parse_usage};
+use app_hoist::models::{OptionInfo, ValueArity};
+
+use ValueArity::{None as NoValue, Optional, Required};
+
+struct Fixture {
+    file: &'static str,
+    options: usize,
+    /// Flags as the parser groups them, with the value each takes
+    flags: &'static [(&'static [&'static str], ValueArity)],
+    /// The first few subcommands, in order
+    subcommands: &'static [&'static str],
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        file: "clap-cargo-build.txt",
+        options: 39,
+        flags: &[
+            (&["--message-format"], Required),
+            (&["-v", "--verbose"], NoValue),
+            (&["-p", "--package"], Optional),
+            (&["--workspace"], NoValue),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "argparse.txt",
+        options: 6,
+        flags: &[
+            (&["-h", "--help"], NoValue),
+            (&["-o", "--output"], Required),
+            (&["-f", "--format"], Required),
+            (&["-j", "--jobs"], Required),
+            (&["--dry-run"], NoValue),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "argparse-zipfile.txt",
+        options: 6,
+        flags: &[
+            (&["-l", "--list"], Required),
+            (&["-c", "--create"], Required),
+            (&["--metadata-encoding"], Required),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "grep.txt",
+        options: 49,
+        flags: &[
+            (&["-E", "--extended-regexp"], NoValue),
+            (&["-e", "--regexp"], Required),
+            (&["-C", "--context"], Required),
+            (&["-i", "--ignore-case"], NoValue),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "tar.txt",
+        options: 157,
+        flags: &[
+            (&["-A", "--catenate", "--concatenate"], NoValue),
+            (&["-f", "--file"], Required),
+            (&["-g", "--listed-incremental"], Required),
+            (&["--occurrence"], Optional),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "curl.txt",
+        options: 249,
+        flags: &[
+            (&["-o", "--output"], Required),
+            (&["-a", "--append"], NoValue),
+            (&["--cacert"], Required),
+        ],
+        subcommands: &[],
+    },
+    Fixture {
+        file: "pip.txt",
+        options: 25,
+        flags: &[
+            (&["-h", "--help"], NoValue),
+            (&["--python"], Required),
+            (&["-v", "--verbose"], NoValue),
+        ],
+        subcommands: &["install", "download", "uninstall"],
+    },
+    Fixture {
+        file: "git.txt",
+        options: 0,
+        flags: &[],
+        subcommands: &["clone", "init", "add", "mv"],
+    },
+    Fixture {
+        file: "winget.txt",
+        options: 7,
+        flags: &[(&["-v", "--version"], NoValue), (&["--info"], NoValue)],
+        subcommands: &["install", "show", "source"],
+    },
+    Fixture {
+        file: "robocopy.txt",
+        options: 15,
+        flags: &[
+            (&["/S"], NoValue),
+            (&["/LEV"], Required),
+            (&["/XF"], Required),
+            (&["/LOG"], Required),
+        ],
+        subcommands: &[],
+    },
+];
+
+fn read(file: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/help")
+        .join(file);
+    let raw =
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    help_text(&raw, "")
+}
+
+fn find<'a>(options: &'a [OptionInfo], flags: &[&str]) -> Option<&'a OptionInfo> {
+    options.iter().find(|option| option.flags == flags)
+}
+
+#[test]
+fn fixtures_parse_as_recorded() {
+    for fixture in FIXTURES {
+        let text = read(fixture.file);
+        let options = parse_help(&text);
+        assert_eq!(options.len(), fixture.options, "{}", fixture.file);
+
+        for (flags, value) in fixture.flags {
+            let option = find(&options, flags)
+                .unwrap_or_else(|| panic!("{}: {:?} wasn't parsed", fixture.file, flags));
+            assert_eq!(option.value, *value, "{}: {:?}", fixture.file, flags);
+        }
+
+        let subcommands: Vec<String> = parse_subcommands(&text)
+            .into_iter()
+            .map(|sub| sub.name)
+            .collect();
+        assert!(
+            subcommands.starts_with(
+                &fixture
+                    .subcommands
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>()
+            ),
+            "{}: {:?}",
+            fixture.file,
+            subcommands
+        );
+    }
+}
+
+#[test]
+fn every_fixture_is_covered() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/help");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+        assert!(
+            name == "ssh.txt" || FIXTURES.iter().any(|fixture| fixture.file == name),
+            "{} has no expectations",
+            name
+        );
+    }
+}
+
+#[test]
+fn parsed_flags_are_real_flags() {
+    for fixture in FIXTURES {
+        for option in parse_help(&read(fixture.file)) {
+            for flag in &option.flags {
+                assert!(
+                    flag.starts_with(['-', '/']) && !flag.contains(char::is_whitespace),
+                    "{}: {:?}",
+                    fixture.file,
+                    flag
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn git_lists_every_command_group() {
+    let subcommands = parse_subcommands(&read("git.txt"));
+    assert_eq!(subcommands.len(), 22);
+    assert_eq!(subcommands.last().unwrap().name, "push");
+}
+
+#[test]
+fn ssh_options_come_from_its_usage_line() {
+    let text = read("ssh.txt");
+    assert!(parse_help(&text).is_empty());
+
+    let options = parse_usage(&text);
+    assert_eq!(find(&options, &["-4"]).unwrap().value, NoValue);
+    assert_eq!(find(&options, &["-p"]).unwrap().value, Required);
+    assert_eq!(find(&options, &["-L"]).unwrap().value, Required);
+}